@@ -74,7 +74,21 @@ export const TokenSwapLayout = BufferLayout.struct([
   Layout.uint64('hostFeeNumerator'),
   Layout.uint64('hostFeeDenominator'),
   BufferLayout.u8('curveType'),
-  BufferLayout.blob(32, 'curveParameters'),
+  BufferLayout.blob(40, 'curveParameters'),
+  Layout.uint64('bondedTokenAAmount'),
+  BufferLayout.u8('tokenBMintAndBurn'),
+  BufferLayout.u32('adminOption'),
+  Layout.publicKey('admin'),
+  BufferLayout.u32('pendingAdminOption'),
+  Layout.publicKey('pendingAdmin'),
+  BufferLayout.u8('paused'),
+  BufferLayout.u8('emergencyMode'),
+  Layout.uint64('emergencyCirculatingTokenBAmount'),
+  BufferLayout.u8('accrueFees'),
+  Layout.uint64('accruedTokenAFees'),
+  Layout.uint64('accruedTokenBFees'),
+  Layout.uint64('soldTokenBAmount'),
+  BufferLayout.blob(94, 'reserved'),
 ]);
 
 export const CurveType = Object.freeze({
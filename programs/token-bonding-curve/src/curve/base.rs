@@ -2,18 +2,27 @@
 
 use solana_program::{
     program_error::ProgramError,
-    program_pack::{Pack, Sealed},
+    program_pack::{IsInitialized, Pack, Sealed},
 };
 
-use crate::curve::{
-    calculator::{CurveCalculator, SwapWithoutFeesResult, TradeDirection},
-    constant_price::ConstantPriceCurve,
-    constant_product::ConstantProductCurve,
-    fees::Fees,
-    linear_price::LinearPriceCurve,
-    offset::OffsetCurve,
-    stable::StableCurve,
+use crate::{
+    curve::{
+        calculator::{CurveCalculator, SwapWithoutFeesResult, TradeDirection},
+        constant_price::ConstantPriceCurve,
+        constant_product::ConstantProductCurve,
+        exponential_price::ExponentialPriceCurve,
+        fees::Fees,
+        linear_price::LinearPriceCurve,
+        offset::OffsetCurve,
+        piecewise_linear::{PiecewiseLinearCurve, PiecewiseLinearSegment, PiecewiseLinearSegments},
+        power_price::PowerPriceCurve,
+        reserve_ratio::ReserveRatioCurve,
+        sigmoid_price::SigmoidPriceCurve,
+        stable::StableCurve,
+    },
+    error::SwapError,
 };
+use anchor_lang::prelude::{borsh, AnchorDeserialize, AnchorSerialize};
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Debug;
@@ -24,9 +33,10 @@ use arbitrary::Arbitrary;
 /// Curve types supported by the token-swap program.
 #[cfg_attr(feature = "fuzz", derive(Arbitrary))]
 #[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub enum CurveType {
     /// Uniswap-style constant product curve, invariant = token_a_amount * token_b_amount
+    #[default]
     ConstantProduct,
     /// Flat line, always providing 1:1 from one token to another
     ConstantPrice,
@@ -38,6 +48,18 @@ pub enum CurveType {
     /// (Make this explicit and leave some room just in case other curves get added in that we
     /// want to incorporate)
     LinearPrice = 30,
+    /// Price of token B grows by a fixed percentage for every token B swapped out of the curve
+    ExponentialPrice = 31,
+    /// Price of token B increases with a power (e.g. square or cube) of the amount of token B swapped
+    /// out of the curve
+    PowerPrice = 32,
+    /// Price of token B increases linearly with a different slope for each segment, the segments are kept in a
+    /// separate account (see `CurveType::curve_params_len`)
+    PiecewiseLinear = 33,
+    /// Price of token B follows an S-shaped (logistic) curve, starting near 0 and leveling off at a max price
+    SigmoidPrice = 34,
+    /// Bancor-style curve, price of token B is `reserve / (supply * reserve_ratio)` with a constant reserve ratio
+    ReserveRatio = 35,
 }
 
 /// Encodes all results of swapping from a source token to a destination token
@@ -57,6 +79,22 @@ pub struct SwapResult {
     pub owner_fee: u128,
 }
 
+/// Quote for a swap against the swap's current balances, returned by the
+/// `quote_swap` instruction
+#[derive(Debug, PartialEq)]
+pub struct SwapQuote {
+    /// Result of the swap, as it would be executed by the `swap` instruction
+    pub swap_result: SwapResult,
+    /// Spot price of token B denominated in token A before the swap, as a
+    /// (numerator, denominator) fraction. (0, 0) if it can't be calculated
+    /// (e.g. the swap is empty)
+    pub spot_price_before: (u128, u128),
+    /// Spot price of token B denominated in token A after the swap, as a
+    /// (numerator, denominator) fraction. (0, 0) if it can't be calculated
+    /// (e.g. the swap is empty)
+    pub spot_price_after: (u128, u128),
+}
+
 /// Concrete struct to wrap around the trait object which performs calculation.
 #[repr(C)]
 #[derive(Debug)]
@@ -97,9 +135,36 @@ impl SwapCurve {
             trade_direction,
         )?;
 
+        // the bonding curves can use less than they're given (e.g. when they run
+        // out of the destination token), in which case the fees are only charged
+        // on the amount they used. The AMM curves keep the fees on the full
+        // amount, same as upstream (e.g. the constant price curve flooring off a
+        // remainder)
+        let (trade_fee, owner_fee) = if self.calculator.collects_fees_in_trading_tokens()
+            && source_amount_swapped < source_amount_less_fees
+        {
+            let used_source_amount = fees
+                .pre_trading_fee_amount(source_amount_swapped)?
+                .min(source_amount);
+            (
+                fees.trading_fee(used_source_amount)?.min(trade_fee),
+                fees.owner_trading_fee(used_source_amount)?.min(owner_fee),
+            )
+        } else {
+            (trade_fee, owner_fee)
+        };
+        let total_fees = trade_fee.checked_add(owner_fee)?;
+
+        // fees paid out in trading tokens never reach the swap's source account
+        let new_swap_source_amount = swap_source_amount.checked_add(source_amount_swapped)?;
+        let new_swap_source_amount = match self.calculator.collects_fees_in_trading_tokens() {
+            true => new_swap_source_amount,
+            false => new_swap_source_amount.checked_add(total_fees)?,
+        };
+
         let source_amount_swapped = source_amount_swapped.checked_add(total_fees)?;
         Some(SwapResult {
-            new_swap_source_amount: swap_source_amount.checked_add(source_amount_swapped)?,
+            new_swap_source_amount,
             new_swap_destination_amount: swap_destination_amount
                 .checked_sub(destination_amount_swapped)?,
             source_amount_swapped,
@@ -109,6 +174,190 @@ impl SwapCurve {
         })
     }
 
+    /// Calculate how much source token (including fees) must be provided to get
+    /// exactly `destination_amount` of destination token out.
+    pub fn swap_exact_out(
+        &self,
+        destination_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+        fees: &Fees,
+    ) -> Option<SwapResult> {
+        let SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped,
+        } = self.calculator.swap_without_fees_exact_out(
+            destination_amount,
+            swap_source_amount,
+            swap_destination_amount,
+            trade_direction,
+        )?;
+
+        // gross up the source amount so the fees can be taken out of it
+        let source_amount = fees.pre_trading_fee_amount(source_amount_swapped)?;
+        let trade_fee = fees.trading_fee(source_amount)?;
+        let owner_fee = fees.owner_trading_fee(source_amount)?;
+
+        // any rounding left over after the fees goes to the swap along with the
+        // amount required by the curve
+        let total_fees = trade_fee.checked_add(owner_fee)?;
+        let source_amount_less_fees = source_amount.checked_sub(total_fees)?;
+
+        // fees paid out in trading tokens never reach the swap's source account
+        let new_swap_source_amount = swap_source_amount.checked_add(source_amount_less_fees)?;
+        let new_swap_source_amount = match self.calculator.collects_fees_in_trading_tokens() {
+            true => new_swap_source_amount,
+            false => new_swap_source_amount.checked_add(total_fees)?,
+        };
+
+        Some(SwapResult {
+            new_swap_source_amount,
+            new_swap_destination_amount: swap_destination_amount
+                .checked_sub(destination_amount_swapped)?,
+            source_amount_swapped: source_amount,
+            destination_amount_swapped,
+            trade_fee,
+            owner_fee,
+        })
+    }
+
+    /// Quote a swap (see `swap`), along with the spot price before and after it
+    pub fn quote_swap(
+        &self,
+        source_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+        fees: &Fees,
+    ) -> Option<SwapQuote> {
+        let swap_result = self.swap(
+            source_amount,
+            swap_source_amount,
+            swap_destination_amount,
+            trade_direction,
+            fees,
+        )?;
+
+        let spot_price = |swap_source_amount, swap_destination_amount| {
+            let (swap_token_a_amount, swap_token_b_amount) = match trade_direction {
+                TradeDirection::AtoB => (swap_source_amount, swap_destination_amount),
+                TradeDirection::BtoA => (swap_destination_amount, swap_source_amount),
+            };
+            self.calculator
+                .spot_price(swap_token_a_amount, swap_token_b_amount)
+                .unwrap_or((0, 0))
+        };
+        let spot_price_before = spot_price(swap_source_amount, swap_destination_amount);
+        let spot_price_after = spot_price(
+            swap_result.new_swap_source_amount,
+            swap_result.new_swap_destination_amount,
+        );
+
+        Some(SwapQuote {
+            swap_result,
+            spot_price_before,
+            spot_price_after,
+        })
+    }
+
+    /// Works out why `swap` returned None for the same arguments, so the
+    /// failure can be reported as something more specific than
+    /// `SwapError::ZeroTradingTokens` where possible
+    pub fn swap_error(
+        &self,
+        source_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+        fees: &Fees,
+    ) -> SwapError {
+        if swap_destination_amount == 0 {
+            return SwapError::CurveSoldOut;
+        }
+        let source_amount_less_fees = fees
+            .trading_fee(source_amount)
+            .zip(fees.owner_trading_fee(source_amount))
+            .and_then(|(trade_fee, owner_fee)| trade_fee.checked_add(owner_fee))
+            .and_then(|total_fees| source_amount.checked_sub(total_fees));
+        let source_amount_less_fees = match source_amount_less_fees {
+            Some(0) => return SwapError::AmountBelowMinimum,
+            Some(source_amount_less_fees) => source_amount_less_fees,
+            None => return SwapError::FeeCalculationFailure,
+        };
+
+        // the spot price is the best price the trade can get since the price
+        // moves against the trader, so if the amount doesn't buy a whole unit
+        // of the destination token at that price it's too small to trade
+        let (swap_token_a_amount, swap_token_b_amount) = match trade_direction {
+            TradeDirection::AtoB => (swap_source_amount, swap_destination_amount),
+            TradeDirection::BtoA => (swap_destination_amount, swap_source_amount),
+        };
+        if let Some((numerator, denominator)) = self
+            .calculator
+            .spot_price(swap_token_a_amount, swap_token_b_amount)
+        {
+            let (numerator, denominator) = match trade_direction {
+                TradeDirection::AtoB => (denominator, numerator),
+                TradeDirection::BtoA => (numerator, denominator),
+            };
+            let estimated_destination_amount = source_amount_less_fees
+                .checked_mul(numerator)
+                .and_then(|amount| amount.checked_div(denominator));
+            if estimated_destination_amount == Some(0) {
+                return SwapError::AmountBelowMinimum;
+            }
+        }
+
+        // the amount swapped out only grows with the amount swapped in, so if
+        // a smaller trade goes through the calculation broke on this one
+        if source_amount_less_fees > 1
+            && self
+                .calculator
+                .swap_without_fees(
+                    source_amount_less_fees / 2,
+                    swap_source_amount,
+                    swap_destination_amount,
+                    trade_direction,
+                )
+                .is_some()
+        {
+            return SwapError::CurveCalculationOverflow;
+        }
+        SwapError::ZeroTradingTokens
+    }
+
+    /// Works out why `swap_exact_out` returned None for the same arguments,
+    /// see `swap_error`
+    pub fn swap_exact_out_error(
+        &self,
+        destination_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+    ) -> SwapError {
+        if destination_amount > swap_destination_amount {
+            return SwapError::CurveSoldOut;
+        }
+        if destination_amount == 0 {
+            return SwapError::AmountBelowMinimum;
+        }
+        // the curve priced the trade, so it failed adding the fees on top
+        if self
+            .calculator
+            .swap_without_fees_exact_out(
+                destination_amount,
+                swap_source_amount,
+                swap_destination_amount,
+                trade_direction,
+            )
+            .is_some()
+        {
+            return SwapError::FeeCalculationFailure;
+        }
+        SwapError::ZeroTradingTokens
+    }
+
     /// Get the amount of pool tokens for the deposited amount of token A or B
     pub fn deposit_single_token_type(
         &self,
@@ -164,6 +413,40 @@ impl SwapCurve {
             trade_direction,
         )
     }
+
+    /// Returns a copy of the curve with the parameters kept in the swap's
+    /// curve params account loaded from that account's data (see
+    /// `CurveType::curve_params_len`)
+    pub fn load_curve_params(&self, curve_params_data: &[u8]) -> Result<Self, ProgramError> {
+        Ok(Self {
+            curve_type: self.curve_type,
+            calculator: match self.curve_type {
+                CurveType::PiecewiseLinear => Box::new(PiecewiseLinearCurve {
+                    segments: PiecewiseLinearSegments::unpack(curve_params_data)?,
+                }),
+                _ => return Err(SwapError::UnsupportedCurveOperation.into()),
+            },
+        })
+    }
+
+    /// Returns a copy of the curve priced off `token_b_supply`, the circulating supply of token B plus the swap's
+    /// token B amount, for curves that use it (see `CurveType::uses_token_b_supply`)
+    pub fn with_token_b_supply(&self, token_b_supply: u64) -> Result<Self, ProgramError> {
+        Ok(Self {
+            curve_type: self.curve_type,
+            calculator: match self.curve_type {
+                CurveType::ReserveRatio => {
+                    let mut packed = [0u8; ReserveRatioCurve::LEN];
+                    self.calculator.pack_into_slice(&mut packed);
+                    Box::new(ReserveRatioCurve {
+                        token_b_supply: Some(token_b_supply),
+                        ..ReserveRatioCurve::unpack_from_slice(&packed)?
+                    })
+                }
+                _ => return Err(SwapError::UnsupportedCurveOperation.into()),
+            },
+        })
+    }
 }
 
 /// Default implementation for SwapCurve cannot be derived because of
@@ -180,9 +463,8 @@ impl Default for SwapCurve {
 }
 
 /// Clone takes advantage of pack / unpack to get around the difficulty of
-/// cloning dynamic objects.
-/// Note that this is only to be used for testing.
-#[cfg(any(test, feature = "fuzz"))]
+/// cloning dynamic objects (the swap is cloned when instructions take it as a
+/// typed `Account<SwapV2>`)
 impl Clone for SwapCurve {
     fn clone(&self) -> Self {
         let mut packed_self = [0u8; Self::LEN];
@@ -207,15 +489,15 @@ impl Sealed for SwapCurve {}
 impl Pack for SwapCurve {
     /// Size of encoding of all curve parameters, which include fees and any other
     /// constants used to calculate swaps, deposits, and withdrawals.
-    /// This includes 1 byte for the type, and 72 for the calculator to use as
-    /// it needs.  Some calculators may be smaller than 72 bytes.
-    const LEN: usize = 33;
+    /// This includes 1 byte for the type, and 40 for the calculator to use as
+    /// it needs.  Some calculators may be smaller than 40 bytes.
+    const LEN: usize = 41;
 
     /// Unpacks a byte buffer into a SwapCurve
     fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
-        let input = array_ref![input, 0, 33];
+        let input = array_ref![input, 0, 41];
         #[allow(clippy::ptr_offset_with_cast)]
-        let (curve_type, calculator) = array_refs![input, 1, 32];
+        let (curve_type, calculator) = array_refs![input, 1, 40];
         let curve_type = curve_type[0].try_into()?;
         Ok(Self {
             curve_type,
@@ -231,24 +513,125 @@ impl Pack for SwapCurve {
                 CurveType::LinearPrice => {
                     Box::new(LinearPriceCurve::unpack_from_slice(calculator)?)
                 }
+                CurveType::ExponentialPrice => {
+                    Box::new(ExponentialPriceCurve::unpack_from_slice(calculator)?)
+                }
+                CurveType::PowerPrice => Box::new(PowerPriceCurve::unpack_from_slice(calculator)?),
+                CurveType::PiecewiseLinear => {
+                    Box::new(PiecewiseLinearCurve::unpack_from_slice(calculator)?)
+                }
+                CurveType::SigmoidPrice => {
+                    Box::new(SigmoidPriceCurve::unpack_from_slice(calculator)?)
+                }
+                CurveType::ReserveRatio => {
+                    Box::new(ReserveRatioCurve::unpack_from_slice(calculator)?)
+                }
             },
         })
     }
 
     /// Pack SwapCurve into a byte buffer
     fn pack_into_slice(&self, output: &mut [u8]) {
-        let output = array_mut_ref![output, 0, 33];
-        let (curve_type, calculator) = mut_array_refs![output, 1, 32];
+        let output = array_mut_ref![output, 0, 41];
+        let (curve_type, calculator) = mut_array_refs![output, 1, 40];
         curve_type[0] = self.curve_type as u8;
         self.calculator.pack_into_slice(&mut calculator[..]);
     }
 }
 
+impl Sealed for SwapQuote {}
+impl IsInitialized for SwapQuote {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+impl Pack for SwapQuote {
+    /// All amounts and prices are encoded as little-endian u128s
+    const LEN: usize = 160;
+
+    /// Unpacks a byte buffer into a SwapQuote
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 160];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            new_swap_source_amount,
+            new_swap_destination_amount,
+            source_amount_swapped,
+            destination_amount_swapped,
+            trade_fee,
+            owner_fee,
+            spot_price_before_numerator,
+            spot_price_before_denominator,
+            spot_price_after_numerator,
+            spot_price_after_denominator,
+        ) = array_refs![input, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16];
+        Ok(Self {
+            swap_result: SwapResult {
+                new_swap_source_amount: u128::from_le_bytes(*new_swap_source_amount),
+                new_swap_destination_amount: u128::from_le_bytes(*new_swap_destination_amount),
+                source_amount_swapped: u128::from_le_bytes(*source_amount_swapped),
+                destination_amount_swapped: u128::from_le_bytes(*destination_amount_swapped),
+                trade_fee: u128::from_le_bytes(*trade_fee),
+                owner_fee: u128::from_le_bytes(*owner_fee),
+            },
+            spot_price_before: (
+                u128::from_le_bytes(*spot_price_before_numerator),
+                u128::from_le_bytes(*spot_price_before_denominator),
+            ),
+            spot_price_after: (
+                u128::from_le_bytes(*spot_price_after_numerator),
+                u128::from_le_bytes(*spot_price_after_denominator),
+            ),
+        })
+    }
+
+    /// Pack SwapQuote into a byte buffer
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 160];
+        let (
+            new_swap_source_amount,
+            new_swap_destination_amount,
+            source_amount_swapped,
+            destination_amount_swapped,
+            trade_fee,
+            owner_fee,
+            spot_price_before_numerator,
+            spot_price_before_denominator,
+            spot_price_after_numerator,
+            spot_price_after_denominator,
+        ) = mut_array_refs![output, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16];
+        *new_swap_source_amount = self.swap_result.new_swap_source_amount.to_le_bytes();
+        *new_swap_destination_amount = self.swap_result.new_swap_destination_amount.to_le_bytes();
+        *source_amount_swapped = self.swap_result.source_amount_swapped.to_le_bytes();
+        *destination_amount_swapped = self.swap_result.destination_amount_swapped.to_le_bytes();
+        *trade_fee = self.swap_result.trade_fee.to_le_bytes();
+        *owner_fee = self.swap_result.owner_fee.to_le_bytes();
+        *spot_price_before_numerator = self.spot_price_before.0.to_le_bytes();
+        *spot_price_before_denominator = self.spot_price_before.1.to_le_bytes();
+        *spot_price_after_numerator = self.spot_price_after.0.to_le_bytes();
+        *spot_price_after_denominator = self.spot_price_after.1.to_le_bytes();
+    }
+}
+
 /// Sensible default of CurveType to ConstantProduct, the most popular and
 /// well-known curve type.
-impl Default for CurveType {
-    fn default() -> Self {
-        CurveType::ConstantProduct
+impl CurveType {
+    /// Size of the curve params account for curves whose parameters don't fit in the swap's 40 byte calculator
+    /// slot, None for curves that store everything in the swap. That account is a PDA of the swap (see
+    /// `Processor::curve_params_address`) that the swap links to, and it has to be passed in right after the
+    /// fixed accounts of every instruction that uses the curve
+    pub fn curve_params_len(&self) -> Option<usize> {
+        match self {
+            CurveType::PiecewiseLinear => Some(PiecewiseLinearSegments::LEN),
+            _ => None,
+        }
+    }
+
+    /// Whether the curve is priced off the circulating supply of token B, which isn't stored in the swap. Those
+    /// curves read it from token B's mint, which has to be passed in after the curve's accounts (in the same
+    /// spot as for a mint-and-burn swap, see `SwapV2::token_b_mint_and_burn`) to price a swap
+    pub fn uses_token_b_supply(&self) -> bool {
+        matches!(self, CurveType::ReserveRatio)
     }
 }
 
@@ -262,14 +645,238 @@ impl TryFrom<u8> for CurveType {
             2 => Ok(CurveType::Stable),
             3 => Ok(CurveType::Offset),
             30 => Ok(CurveType::LinearPrice),
+            31 => Ok(CurveType::ExponentialPrice),
+            32 => Ok(CurveType::PowerPrice),
+            33 => Ok(CurveType::PiecewiseLinear),
+            34 => Ok(CurveType::SigmoidPrice),
+            35 => Ok(CurveType::ReserveRatio),
             _ => Err(ProgramError::InvalidAccountData),
         }
     }
 }
 
+/// Parameters of every curve type, passed to the `initialize` instruction and turned into a `SwapCurve` there.
+/// `SwapCurve` holds a trait object so it can't be borsh serialized itself, the variants mirror the fields of
+/// each curve's calculator instead (see the calculator structs for what they mean)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum CurveParams {
+    /// See `ConstantProductCurve`
+    ConstantProduct,
+    /// See `ConstantPriceCurve`
+    ConstantPrice {
+        /// Amount of token A required to get 1 token B
+        token_b_price: u64,
+    },
+    /// See `StableCurve`
+    Stable {
+        /// Amplifier constant
+        amp: u64,
+    },
+    /// See `OffsetCurve`
+    Offset {
+        /// Amount to offset the token B liquidity account
+        token_b_offset: u64,
+    },
+    /// See `LinearPriceCurve`
+    LinearPrice {
+        /// Slope numerator
+        slope_numerator: u64,
+        /// Slope denominator
+        slope_denominator: u64,
+        /// Initial price numerator
+        initial_token_a_price_numerator: u64,
+        /// Initial price denominator
+        initial_token_a_price_denominator: u64,
+        /// Token A the curve is seeded with
+        initial_token_a_amount: u64,
+    },
+    /// See `ExponentialPriceCurve`
+    ExponentialPrice {
+        /// Initial price numerator
+        initial_token_a_price_numerator: u64,
+        /// Initial price denominator
+        initial_token_a_price_denominator: u64,
+        /// Growth rate numerator
+        growth_rate_numerator: u32,
+        /// Growth rate denominator
+        growth_rate_denominator: u32,
+        /// Token B swapped out per growth step
+        growth_interval: u64,
+    },
+    /// See `PowerPriceCurve`
+    PowerPrice {
+        /// Slope numerator
+        slope_numerator: u64,
+        /// Slope denominator
+        slope_denominator: u64,
+        /// Initial price numerator
+        initial_token_a_price_numerator: u64,
+        /// Initial price denominator
+        initial_token_a_price_denominator: u32,
+        /// Exponent of the curve
+        exponent: u8,
+        /// Decimals of token B
+        token_b_decimals: u8,
+    },
+    /// See `PiecewiseLinearCurve`
+    PiecewiseLinear {
+        /// Initial price numerator
+        initial_token_a_price_numerator: u64,
+        /// Initial price denominator
+        initial_token_a_price_denominator: u64,
+        /// Segments of the curve, sorted by breakpoint
+        segments: Vec<PiecewiseLinearSegment>,
+    },
+    /// See `SigmoidPriceCurve`
+    SigmoidPrice {
+        /// Max price numerator
+        max_price_numerator: u64,
+        /// Max price denominator
+        max_price_denominator: u32,
+        /// Steepness numerator
+        steepness_numerator: u32,
+        /// Steepness denominator
+        steepness_denominator: u64,
+        /// Token B swapped out where the price is half of the max price
+        midpoint: u64,
+    },
+    /// See `ReserveRatioCurve`
+    ReserveRatio {
+        /// Reserve ratio numerator
+        reserve_ratio_numerator: u32,
+        /// Reserve ratio denominator
+        reserve_ratio_denominator: u32,
+        /// Virtual token A reserve the curve starts from
+        initial_reserve: u64,
+        /// Virtual token B supply the curve starts from
+        initial_supply: u64,
+    },
+}
+
+impl From<CurveParams> for SwapCurve {
+    fn from(curve_params: CurveParams) -> Self {
+        match curve_params {
+            CurveParams::ConstantProduct => SwapCurve {
+                curve_type: CurveType::ConstantProduct,
+                calculator: Box::new(ConstantProductCurve {}),
+            },
+            CurveParams::ConstantPrice { token_b_price } => SwapCurve {
+                curve_type: CurveType::ConstantPrice,
+                calculator: Box::new(ConstantPriceCurve { token_b_price }),
+            },
+            CurveParams::Stable { amp } => SwapCurve {
+                curve_type: CurveType::Stable,
+                calculator: Box::new(StableCurve { amp }),
+            },
+            CurveParams::Offset { token_b_offset } => SwapCurve {
+                curve_type: CurveType::Offset,
+                calculator: Box::new(OffsetCurve { token_b_offset }),
+            },
+            CurveParams::LinearPrice {
+                slope_numerator,
+                slope_denominator,
+                initial_token_a_price_numerator,
+                initial_token_a_price_denominator,
+                initial_token_a_amount,
+            } => SwapCurve {
+                curve_type: CurveType::LinearPrice,
+                calculator: Box::new(LinearPriceCurve {
+                    slope_numerator,
+                    slope_denominator,
+                    initial_token_a_price_numerator,
+                    initial_token_a_price_denominator,
+                    initial_token_a_amount,
+                }),
+            },
+            CurveParams::ExponentialPrice {
+                initial_token_a_price_numerator,
+                initial_token_a_price_denominator,
+                growth_rate_numerator,
+                growth_rate_denominator,
+                growth_interval,
+            } => SwapCurve {
+                curve_type: CurveType::ExponentialPrice,
+                calculator: Box::new(ExponentialPriceCurve {
+                    initial_token_a_price_numerator,
+                    initial_token_a_price_denominator,
+                    growth_rate_numerator,
+                    growth_rate_denominator,
+                    growth_interval,
+                }),
+            },
+            CurveParams::PowerPrice {
+                slope_numerator,
+                slope_denominator,
+                initial_token_a_price_numerator,
+                initial_token_a_price_denominator,
+                exponent,
+                token_b_decimals,
+            } => SwapCurve {
+                curve_type: CurveType::PowerPrice,
+                calculator: Box::new(PowerPriceCurve {
+                    slope_numerator,
+                    slope_denominator,
+                    initial_token_a_price_numerator,
+                    initial_token_a_price_denominator,
+                    exponent,
+                    token_b_decimals,
+                }),
+            },
+            CurveParams::PiecewiseLinear {
+                initial_token_a_price_numerator,
+                initial_token_a_price_denominator,
+                segments,
+            } => SwapCurve {
+                curve_type: CurveType::PiecewiseLinear,
+                calculator: Box::new(PiecewiseLinearCurve {
+                    segments: PiecewiseLinearSegments {
+                        is_initialized: true,
+                        initial_token_a_price_numerator,
+                        initial_token_a_price_denominator,
+                        segments,
+                    },
+                }),
+            },
+            CurveParams::SigmoidPrice {
+                max_price_numerator,
+                max_price_denominator,
+                steepness_numerator,
+                steepness_denominator,
+                midpoint,
+            } => SwapCurve {
+                curve_type: CurveType::SigmoidPrice,
+                calculator: Box::new(SigmoidPriceCurve {
+                    max_price_numerator,
+                    max_price_denominator,
+                    steepness_numerator,
+                    steepness_denominator,
+                    midpoint,
+                }),
+            },
+            CurveParams::ReserveRatio {
+                reserve_ratio_numerator,
+                reserve_ratio_denominator,
+                initial_reserve,
+                initial_supply,
+            } => SwapCurve {
+                curve_type: CurveType::ReserveRatio,
+                calculator: Box::new(ReserveRatioCurve {
+                    reserve_ratio_numerator,
+                    reserve_ratio_denominator,
+                    initial_reserve,
+                    initial_supply,
+                    token_b_supply: None,
+                }),
+            },
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::curve::calculator::CurveCalculator;
+    use crate::dfs_precise_number::ONE;
 
     #[test]
     fn pack_swap_curve() {
@@ -286,7 +893,7 @@ mod tests {
         assert_eq!(swap_curve, unpacked);
 
         let mut packed = vec![curve_type as u8];
-        packed.extend_from_slice(&[0u8; 32]); // 32 bytes reserved for curve
+        packed.extend_from_slice(&[0u8; 40]); // 40 bytes reserved for curve
         let unpacked = SwapCurve::unpack_from_slice(&packed).unwrap();
         assert_eq!(swap_curve, unpacked);
     }
@@ -382,6 +989,264 @@ mod tests {
         assert_eq!(result.owner_fee, 1);
     }
 
+    #[test]
+    fn constant_price_fees_not_recalculated() {
+        let swap_source_amount: u128 = 1000;
+        let swap_destination_amount: u128 = 50000;
+        let fees = Fees {
+            trade_fee_numerator: 10,
+            trade_fee_denominator: 100,
+            owner_trade_fee_numerator: 0,
+            owner_trade_fee_denominator: 0,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantPrice,
+            calculator: Box::new(ConstantPriceCurve { token_b_price: 400 }),
+        };
+
+        // 1000 A less the 100 A fee buys 2 B for 800 A, the 100 A remainder is
+        // floored off but the fee is still taken on the full amount
+        let result = swap_curve
+            .swap(
+                1000,
+                swap_source_amount,
+                swap_destination_amount,
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        assert_eq!(result.destination_amount_swapped, 2);
+        assert_eq!(result.trade_fee, 100);
+        assert_eq!(result.owner_fee, 0);
+        assert_eq!(result.source_amount_swapped, 900);
+        assert_eq!(result.new_swap_source_amount, 1900);
+        assert_eq!(result.new_swap_destination_amount, 49998);
+    }
+
+    #[test]
+    fn linear_price_fees_stay_out_of_swap() {
+        let swap_source_amount: u128 = 0;
+        let swap_destination_amount: u128 = 5000;
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 100,
+            owner_trade_fee_numerator: 2,
+            owner_trade_fee_denominator: 100,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
+        };
+        let curve = LinearPriceCurve {
+            slope_numerator: 1,
+            slope_denominator: 2,
+            initial_token_a_price_numerator: 150,
+            initial_token_a_price_denominator: 3,
+            initial_token_a_amount: 0,
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::LinearPrice,
+            calculator: Box::new(curve.clone()),
+        };
+
+        // 110 A in, 1 trade fee + 2 owner fee, 107 A bonded to the curve
+        let result = swap_curve
+            .swap(
+                110,
+                swap_source_amount,
+                swap_destination_amount,
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        assert_eq!(result.trade_fee, 1);
+        assert_eq!(result.owner_fee, 2);
+        assert_eq!(result.source_amount_swapped, 110);
+        // fees don't end up in the swap, so they don't move the price
+        assert_eq!(result.new_swap_source_amount, 107);
+        let without_fees = curve
+            .swap_without_fees(
+                107,
+                swap_source_amount,
+                swap_destination_amount,
+                TradeDirection::AtoB,
+            )
+            .unwrap();
+        assert_eq!(
+            result.destination_amount_swapped,
+            without_fees.destination_amount_swapped
+        );
+        assert_eq!(
+            result.new_swap_destination_amount,
+            swap_destination_amount - without_fees.destination_amount_swapped
+        );
+
+        // selling B back pays the fees in B
+        let result = swap_curve
+            .swap(
+                100,
+                result.new_swap_destination_amount,
+                result.new_swap_source_amount,
+                TradeDirection::BtoA,
+                &fees,
+            )
+            .unwrap();
+        // only the B needed to get down to b = 0 is taken, plus the fees on
+        // that amount (the minimum of one token each) rather than on all 100
+        assert_eq!(result.trade_fee, 1);
+        assert_eq!(result.owner_fee, 1);
+        assert_eq!(result.source_amount_swapped, 2 + 2);
+        assert_eq!(result.destination_amount_swapped, 107);
+        assert_eq!(result.new_swap_destination_amount, 0);
+    }
+
+    #[test]
+    fn linear_price_swap_exact_out() {
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 100,
+            owner_trade_fee_numerator: 2,
+            owner_trade_fee_denominator: 100,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
+        };
+        let curve = LinearPriceCurve {
+            slope_numerator: 1,
+            slope_denominator: 2,
+            initial_token_a_price_numerator: 150,
+            initial_token_a_price_denominator: 3,
+            initial_token_a_amount: 0,
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::LinearPrice,
+            calculator: Box::new(curve),
+        };
+
+        // 2 B out costs 101 A on the curve, grossed up to 105 A to cover the fees
+        let result = swap_curve
+            .swap_exact_out(2, 0, 5000, TradeDirection::AtoB, &fees)
+            .unwrap();
+        assert_eq!(result.destination_amount_swapped, 2);
+        assert_eq!(result.source_amount_swapped, 105);
+        assert_eq!(result.trade_fee, 1);
+        assert_eq!(result.owner_fee, 2);
+        // the extra A left over after the fees stays in the swap
+        assert_eq!(result.new_swap_source_amount, 102);
+        assert_eq!(result.new_swap_destination_amount, 4998);
+
+        // curves that don't support exact output swaps
+        let swap_curve = SwapCurve::default();
+        assert_eq!(
+            swap_curve.swap_exact_out(2, 1000, 5000, TradeDirection::AtoB, &fees),
+            None
+        );
+    }
+
+    #[test]
+    fn linear_price_quote_swap() {
+        let curve = LinearPriceCurve {
+            slope_numerator: 1,
+            slope_denominator: 2,
+            initial_token_a_price_numerator: 150,
+            initial_token_a_price_denominator: 3,
+            initial_token_a_amount: 0,
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::LinearPrice,
+            calculator: Box::new(curve),
+        };
+        let fees = Fees::default();
+
+        // 101 A in, 2 B out, moving the price from 50 A to 51 A
+        let quote = swap_curve
+            .quote_swap(101, 0, 5000, TradeDirection::AtoB, &fees)
+            .unwrap();
+        assert_eq!(
+            quote.swap_result,
+            swap_curve
+                .swap(101, 0, 5000, TradeDirection::AtoB, &fees)
+                .unwrap()
+        );
+        assert_eq!(quote.swap_result.destination_amount_swapped, 2);
+        assert_eq!(quote.spot_price_before, (50 * ONE, ONE));
+        assert_eq!(quote.spot_price_after, (51 * ONE, ONE));
+
+        // selling the 2 B back moves the price from 51 A to 50 A
+        let quote = swap_curve
+            .quote_swap(2, 4998, 101, TradeDirection::BtoA, &fees)
+            .unwrap();
+        assert_eq!(quote.swap_result.destination_amount_swapped, 101);
+        assert_eq!(quote.spot_price_before, (51 * ONE, ONE));
+        assert_eq!(quote.spot_price_after, (50 * ONE, ONE));
+
+        let mut packed = [0u8; SwapQuote::LEN];
+        Pack::pack_into_slice(&quote, &mut packed[..]);
+        let unpacked = SwapQuote::unpack_from_slice(&packed).unwrap();
+        assert_eq!(quote, unpacked);
+        assert_eq!(&packed[48..64], &101u128.to_le_bytes());
+    }
+
+    #[test]
+    fn linear_price_swap_error() {
+        let curve = LinearPriceCurve {
+            slope_numerator: 1,
+            slope_denominator: 2,
+            initial_token_a_price_numerator: 150,
+            initial_token_a_price_denominator: 3,
+            initial_token_a_amount: 0,
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::LinearPrice,
+            calculator: Box::new(curve),
+        };
+        let fees = Fees::default();
+
+        // B starts at 50 A, so 49 A doesn't buy a whole B
+        assert_eq!(
+            swap_curve.swap(49, 0, 5000, TradeDirection::AtoB, &fees),
+            None
+        );
+        assert_eq!(
+            swap_curve.swap_error(49, 0, 5000, TradeDirection::AtoB, &fees),
+            SwapError::AmountBelowMinimum
+        );
+
+        // nothing left to buy
+        assert_eq!(
+            swap_curve.swap(101, 5000, 0, TradeDirection::AtoB, &fees),
+            None
+        );
+        assert_eq!(
+            swap_curve.swap_error(101, 5000, 0, TradeDirection::AtoB, &fees),
+            SwapError::CurveSoldOut
+        );
+        assert_eq!(
+            swap_curve.swap_exact_out_error(5001, 0, 5000, TradeDirection::AtoB),
+            SwapError::CurveSoldOut
+        );
+
+        // the fees take the whole amount
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 1,
+            ..Fees::default()
+        };
+        assert_eq!(
+            swap_curve.swap(101, 0, 5000, TradeDirection::AtoB, &fees),
+            None
+        );
+        assert_eq!(
+            swap_curve.swap_error(101, 0, 5000, TradeDirection::AtoB, &fees),
+            SwapError::AmountBelowMinimum
+        );
+    }
+
     #[test]
     fn constant_product_no_fee() {
         let swap_source_amount: u128 = 1_000;
@@ -406,4 +1271,58 @@ mod tests {
         assert_eq!(result.destination_amount_swapped, 4545);
         assert_eq!(result.new_swap_destination_amount, 45455);
     }
+
+    #[test]
+    fn curve_params_into_swap_curve() {
+        let curve_params = CurveParams::LinearPrice {
+            slope_numerator: 1,
+            slope_denominator: 200_000_000,
+            initial_token_a_price_numerator: 150,
+            initial_token_a_price_denominator: 1,
+            initial_token_a_amount: 0,
+        };
+        let serialized = curve_params.try_to_vec().unwrap();
+        assert_eq!(serialized[0], 4);
+        assert_eq!(
+            CurveParams::try_from_slice(&serialized).unwrap(),
+            curve_params
+        );
+
+        let swap_curve: SwapCurve = curve_params.into();
+        assert_eq!(
+            swap_curve,
+            SwapCurve {
+                curve_type: CurveType::LinearPrice,
+                calculator: Box::new(LinearPriceCurve {
+                    slope_numerator: 1,
+                    slope_denominator: 200_000_000,
+                    initial_token_a_price_numerator: 150,
+                    initial_token_a_price_denominator: 1,
+                    initial_token_a_amount: 0,
+                }),
+            }
+        );
+
+        let swap_curve: SwapCurve = CurveParams::PiecewiseLinear {
+            initial_token_a_price_numerator: 1,
+            initial_token_a_price_denominator: 1,
+            segments: vec![PiecewiseLinearSegment {
+                breakpoint: 0,
+                slope_numerator: 1,
+                slope_denominator: 1,
+            }],
+        }
+        .into();
+        assert_eq!(swap_curve.curve_type, CurveType::PiecewiseLinear);
+        assert!(swap_curve.calculator.validate().is_ok());
+
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 100,
+            ..Fees::default()
+        };
+        let serialized = fees.try_to_vec().unwrap();
+        assert_eq!(serialized.len(), 64);
+        assert_eq!(Fees::try_from_slice(&serialized).unwrap(), fees);
+    }
 }
@@ -1,6 +1,9 @@
 //! Swap calculations
 
-use {crate::error::SwapError, spl_math::precise_number::PreciseNumber, std::fmt::Debug};
+use {
+    crate::error::SwapError, solana_program::program_error::ProgramError,
+    spl_math::precise_number::PreciseNumber, std::fmt::Debug,
+};
 
 #[cfg(feature = "fuzz")]
 use arbitrary::Arbitrary;
@@ -95,6 +98,23 @@ pub trait CurveCalculator: Debug + DynPack {
         trade_direction: TradeDirection,
     ) -> Option<SwapWithoutFeesResult>;
 
+    /// Calculate how much source token is required to get exactly
+    /// `destination_amount` of destination token out, i.e. the inverse of
+    /// `swap_without_fees`. Implementations must round the source amount up
+    /// so that the pool never gives out more than it takes in.
+    ///
+    /// The default implementation returns None, meaning the curve doesn't
+    /// support exact output swaps.
+    fn swap_without_fees_exact_out(
+        &self,
+        _destination_amount: u128,
+        _swap_source_amount: u128,
+        _swap_destination_amount: u128,
+        _trade_direction: TradeDirection,
+    ) -> Option<SwapWithoutFeesResult> {
+        None
+    }
+
     /// Get the supply for a new pool
     /// The default implementation is a Balancer-style fixed initial supply
     fn new_pool_supply(&self) -> u128 {
@@ -173,6 +193,30 @@ pub trait CurveCalculator: Debug + DynPack {
         true
     }
 
+    /// Curves without liquidity providers (e.g. the bonding curve in
+    /// `linear_price.rs`) derive their position from the swap's token balances,
+    /// so any fee left inside the swap token accounts would move the price.
+    /// These curves take their trade and owner fees in the source trading token
+    /// instead of minting pool tokens to the pool fee account, and either send
+    /// them straight to a fee token account or set them aside in the swap until
+    /// they're claimed (see `SwapV2::accrue_fees`).
+    fn collects_fees_in_trading_tokens(&self) -> bool {
+        false
+    }
+
+    /// Calculate the spot price of token B denominated in token A, i.e. how
+    /// much token A a marginal amount of token B is worth, given the liquidity
+    /// parameters. Returned as a (numerator, denominator) fraction, which is
+    /// not necessarily reduced.
+    ///
+    /// This is the marginal price of the curve, so it doesn't include fees or
+    /// price impact.
+    fn spot_price(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<(u128, u128)>;
+
     /// Calculates the total normalized value of the curve given the liquidity
     /// parameters.
     ///
@@ -189,6 +233,14 @@ pub trait CurveCalculator: Debug + DynPack {
         swap_token_a_amount: u128,
         swap_token_b_amount: u128,
     ) -> Option<PreciseNumber>;
+
+    /// Pack the parameters kept in the swap's curve params account (for
+    /// curves whose parameters don't fit in the swap, see
+    /// `CurveType::curve_params_len`) into that account's data, called once
+    /// on initialization.
+    fn pack_curve_params(&self, _dst: &mut [u8]) -> Result<(), ProgramError> {
+        Ok(())
+    }
 }
 
 /// Test helpers for curves
@@ -200,6 +200,16 @@ impl CurveCalculator for ConstantPriceCurve {
         Ok(())
     }
 
+    /// The constant price curve always has the same price, regardless of the
+    /// amounts in the swap
+    fn spot_price(
+        &self,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+    ) -> Option<(u128, u128)> {
+        Some((self.token_b_price as u128, 1))
+    }
+
     /// The total normalized value of the constant price curve adds the total
     /// value of the token B side to the token A side.
     ///
@@ -322,6 +332,14 @@ mod tests {
         assert_eq!(curve, unpacked);
     }
 
+    #[test]
+    fn spot_price_is_constant() {
+        let token_b_price = 1_251_258;
+        let curve = ConstantPriceCurve { token_b_price };
+        assert_eq!(curve.spot_price(0, 1), Some((1_251_258, 1)));
+        assert_eq!(curve.spot_price(1_000_000, 0), Some((1_251_258, 1)));
+    }
+
     #[test]
     fn swap_calculation_large_price() {
         let token_b_price = 1123513u128;
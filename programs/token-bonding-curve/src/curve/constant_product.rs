@@ -170,6 +170,16 @@ pub fn normalized_value(
         .sqrt()
 }
 
+/// Calculates the spot price of token B denominated in token A given the
+/// liquidity parameters.
+///
+/// The constant product implementation for this function is simply the ratio
+/// of the two token amounts, since `d(a * b) = 0` gives `-da / db = a / b`.
+pub fn spot_price(swap_token_a_amount: u128, swap_token_b_amount: u128) -> Option<(u128, u128)> {
+    map_zero_to_none(swap_token_b_amount)?;
+    Some((swap_token_a_amount, swap_token_b_amount))
+}
+
 impl CurveCalculator for ConstantProductCurve {
     /// Constant product swap ensures x * y = constant
     fn swap_without_fees(
@@ -246,6 +256,14 @@ impl CurveCalculator for ConstantProductCurve {
         normalized_value(swap_token_a_amount, swap_token_b_amount)
     }
 
+    fn spot_price(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<(u128, u128)> {
+        spot_price(swap_token_a_amount, swap_token_b_amount)
+    }
+
     fn validate(&self) -> Result<(), SwapError> {
         Ok(())
     }
@@ -322,6 +340,14 @@ mod tests {
         check_pool_token_rate(5, 501, 2, 10, 1, 101);
     }
 
+    #[test]
+    fn spot_price_is_ratio() {
+        let calculator = ConstantProductCurve {};
+        assert_eq!(calculator.spot_price(100, 400), Some((100, 400)));
+        assert_eq!(calculator.spot_price(0, 400), Some((0, 400)));
+        assert_eq!(calculator.spot_price(100, 0), None);
+    }
+
     #[test]
     fn fail_trading_token_conversion() {
         let calculator = ConstantProductCurve {};
@@ -0,0 +1,835 @@
+//! Exponential price swap curve, initial price and growth rate set at init
+//! Works under the same assumptions as the linear price curve (see `linear_price.rs`):
+//! Deposits (except the initial deposit) are disabled
+//! The initial deposit should only have token B (the bonded token) and 0 token A (the collateral token)
+//! Trade and owner fees are taken in the source token and either sent straight to a fee token account or set aside
+//! in the swap for `claim_fees` (see `SwapV2::accrue_fees`), so they never count as liquidity or move the price
+//! (see `collects_fees_in_trading_tokens`)
+//! Withdrawals are disabled, will panic if those instructions are called
+
+use {
+    crate::{
+        curve::calculator::{
+            map_zero_to_none, CurveCalculator, DynPack, RoundDirection, SwapWithoutFeesResult,
+            TradeDirection, TradingTokenResult,
+        },
+        dfs_precise_number::{DFSPreciseNumber, ONE},
+        error::SwapError,
+    },
+    arrayref::{array_mut_ref, array_ref},
+    solana_program::{
+        program_error::ProgramError,
+        program_pack::{IsInitialized, Pack, Sealed},
+    },
+};
+
+/// exp and ln are only accurate to ~18 decimals, so amounts computed with them are nudged in favor of the swap
+/// by 1/ROUNDING_MARGIN_DENOMINATOR of the amount (plus the same fraction of the scale of the numbers that went
+/// into exp/ln, since the error doesn't shrink along with tiny amounts) before being rounded
+const ROUNDING_MARGIN_DENOMINATOR: u128 = 1_000_000_000_000_000;
+
+/// ExponentialPriceCurve struct implementing CurveCalculator
+/// A is the "collateral" token (e.g. RLY), B is the "bonded" token (e.g. TAKI).
+/// The price of a single B token (a, denominated in amount of token A) is defined by
+/// `a = initial_token_a_price * (1 + growth_rate)^(b / growth_interval)`
+/// where b is the amount of token B that's been swapped out of this curve
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExponentialPriceCurve {
+    /// When there's 0 liquidity in the pool, what should the initial price point a0 defining the curve be?
+    /// i.e. what is the cost of 1 b token (denominated in A) when there's 0 liquidity
+    pub initial_token_a_price_numerator: u64,
+    /// When there's 0 liquidity in the pool, what should the initial price point a0 defining the curve be?
+    /// i.e. what is the cost of 1 b token (denominated in A) when there's 0 liquidity
+    pub initial_token_a_price_denominator: u64,
+    /// How much the price of token B grows by (as a fraction of the current price) every `growth_interval`
+    /// numerator
+    pub growth_rate_numerator: u32,
+    /// How much the price of token B grows by (as a fraction of the current price) every `growth_interval`
+    /// denominator
+    pub growth_rate_denominator: u32,
+    /// Amount of token B that has to be swapped out of the curve for the price to grow by the growth rate,
+    /// e.g. 10^decimals of token B for the price to grow by the growth rate for every whole token
+    pub growth_interval: u64,
+}
+
+/// Rounds an amount the user receives down (after subtracting the rounding margin)
+fn round_amount_out(amount: &DFSPreciseNumber, scale: &DFSPreciseNumber) -> Option<u128> {
+    let margin = amount
+        .checked_add(scale)?
+        .checked_div(&(DFSPreciseNumber::new(ROUNDING_MARGIN_DENOMINATOR)?))?;
+    let (amount, is_negative) = amount.unsigned_sub(&margin);
+    if is_negative {
+        return Some(0);
+    }
+    amount.floor()?.to_imprecise()
+}
+
+/// Rounds an amount the user pays up (after adding the rounding margin)
+fn round_amount_in(amount: &DFSPreciseNumber, scale: &DFSPreciseNumber) -> Option<u128> {
+    let margin = amount
+        .checked_add(scale)?
+        .checked_div(&(DFSPreciseNumber::new(ROUNDING_MARGIN_DENOMINATOR)?))?;
+    amount.checked_add(&margin)?.ceiling()?.to_imprecise()
+}
+
+/// Writing the price as `a = a0 * e^(g*b/u)` (where g = ln(1 + growth_rate) and u = growth_interval), the
+/// liquidity integral is `token_a_bonded = K * (e^(g*b/u) - 1)` with `K = a0*u/g`
+/// (integration constant is chosen so there's 0 token A bonded at b = 0)
+/// So `K + token_a_bonded = K * e^(g*b/u)`, which means that moving from b to b + db always multiplies
+/// `K + token_a_bonded` by `e^(g*db/u)`, regardless of where we are on the curve. All the swaps below just use
+/// that (and its inverse, `db = (u/g) * ln(new / old)`) so they only need a single exp or ln each
+///
+/// swap_a_to_b and swap_b_to_a are the key functions at the bottom
+impl ExponentialPriceCurve {
+    /// Returns g = ln(1 + growth_rate), i.e. the price at b is `a0 * e^(g*b/growth_interval)`
+    fn growth_exponent(&self) -> Option<DFSPreciseNumber> {
+        let growth_rate_denominator = u128::from(self.growth_rate_denominator);
+        DFSPreciseNumber::new(growth_rate_denominator + u128::from(self.growth_rate_numerator))?
+            .checked_div(&(DFSPreciseNumber::new(growth_rate_denominator)?))?
+            .ln()
+    }
+
+    /// Returns u/g, the amount of token B it takes for the price to grow by a factor of e
+    fn token_b_scale(&self, growth_exponent: &DFSPreciseNumber) -> Option<DFSPreciseNumber> {
+        DFSPreciseNumber::new(self.growth_interval.into())?.checked_div(growth_exponent)
+    }
+
+    /// Returns K = a0*u/g, i.e. `K + token_a_bonded` is the value that gets multiplied on every swap
+    fn token_a_scale(&self, growth_exponent: &DFSPreciseNumber) -> Option<DFSPreciseNumber> {
+        // make sure to do division last
+        DFSPreciseNumber::new(self.initial_token_a_price_numerator.into())?
+            .checked_mul(&(DFSPreciseNumber::new(self.growth_interval.into())?))?
+            .checked_div(
+                &(DFSPreciseNumber::new(self.initial_token_a_price_denominator.into())?
+                    .checked_mul(growth_exponent)?),
+            )
+    }
+
+    /// Returns the b value (amount of token B swapped out of the curve) given the amount of token A locked
+    /// b = (u/g) * ln((K + token_a_bonded) / K)
+    fn b_value_with_amt_a_locked(
+        &self,
+        token_a_amount: &DFSPreciseNumber,
+    ) -> Option<DFSPreciseNumber> {
+        let growth_exponent = self.growth_exponent()?;
+        let token_a_scale = self.token_a_scale(&growth_exponent)?;
+        token_a_scale
+            .checked_add(token_a_amount)?
+            .checked_div(&token_a_scale)?
+            .ln()?
+            .checked_mul(&(self.token_b_scale(&growth_exponent)?))
+    }
+
+    /// Swap's in user's collateral token and returns out the bonded token,
+    /// moving right on the price curve and increasing the price of the bonded token
+    fn swap_a_to_b(
+        &self,
+        source_amount: u128,      // amount of user's token a (collateral token)
+        swap_source_amount: u128, // swap's token a (collateral token)
+        swap_destination_amount: u128, // swap's remaining token b (bonded token)
+    ) -> Option<(u128, u128)> {
+        // if source_amount is enough to buy all the remaining B, only take what's needed for that
+        // no need to return None here if the exact out calculation fails (it overflows for amounts too large
+        // to ever be paid for), can just skip this check and do real calculation below
+        if let Some((maximum_a_remaining, _)) = self.swap_a_to_b_exact_out(
+            swap_destination_amount,
+            swap_source_amount,
+            swap_destination_amount,
+        ) {
+            if maximum_a_remaining <= source_amount {
+                return Some((maximum_a_remaining, swap_destination_amount));
+            }
+        }
+
+        let growth_exponent = self.growth_exponent()?;
+        let token_b_scale = self.token_b_scale(&growth_exponent)?;
+        let a_start = self
+            .token_a_scale(&growth_exponent)?
+            .checked_add(&(DFSPreciseNumber::new(swap_source_amount)?))?;
+
+        // db = (u/g) * ln(1 + da / (K + a_start))
+        let b_difference = DFSPreciseNumber::new(source_amount)?
+            .checked_div(&a_start)?
+            .checked_add(&(DFSPreciseNumber::new(1)?))?
+            .ln()?
+            .checked_mul(&token_b_scale)?;
+        let destination_amount = round_amount_out(&b_difference, &token_b_scale)?;
+
+        Some((source_amount, destination_amount))
+    }
+
+    fn swap_b_to_a(
+        &self,
+        source_amount: u128,
+        _swap_source_amount: u128,
+        swap_destination_amount: u128,
+    ) -> Option<(u128, u128)> {
+        let growth_exponent = self.growth_exponent()?;
+        let token_b_scale = self.token_b_scale(&growth_exponent)?;
+        let token_a_scale = self.token_a_scale(&growth_exponent)?;
+        let a_start =
+            token_a_scale.checked_add(&(DFSPreciseNumber::new(swap_destination_amount)?))?;
+
+        // if the user put in more B tokens than it takes to get back down to b = 0, then just give them all of
+        // the a tokens (swap_destination_amount) and only take the B tokens required to get down to 0
+        let b_start = a_start
+            .checked_div(&token_a_scale)?
+            .ln()?
+            .checked_mul(&token_b_scale)?;
+        let maximum_b_remaining = round_amount_in(&b_start, &token_b_scale)?;
+        if source_amount >= maximum_b_remaining {
+            return Some((maximum_b_remaining, swap_destination_amount));
+        }
+
+        // da = (K + a_start) * (1 - e^(-g*db/u)) = (K + a_start) * (e^(g*db/u) - 1) / e^(g*db/u)
+        let growth = DFSPreciseNumber::new(source_amount)?
+            .checked_div(&token_b_scale)?
+            .exp()?;
+        let a_difference = a_start
+            .checked_mul(&(growth.checked_sub(&(DFSPreciseNumber::new(1)?))?))?
+            .checked_div(&growth)?;
+        let destination_amount = round_amount_out(&a_difference, &a_start)?;
+
+        Some((source_amount, destination_amount))
+    }
+
+    /// Inverse of swap_a_to_b: returns the amount of token A the user has to put in to get exactly
+    /// `destination_amount` of token B out
+    fn swap_a_to_b_exact_out(
+        &self,
+        destination_amount: u128, // amount of token b (bonded token) the user wants out
+        swap_source_amount: u128, // swap's token a (collateral token)
+        swap_destination_amount: u128, // swap's remaining token b (bonded token)
+    ) -> Option<(u128, u128)> {
+        // unlike swap_a_to_b we can't just hand out whatever's left, the user asked for an exact amount
+        if destination_amount > swap_destination_amount {
+            return None;
+        }
+
+        let growth_exponent = self.growth_exponent()?;
+        let a_start = self
+            .token_a_scale(&growth_exponent)?
+            .checked_add(&(DFSPreciseNumber::new(swap_source_amount)?))?;
+
+        // da = (K + a_start) * (e^(g*db/u) - 1)
+        let growth = DFSPreciseNumber::new(destination_amount)?
+            .checked_div(&(self.token_b_scale(&growth_exponent)?))?
+            .exp()?;
+        let a_difference =
+            a_start.checked_mul(&(growth.checked_sub(&(DFSPreciseNumber::new(1)?))?))?;
+        let source_amount = round_amount_in(&a_difference, &a_start)?;
+
+        Some((source_amount, destination_amount))
+    }
+
+    /// Inverse of swap_b_to_a: returns the amount of token B the user has to put in to get exactly
+    /// `destination_amount` of token A out
+    fn swap_b_to_a_exact_out(
+        &self,
+        destination_amount: u128,
+        _swap_source_amount: u128,
+        swap_destination_amount: u128,
+    ) -> Option<(u128, u128)> {
+        // a_end can't be negative, there's only swap_destination_amount of token A bonded to the curve
+        let a_end = swap_destination_amount.checked_sub(destination_amount)?;
+
+        let growth_exponent = self.growth_exponent()?;
+        let token_b_scale = self.token_b_scale(&growth_exponent)?;
+        let token_a_scale = self.token_a_scale(&growth_exponent)?;
+
+        // db = (u/g) * ln((K + a_start) / (K + a_end))
+        let b_difference = token_a_scale
+            .checked_add(&(DFSPreciseNumber::new(swap_destination_amount)?))?
+            .checked_div(&(token_a_scale.checked_add(&(DFSPreciseNumber::new(a_end)?))?))?
+            .ln()?
+            .checked_mul(&token_b_scale)?;
+        let source_amount = round_amount_in(&b_difference, &token_b_scale)?;
+
+        Some((source_amount, destination_amount))
+    }
+}
+
+/// Returns None if any of the parameters are 0 or the growth rate is too small to be represented with
+/// PreciseNumber
+fn is_curve_param_valid(curve: &ExponentialPriceCurve) -> Option<()> {
+    if curve.initial_token_a_price_numerator == 0
+        || curve.initial_token_a_price_denominator == 0
+        || curve.growth_rate_numerator == 0
+        || curve.growth_rate_denominator == 0
+        || curve.growth_interval == 0
+    {
+        return None;
+    };
+
+    // since PreciseNumber only has 18 decimals, ln(1 + growth_rate) can round to 0 for tiny growth rates
+    let growth_exponent = curve.growth_exponent()?;
+    if growth_exponent.value.is_zero() {
+        return None;
+    }
+    match curve.token_a_scale(&growth_exponent)?.value.is_zero() {
+        true => None,
+        false => Some(()),
+    }
+}
+
+impl CurveCalculator for ExponentialPriceCurve {
+    /// Calculate how much destination token will be provided given an amount
+    /// of source token.
+    fn swap_without_fees(
+        &self,
+        source_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<SwapWithoutFeesResult> {
+        let (source_amount_swapped, destination_amount_swapped) = match trade_direction {
+            TradeDirection::AtoB => {
+                self.swap_a_to_b(source_amount, swap_source_amount, swap_destination_amount)?
+            }
+            TradeDirection::BtoA => {
+                self.swap_b_to_a(source_amount, swap_source_amount, swap_destination_amount)?
+            }
+        };
+        let source_amount_swapped = map_zero_to_none(source_amount_swapped)?;
+        let destination_amount_swapped = map_zero_to_none(destination_amount_swapped)?;
+        Some(SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped,
+        })
+    }
+
+    /// Calculate how much source token is required to get exactly `destination_amount` of
+    /// destination token out (rounded up in favor of the swap)
+    fn swap_without_fees_exact_out(
+        &self,
+        destination_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<SwapWithoutFeesResult> {
+        let (source_amount_swapped, destination_amount_swapped) = match trade_direction {
+            TradeDirection::AtoB => self.swap_a_to_b_exact_out(
+                destination_amount,
+                swap_source_amount,
+                swap_destination_amount,
+            )?,
+            TradeDirection::BtoA => self.swap_b_to_a_exact_out(
+                destination_amount,
+                swap_source_amount,
+                swap_destination_amount,
+            )?,
+        };
+        let source_amount_swapped = map_zero_to_none(source_amount_swapped)?;
+        let destination_amount_swapped = map_zero_to_none(destination_amount_swapped)?;
+        Some(SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped,
+        })
+    }
+
+    /// Deposits and withdrawals are disabled, same as the linear price curve
+    fn pool_tokens_to_trading_tokens(
+        &self,
+        _pool_tokens: u128,
+        _pool_token_supply: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _round_direction: RoundDirection,
+    ) -> Option<TradingTokenResult> {
+        // this causes a panic if withdraw_all_token_types is called but that's ok for now, cheap way of
+        // disabling withdrawals without having to change how SwapCurve works
+        None
+    }
+
+    /// Deposits are disabled, same as the linear price curve
+    fn deposit_single_token_type(
+        &self,
+        _source_amount: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _pool_supply: u128,
+        _trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        // this never gets called since allows_withdrawals is false (would panic otherwise so still safe)
+        None
+    }
+
+    /// Withdrawals are disabled, and fees are paid out in trading tokens instead of being converted into
+    /// pool tokens (see `collects_fees_in_trading_tokens`)
+    fn withdraw_single_token_type_exact_out(
+        &self,
+        _source_amount: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _pool_supply: u128,
+        _trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        // this causes a panic if SwapCurve.withdraw_single_token_type_exact_out instruction is called
+        // but that's ok for now, cheap way of disabling withdrawals without having to change how SwapCurve works
+        None
+    }
+
+    /// Validate that the given curve has no invalid parameters
+    /// Called on `initialize` - initial price, growth rate and growth interval must all be positive
+    fn validate(&self) -> Result<(), SwapError> {
+        match is_curve_param_valid(self) {
+            Some(_val) => Ok(()),
+            None => Err(SwapError::InvalidCurve),
+        }
+    }
+
+    /// Validate the given supply on initialization.
+    /// We require at least some bonded token B for the curve to be useful (collateral token must be 0)
+    fn validate_supply(&self, token_a_amount: u64, token_b_amount: u64) -> Result<(), SwapError> {
+        if token_b_amount == 0 {
+            return Err(SwapError::EmptySupply);
+        }
+
+        if token_a_amount != 0 {
+            return Err(SwapError::InvalidSupply);
+        }
+        Ok(())
+    }
+
+    /// Both deposits and withdrawals are intentionally disabled
+    fn allows_deposits(&self) -> bool {
+        false
+    }
+
+    /// The curve position is derived from the amount of token A in the swap, so fees can't be left in the
+    /// swap token accounts (they'd look like bonded collateral and move the price)
+    fn collects_fees_in_trading_tokens(&self) -> bool {
+        true
+    }
+
+    /// Since `K + token_a_bonded = K * e^(g*b/u)`, the spot price `a0 * e^(g*b/u)` simplifies to
+    /// `a0 + token_a_bonded * g/u`, so no exp is needed. Returned with a denominator of `dfs_precise_number::ONE`
+    fn spot_price(
+        &self,
+        swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+    ) -> Option<(u128, u128)> {
+        let a0 = DFSPreciseNumber::new(self.initial_token_a_price_numerator.into())?.checked_div(
+            &(DFSPreciseNumber::new(self.initial_token_a_price_denominator.into())?),
+        )?;
+        let growth = DFSPreciseNumber::new(swap_token_a_amount)?
+            .checked_mul(&(self.growth_exponent()?))?
+            .checked_div(&(DFSPreciseNumber::new(self.growth_interval.into())?))?;
+        let price = a0.checked_add(&growth)?;
+
+        // the raw DFSPreciseNumber value is the numerator over ONE, as long as it fits in a u128
+        if price.value.bits() > 128 {
+            return None;
+        }
+        Some((price.value.as_u128(), ONE))
+    }
+
+    /// The total normalized value of the exponential price curve adds the total
+    /// value of the token A side (as denominated in token B) to the token B side.
+    fn normalized_value(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<spl_math::precise_number::PreciseNumber> {
+        let b_value_of_a =
+            self.b_value_with_amt_a_locked(&(DFSPreciseNumber::new(swap_token_a_amount)?))?;
+        let total_value =
+            b_value_of_a.checked_add(&(DFSPreciseNumber::new(swap_token_b_amount)?))?;
+
+        // same as the linear price curve, truncate to 32 bits so that errors from ln (which are rounded in
+        // favor of the swap when actually swapping) don't show up as a decrease in value
+        let value_bits = total_value.value.bits();
+        let truncated_value = match value_bits > 32 {
+            true => total_value.value >> (value_bits - 32),
+            false => total_value.value,
+        };
+
+        Some(spl_math::precise_number::PreciseNumber {
+            value: truncated_value,
+        })
+    }
+}
+
+/// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
+impl IsInitialized for ExponentialPriceCurve {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+impl Sealed for ExponentialPriceCurve {}
+impl Pack for ExponentialPriceCurve {
+    const LEN: usize = 32;
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        (self as &dyn DynPack).pack_into_slice(output);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<ExponentialPriceCurve, ProgramError> {
+        let initial_token_a_price_numerator = array_ref![input, 0, 8];
+        let initial_token_a_price_denominator = array_ref![input, 8, 8];
+        let growth_rate_numerator = array_ref![input, 16, 4];
+        let growth_rate_denominator = array_ref![input, 20, 4];
+        let growth_interval = array_ref![input, 24, 8];
+        Ok(Self {
+            initial_token_a_price_numerator: u64::from_le_bytes(*initial_token_a_price_numerator),
+            initial_token_a_price_denominator: u64::from_le_bytes(
+                *initial_token_a_price_denominator,
+            ),
+            growth_rate_numerator: u32::from_le_bytes(*growth_rate_numerator),
+            growth_rate_denominator: u32::from_le_bytes(*growth_rate_denominator),
+            growth_interval: u64::from_le_bytes(*growth_interval),
+        })
+    }
+}
+
+impl DynPack for ExponentialPriceCurve {
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let initial_token_a_price = array_mut_ref![output, 0, 8];
+        *initial_token_a_price = self.initial_token_a_price_numerator.to_le_bytes();
+        let initial_token_a_price = array_mut_ref![output, 8, 8];
+        *initial_token_a_price = self.initial_token_a_price_denominator.to_le_bytes();
+        let growth_rate_numerator = array_mut_ref![output, 16, 4];
+        *growth_rate_numerator = self.growth_rate_numerator.to_le_bytes();
+        let growth_rate_denominator = array_mut_ref![output, 20, 4];
+        *growth_rate_denominator = self.growth_rate_denominator.to_le_bytes();
+        let growth_interval = array_mut_ref![output, 24, 8];
+        *growth_interval = self.growth_interval.to_le_bytes();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::calculator::test::check_curve_value_from_swap;
+    use proptest::prelude::*;
+
+    /// Price starts at 50 A per B and grows by 1% every 100 B
+    const TEST_CURVE: ExponentialPriceCurve = ExponentialPriceCurve {
+        initial_token_a_price_numerator: 50,
+        initial_token_a_price_denominator: 1,
+        growth_rate_numerator: 1,
+        growth_rate_denominator: 100,
+        growth_interval: 100,
+    };
+
+    #[test]
+    fn swap_a_to_b_basic() {
+        let curve = TEST_CURVE;
+
+        // put in 5000 A, should get 99.5 B out (rounded down to 99)
+        let (source_amount, destination_amount) = curve.swap_a_to_b(5000, 0, 5000).unwrap();
+        assert_eq!(source_amount, 5000);
+        assert_eq!(destination_amount, 99);
+
+        // put in 5000 more A, price has gone up so should get 98.5 B out (rounded down to 98)
+        let (source_amount, destination_amount) = curve.swap_a_to_b(5000, 5000, 4901).unwrap();
+        assert_eq!(source_amount, 5000);
+        assert_eq!(destination_amount, 98);
+
+        // putting in 100K A @ 0 A locked/100 B remaining should give out the last 100 B and only take the
+        // 5024.96 A that they cost (rounded up to 5025)
+        let (source_amount, destination_amount) = curve.swap_a_to_b(100_000, 0, 100).unwrap();
+        assert_eq!(source_amount, 5025);
+        assert_eq!(destination_amount, 100);
+
+        // B with 9 decimals, price starts at 0.5 base A per base B and grows 10% every whole B token
+        let curve = ExponentialPriceCurve {
+            initial_token_a_price_numerator: 1,
+            initial_token_a_price_denominator: 2,
+            growth_rate_numerator: 1,
+            growth_rate_denominator: 10,
+            growth_interval: 1_000_000_000,
+        };
+
+        // the first 10 whole B tokens cost 0.5e9 * (1.1^10 - 1) / ln(1.1) = 8_360_819_711.87 A
+        let (source_amount, destination_amount) = curve
+            .swap_a_to_b(8_360_819_712, 0, 1_000_000_000_000)
+            .unwrap();
+        assert_eq!(source_amount, 8_360_819_712);
+        assert_eq!(destination_amount, 10_000_000_000);
+    }
+
+    #[test]
+    fn swap_b_to_a_basic() {
+        let curve = TEST_CURVE;
+
+        // pretty much the opposite cases as above
+
+        // put in 99 B at 5000 A, should get 4974.7 A out (rounded down to 4974)
+        let (source_amount, destination_amount) = curve.swap_b_to_a(99, 4901, 5000).unwrap();
+        assert_eq!(source_amount, 99);
+        assert_eq!(destination_amount, 4974);
+
+        // put in 200 B at 5000 A, should get all 5000 A out and only take the 99.5 B it took to get there
+        // (rounded up to 100)
+        let (source_amount, destination_amount) = curve.swap_b_to_a(200, 4901, 5000).unwrap();
+        assert_eq!(source_amount, 100);
+        assert_eq!(destination_amount, 5000);
+
+        // nothing to get out of an empty curve
+        assert!(curve
+            .swap_without_fees(200, 5000, 0, TradeDirection::BtoA)
+            .is_none());
+    }
+
+    #[test]
+    fn swap_exact_out_basic() {
+        let curve = TEST_CURVE;
+
+        // the first 100 B cost 5024.96 A, rounded up to 5025
+        let (source_amount, destination_amount) =
+            curve.swap_a_to_b_exact_out(100, 0, 5000).unwrap();
+        assert_eq!(source_amount, 5025);
+        assert_eq!(destination_amount, 100);
+
+        // can't get out more B than the swap has
+        assert!(curve.swap_a_to_b_exact_out(101, 0, 100).is_none());
+
+        // getting 2500 A out @ 5000 A takes 49.63 B, rounded up to 50
+        let (source_amount, destination_amount) =
+            curve.swap_b_to_a_exact_out(2500, 4901, 5000).unwrap();
+        assert_eq!(source_amount, 50);
+        assert_eq!(destination_amount, 2500);
+
+        // can't get out more A than the swap has
+        assert!(curve.swap_b_to_a_exact_out(5001, 4901, 5000).is_none());
+    }
+
+    #[test]
+    fn spot_price_basic() {
+        let curve = TEST_CURVE;
+
+        // initial price
+        assert_eq!(curve.spot_price(0, 5000), Some((50 * ONE, ONE)));
+
+        // after 5000 A is bonded (99.5 B out) the price is 50 * 1.01^0.995 = 50.497516542658404142
+        // (ln(1.01) is only accurate to 18 decimals, so after multiplying by 5000 this is accurate to ~14)
+        let (numerator, denominator) = curve.spot_price(5000, 0).unwrap();
+        assert_eq!(denominator, ONE);
+        let expected = 50_497516542658404142;
+        assert!(numerator.max(expected) - numerator.min(expected) <= 10_000);
+    }
+
+    #[test]
+    fn swap_large_amounts() {
+        let curve = TEST_CURVE;
+
+        // the price grows way past u64::MAX before even 1 B of u64::MAX B is sold, shouldn't panic
+        let (source_amount, destination_amount) = curve
+            .swap_a_to_b(u64::MAX.into(), 0, u64::MAX.into())
+            .unwrap();
+        assert_eq!(source_amount, u64::MAX.into());
+        assert!(destination_amount < 500_000);
+        assert!(curve
+            .swap_a_to_b_exact_out(u64::MAX.into(), 0, u64::MAX.into())
+            .is_none());
+
+        // selling it all back shouldn't give out more than was put in
+        let (_, amount_a_back) = curve
+            .swap_b_to_a(
+                destination_amount,
+                u128::from(u64::MAX) - destination_amount,
+                u64::MAX.into(),
+            )
+            .unwrap();
+        assert!(amount_a_back <= u64::MAX.into());
+    }
+
+    #[test]
+    fn pack_curve() {
+        let curve = ExponentialPriceCurve {
+            initial_token_a_price_numerator: u64::MAX,
+            initial_token_a_price_denominator: u64::MAX - 1,
+            growth_rate_numerator: u32::MAX,
+            growth_rate_denominator: u32::MAX - 1,
+            growth_interval: 1_000_000_000,
+        };
+
+        let mut packed = [0u8; ExponentialPriceCurve::LEN];
+        Pack::pack_into_slice(&curve, &mut packed[..]);
+        let unpacked = ExponentialPriceCurve::unpack(&packed).unwrap();
+        assert_eq!(curve, unpacked);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&curve.initial_token_a_price_numerator.to_le_bytes());
+        packed.extend_from_slice(&curve.initial_token_a_price_denominator.to_le_bytes());
+        packed.extend_from_slice(&curve.growth_rate_numerator.to_le_bytes());
+        packed.extend_from_slice(&curve.growth_rate_denominator.to_le_bytes());
+        packed.extend_from_slice(&curve.growth_interval.to_le_bytes());
+        let unpacked = ExponentialPriceCurve::unpack(&packed).unwrap();
+        assert_eq!(curve, unpacked);
+    }
+
+    #[test]
+    fn validate_curve_params() {
+        assert!(TEST_CURVE.validate().is_ok());
+
+        // smallest possible growth rate still works
+        let curve = ExponentialPriceCurve {
+            growth_rate_numerator: 1,
+            growth_rate_denominator: u32::MAX,
+            ..TEST_CURVE
+        };
+        assert!(curve.validate().is_ok());
+
+        for curve in [
+            ExponentialPriceCurve {
+                initial_token_a_price_numerator: 0,
+                ..TEST_CURVE
+            },
+            ExponentialPriceCurve {
+                initial_token_a_price_denominator: 0,
+                ..TEST_CURVE
+            },
+            ExponentialPriceCurve {
+                growth_rate_numerator: 0,
+                ..TEST_CURVE
+            },
+            ExponentialPriceCurve {
+                growth_rate_denominator: 0,
+                ..TEST_CURVE
+            },
+            ExponentialPriceCurve {
+                growth_interval: 0,
+                ..TEST_CURVE
+            },
+        ] {
+            assert_eq!(curve.validate(), Err(SwapError::InvalidCurve));
+        }
+    }
+
+    #[test]
+    fn swap_round_trip_does_not_give_free_tokens() {
+        let curve = TEST_CURVE;
+
+        // buying and immediately selling back should never return more A than was put in
+        let mut swap_token_a_amount = 0u128;
+        let mut swap_token_b_amount = 1_000_000u128;
+        for amount_a in [1u128, 49, 50, 51, 1_000, 123_456, 10_000_000] {
+            let (source_amount, destination_amount) =
+                match curve.swap_a_to_b(amount_a, swap_token_a_amount, swap_token_b_amount) {
+                    Some((_, 0)) | None => continue,
+                    Some(result) => result,
+                };
+            let (_, amount_a_back) = curve
+                .swap_b_to_a(
+                    destination_amount,
+                    swap_token_b_amount - destination_amount,
+                    swap_token_a_amount + source_amount,
+                )
+                .unwrap();
+            assert!(amount_a_back <= source_amount);
+
+            // leave the first trade in the swap so the next one starts further up the curve
+            swap_token_a_amount += source_amount;
+            swap_token_b_amount -= destination_amount;
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_swap_a_to_b(
+            // how much a user is swapping in
+            source_token_amount in 1..u64::MAX,
+            // how much a is already in swap (determines spot price)
+            swap_source_amount in 0..u64::MAX,
+        ) {
+            // B with 9 decimals, price starts at 0.001 base A per base B and grows 1% per whole token
+            let curve = ExponentialPriceCurve {
+                initial_token_a_price_numerator: 1,
+                initial_token_a_price_denominator: 1_000,
+                growth_rate_numerator: 1,
+                growth_rate_denominator: 100,
+                growth_interval: 1_000_000_000,
+            };
+
+            let (_source_amount_swapped, destination_amount_swapped) = curve
+                .swap_a_to_b(
+                    source_token_amount as u128,
+                    swap_source_amount as u128,
+                    u64::MAX as u128,
+                )
+                .unwrap();
+
+            // ignore the trades where not enough source_token_amount was put in to get any b out
+            if destination_amount_swapped > 0 {
+                check_curve_value_from_swap(
+                    &curve,
+                    source_token_amount as u128,
+                    swap_source_amount as u128,
+                    // swap_destination_amount (the amount of token b in the swap) doesn't affect any of the math
+                    u64::MAX as u128,
+                    TradeDirection::AtoB
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_swap_b_to_a(
+            // how much b user is swapping in
+            source_token_amount in 1..u64::MAX,
+            // how much a is already in swap (determines spot price)
+            swap_destination_amount in 1..u64::MAX,
+        ) {
+            // same curve as above
+            let curve = ExponentialPriceCurve {
+                initial_token_a_price_numerator: 1,
+                initial_token_a_price_denominator: 1_000,
+                growth_rate_numerator: 1,
+                growth_rate_denominator: 100,
+                growth_interval: 1_000_000_000,
+            };
+
+            let (_source_amount_swapped, destination_amount_swapped) = curve
+                .swap_b_to_a(
+                    source_token_amount as u128,
+                    u64::MAX as u128,
+                    swap_destination_amount as u128,
+                )
+                .unwrap();
+
+            // ignore the trades where not enough source_token_amount was put in to get any a out
+            if destination_amount_swapped > 0 {
+                check_curve_value_from_swap(
+                    &curve,
+                    source_token_amount as u128,
+                    // swap_source_amount (the amount of token b in the swap) doesn't affect any of the math
+                    u64::MAX as u128,
+                    swap_destination_amount as u128,
+                    TradeDirection::BtoA
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn swap_round_trip_does_not_decrease_curve_value(
+            // how much a user is swapping in
+            source_token_amount in 1..u64::MAX,
+            // how much a is already in swap (determines spot price)
+            swap_token_a_amount in 0..u64::MAX,
+        ) {
+            let curve = TEST_CURVE;
+            let swap_token_b_amount = u64::MAX as u128;
+
+            if let Some((source_amount, destination_amount)) = curve.swap_a_to_b(
+                source_token_amount as u128,
+                swap_token_a_amount as u128,
+                swap_token_b_amount,
+            ) {
+                if destination_amount > 0 {
+                    let (_, amount_a_back) = curve
+                        .swap_b_to_a(
+                            destination_amount,
+                            swap_token_b_amount - destination_amount,
+                            swap_token_a_amount as u128 + source_amount,
+                        )
+                        .unwrap();
+                    assert!(amount_a_back <= source_amount);
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 //! All fee information, to be used for validation currently
 
 use crate::error::SwapError;
+use anchor_lang::prelude::{borsh, AnchorDeserialize, AnchorSerialize};
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use solana_program::{
     program_error::ProgramError,
@@ -9,7 +10,7 @@ use solana_program::{
 use std::convert::TryFrom;
 
 /// Encapsulates all fee information and calculations for swap operations
-#[derive(Clone, Debug, Default, PartialEq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
 pub struct Fees {
     /// Trade fees are extra token amounts that are held inside the token
     /// accounts during a trade, making the value of liquidity tokens rise.
@@ -61,6 +62,16 @@ pub fn calculate_fee(
     }
 }
 
+/// Helper function for turning a fee into a fraction usable in calculations,
+/// since a zero fee is allowed to have a zero denominator
+fn fee_fraction(fee_numerator: u64, fee_denominator: u64) -> (u128, u128) {
+    if fee_numerator == 0 {
+        (0, 1)
+    } else {
+        (u128::from(fee_numerator), u128::from(fee_denominator))
+    }
+}
+
 fn validate_fraction(numerator: u64, denominator: u64) -> Result<(), SwapError> {
     if denominator == 0 && numerator == 0 {
         Ok(())
@@ -99,6 +110,44 @@ impl Fees {
         )
     }
 
+    /// Calculate the amount of trading tokens that must be provided so that at
+    /// least `post_fee_amount` is left after the trading and owner trading fees
+    /// are taken out, used for exact output swaps
+    pub fn pre_trading_fee_amount(&self, post_fee_amount: u128) -> Option<u128> {
+        if post_fee_amount == 0 {
+            return Some(0);
+        }
+        let (trade_fee_numerator, trade_fee_denominator) =
+            fee_fraction(self.trade_fee_numerator, self.trade_fee_denominator);
+        let (owner_trade_fee_numerator, owner_trade_fee_denominator) = fee_fraction(
+            self.owner_trade_fee_numerator,
+            self.owner_trade_fee_denominator,
+        );
+        let fee_numerator = trade_fee_numerator
+            .checked_mul(owner_trade_fee_denominator)?
+            .checked_add(owner_trade_fee_numerator.checked_mul(trade_fee_denominator)?)?;
+        let fee_denominator = trade_fee_denominator.checked_mul(owner_trade_fee_denominator)?;
+
+        // post_fee_amount = pre_fee_amount * (1 - fee), rounded up
+        let remaining_denominator = fee_denominator.checked_sub(fee_numerator)?;
+        let mut pre_fee_amount = post_fee_amount
+            .checked_mul(fee_denominator)?
+            .checked_add(remaining_denominator.checked_sub(1)?)?
+            .checked_div(remaining_denominator)?;
+
+        // each fee is calculated separately (with a minimum of one token), so the
+        // estimate above can come up a token or two short
+        loop {
+            let total_fees = self
+                .trading_fee(pre_fee_amount)?
+                .checked_add(self.owner_trading_fee(pre_fee_amount)?)?;
+            if pre_fee_amount.checked_sub(total_fees)? >= post_fee_amount {
+                return Some(pre_fee_amount);
+            }
+            pre_fee_amount = pre_fee_amount.checked_add(1)?;
+        }
+    }
+
     /// Calculate the host fee based on the owner fee, only used in production
     /// situations where a program is hosted by multiple frontends
     pub fn host_fee(&self, owner_fee: u128) -> Option<u128> {
@@ -225,4 +274,40 @@ mod tests {
         let unpacked = Fees::unpack_from_slice(&packed).unwrap();
         assert_eq!(fees, unpacked);
     }
+
+    #[test]
+    fn pre_trading_fee_amount() {
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 100,
+            owner_trade_fee_numerator: 2,
+            owner_trade_fee_denominator: 100,
+            ..Fees::default()
+        };
+        assert_eq!(fees.pre_trading_fee_amount(0).unwrap(), 0);
+        // 3% of 100 is exactly 3
+        assert_eq!(fees.pre_trading_fee_amount(97).unwrap(), 100);
+        // minimum fee of one token per fee
+        assert_eq!(fees.pre_trading_fee_amount(1).unwrap(), 3);
+        for post_fee_amount in 1..10_000 {
+            let pre_fee_amount = fees.pre_trading_fee_amount(post_fee_amount).unwrap();
+            let total_fees = fees.trading_fee(pre_fee_amount).unwrap()
+                + fees.owner_trading_fee(pre_fee_amount).unwrap();
+            assert!(pre_fee_amount - total_fees >= post_fee_amount);
+        }
+
+        // zero fees can have a zero denominator
+        let fees = Fees::default();
+        assert_eq!(fees.pre_trading_fee_amount(97).unwrap(), 97);
+
+        // total fees of 100% can't be paid
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 2,
+            owner_trade_fee_numerator: 1,
+            owner_trade_fee_denominator: 2,
+            ..Fees::default()
+        };
+        assert_eq!(fees.pre_trading_fee_amount(97), None);
+    }
 }
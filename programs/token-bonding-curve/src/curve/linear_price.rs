@@ -1,10 +1,15 @@
 //! Linear price swap curve, slope and initial price point set at init
 //! Currently this (especially `swap`) only works under the following assumptions:
 //! Deposits (except the initial deposit) are disabled
-//! The initial deposit should only have token B (the bonded token) and 0 token A (the collateral token)
-//! This curve only works with fees set to 0 (process_swap will panic otherwise)
-//! Withdrawals are disabled (maybe we can add in a check to enable it in emergencies?), will panic if those
-//! instructions are called
+//! The initial deposit should have token B (the bonded token) and exactly `initial_token_a_amount` of token A (the
+//! collateral token), which is 0 unless the curve is seeded to start part-way along the price schedule
+//! Trade and owner fees are taken in the source token (A on buys, B on sells) and either sent straight to a fee
+//! token account or, when the swap accrues fees (see `SwapV2::accrue_fees`), left in the swap's own token accounts
+//! and set aside for `claim_fees`. Either way they're never counted as swap liquidity and never move the price
+//! (see `collects_fees_in_trading_tokens`)
+//! Withdrawals are disabled, will panic if those instructions are called. If the curve ever needs to be shut down
+//! the admin can enable emergency mode instead, which lets token B holders redeem their share of the bonded token A
+//! (see `Processor::process_emergency_redeem`)
 
 use {
     crate::{
@@ -12,7 +17,7 @@ use {
             map_zero_to_none, CurveCalculator, DynPack, RoundDirection, SwapWithoutFeesResult,
             TradeDirection, TradingTokenResult,
         },
-        dfs_precise_number::DFSPreciseNumber,
+        dfs_precise_number::{DFSPreciseNumber, ONE},
         error::SwapError,
     },
     arrayref::{array_mut_ref, array_ref},
@@ -39,6 +44,11 @@ pub struct LinearPriceCurve {
     /// When there's 0 liquidity in the pool, what should the initial price point a0 defining the curve be?
     /// i.e. what is the cost of 1 b token (denominated in A) when there's 0 liquidity
     pub initial_token_a_price_denominator: u64,
+    /// Amount of token A (collateral) the curve is seeded with on initialization, 0 for a curve that starts at b = 0
+    /// A seeded curve starts part-way along the price schedule, at the b value b0 where
+    /// `initial_token_a_amount = 0.5m*b0^2 + a0*b0` is locked. The seeded collateral is the curve's floor, selling
+    /// can never bring the amount of token A in the swap (or b) below it
+    pub initial_token_a_amount: u64,
 }
 
 /// Returns the positive root of x given lhs = k*x^2 + e*x, i.e.
@@ -47,7 +57,7 @@ pub struct LinearPriceCurve {
 /// (We're using k/e/c instead of a/b/c to not clash with token a/b names)
 /// Since e is always positive and c is always negative, the quadratic will always have one positive
 /// and one negative root, just return the positive one
-fn solve_quadratic_positive_root(
+pub fn solve_quadratic_positive_root(
     k_numerator: &DFSPreciseNumber,
     k_denominator: &DFSPreciseNumber,
     e_value_numerator: &DFSPreciseNumber,
@@ -194,8 +204,9 @@ impl LinearPriceCurve {
         swap_destination_amount: u128, // swap's remaining token b (bonded token)
     ) -> Option<(u128, u128)> {
         // use swap_source_amount (collateral token) to determine where we are on the integration curve
-        // note this only works if non-init deposits are disabled (and maybe if the initial deposit didn't have any token A in it?),
-        // otherwise there could be some A token in the pool that isn't part of the bonding curve
+        // this is the bonded amount stored in the swap rather than the token A account's balance, so any token A sent
+        // straight to the pool doesn't move the price (the seeded initial_token_a_amount is bonded, it's just the A
+        // locked at b0)
 
         // quadratic formula version:
         let a_start = DFSPreciseNumber::new(swap_source_amount)?;
@@ -227,6 +238,18 @@ impl LinearPriceCurve {
         Some((source_amount, destination_amount))
     }
 
+    /// Returns the b value the curve can't be sold below, i.e. where the seeded `initial_token_a_amount` is locked
+    /// (rounded down, so that the amount of token B it takes to get down to the floor is over-estimated)
+    fn b_value_floor(&self) -> Option<DFSPreciseNumber> {
+        match self.initial_token_a_amount {
+            0 => DFSPreciseNumber::new(0),
+            initial_token_a_amount => self.b_value_with_amt_a_locked_quadratic(
+                &(DFSPreciseNumber::new(initial_token_a_amount.into())?),
+                false,
+            ),
+        }
+    }
+
     fn swap_b_to_a(
         &self,
         source_amount: u128,
@@ -234,8 +257,10 @@ impl LinearPriceCurve {
         swap_destination_amount: u128,
     ) -> Option<(u128, u128)> {
         // use swap_destination_amount (collateral token) to determine where we are on the integration curve
-        // note this only works if non-init deposits are disabled (and maybe if the initial deposit didn't have any token A in it?),
-        // otherwise there could be some A token in the pool that isn't part of the bonding curve
+        // (the bonded amount stored in the swap, see swap_a_to_b)
+
+        // the seeded collateral can never be swapped out
+        let initial_token_a_amount = u128::from(self.initial_token_a_amount);
 
         // make sure we round up here so that b_end and a_end are also over-estimated, which rounds down the final
         // token a output
@@ -243,6 +268,7 @@ impl LinearPriceCurve {
             &(DFSPreciseNumber::new(swap_destination_amount)?),
             true,
         )?;
+        let b_floor = self.b_value_floor()?;
 
         // b_end can be negative if the user put in too many B tokens (handled below)
         let (b_end, b_end_is_negative) =
@@ -255,17 +281,28 @@ impl LinearPriceCurve {
         // due to rounding)
         let b_end = b_end.ceiling()?;
 
-        // if b_end < 0 (i.e. there aren't enough A tokens in the swap for all the B tokens they put in),
-        // then just give them all of the a tokens (swap_destination_amount) and only take the B tokens required to
-        // get down from b_start to 0. this only works if we assume 0 A locked at b = 0
-        if b_end_is_negative {
-            return Some((b_start.to_imprecise()?, swap_destination_amount));
+        // if b_end < b_floor (i.e. there aren't enough unseeded A tokens in the swap for all the B tokens they put
+        // in), then just give them all of the a tokens above the floor (swap_destination_amount -
+        // initial_token_a_amount) and only take the B tokens required to get down from b_start to b_floor
+        if b_end_is_negative || b_end.less_than(&b_floor) {
+            return Some((
+                b_start.checked_sub(&b_floor)?.to_imprecise()?,
+                swap_destination_amount.checked_sub(initial_token_a_amount)?,
+            ));
         }
 
         // otherwise if there's enough A tokens locked in swap_destination_amount, figure out the A value at
         // b_end and give them the difference (swap_destination_amount - a_end) tokens
         let a_end = self.amt_a_locked_at_b_value_quadratic(&b_end)?;
 
+        // sqrt rounding can leave b_end right at the floor with a_end a hair under the seeded collateral, make sure
+        // none of it is ever given out
+        let initial_token_a_amount = DFSPreciseNumber::new(initial_token_a_amount)?;
+        let a_end = match a_end.less_than(&initial_token_a_amount) {
+            true => initial_token_a_amount,
+            false => a_end,
+        };
+
         // PreciseNumber rounds .5+ up by default, make sure to floor instead so we don't allow
         // dust to round up for free
         let destination_amount = DFSPreciseNumber::new(swap_destination_amount)?
@@ -275,6 +312,61 @@ impl LinearPriceCurve {
 
         Some((source_amount, destination_amount))
     }
+
+    /// Inverse of swap_a_to_b: returns the amount of token A the user has to put in to get exactly
+    /// `destination_amount` of token B out
+    fn swap_a_to_b_exact_out(
+        &self,
+        destination_amount: u128, // amount of token b (bonded token) the user wants out
+        swap_source_amount: u128, // swap's token a (collateral token)
+        swap_destination_amount: u128, // swap's remaining token b (bonded token)
+    ) -> Option<(u128, u128)> {
+        // unlike swap_a_to_b we can't just hand out whatever's left, the user asked for an exact amount
+        if destination_amount > swap_destination_amount {
+            return None;
+        }
+
+        let a_start = DFSPreciseNumber::new(swap_source_amount)?;
+
+        // make sure we round up here so that b_end and a_end are also over-estimated, which rounds up the
+        // token a input
+        let b_start = self.b_value_with_amt_a_locked_quadratic(&a_start, true)?;
+        let b_end = b_start.checked_add(&(DFSPreciseNumber::new(destination_amount)?))?;
+        let a_end = self.amt_a_locked_at_b_value_quadratic(&b_end)?;
+
+        // ceiling instead of floor here, the user pays for any dust
+        let source_amount = a_end.checked_sub(&a_start)?.ceiling()?.to_imprecise()?;
+
+        Some((source_amount, destination_amount))
+    }
+
+    /// Inverse of swap_b_to_a: returns the amount of token B the user has to put in to get exactly
+    /// `destination_amount` of token A out
+    fn swap_b_to_a_exact_out(
+        &self,
+        destination_amount: u128,
+        _swap_source_amount: u128,
+        swap_destination_amount: u128,
+    ) -> Option<(u128, u128)> {
+        // a_end can't be negative, there's only swap_destination_amount of token A bonded to the curve (and can't go
+        // below the seeded collateral either)
+        let a_end = swap_destination_amount.checked_sub(destination_amount)?;
+        if a_end < self.initial_token_a_amount.into() {
+            return None;
+        }
+
+        // round b_start up and b_end down so that the token b input (b_start - b_end) is over-estimated
+        let b_start = self.b_value_with_amt_a_locked_quadratic(
+            &(DFSPreciseNumber::new(swap_destination_amount)?),
+            true,
+        )?;
+        let b_end =
+            self.b_value_with_amt_a_locked_quadratic(&(DFSPreciseNumber::new(a_end)?), false)?;
+
+        let source_amount = b_start.checked_sub(&b_end)?.ceiling()?.to_imprecise()?;
+
+        Some((source_amount, destination_amount))
+    }
 }
 
 /// Returns None iff slope is 0 or close enough to 0 with PreciseNumber
@@ -327,6 +419,35 @@ impl CurveCalculator for LinearPriceCurve {
         })
     }
 
+    /// Calculate how much source token is required to get exactly `destination_amount` of
+    /// destination token out (rounded up in favor of the swap)
+    fn swap_without_fees_exact_out(
+        &self,
+        destination_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<SwapWithoutFeesResult> {
+        let (source_amount_swapped, destination_amount_swapped) = match trade_direction {
+            TradeDirection::AtoB => self.swap_a_to_b_exact_out(
+                destination_amount,
+                swap_source_amount,
+                swap_destination_amount,
+            )?,
+            TradeDirection::BtoA => self.swap_b_to_a_exact_out(
+                destination_amount,
+                swap_source_amount,
+                swap_destination_amount,
+            )?,
+        };
+        let source_amount_swapped = map_zero_to_none(source_amount_swapped)?;
+        let destination_amount_swapped = map_zero_to_none(destination_amount_swapped)?;
+        Some(SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped,
+        })
+    }
+
     /// Get the amount of trading tokens for the given amount of pool tokens,
     /// provided the total trading tokens and supply of pool tokens.
     /// this isn't needed since we disabled deposit/withdraw, otherwise
@@ -370,9 +491,8 @@ impl CurveCalculator for LinearPriceCurve {
     }
 
     /// Get the amount of pool tokens for the withdrawn amount of token A or B.
-    /// this mostly isn't needed since we disable withdrawals, UNLESS we have non-zero host fees/trade fees
-    /// (which are currently disabled on the instruction level), in which case it's used in `swap` to determine
-    /// how much pool token to mint (to account for fees) into the various fee accounts
+    /// this isn't needed since we disable withdrawals, and fees are paid out in trading tokens instead of
+    /// being converted into pool tokens (see `collects_fees_in_trading_tokens`)
     fn withdraw_single_token_type_exact_out(
         &self,
         _source_amount: u128,
@@ -383,8 +503,6 @@ impl CurveCalculator for LinearPriceCurve {
     ) -> Option<u128> {
         // this causes a panic if SwapCurve.withdraw_single_token_type_exact_out instruction is called
         // but that's ok for now, cheap way of disabling withdrawals without having to change how SwapCurve works
-        // (also if a non-zero fee curve is created this would also cause a panic, though that's disabled at the
-        // lib.rs level)
         None
     }
 
@@ -398,13 +516,14 @@ impl CurveCalculator for LinearPriceCurve {
     }
 
     /// Validate the given supply on initialization.
-    /// We require at least some bonded token B for the curve to be useful (collateral token must be 0)
+    /// We require at least some bonded token B for the curve to be useful (collateral token must be exactly the
+    /// seeded `initial_token_a_amount`, any more would move the price)
     fn validate_supply(&self, token_a_amount: u64, token_b_amount: u64) -> Result<(), SwapError> {
         if token_b_amount == 0 {
             return Err(SwapError::EmptySupply);
         }
 
-        if token_a_amount != 0 {
+        if token_a_amount != self.initial_token_a_amount {
             return Err(SwapError::InvalidSupply);
         }
         Ok(())
@@ -415,6 +534,39 @@ impl CurveCalculator for LinearPriceCurve {
         false
     }
 
+    /// The curve position is derived from the amount of token A in the swap, so fees can't be left in the
+    /// swap token accounts (they'd look like bonded collateral and move the price)
+    fn collects_fees_in_trading_tokens(&self) -> bool {
+        true
+    }
+
+    /// The spot price is just the price formula `slope*b + a0`, where b is derived from the amount of token A
+    /// locked in the curve. Returned with a denominator of `dfs_precise_number::ONE`
+    fn spot_price(
+        &self,
+        swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+    ) -> Option<(u128, u128)> {
+        let b_value = self.b_value_with_amt_a_locked_quadratic(
+            &(DFSPreciseNumber::new(swap_token_a_amount)?),
+            false,
+        )?;
+
+        let slope_times_b = DFSPreciseNumber::new(self.slope_numerator.into())?
+            .checked_mul(&b_value)?
+            .checked_div(&(DFSPreciseNumber::new(self.slope_denominator.into())?))?;
+        let a0 = DFSPreciseNumber::new(self.initial_token_a_price_numerator.into())?.checked_div(
+            &(DFSPreciseNumber::new(self.initial_token_a_price_denominator.into())?),
+        )?;
+        let price = slope_times_b.checked_add(&a0)?;
+
+        // the raw DFSPreciseNumber value is the numerator over ONE, as long as it fits in a u128
+        if price.value.bits() > 128 {
+            return None;
+        }
+        Some((price.value.as_u128(), ONE))
+    }
+
     /// The total normalized value of the linear price curve adds the total
     /// value of the token A side (as denominated in token B) to the token B side.
     fn normalized_value(
@@ -451,7 +603,7 @@ impl IsInitialized for LinearPriceCurve {
 }
 impl Sealed for LinearPriceCurve {}
 impl Pack for LinearPriceCurve {
-    const LEN: usize = 32;
+    const LEN: usize = 40;
     fn pack_into_slice(&self, output: &mut [u8]) {
         (self as &dyn DynPack).pack_into_slice(output);
     }
@@ -461,6 +613,7 @@ impl Pack for LinearPriceCurve {
         let slope_denominator = array_ref![input, 8, 8];
         let initial_token_a_price_numerator = array_ref![input, 16, 8];
         let initial_token_a_price_denominator = array_ref![input, 24, 8];
+        let initial_token_a_amount = array_ref![input, 32, 8];
         Ok(Self {
             slope_numerator: u64::from_le_bytes(*slope_numerator),
             slope_denominator: u64::from_le_bytes(*slope_denominator),
@@ -468,6 +621,7 @@ impl Pack for LinearPriceCurve {
             initial_token_a_price_denominator: u64::from_le_bytes(
                 *initial_token_a_price_denominator,
             ),
+            initial_token_a_amount: u64::from_le_bytes(*initial_token_a_amount),
         })
     }
 }
@@ -482,6 +636,8 @@ impl DynPack for LinearPriceCurve {
         *initial_token_a_price = self.initial_token_a_price_numerator.to_le_bytes();
         let initial_token_a_price = array_mut_ref![output, 24, 8];
         *initial_token_a_price = self.initial_token_a_price_denominator.to_le_bytes();
+        let initial_token_a_amount = array_mut_ref![output, 32, 8];
+        *initial_token_a_amount = self.initial_token_a_amount.to_le_bytes();
     }
 }
 
@@ -499,6 +655,7 @@ mod tests {
             slope_denominator: 2,
             initial_token_a_price_numerator: 150,
             initial_token_a_price_denominator: 3, // using non-1 just to test out
+            initial_token_a_amount: 0,
         };
 
         // put in 101 A, should get 2 B out
@@ -517,6 +674,7 @@ mod tests {
             slope_denominator: 2_0000_0000, // slope needs to be scaled down to take into account B having 8 decimals
             initial_token_a_price_numerator: 150, // since they both have 8 decimals, no need to scale this (it's still 50 base A for 1 base B)
             initial_token_a_price_denominator: 3, // using non-1 just to test out
+            initial_token_a_amount: 0,
         };
 
         let (source_amount, destination_amount) =
@@ -546,6 +704,7 @@ mod tests {
             slope_denominator: 1_000_000_000_000,
             initial_token_a_price_numerator: 35_915742_315103, // 35.9157423151027 in forte, so should be 3.59...e13 now
             initial_token_a_price_denominator: 1,
+            initial_token_a_amount: 0,
         };
 
         // putting in 7296... A in, should move price to 145_199_999999.99
@@ -575,6 +734,7 @@ mod tests {
             slope_denominator: 2,
             initial_token_a_price_numerator: 150,
             initial_token_a_price_denominator: 3, // using non-1 just to test out
+            initial_token_a_amount: 0,
         };
 
         // pretty much the opposite cases as above
@@ -595,6 +755,7 @@ mod tests {
             slope_denominator: 2_0000_0000, // slope needs to be scaled down to take into account B having 8 decimals
             initial_token_a_price_numerator: 150, // since they both have 8 decimals, no need to scale this (it's still 50 base A for 1 base B)
             initial_token_a_price_denominator: 3, // using non-1 just to test out
+            initial_token_a_amount: 0,
         };
 
         let (source_amount, destination_amount) = curve
@@ -611,6 +772,7 @@ mod tests {
             slope_denominator: 1_000_000_000_000,
             initial_token_a_price_numerator: 35_915742_315103, // 35.9157423151027 in forte, so should be 3.59...e13 now
             initial_token_a_price_denominator: 1,
+            initial_token_a_amount: 0,
         };
 
         // putting in 200 B at 7296.9394630144 A, should get it all out
@@ -637,6 +799,98 @@ mod tests {
         assert_eq!(destination_amount, 7296_939463_019977_480000);
     }
 
+    #[test]
+    fn swap_exact_out_basic() {
+        let curve = LinearPriceCurve {
+            slope_numerator: 1,
+            slope_denominator: 2,
+            initial_token_a_price_numerator: 150,
+            initial_token_a_price_denominator: 3, // using non-1 just to test out
+            initial_token_a_amount: 0,
+        };
+
+        // the inverses of swap_a_to_b_basic and swap_b_to_a_basic
+
+        // get out 2 B at 0 A, should cost 101 A
+        let (source_amount, destination_amount) = curve.swap_a_to_b_exact_out(2, 0, 5000).unwrap();
+        assert_eq!(source_amount, 101);
+        assert_eq!(destination_amount, 2);
+
+        // get out 2 more B at 101 A, should cost 103 A
+        let (source_amount, destination_amount) =
+            curve.swap_a_to_b_exact_out(2, 101, 4998).unwrap();
+        assert_eq!(source_amount, 103);
+        assert_eq!(destination_amount, 2);
+
+        // get out 103 A at 204 A, should cost 2 B
+        let (source_amount, destination_amount) =
+            curve.swap_b_to_a_exact_out(103, 4996, 204).unwrap();
+        assert_eq!(source_amount, 2);
+        assert_eq!(destination_amount, 103);
+
+        // get out all 101 A, should cost 2 B
+        let (source_amount, destination_amount) =
+            curve.swap_b_to_a_exact_out(101, 4998, 101).unwrap();
+        assert_eq!(source_amount, 2);
+        assert_eq!(destination_amount, 101);
+
+        // can't get out more than what's in the swap
+        assert_eq!(curve.swap_a_to_b_exact_out(5001, 0, 5000), None);
+        assert_eq!(curve.swap_b_to_a_exact_out(102, 4998, 101), None);
+        assert_eq!(
+            curve.swap_without_fees_exact_out(0, 0, 5000, TradeDirection::AtoB),
+            None
+        );
+
+        // similar to 145K segment of forte curve (see swap_b_to_a_basic)
+        let curve = LinearPriceCurve {
+            slope_numerator: 5_689_549_999_968_874,
+            slope_denominator: 1_000_000_000_000,
+            initial_token_a_price_numerator: 35_915742_315103,
+            initial_token_a_price_denominator: 1,
+            initial_token_a_amount: 0,
+        };
+
+        // get out 200 B at 0 A, should cost 7296.9394630144 A
+        let (source_amount, destination_amount) = curve
+            .swap_a_to_b_exact_out(200_000000, 0, 5000_000000)
+            .unwrap();
+        // note this rounds up from 7296_939463019977480000 due to sqrt rounding
+        assert_eq!(source_amount, 7296_939507_215972_451546);
+        assert_eq!(destination_amount, 200_000000);
+
+        // get out 7524.5214630093 A at 14821.4609260237 A, should cost 200 B (rounded up)
+        let (source_amount, destination_amount) = curve
+            .swap_b_to_a_exact_out(
+                7524_521463_018732_440000,
+                4600_000000,
+                14821_460926_038709_920000,
+            )
+            .unwrap();
+        // note this rounds up from 200_000000 due to sqrt rounding
+        assert_eq!(source_amount, 200_000003);
+        assert_eq!(destination_amount, 7524_521463_018732_440000);
+    }
+
+    #[test]
+    fn spot_price_basic() {
+        let curve = LinearPriceCurve {
+            slope_numerator: 1,
+            slope_denominator: 2,
+            initial_token_a_price_numerator: 150,
+            initial_token_a_price_denominator: 3,
+            initial_token_a_amount: 0,
+        };
+
+        // a = 0.5b + 50, with b = 0, 2 and 4 (see swap_a_to_b_basic)
+        assert_eq!(curve.spot_price(0, 5000), Some((50 * ONE, ONE)));
+        assert_eq!(curve.spot_price(101, 4998), Some((51 * ONE, ONE)));
+        assert_eq!(curve.spot_price(204, 4996), Some((52 * ONE, ONE)));
+
+        // token B amount doesn't matter
+        assert_eq!(curve.spot_price(204, 0), Some((52 * ONE, ONE)));
+    }
+
     #[test]
     fn swap_0_0_curve() {
         // a curve that starts at 0/0
@@ -645,6 +899,7 @@ mod tests {
             slope_denominator: 2,
             initial_token_a_price_numerator: 0,
             initial_token_a_price_denominator: 1,
+            initial_token_a_amount: 0,
         };
 
         // put in 9 A, should get 6 B out
@@ -663,6 +918,60 @@ mod tests {
         assert_eq!(destination_amount, 9);
     }
 
+    #[test]
+    fn swap_seeded_curve() {
+        // same curve as swap_a_to_b_basic but seeded with the 101 A locked at b = 2
+        let curve = LinearPriceCurve {
+            slope_numerator: 1,
+            slope_denominator: 2,
+            initial_token_a_price_numerator: 150,
+            initial_token_a_price_denominator: 3,
+            initial_token_a_amount: 101,
+        };
+
+        // initial deposit has to be exactly the seeded collateral
+        assert_eq!(curve.validate_supply(101, 5000), Ok(()));
+        assert_eq!(
+            curve.validate_supply(0, 5000),
+            Err(SwapError::InvalidSupply)
+        );
+        assert_eq!(
+            curve.validate_supply(102, 5000),
+            Err(SwapError::InvalidSupply)
+        );
+
+        // starts at the b = 2 price
+        assert_eq!(curve.spot_price(101, 4998), Some((51 * ONE, ONE)));
+
+        // put in 103 A at 101 A, should get 2 B out (same as the unseeded curve)
+        let (source_amount, destination_amount) = curve.swap_a_to_b(103, 101, 4998).unwrap();
+        assert_eq!(source_amount, 103);
+        assert_eq!(destination_amount, 2);
+
+        // put in 2 B at 204 A, should get 103 A out (same as the unseeded curve)
+        let (source_amount, destination_amount) = curve.swap_b_to_a(2, 4996, 204).unwrap();
+        assert_eq!(source_amount, 2);
+        assert_eq!(destination_amount, 103);
+
+        // put in 4 B at 204 A, should only get the 103 A above the floor out and only take 2 B
+        let (source_amount, destination_amount) = curve.swap_b_to_a(4, 4996, 204).unwrap();
+        assert_eq!(source_amount, 2);
+        assert_eq!(destination_amount, 103);
+
+        // can't sell anything at the floor
+        assert_eq!(
+            curve.swap_without_fees(1, 4998, 101, TradeDirection::BtoA),
+            None
+        );
+
+        // exact out can get everything above the floor but none of the seeded collateral
+        let (source_amount, destination_amount) =
+            curve.swap_b_to_a_exact_out(103, 4996, 204).unwrap();
+        assert_eq!(source_amount, 2);
+        assert_eq!(destination_amount, 103);
+        assert_eq!(curve.swap_b_to_a_exact_out(104, 4996, 204), None);
+    }
+
     #[test]
     fn swap_without_fees() {
         let curve = LinearPriceCurve {
@@ -670,6 +979,7 @@ mod tests {
             slope_denominator: 2,
             initial_token_a_price_numerator: 350,
             initial_token_a_price_denominator: 7, // using non-1 just to test out
+            initial_token_a_amount: 0,
         };
 
         let result = curve
@@ -702,6 +1012,7 @@ mod tests {
             slope_denominator: u64::MAX - 1,
             initial_token_a_price_numerator: 0,
             initial_token_a_price_denominator: u32::MAX.into(),
+            initial_token_a_amount: u64::MAX - 2,
         };
 
         let mut packed = [0u8; LinearPriceCurve::LEN];
@@ -714,6 +1025,7 @@ mod tests {
         packed.extend_from_slice(&curve.slope_denominator.to_le_bytes());
         packed.extend_from_slice(&curve.initial_token_a_price_numerator.to_le_bytes());
         packed.extend_from_slice(&curve.initial_token_a_price_denominator.to_le_bytes());
+        packed.extend_from_slice(&curve.initial_token_a_amount.to_le_bytes());
         let unpacked = LinearPriceCurve::unpack(&packed).unwrap();
         assert_eq!(curve, unpacked);
     }
@@ -731,6 +1043,7 @@ mod tests {
             slope_denominator: u64::MAX - 1,
             initial_token_a_price_numerator: u64::MAX,
             initial_token_a_price_denominator: 1,
+            initial_token_a_amount: 0,
         };
 
         // with initial_token_a_price == u64::MAX, there aren't enough ever enough A tokens to get any
@@ -805,6 +1118,7 @@ mod tests {
             slope_denominator: u64::MAX - 1,
             initial_token_a_price_numerator: u32::MAX.into(),
             initial_token_a_price_denominator: 1,
+            initial_token_a_amount: 0,
         };
 
         // testing a -> b
@@ -947,6 +1261,7 @@ mod tests {
             slope_denominator: 1_000_000_000_000_000_000,
             initial_token_a_price_numerator: 0,
             initial_token_a_price_denominator: 1,
+            initial_token_a_amount: 0,
         };
 
         // 0 <- B value at A = 0
@@ -1087,6 +1402,7 @@ mod tests {
             slope_denominator: 1_000_000_000_000_000_000,
             initial_token_a_price_numerator: 0,
             initial_token_a_price_denominator: 1,
+            initial_token_a_amount: 0,
         };
 
         // same as above but we only use u64 values (realistically that's the maximum unless SPL
@@ -1222,6 +1538,7 @@ mod tests {
             slope_denominator: u64::MAX - 1,
             initial_token_a_price_numerator: u64::MAX - 1,
             initial_token_a_price_denominator: u64::MAX,
+            initial_token_a_amount: 0,
         };
 
         // testing a -> b
@@ -1341,6 +1658,7 @@ mod tests {
             slope_denominator: 1,
             initial_token_a_price_numerator: u64::MAX - 1,
             initial_token_a_price_denominator: u64::MAX,
+            initial_token_a_amount: 0,
         };
 
         // before putting in 2^63 A tokens, there's not enough to get any B tokens out
@@ -1407,6 +1725,7 @@ mod tests {
             slope_denominator: 1,
             initial_token_a_price_numerator: u64::MAX - 1,
             initial_token_a_price_denominator: u64::MAX,
+            initial_token_a_amount: 0,
         };
 
         // testing a -> b
@@ -1517,6 +1836,7 @@ mod tests {
             slope_denominator: 1_000_000_000_001,
             initial_token_a_price_numerator: 1,
             initial_token_a_price_denominator: 1,
+            initial_token_a_amount: 0,
         };
         assert!(!curve.validate().is_ok());
 
@@ -1526,6 +1846,7 @@ mod tests {
             slope_denominator: 0,
             initial_token_a_price_numerator: 1,
             initial_token_a_price_denominator: 1,
+            initial_token_a_amount: 0,
         };
         assert!(!curve.validate().is_ok());
 
@@ -1535,6 +1856,7 @@ mod tests {
             slope_denominator: 1_000_000_000_000_000_001,
             initial_token_a_price_numerator: 1,
             initial_token_a_price_denominator: 1,
+            initial_token_a_amount: 0,
         };
         assert!(!curve.validate().is_ok());
 
@@ -1544,6 +1866,7 @@ mod tests {
             slope_denominator: 1_000_000_000_000_000_000,
             initial_token_a_price_numerator: 1,
             initial_token_a_price_denominator: 1,
+            initial_token_a_amount: 0,
         };
         assert!(curve.validate().is_ok());
 
@@ -1553,6 +1876,7 @@ mod tests {
             slope_denominator: 1_400_000_000_000_000_000,
             initial_token_a_price_numerator: 1,
             initial_token_a_price_denominator: 1,
+            initial_token_a_amount: 0,
         };
         assert!(curve.validate().is_ok());
 
@@ -1562,6 +1886,7 @@ mod tests {
             slope_denominator: 1_000_000_000_000,
             initial_token_a_price_numerator: 1,
             initial_token_a_price_denominator: 0,
+            initial_token_a_amount: 0,
         };
         assert!(!curve.validate().is_ok());
     }
@@ -1573,6 +1898,7 @@ mod tests {
             slope_denominator: 1400000000000000000,
             initial_token_a_price_numerator: 7,
             initial_token_a_price_denominator: 2,
+            initial_token_a_amount: 0,
         };
 
         let result = curve.swap_without_fees(1, 0, 1_285_000_000_000_000, TradeDirection::AtoB);
@@ -1809,6 +2135,7 @@ mod tests {
             slope_denominator: 1400_000_000_000_000_000,
             initial_token_a_price_numerator: 7,
             initial_token_a_price_denominator: 2,
+            initial_token_a_amount: 0,
         };
         let starting_supply_b: u128 = 1_000_000_000_000_000_000;
 
@@ -1981,6 +2308,7 @@ mod tests {
                 slope_denominator: 1_400_000_000_000_000_000,
                 initial_token_a_price_numerator: 7,
                 initial_token_a_price_denominator: 2,
+                initial_token_a_amount: 0,
             };
 
             let (_source_amount_swapped, destination_amount_swapped) = curve
@@ -2020,6 +2348,7 @@ mod tests {
                 slope_denominator: 1_000_000_000_000,
                 initial_token_a_price_numerator: 0,
                 initial_token_a_price_denominator: 1,
+                initial_token_a_amount: 0,
             };
 
             let (_source_amount_swapped, destination_amount_swapped) = curve
@@ -2093,4 +2422,68 @@ mod tests {
         .unwrap();
         assert_eq!(result, 1); // should return positive root
     }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_swap_exact_out(
+            // how much a user wants out
+            destination_token_amount in 1..u32::MAX,
+            // how much a is already in swap (determines spot price)
+            swap_token_a_amount in 1..u64::MAX,
+        ) {
+            // same curve as curve_value_does_not_decrease_from_swap_a_to_b
+            let curve = LinearPriceCurve {
+                slope_numerator: 37,
+                slope_denominator: 1_400_000_000_000_000_000,
+                initial_token_a_price_numerator: 7,
+                initial_token_a_price_denominator: 2,
+                initial_token_a_amount: 0,
+            };
+            let destination_token_amount = destination_token_amount as u128;
+            let swap_token_a_amount = swap_token_a_amount as u128;
+            // token b in the swap doesn't affect any of the math
+            let swap_token_b_amount = u64::MAX as u128;
+            let previous_value = curve
+                .normalized_value(swap_token_a_amount, swap_token_b_amount)
+                .unwrap();
+
+            // buying exact b
+            let result = curve
+                .swap_without_fees_exact_out(
+                    destination_token_amount,
+                    swap_token_a_amount,
+                    swap_token_b_amount,
+                    TradeDirection::AtoB,
+                )
+                .unwrap();
+            assert_eq!(result.destination_amount_swapped, destination_token_amount);
+            let new_value = curve
+                .normalized_value(
+                    swap_token_a_amount + result.source_amount_swapped,
+                    swap_token_b_amount - destination_token_amount,
+                )
+                .unwrap();
+            assert!(new_value.greater_than_or_equal(&previous_value));
+
+            // selling for exact a
+            if destination_token_amount <= swap_token_a_amount {
+                let result = curve
+                    .swap_without_fees_exact_out(
+                        destination_token_amount,
+                        swap_token_b_amount,
+                        swap_token_a_amount,
+                        TradeDirection::BtoA,
+                    )
+                    .unwrap();
+                assert_eq!(result.destination_amount_swapped, destination_token_amount);
+                let new_value = curve
+                    .normalized_value(
+                        swap_token_a_amount - destination_token_amount,
+                        swap_token_b_amount + result.source_amount_swapped,
+                    )
+                    .unwrap();
+                assert!(new_value.greater_than_or_equal(&previous_value));
+            }
+        }
+    }
 }
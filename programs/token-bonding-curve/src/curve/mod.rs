@@ -4,7 +4,12 @@ pub mod base;
 pub mod calculator;
 pub mod constant_price;
 pub mod constant_product;
+pub mod exponential_price;
 pub mod fees;
 pub mod linear_price;
 pub mod offset;
+pub mod piecewise_linear;
+pub mod power_price;
+pub mod reserve_ratio;
+pub mod sigmoid_price;
 pub mod stable;
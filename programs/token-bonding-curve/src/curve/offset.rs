@@ -8,8 +8,8 @@ use {
                 TradingTokenResult,
             },
             constant_product::{
-                deposit_single_token_type, normalized_value, pool_tokens_to_trading_tokens, swap,
-                withdraw_single_token_type_exact_out,
+                deposit_single_token_type, normalized_value, pool_tokens_to_trading_tokens,
+                spot_price, swap, withdraw_single_token_type_exact_out,
             },
         },
         error::SwapError,
@@ -153,6 +153,20 @@ impl CurveCalculator for OffsetCurve {
             swap_token_b_amount.checked_add(token_b_offset)?,
         )
     }
+
+    /// The spot price of the offset curve also adds the offset to the token B
+    /// side before calculating
+    fn spot_price(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<(u128, u128)> {
+        let token_b_offset = self.token_b_offset as u128;
+        spot_price(
+            swap_token_a_amount,
+            swap_token_b_amount.checked_add(token_b_offset)?,
+        )
+    }
 }
 
 /// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
@@ -213,6 +227,18 @@ mod tests {
         assert_eq!(curve, unpacked);
     }
 
+    #[test]
+    fn spot_price_includes_offset() {
+        let curve = OffsetCurve {
+            token_b_offset: 1_000,
+        };
+        assert_eq!(curve.spot_price(500, 0), Some((500, 1_000)));
+        assert_eq!(curve.spot_price(500, 1_000), Some((500, 2_000)));
+
+        let curve = OffsetCurve::default();
+        assert_eq!(curve.spot_price(500, 0), None);
+    }
+
     #[test]
     fn swap_no_offset() {
         let swap_source_amount: u128 = 1_000;
@@ -0,0 +1,1106 @@
+//! Piecewise linear price swap curve, made up of several linear segments (breakpoint, slope) set at init
+//! Each segment starts at the price the previous one ended at, so the price is continuous and the whole curve
+//! can be traded in a single swap instead of routing across several linear curves
+//! Works under the same assumptions as the linear price curve (see `linear_price.rs`):
+//! Deposits (except the initial deposit) are disabled
+//! The initial deposit should only have token B (the bonded token) and 0 token A (the collateral token)
+//! Trade and owner fees are taken in the source token and either sent straight to a fee token account or set aside
+//! in the swap for `claim_fees` (see `SwapV2::accrue_fees`), so they never count as liquidity or move the price
+//! (see `collects_fees_in_trading_tokens`)
+//! Withdrawals are disabled, will panic if those instructions are called
+//!
+//! The segments don't fit in SwapCurve's 40 byte calculator slot, so they're kept in the swap's curve params
+//! account (`PiecewiseLinearSegments`), a PDA that the swap links to (see `CurveType::curve_params_len`)
+
+use {
+    crate::{
+        curve::{
+            calculator::{
+                map_zero_to_none, CurveCalculator, DynPack, RoundDirection, SwapWithoutFeesResult,
+                TradeDirection, TradingTokenResult,
+            },
+            linear_price::solve_quadratic_positive_root,
+        },
+        dfs_precise_number::{DFSPreciseNumber, ONE},
+        error::SwapError,
+    },
+    anchor_lang::prelude::{borsh, AnchorDeserialize, AnchorSerialize},
+    arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
+    solana_program::{
+        program_error::ProgramError,
+        program_pack::{IsInitialized, Pack, Sealed},
+    },
+};
+
+/// Maximum number of segments in a piecewise linear curve
+pub const MAX_SEGMENTS: usize = 16;
+
+/// Packed length of a single PiecewiseLinearSegment
+const SEGMENT_LEN: usize = 24;
+
+/// A single linear segment of the curve, the price of token B (denominated in token A) increases by
+/// `slope` for every token B swapped out of the curve from `breakpoint` until the next segment's `breakpoint`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct PiecewiseLinearSegment {
+    /// Amount of token B that's been swapped out of the curve where this segment starts, the first segment
+    /// must start at 0
+    pub breakpoint: u64,
+    /// Slope numerator of this segment
+    pub slope_numerator: u64,
+    /// Slope denominator of this segment
+    pub slope_denominator: u64,
+}
+
+impl PiecewiseLinearSegment {
+    /// Returns how much the price increases over `width` token B in this segment, i.e. `m*width`
+    fn price_increase(&self, width: &DFSPreciseNumber) -> Option<DFSPreciseNumber> {
+        DFSPreciseNumber::new(self.slope_numerator.into())?
+            .checked_mul(width)?
+            .checked_div(&(DFSPreciseNumber::new(self.slope_denominator.into())?))
+    }
+
+    /// Returns the amount of token A locked over `width` token B in this segment, given the price at the start
+    /// of the segment, i.e. the segment's integral `0.5m*width^2 + start_price*width`
+    fn amt_a_locked(
+        &self,
+        start_price: &DFSPreciseNumber,
+        width: &DFSPreciseNumber,
+    ) -> Option<DFSPreciseNumber> {
+        // 0.5 * m * width^2 - make sure to do division last
+        let half_m_width_squared = DFSPreciseNumber::new(self.slope_numerator.into())?
+            .checked_mul(width)?
+            .checked_mul(width)?
+            .checked_div(&(DFSPreciseNumber::new(self.slope_denominator.into())?))?
+            .checked_div(&(DFSPreciseNumber::new(2)?))?;
+
+        half_m_width_squared.checked_add(&(start_price.checked_mul(width)?))
+    }
+}
+
+/// The curve parameters that are stored in the curve's segments account
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PiecewiseLinearSegments {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// When there's 0 liquidity in the pool, what should the initial price point a0 defining the curve be?
+    /// i.e. what is the cost of 1 b token (denominated in A) when there's 0 liquidity
+    pub initial_token_a_price_numerator: u64,
+    /// When there's 0 liquidity in the pool, what should the initial price point a0 defining the curve be?
+    /// i.e. what is the cost of 1 b token (denominated in A) when there's 0 liquidity
+    pub initial_token_a_price_denominator: u64,
+    /// Segments of the curve, sorted by breakpoint
+    pub segments: Vec<PiecewiseLinearSegment>,
+}
+
+/// PiecewiseLinearCurve struct implementing CurveCalculator
+/// A is the "collateral" token (e.g. RLY), B is the "bonded" token (e.g. TAKI).
+/// The price of a single B token (a, denominated in amount of token A) in segment i is defined by
+/// `a = m_i*(b - breakpoint_i) + a_i`
+/// where b is the amount of token B that's been swapped out of this curve and a_i is the price at the end of the
+/// previous segment (or initial_token_a_price for the first segment)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PiecewiseLinearCurve {
+    /// Segments loaded from the swap's curve params account, uninitialized when unpacked from the swap
+    /// (see `SwapCurve::load_curve_params`)
+    pub segments: PiecewiseLinearSegments,
+}
+
+/// These functions walk the curve's segments from b = 0 summing up each segment's integral to determine
+/// liquidity of A at a given B value (amt_a_locked_at_b_value)
+/// The segment that a given liquidity falls in is solved with the quadratic formula to determine the B value for
+/// that liquidity (b_value_with_amt_a_locked)
+///
+/// swap_a_to_b and swap_b_to_a are the key functions at the bottom
+impl PiecewiseLinearCurve {
+    /// Returns the initial price a0 as a PreciseNumber
+    fn initial_price(&self) -> Option<DFSPreciseNumber> {
+        DFSPreciseNumber::new(self.segments.initial_token_a_price_numerator.into())?.checked_div(
+            &(DFSPreciseNumber::new(self.segments.initial_token_a_price_denominator.into())?),
+        )
+    }
+
+    /// Returns the breakpoint where segment `index` ends, or None for the last segment
+    fn segment_end(&self, index: usize) -> Option<DFSPreciseNumber> {
+        DFSPreciseNumber::new(self.segments.segments.get(index + 1)?.breakpoint.into())
+    }
+
+    /// Returns the amount of A token locked at a given b_value by adding up the integral of every segment
+    /// before b_value (integration constant is 0 since we know there's 0 token A bonded at b = 0)
+    fn amt_a_locked_at_b_value(&self, b_value: &DFSPreciseNumber) -> Option<DFSPreciseNumber> {
+        let mut a_locked = DFSPreciseNumber::new(0)?;
+        let mut start_price = self.initial_price()?;
+
+        for (index, segment) in self.segments.segments.iter().enumerate() {
+            let segment_start = DFSPreciseNumber::new(segment.breakpoint.into())?;
+
+            // b_value is in this segment if it's the last one or b_value is before the next breakpoint
+            let segment_end = match self.segment_end(index) {
+                Some(segment_end) if segment_end.less_than(b_value) => segment_end,
+                _ => {
+                    let width = b_value.checked_sub(&segment_start)?;
+                    return a_locked.checked_add(&(segment.amt_a_locked(&start_price, &width)?));
+                }
+            };
+
+            let width = segment_end.checked_sub(&segment_start)?;
+            a_locked = a_locked.checked_add(&(segment.amt_a_locked(&start_price, &width)?))?;
+            start_price = start_price.checked_add(&(segment.price_increase(&width)?))?;
+        }
+
+        // only reachable if there are no segments (i.e. they haven't been loaded)
+        None
+    }
+
+    /// Returns the price of token B at a given b_value, i.e. the derivative of amt_a_locked_at_b_value
+    fn price_at_b_value(&self, b_value: &DFSPreciseNumber) -> Option<DFSPreciseNumber> {
+        let mut price = self.initial_price()?;
+
+        for (index, segment) in self.segments.segments.iter().enumerate() {
+            let segment_start = DFSPreciseNumber::new(segment.breakpoint.into())?;
+
+            let segment_end = match self.segment_end(index) {
+                Some(segment_end) if segment_end.less_than(b_value) => segment_end,
+                _ => {
+                    let width = b_value.checked_sub(&segment_start)?;
+                    return price.checked_add(&(segment.price_increase(&width)?));
+                }
+            };
+
+            let width = segment_end.checked_sub(&segment_start)?;
+            price = price.checked_add(&(segment.price_increase(&width)?))?;
+        }
+
+        // only reachable if there are no segments (i.e. they haven't been loaded)
+        None
+    }
+
+    /// Returns the b value for a given token_a_amount, by finding the segment that token_a_amount ends in and
+    /// solving that segment's integral `remaining_a = 0.5m*width^2 + start_price*width` for width
+    fn b_value_with_amt_a_locked(
+        &self,
+        token_a_amount: &DFSPreciseNumber,
+        should_round_sqrt_up: bool,
+    ) -> Option<DFSPreciseNumber> {
+        let mut a_locked = DFSPreciseNumber::new(0)?;
+        let mut start_price = self.initial_price()?;
+
+        for (index, segment) in self.segments.segments.iter().enumerate() {
+            let segment_start = DFSPreciseNumber::new(segment.breakpoint.into())?;
+            let remaining_a = token_a_amount.checked_sub(&a_locked)?;
+
+            // skip over every segment that's completely filled by token_a_amount
+            if let Some(segment_end) = self.segment_end(index) {
+                let width = segment_end.checked_sub(&segment_start)?;
+                let segment_a_locked = segment.amt_a_locked(&start_price, &width)?;
+                if segment_a_locked.less_than(&remaining_a) {
+                    a_locked = a_locked.checked_add(&segment_a_locked)?;
+                    start_price = start_price.checked_add(&(segment.price_increase(&width)?))?;
+                    continue;
+                }
+            }
+
+            // solve 0 = 0.5m*width^2 + start_price*width - remaining_a
+            // (We're using k/e for quadratic coefficients instead of a/b to not clash with token a/b names)
+            let k_numerator = DFSPreciseNumber::new(segment.slope_numerator.into())?;
+            let k_denominator = DFSPreciseNumber::new(segment.slope_denominator.into())?
+                .checked_mul(&(DFSPreciseNumber::new(2)?))?;
+            let width = solve_quadratic_positive_root(
+                &k_numerator,
+                &k_denominator,
+                &start_price,
+                &(DFSPreciseNumber::new(1)?),
+                &remaining_a,
+                should_round_sqrt_up,
+            )?;
+
+            return segment_start.checked_add(&width);
+        }
+
+        // only reachable if there are no segments (i.e. they haven't been loaded)
+        None
+    }
+
+    /// If `source_amount` will cause the swap to return all of its remaining `swap_destination_amount`,
+    /// this returns the (maximum_token_a_amount, swap_destination_amount) that the swap can take
+    /// Otherwise (if there's enough `swap_destination_amount` to handle all the `source_amount`), returns None
+    fn maximum_a_remaining_for_swap_a_to_b(
+        &self,
+        a_start: &DFSPreciseNumber,
+        b_start: &DFSPreciseNumber,
+        source_amount: u128,
+        swap_destination_amount: u128,
+    ) -> Option<(u128, u128)> {
+        // if at b_start + swap_destination_amount (the maximum B that be given out by the swap),
+        // then the A value is <= source_amount, so only take that amount of A instead and give them all the
+        // Bs remaining
+        let maximum_b_value =
+            b_start.checked_add(&(DFSPreciseNumber::new(swap_destination_amount)?))?;
+        let maximum_a_locked = self.amt_a_locked_at_b_value(&maximum_b_value)?;
+        let maximum_a_remaining = maximum_a_locked
+            .checked_sub(a_start)?
+            .ceiling()?
+            .to_imprecise()?;
+
+        match maximum_a_remaining <= source_amount {
+            true => Some((maximum_a_remaining, swap_destination_amount)),
+            false => None,
+        }
+    }
+
+    /// Swap's in user's collateral token and returns out the bonded token,
+    /// moving right on the price curve and increasing the price of the bonded token
+    fn swap_a_to_b(
+        &self,
+        source_amount: u128,      // amount of user's token a (collateral token)
+        swap_source_amount: u128, // swap's token a (collateral token)
+        swap_destination_amount: u128, // swap's remaining token b (bonded token)
+    ) -> Option<(u128, u128)> {
+        let a_start = DFSPreciseNumber::new(swap_source_amount)?;
+
+        // make sure we round up here so that b_start is over-estimated, which rounds down the final token b output
+        let b_start = self.b_value_with_amt_a_locked(&a_start, true)?;
+
+        // no need to return None here if this fails, can just skip this check and do real calculation below
+        if let Some(val) = self.maximum_a_remaining_for_swap_a_to_b(
+            &a_start,
+            &b_start,
+            source_amount,
+            swap_destination_amount,
+        ) {
+            return Some(val);
+        }
+
+        // otherwise, there's enough B tokens for all the A they put in, find the b_end value for the amount of A
+        // they're putting in and give them `b_end - b_start` tokens out (b_end can be in a later segment than
+        // b_start, b_value_with_amt_a_locked integrates across every segment in between)
+        let a_end = a_start.checked_add(&(DFSPreciseNumber::new(source_amount)?))?;
+
+        let b_end = self.b_value_with_amt_a_locked(&a_end, false)?;
+
+        // b_end can be below b_start if source_amount is too small to move the curve at all
+        let (difference, is_negative) = b_end.unsigned_sub(&b_start);
+        if is_negative {
+            return Some((source_amount, 0));
+        }
+        // PreciseNumber rounds .5+ up by default, make sure to floor instead so we don't allow
+        // dust to round up for free
+        let destination_amount = difference.floor()?.to_imprecise()?;
+
+        Some((source_amount, destination_amount))
+    }
+
+    fn swap_b_to_a(
+        &self,
+        source_amount: u128,
+        _swap_source_amount: u128,
+        swap_destination_amount: u128,
+    ) -> Option<(u128, u128)> {
+        // make sure we round up here so that b_end and a_end are also over-estimated, which rounds down the final
+        // token a output
+        let b_start = self
+            .b_value_with_amt_a_locked(&(DFSPreciseNumber::new(swap_destination_amount)?), true)?;
+
+        // b_end can be negative if the user put in too many B tokens (handled below)
+        let (b_end, b_end_is_negative) =
+            b_start.unsigned_sub(&(DFSPreciseNumber::new(source_amount)?));
+
+        // if b_end < 0 (i.e. there aren't enough A tokens in the swap for all the B tokens they put in),
+        // then just give them all of the a tokens (swap_destination_amount) and only take the B tokens required to
+        // get down from b_start to 0. this only works if we assume 0 A locked at b = 0
+        if b_end_is_negative {
+            return Some((b_start.ceiling()?.to_imprecise()?, swap_destination_amount));
+        }
+
+        // make sure to use b_end.ceiling() so we don't round in favor of the user
+        let a_end = self.amt_a_locked_at_b_value(&(b_end.ceiling()?))?;
+
+        // a_end can be above swap_destination_amount if source_amount is too small to move the curve at all
+        let (difference, is_negative) =
+            DFSPreciseNumber::new(swap_destination_amount)?.unsigned_sub(&a_end);
+        if is_negative {
+            return Some((source_amount, 0));
+        }
+        // PreciseNumber rounds .5+ up by default, make sure to floor instead so we don't allow
+        // dust to round up for free
+        let destination_amount = difference.floor()?.to_imprecise()?;
+
+        Some((source_amount, destination_amount))
+    }
+
+    /// Inverse of swap_a_to_b: returns the amount of token A the user has to put in to get exactly
+    /// `destination_amount` of token B out
+    fn swap_a_to_b_exact_out(
+        &self,
+        destination_amount: u128, // amount of token b (bonded token) the user wants out
+        swap_source_amount: u128, // swap's token a (collateral token)
+        swap_destination_amount: u128, // swap's remaining token b (bonded token)
+    ) -> Option<(u128, u128)> {
+        // unlike swap_a_to_b we can't just hand out whatever's left, the user asked for an exact amount
+        if destination_amount > swap_destination_amount {
+            return None;
+        }
+
+        let a_start = DFSPreciseNumber::new(swap_source_amount)?;
+
+        // make sure we round up here so that b_end and a_end are also over-estimated, which rounds up the
+        // token a input
+        let b_start = self.b_value_with_amt_a_locked(&a_start, true)?;
+        let b_end = b_start.checked_add(&(DFSPreciseNumber::new(destination_amount)?))?;
+        let a_end = self.amt_a_locked_at_b_value(&b_end)?;
+
+        // ceiling instead of floor here, the user pays for any dust
+        let source_amount = a_end.checked_sub(&a_start)?.ceiling()?.to_imprecise()?;
+
+        Some((source_amount, destination_amount))
+    }
+
+    /// Inverse of swap_b_to_a: returns the amount of token B the user has to put in to get exactly
+    /// `destination_amount` of token A out
+    fn swap_b_to_a_exact_out(
+        &self,
+        destination_amount: u128,
+        _swap_source_amount: u128,
+        swap_destination_amount: u128,
+    ) -> Option<(u128, u128)> {
+        // a_end can't be negative, there's only swap_destination_amount of token A bonded to the curve
+        let a_end = swap_destination_amount.checked_sub(destination_amount)?;
+
+        // round b_start up and b_end down so that the token b input (b_start - b_end) is over-estimated
+        let b_start = self
+            .b_value_with_amt_a_locked(&(DFSPreciseNumber::new(swap_destination_amount)?), true)?;
+        let b_end = self.b_value_with_amt_a_locked(&(DFSPreciseNumber::new(a_end)?), false)?;
+
+        let source_amount = b_start.checked_sub(&b_end)?.ceiling()?.to_imprecise()?;
+
+        Some((source_amount, destination_amount))
+    }
+}
+
+/// Returns None if there are no segments, the segments aren't sorted, or any slope is 0 or close enough to 0
+/// with PreciseNumber
+fn is_curve_param_valid(curve: &PiecewiseLinearCurve) -> Option<()> {
+    let segments = &curve.segments;
+    if !segments.is_initialized
+        || segments.initial_token_a_price_denominator == 0
+        || segments.segments.is_empty()
+        || segments.segments.len() > MAX_SEGMENTS
+        || segments.segments[0].breakpoint != 0
+    {
+        return None;
+    }
+
+    // since PreciseNumber only has 18 decimals, any slope < 1e-18 will be treated as 0
+    let minimum = DFSPreciseNumber::new(1)?
+        .checked_div(&(DFSPreciseNumber::new(1_000_000_000_000_000_000)?))?;
+
+    for (index, segment) in segments.segments.iter().enumerate() {
+        if segment.slope_numerator == 0 || segment.slope_denominator == 0 {
+            return None;
+        }
+        let slope = DFSPreciseNumber::new(segment.slope_numerator.into())?
+            .checked_div(&(DFSPreciseNumber::new(segment.slope_denominator.into())?))?;
+        if slope.less_than(&minimum) {
+            return None;
+        }
+
+        // breakpoints have to be strictly increasing
+        if let Some(next_segment) = segments.segments.get(index + 1) {
+            if next_segment.breakpoint <= segment.breakpoint {
+                return None;
+            }
+        }
+    }
+
+    Some(())
+}
+
+impl CurveCalculator for PiecewiseLinearCurve {
+    /// Calculate how much destination token will be provided given an amount
+    /// of source token.
+    fn swap_without_fees(
+        &self,
+        source_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<SwapWithoutFeesResult> {
+        let (source_amount_swapped, destination_amount_swapped) = match trade_direction {
+            TradeDirection::AtoB => {
+                self.swap_a_to_b(source_amount, swap_source_amount, swap_destination_amount)?
+            }
+            TradeDirection::BtoA => {
+                self.swap_b_to_a(source_amount, swap_source_amount, swap_destination_amount)?
+            }
+        };
+        let source_amount_swapped = map_zero_to_none(source_amount_swapped)?;
+        let destination_amount_swapped = map_zero_to_none(destination_amount_swapped)?;
+        Some(SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped,
+        })
+    }
+
+    /// Calculate how much source token is required to get exactly `destination_amount` of
+    /// destination token out (rounded up in favor of the swap)
+    fn swap_without_fees_exact_out(
+        &self,
+        destination_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<SwapWithoutFeesResult> {
+        let (source_amount_swapped, destination_amount_swapped) = match trade_direction {
+            TradeDirection::AtoB => self.swap_a_to_b_exact_out(
+                destination_amount,
+                swap_source_amount,
+                swap_destination_amount,
+            )?,
+            TradeDirection::BtoA => self.swap_b_to_a_exact_out(
+                destination_amount,
+                swap_source_amount,
+                swap_destination_amount,
+            )?,
+        };
+        let source_amount_swapped = map_zero_to_none(source_amount_swapped)?;
+        let destination_amount_swapped = map_zero_to_none(destination_amount_swapped)?;
+        Some(SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped,
+        })
+    }
+
+    /// Deposits and withdrawals are disabled, same as the linear price curve
+    fn pool_tokens_to_trading_tokens(
+        &self,
+        _pool_tokens: u128,
+        _pool_token_supply: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _round_direction: RoundDirection,
+    ) -> Option<TradingTokenResult> {
+        // this causes a panic if withdraw_all_token_types is called but that's ok for now, cheap way of
+        // disabling withdrawals without having to change how SwapCurve works
+        None
+    }
+
+    /// Deposits are disabled, same as the linear price curve
+    fn deposit_single_token_type(
+        &self,
+        _source_amount: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _pool_supply: u128,
+        _trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        // this never gets called since allows_withdrawals is false (would panic otherwise so still safe)
+        None
+    }
+
+    /// Withdrawals are disabled, and fees are paid out in trading tokens instead of being converted into
+    /// pool tokens (see `collects_fees_in_trading_tokens`)
+    fn withdraw_single_token_type_exact_out(
+        &self,
+        _source_amount: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _pool_supply: u128,
+        _trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        // this causes a panic if SwapCurve.withdraw_single_token_type_exact_out instruction is called
+        // but that's ok for now, cheap way of disabling withdrawals without having to change how SwapCurve works
+        None
+    }
+
+    /// Validate that the given curve has no invalid parameters
+    /// Called on `initialize` - every slope must be positive and the breakpoints must start at 0 and be strictly
+    /// increasing, but initial point can be (0,0)
+    fn validate(&self) -> Result<(), SwapError> {
+        match is_curve_param_valid(self) {
+            Some(_val) => Ok(()),
+            None => Err(SwapError::InvalidCurve),
+        }
+    }
+
+    /// Validate the given supply on initialization.
+    /// We require at least some bonded token B for the curve to be useful (collateral token must be 0)
+    fn validate_supply(&self, token_a_amount: u64, token_b_amount: u64) -> Result<(), SwapError> {
+        if token_b_amount == 0 {
+            return Err(SwapError::EmptySupply);
+        }
+
+        if token_a_amount != 0 {
+            return Err(SwapError::InvalidSupply);
+        }
+        Ok(())
+    }
+
+    /// Both deposits and withdrawals are intentionally disabled
+    fn allows_deposits(&self) -> bool {
+        false
+    }
+
+    /// The curve position is derived from the amount of token A in the swap, so fees can't be left in the
+    /// swap token accounts (they'd look like bonded collateral and move the price)
+    fn collects_fees_in_trading_tokens(&self) -> bool {
+        true
+    }
+
+    /// The spot price is the price formula of the segment that b is in, where b is derived from the amount of
+    /// token A locked in the curve. Returned with a denominator of `dfs_precise_number::ONE`
+    fn spot_price(
+        &self,
+        swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+    ) -> Option<(u128, u128)> {
+        let b_value =
+            self.b_value_with_amt_a_locked(&(DFSPreciseNumber::new(swap_token_a_amount)?), false)?;
+        let price = self.price_at_b_value(&b_value)?;
+
+        // the raw DFSPreciseNumber value is the numerator over ONE, as long as it fits in a u128
+        if price.value.bits() > 128 {
+            return None;
+        }
+        Some((price.value.as_u128(), ONE))
+    }
+
+    /// The total normalized value of the piecewise linear curve adds the total
+    /// value of the token A side (as denominated in token B) to the token B side.
+    fn normalized_value(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<spl_math::precise_number::PreciseNumber> {
+        let b_value_of_a =
+            self.b_value_with_amt_a_locked(&(DFSPreciseNumber::new(swap_token_a_amount)?), false)?;
+        let total_value =
+            b_value_of_a.checked_add(&(DFSPreciseNumber::new(swap_token_b_amount)?))?;
+
+        // same as the linear price curve, truncate to 32 bits so that rounding errors (which are rounded in
+        // favor of the swap when actually swapping) don't show up as a decrease in value
+        let value_bits = total_value.value.bits();
+        let truncated_value = match value_bits > 32 {
+            true => total_value.value >> (value_bits - 32),
+            false => total_value.value,
+        };
+
+        Some(spl_math::precise_number::PreciseNumber {
+            value: truncated_value,
+        })
+    }
+
+    /// Packs the segments into the curve params account, which must not have been initialized yet
+    fn pack_curve_params(&self, output: &mut [u8]) -> Result<(), ProgramError> {
+        if PiecewiseLinearSegments::unpack_unchecked(output)?.is_initialized {
+            return Err(SwapError::AlreadyInUse.into());
+        }
+        PiecewiseLinearSegments::pack(self.segments.clone(), output)
+    }
+}
+
+/// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
+impl IsInitialized for PiecewiseLinearCurve {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+impl Sealed for PiecewiseLinearCurve {}
+impl Pack for PiecewiseLinearCurve {
+    const LEN: usize = 0;
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        (self as &dyn DynPack).pack_into_slice(output);
+    }
+
+    /// Nothing is stored in the swap, the segments have to be loaded from the curve params account separately
+    /// (see `SwapCurve::load_curve_params`)
+    fn unpack_from_slice(_input: &[u8]) -> Result<PiecewiseLinearCurve, ProgramError> {
+        Ok(Self {
+            segments: PiecewiseLinearSegments::default(),
+        })
+    }
+}
+
+impl DynPack for PiecewiseLinearCurve {
+    fn pack_into_slice(&self, _output: &mut [u8]) {}
+}
+
+impl IsInitialized for PiecewiseLinearSegments {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+impl Sealed for PiecewiseLinearSegments {}
+impl Pack for PiecewiseLinearSegments {
+    /// 1 byte for is_initialized, 16 for the initial price, 1 for the number of segments and
+    /// 24 for each of the MAX_SEGMENTS segments
+    const LEN: usize = 402;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 402];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            initial_token_a_price_numerator,
+            initial_token_a_price_denominator,
+            segment_count,
+            segments,
+        ) = mut_array_refs![output, 1, 8, 8, 1, SEGMENT_LEN * MAX_SEGMENTS];
+        is_initialized[0] = self.is_initialized as u8;
+        *initial_token_a_price_numerator = self.initial_token_a_price_numerator.to_le_bytes();
+        *initial_token_a_price_denominator = self.initial_token_a_price_denominator.to_le_bytes();
+        segment_count[0] = self.segments.len() as u8;
+        for (segment, output) in self
+            .segments
+            .iter()
+            .zip(segments.chunks_exact_mut(SEGMENT_LEN))
+        {
+            let output = array_mut_ref![output, 0, SEGMENT_LEN];
+            let (breakpoint, slope_numerator, slope_denominator) = mut_array_refs![output, 8, 8, 8];
+            *breakpoint = segment.breakpoint.to_le_bytes();
+            *slope_numerator = segment.slope_numerator.to_le_bytes();
+            *slope_denominator = segment.slope_denominator.to_le_bytes();
+        }
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<PiecewiseLinearSegments, ProgramError> {
+        let input = array_ref![input, 0, 402];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            initial_token_a_price_numerator,
+            initial_token_a_price_denominator,
+            segment_count,
+            segments,
+        ) = array_refs![input, 1, 8, 8, 1, SEGMENT_LEN * MAX_SEGMENTS];
+        let segment_count = segment_count[0] as usize;
+        if segment_count > MAX_SEGMENTS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            initial_token_a_price_numerator: u64::from_le_bytes(*initial_token_a_price_numerator),
+            initial_token_a_price_denominator: u64::from_le_bytes(
+                *initial_token_a_price_denominator,
+            ),
+            segments: segments
+                .chunks_exact(SEGMENT_LEN)
+                .take(segment_count)
+                .map(|input| {
+                    let input = array_ref![input, 0, SEGMENT_LEN];
+                    #[allow(clippy::ptr_offset_with_cast)]
+                    let (breakpoint, slope_numerator, slope_denominator) =
+                        array_refs![input, 8, 8, 8];
+                    PiecewiseLinearSegment {
+                        breakpoint: u64::from_le_bytes(*breakpoint),
+                        slope_numerator: u64::from_le_bytes(*slope_numerator),
+                        slope_denominator: u64::from_le_bytes(*slope_denominator),
+                    }
+                })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::{
+        base::{CurveType, SwapCurve},
+        calculator::test::check_curve_value_from_swap,
+    };
+    use proptest::prelude::*;
+
+    /// Returns an initialized curve from (breakpoint, slope_numerator, slope_denominator) segments
+    fn test_curve(
+        initial_token_a_price_numerator: u64,
+        initial_token_a_price_denominator: u64,
+        segments: &[(u64, u64, u64)],
+    ) -> PiecewiseLinearCurve {
+        PiecewiseLinearCurve {
+            segments: PiecewiseLinearSegments {
+                is_initialized: true,
+                initial_token_a_price_numerator,
+                initial_token_a_price_denominator,
+                segments: segments
+                    .iter()
+                    .map(|&(breakpoint, slope_numerator, slope_denominator)| {
+                        PiecewiseLinearSegment {
+                            breakpoint,
+                            slope_numerator,
+                            slope_denominator,
+                        }
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    /// Price starts at 0 and goes up by 2 per B until b = 10 (A(b) = b^2), then by 4 per B until b = 12
+    /// (A(b) = 100 + 20(b-10) + 2(b-10)^2), then by 1 per B (A(b) = 148 + 28(b-12) + 0.5(b-12)^2)
+    fn three_segment_curve() -> PiecewiseLinearCurve {
+        test_curve(0, 1, &[(0, 2, 1), (10, 4, 1), (12, 1, 1)])
+    }
+
+    #[test]
+    fn swap_a_to_b_basic() {
+        // single segment is the same as linear_price::tests::swap_a_to_b_basic
+        let curve = test_curve(150, 3, &[(0, 1, 2)]);
+
+        // put in 101 A, should get 2 B out
+        let (source_amount, destination_amount) = curve.swap_a_to_b(101, 0, 5000).unwrap();
+        assert_eq!(source_amount, 101);
+        assert_eq!(destination_amount, 2);
+
+        // put in 103 A, should get 2 more B out
+        let (source_amount, destination_amount) = curve.swap_a_to_b(103, 101, 4998).unwrap();
+        assert_eq!(source_amount, 103);
+        assert_eq!(destination_amount, 2);
+
+        let curve = three_segment_curve();
+
+        // put in 64 A, should get 8 B out (all in the first segment)
+        let (source_amount, destination_amount) = curve.swap_a_to_b(64, 0, 1000).unwrap();
+        assert_eq!(source_amount, 64);
+        assert_eq!(destination_amount, 8);
+
+        // put in 84 A at 64 A locked, should get 4 more B out (crossing into the second segment)
+        let (source_amount, destination_amount) = curve.swap_a_to_b(84, 64, 992).unwrap();
+        assert_eq!(source_amount, 84);
+        assert_eq!(destination_amount, 4);
+
+        // put in 206 A, should get 14 B out (crossing both breakpoints in one swap)
+        let (source_amount, destination_amount) = curve.swap_a_to_b(206, 0, 1000).unwrap();
+        assert_eq!(source_amount, 206);
+        assert_eq!(destination_amount, 14);
+
+        // putting in 1000 A @ 64 A locked/4 B remaining should give out the last 4 B and only take 84 A
+        let (source_amount, destination_amount) = curve.swap_a_to_b(1000, 64, 4).unwrap();
+        assert_eq!(source_amount, 84);
+        assert_eq!(destination_amount, 4);
+    }
+
+    #[test]
+    fn swap_b_to_a_basic() {
+        // single segment is the same as linear_price::tests::swap_b_to_a_basic
+        let curve = test_curve(150, 3, &[(0, 1, 2)]);
+
+        // put in 2 B at 101 A, should get 101 A out
+        let (source_amount, destination_amount) = curve.swap_b_to_a(2, 4998, 101).unwrap();
+        assert_eq!(source_amount, 2);
+        assert_eq!(destination_amount, 101);
+
+        let curve = three_segment_curve();
+
+        // put in 4 B at 148 A (b = 12), should get 84 A out (crossing back into the first segment)
+        let (source_amount, destination_amount) = curve.swap_b_to_a(4, 988, 148).unwrap();
+        assert_eq!(source_amount, 4);
+        assert_eq!(destination_amount, 84);
+
+        // put in 14 B at 206 A (b = 14), should get all 206 A out (crossing both breakpoints in one swap)
+        let (source_amount, destination_amount) = curve.swap_b_to_a(14, 986, 206).unwrap();
+        assert_eq!(source_amount, 14);
+        assert_eq!(destination_amount, 206);
+
+        // put in 100 B at 206 A, should get all 206 A out and only take 14 B
+        let (source_amount, destination_amount) = curve.swap_b_to_a(100, 986, 206).unwrap();
+        assert_eq!(source_amount, 14);
+        assert_eq!(destination_amount, 206);
+    }
+
+    #[test]
+    fn swap_exact_out_basic() {
+        let curve = three_segment_curve();
+
+        // the inverses of swap_a_to_b_basic and swap_b_to_a_basic
+
+        // get out 14 B at 0 A, should cost 206 A
+        let (source_amount, destination_amount) = curve.swap_a_to_b_exact_out(14, 0, 1000).unwrap();
+        assert_eq!(source_amount, 206);
+        assert_eq!(destination_amount, 14);
+
+        // get out 4 B at 64 A, should cost 84 A
+        let (source_amount, destination_amount) = curve.swap_a_to_b_exact_out(4, 64, 992).unwrap();
+        assert_eq!(source_amount, 84);
+        assert_eq!(destination_amount, 4);
+
+        // get out 84 A at 148 A, should cost 4 B
+        let (source_amount, destination_amount) =
+            curve.swap_b_to_a_exact_out(84, 988, 148).unwrap();
+        assert_eq!(source_amount, 4);
+        assert_eq!(destination_amount, 84);
+
+        // get out 85 A at 148 A, should cost 5 B (rounded up from ~4.06)
+        let (source_amount, destination_amount) =
+            curve.swap_b_to_a_exact_out(85, 988, 148).unwrap();
+        assert_eq!(source_amount, 5);
+        assert_eq!(destination_amount, 85);
+
+        // can't get out more than what's in the swap
+        assert_eq!(curve.swap_a_to_b_exact_out(1001, 0, 1000), None);
+        assert_eq!(curve.swap_b_to_a_exact_out(149, 988, 148), None);
+    }
+
+    #[test]
+    fn spot_price_basic() {
+        let curve = three_segment_curve();
+
+        // b = 0, 8, 10 (the first breakpoint), 12 (the second breakpoint) and 14
+        assert_eq!(curve.spot_price(0, 1000), Some((0, ONE)));
+        assert_eq!(curve.spot_price(64, 992), Some((16 * ONE, ONE)));
+        assert_eq!(curve.spot_price(100, 990), Some((20 * ONE, ONE)));
+        assert_eq!(curve.spot_price(148, 988), Some((28 * ONE, ONE)));
+        assert_eq!(curve.spot_price(206, 986), Some((30 * ONE, ONE)));
+    }
+
+    #[test]
+    fn segments_not_loaded() {
+        // curves unpacked from the swap don't have their segments, every calculation should fail until the
+        // segments are loaded
+        let curve = three_segment_curve();
+        let mut packed = [0u8; PiecewiseLinearCurve::LEN];
+        Pack::pack_into_slice(&curve, &mut packed[..]);
+        let unpacked = PiecewiseLinearCurve::unpack_from_slice(&packed).unwrap();
+        assert_eq!(unpacked.segments, PiecewiseLinearSegments::default());
+
+        assert_eq!(
+            unpacked.swap_without_fees(206, 0, 1000, TradeDirection::AtoB),
+            None
+        );
+        assert_eq!(unpacked.spot_price(206, 1000), None);
+        assert_eq!(unpacked.validate(), Err(SwapError::InvalidCurve));
+    }
+
+    #[test]
+    fn pack_segments() {
+        let curve = test_curve(
+            u64::MAX,
+            u64::MAX - 1,
+            &[(0, 1, 2), (10, u64::MAX, 3), (u64::MAX, 5, u64::MAX)],
+        );
+
+        let mut packed = [0u8; PiecewiseLinearSegments::LEN];
+        PiecewiseLinearSegments::pack(curve.segments.clone(), &mut packed).unwrap();
+        let unpacked = PiecewiseLinearSegments::unpack(&packed).unwrap();
+        assert_eq!(curve.segments, unpacked);
+
+        let mut packed = vec![1u8];
+        packed.extend_from_slice(&u64::MAX.to_le_bytes());
+        packed.extend_from_slice(&(u64::MAX - 1).to_le_bytes());
+        packed.push(3);
+        for segment in curve.segments.segments.iter() {
+            packed.extend_from_slice(&segment.breakpoint.to_le_bytes());
+            packed.extend_from_slice(&segment.slope_numerator.to_le_bytes());
+            packed.extend_from_slice(&segment.slope_denominator.to_le_bytes());
+        }
+        packed.resize(PiecewiseLinearSegments::LEN, 0);
+        let unpacked = PiecewiseLinearSegments::unpack(&packed).unwrap();
+        assert_eq!(curve.segments, unpacked);
+
+        // more segments than MAX_SEGMENTS
+        packed[17] = MAX_SEGMENTS as u8 + 1;
+        assert_eq!(
+            PiecewiseLinearSegments::unpack(&packed),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn load_curve_params() {
+        let curve = three_segment_curve();
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::PiecewiseLinear,
+            calculator: Box::new(curve.clone()),
+        };
+
+        // the curve params account has to be uninitialized
+        let mut curve_params_data = [0u8; PiecewiseLinearSegments::LEN];
+        swap_curve
+            .calculator
+            .pack_curve_params(&mut curve_params_data)
+            .unwrap();
+        assert_eq!(
+            swap_curve
+                .calculator
+                .pack_curve_params(&mut curve_params_data),
+            Err(SwapError::AlreadyInUse.into())
+        );
+
+        // round trip through the swap and the curve params account
+        let mut packed = [0u8; SwapCurve::LEN];
+        Pack::pack_into_slice(&swap_curve, &mut packed[..]);
+        let unpacked = SwapCurve::unpack_from_slice(&packed).unwrap();
+        assert_eq!(
+            unpacked
+                .calculator
+                .swap_without_fees(206, 0, 1000, TradeDirection::AtoB),
+            None
+        );
+        let loaded = unpacked.load_curve_params(&curve_params_data).unwrap();
+        assert_eq!(loaded.curve_type, CurveType::PiecewiseLinear);
+        assert_eq!(
+            CurveType::PiecewiseLinear.curve_params_len(),
+            Some(PiecewiseLinearSegments::LEN)
+        );
+        assert_eq!(
+            loaded
+                .calculator
+                .swap_without_fees(206, 0, 1000, TradeDirection::AtoB),
+            curve.swap_without_fees(206, 0, 1000, TradeDirection::AtoB)
+        );
+
+        // an uninitialized curve params account can't be loaded
+        assert_eq!(
+            unpacked.load_curve_params(&[0u8; PiecewiseLinearSegments::LEN]),
+            Err(ProgramError::UninitializedAccount)
+        );
+    }
+
+    #[test]
+    fn validate_curve_params() {
+        assert!(three_segment_curve().validate().is_ok());
+
+        let too_many_segments: Vec<(u64, u64, u64)> = (0..=MAX_SEGMENTS as u64)
+            .map(|breakpoint| (breakpoint, 1, 1))
+            .collect();
+        let max_segments = &too_many_segments[..MAX_SEGMENTS];
+        assert!(test_curve(0, 1, max_segments).validate().is_ok());
+
+        for invalid_curve in [
+            // no segments
+            test_curve(0, 1, &[]),
+            test_curve(0, 1, &too_many_segments),
+            test_curve(0, 0, &[(0, 1, 1)]),
+            // first segment doesn't start at 0
+            test_curve(0, 1, &[(1, 1, 1)]),
+            // breakpoints aren't strictly increasing
+            test_curve(0, 1, &[(0, 1, 1), (10, 1, 1), (10, 1, 1)]),
+            test_curve(0, 1, &[(0, 1, 1), (10, 1, 1), (5, 1, 1)]),
+            test_curve(0, 1, &[(0, 1, 1), (10, 0, 1)]),
+            test_curve(0, 1, &[(0, 1, 1), (10, 1, 0)]),
+            // slope rounds to 0
+            test_curve(0, 1, &[(0, 1, 1), (10, 1, u64::MAX)]),
+        ] {
+            assert_eq!(invalid_curve.validate(), Err(SwapError::InvalidCurve));
+        }
+
+        let mut uninitialized_curve = three_segment_curve();
+        uninitialized_curve.segments.is_initialized = false;
+        assert_eq!(uninitialized_curve.validate(), Err(SwapError::InvalidCurve));
+    }
+
+    /// Realistic curve with 8 decimals on both tokens, price starts at 0.5 A per B, goes up by 1 A every 100 B
+    /// until 100 B, then by 10 A every 100 B until 1000 B, then flattens out to 0.1 A every 100 B
+    fn realistic_curve() -> PiecewiseLinearCurve {
+        test_curve(
+            1,
+            2,
+            &[
+                (0, 1, 100_0000_0000),
+                (100_0000_0000, 10, 100_0000_0000),
+                (1000_0000_0000, 1, 1000_0000_0000),
+            ],
+        )
+    }
+
+    #[test]
+    fn swap_round_trip_does_not_give_free_tokens() {
+        let curve = realistic_curve();
+
+        // buying and immediately selling back should never return more A than was put in
+        let mut swap_token_a_amount = 0u128;
+        let mut swap_token_b_amount = 1_000_000_000_000u128;
+        for amount_a in [
+            1u128,
+            3,
+            1_000,
+            123_456_789,
+            100_000_000_000,
+            5_000_000_000_000,
+        ] {
+            let (source_amount, destination_amount) =
+                match curve.swap_a_to_b(amount_a, swap_token_a_amount, swap_token_b_amount) {
+                    Some((_, 0)) | None => continue,
+                    Some(result) => result,
+                };
+            let (_, amount_a_back) = curve
+                .swap_b_to_a(
+                    destination_amount,
+                    swap_token_b_amount - destination_amount,
+                    swap_token_a_amount + source_amount,
+                )
+                .unwrap();
+            assert!(amount_a_back <= source_amount);
+
+            // leave the first trade in the swap so the next one starts further up the curve
+            swap_token_a_amount += source_amount;
+            swap_token_b_amount -= destination_amount;
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_swap_a_to_b(
+            // how much a user is swapping in
+            source_token_amount in 1..u64::MAX,
+            // how much a is already in swap (determines spot price)
+            swap_source_amount in 1..u64::MAX,
+        ) {
+            let curve = realistic_curve();
+
+            let (_source_amount_swapped, destination_amount_swapped) = curve
+                .swap_a_to_b(
+                    source_token_amount as u128,
+                    swap_source_amount as u128,
+                    u64::MAX as u128,
+                )
+                .unwrap();
+
+            // ignore the trades where not enough source_token_amount was put in to get any b out
+            if destination_amount_swapped > 0 {
+                check_curve_value_from_swap(
+                    &curve,
+                    source_token_amount as u128,
+                    swap_source_amount as u128,
+                    // swap_destination_amount (the amount of token b in the swap) doesn't affect any of the math
+                    u64::MAX as u128,
+                    TradeDirection::AtoB
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_swap_b_to_a(
+            // how much b user is swapping in
+            source_token_amount in 1..u64::MAX,
+            // how much a is already in swap (determines spot price)
+            swap_destination_amount in 1..u64::MAX,
+        ) {
+            let curve = realistic_curve();
+
+            let (_source_amount_swapped, destination_amount_swapped) = curve
+                .swap_b_to_a(
+                    source_token_amount as u128,
+                    u64::MAX as u128,
+                    swap_destination_amount as u128,
+                )
+                .unwrap();
+
+            // ignore the trades where not enough source_token_amount was put in to get any a out
+            if destination_amount_swapped > 0 {
+                check_curve_value_from_swap(
+                    &curve,
+                    source_token_amount as u128,
+                    // swap_source_amount (the amount of token b in the swap) doesn't affect any of the math
+                    u64::MAX as u128,
+                    swap_destination_amount as u128,
+                    TradeDirection::BtoA
+                );
+            }
+        }
+    }
+}
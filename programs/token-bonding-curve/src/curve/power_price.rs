@@ -0,0 +1,1089 @@
+//! Power price swap curve, slope, exponent and initial price point set at init
+//! The linear price curve is the exponent = 1 case of this, but that one solves its integral with the quadratic
+//! formula while this one has to use Newton's method (see `DFSPreciseNumber::newton_root`)
+//! Works under the same assumptions as the linear price curve (see `linear_price.rs`):
+//! Deposits (except the initial deposit) are disabled
+//! The initial deposit should only have token B (the bonded token) and 0 token A (the collateral token)
+//! Trade and owner fees are taken in the source token and either sent straight to a fee token account or set aside
+//! in the swap for `claim_fees` (see `SwapV2::accrue_fees`), so they never count as liquidity or move the price
+//! (see `collects_fees_in_trading_tokens`)
+//! Withdrawals are disabled, will panic if those instructions are called
+
+use {
+    crate::{
+        curve::calculator::{
+            map_zero_to_none, CurveCalculator, DynPack, RoundDirection, SwapWithoutFeesResult,
+            TradeDirection, TradingTokenResult,
+        },
+        dfs_precise_number::{DFSPreciseNumber, ONE},
+        error::SwapError,
+    },
+    arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
+    solana_program::{
+        program_error::ProgramError,
+        program_pack::{IsInitialized, Pack, Sealed},
+    },
+};
+
+/// Largest supported exponent, b^(exponent + 1) has to fit in a PreciseNumber
+pub const MAX_EXPONENT: u8 = 4;
+
+/// Largest supported token_b_decimals, 10^token_b_decimals has to fit in a u64
+pub const MAX_TOKEN_B_DECIMALS: u8 = 19;
+
+/// PowerPriceCurve struct implementing CurveCalculator
+/// A is the "collateral" token (e.g. RLY), B is the "bonded" token (e.g. TAKI).
+/// The price of a single B token (a, denominated in amount of token A) is defined by
+/// `a = slope*(b / 10^token_b_decimals)^exponent + initial_token_a_price`
+/// where b is the amount of token B that's been swapped out of this curve
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PowerPriceCurve {
+    /// How much the price of token B increases for every whole token B (raised to `exponent`) that's been swapped
+    /// out of the curve numerator
+    pub slope_numerator: u64,
+    /// How much the price of token B increases for every whole token B (raised to `exponent`) that's been swapped
+    /// out of the curve denominator
+    pub slope_denominator: u64,
+    /// When there's 0 liquidity in the pool, what should the initial price point a0 defining the curve be?
+    /// i.e. what is the cost of 1 b token (denominated in A) when there's 0 liquidity
+    pub initial_token_a_price_numerator: u64,
+    /// When there's 0 liquidity in the pool, what should the initial price point a0 defining the curve be?
+    /// i.e. what is the cost of 1 b token (denominated in A) when there's 0 liquidity
+    pub initial_token_a_price_denominator: u32,
+    /// Exponent of the price curve, e.g. 2 for a quadratic price and 3 for a cubic price
+    pub exponent: u8,
+    /// b is converted to whole tokens (divided by 10^token_b_decimals) before it's raised to `exponent` so that
+    /// b^(exponent + 1) doesn't overflow, set to 0 to use base units of token B instead
+    pub token_b_decimals: u8,
+}
+
+/// These functions use the integral of the power price curve to determine liquidity of A at a
+/// given B value (amt_a_locked_at_b_value)
+/// It also uses Newton's method to solve the same integral to determine the B value for a given
+/// liquidity (b_value_with_amt_a_locked)
+///
+/// swap_a_to_b and swap_b_to_a are the key functions at the bottom
+impl PowerPriceCurve {
+    /// 10^token_b_decimals as a PreciseNumber
+    fn token_b_unit(&self) -> Option<DFSPreciseNumber> {
+        DFSPreciseNumber::new(10u128.checked_pow(self.token_b_decimals.into())?)
+    }
+
+    /// Returns the amount of A token locked at a given b_value (by plugging b_value into the integral function)
+    fn amt_a_locked_at_b_value(&self, b_value: &DFSPreciseNumber) -> Option<DFSPreciseNumber> {
+        // With x = b/u (u = 10^token_b_decimals), the liquidity integral is
+        // `token_a_bonded = m*u*x^(n+1)/(n+1) + a0*b + 0` (integration constant is 0 since we know
+        // there's 0 token A bonded at b = 0)
+
+        // m * u * x^(n+1) / (n+1) - make sure to do division last
+        let token_b_unit = self.token_b_unit()?;
+        let x = b_value.checked_div(&token_b_unit)?;
+        let mut x_power = x.clone();
+        for _ in 0..self.exponent {
+            x_power = x_power.checked_mul(&x)?;
+        }
+        let m_u_x_power = DFSPreciseNumber::new(self.slope_numerator.into())?
+            .checked_mul(&x_power)?
+            .checked_mul(&token_b_unit)?
+            .checked_div(&(DFSPreciseNumber::new(self.slope_denominator.into())?))?
+            .checked_div(&(DFSPreciseNumber::new(u128::from(self.exponent) + 1)?))?;
+
+        // a0 * b (note a0 and b are always positive) - make sure to do division last
+        let a0_times_b = DFSPreciseNumber::new(self.initial_token_a_price_numerator.into())?
+            .checked_mul(b_value)?
+            .checked_div(
+                &(DFSPreciseNumber::new(self.initial_token_a_price_denominator.into())?),
+            )?;
+
+        m_u_x_power.checked_add(&a0_times_b)
+    }
+
+    /// Returns the price of token B at a given b_value, i.e. the derivative of amt_a_locked_at_b_value
+    fn price_at_b_value(&self, b_value: &DFSPreciseNumber) -> Option<DFSPreciseNumber> {
+        let x = b_value.checked_div(&(self.token_b_unit()?))?;
+        let mut x_power = DFSPreciseNumber::new(1)?;
+        for _ in 0..self.exponent {
+            x_power = x_power.checked_mul(&x)?;
+        }
+        let m_x_power = DFSPreciseNumber::new(self.slope_numerator.into())?
+            .checked_mul(&x_power)?
+            .checked_div(&(DFSPreciseNumber::new(self.slope_denominator.into())?))?;
+        let a0 = DFSPreciseNumber::new(self.initial_token_a_price_numerator.into())?.checked_div(
+            &(DFSPreciseNumber::new(self.initial_token_a_price_denominator.into())?),
+        )?;
+
+        m_x_power.checked_add(&a0)
+    }
+
+    /// Returns the b value for token_a_amount = m*u*x^(n+1)/(n+1) + a0*b + 0
+    /// (integration constant is always 0 since we know there's 0 token A bonded at b = 0)
+    fn b_value_with_amt_a_locked(
+        &self,
+        token_a_amount: &DFSPreciseNumber,
+        should_round_up: bool,
+    ) -> Option<DFSPreciseNumber> {
+        // both halves of the integral are <= token_a_amount, so their roots are both upper bounds for b, start
+        // Newton's method from whichever one is lower
+        // a0*b <= token_a_amount => b <= token_a_amount / a0
+        let linear_bound = match self.initial_token_a_price_numerator {
+            0 => None,
+            _ => Some(
+                token_a_amount
+                    .checked_mul(
+                        &(DFSPreciseNumber::new(self.initial_token_a_price_denominator.into())?),
+                    )?
+                    .checked_div(
+                        &(DFSPreciseNumber::new(self.initial_token_a_price_numerator.into())?),
+                    )?,
+            ),
+        };
+
+        // m*u*x^(n+1)/(n+1) <= token_a_amount => x <= (token_a_amount*(n+1)/(m*u))^(1/(n+1))
+        let token_b_unit = self.token_b_unit()?;
+        let exponent_plus_one = DFSPreciseNumber::new(u128::from(self.exponent) + 1)?;
+        let x_power_bound = token_a_amount
+            .checked_mul(&exponent_plus_one)?
+            .checked_mul(&(DFSPreciseNumber::new(self.slope_denominator.into())?))?
+            .checked_div(&(DFSPreciseNumber::new(self.slope_numerator.into())?))?
+            .checked_div(&token_b_unit)?;
+        // ln is only defined for numbers >= 1, but if x^(n+1) < 1 then x < 1 too
+        let x_bound = match x_power_bound.less_than(&(DFSPreciseNumber::new(1)?)) {
+            true => DFSPreciseNumber::new(1)?,
+            false => x_power_bound.ln()?.checked_div(&exponent_plus_one)?.exp()?,
+        };
+        let power_bound = x_bound.checked_mul(&token_b_unit)?;
+
+        let initial_guess = match linear_bound {
+            Some(linear_bound) if linear_bound.less_than(&power_bound) => linear_bound,
+            _ => power_bound,
+        };
+
+        DFSPreciseNumber::newton_root(
+            token_a_amount,
+            initial_guess,
+            |b_value| self.amt_a_locked_at_b_value(b_value),
+            |b_value| self.price_at_b_value(b_value),
+            should_round_up,
+        )
+    }
+
+    /// If `source_amount` will cause the swap to return all of its remaining `swap_destination_amount`,
+    /// this returns the (maximum_token_a_amount, swap_destination_amount) that the swap can take
+    /// Otherwise (if there's enough `swap_destination_amount` to handle all the `source_amount`), returns None
+    fn maximum_a_remaining_for_swap_a_to_b(
+        &self,
+        a_start: &DFSPreciseNumber,
+        b_start: &DFSPreciseNumber,
+        source_amount: u128,
+        swap_destination_amount: u128,
+    ) -> Option<(u128, u128)> {
+        // if at b_start + swap_destination_amount (the maximum B that be given out by the swap),
+        // then the A value is <= source_amount, so only take that amount of A instead and give them all the
+        // Bs remaining
+        let maximum_b_value =
+            b_start.checked_add(&(DFSPreciseNumber::new(swap_destination_amount)?))?;
+        let maximum_a_locked = self.amt_a_locked_at_b_value(&maximum_b_value)?;
+        let maximum_a_remaining = maximum_a_locked
+            .checked_sub(a_start)?
+            .ceiling()?
+            .to_imprecise()?;
+
+        match maximum_a_remaining <= source_amount {
+            true => Some((maximum_a_remaining, swap_destination_amount)),
+            false => None,
+        }
+    }
+
+    /// Swap's in user's collateral token and returns out the bonded token,
+    /// moving right on the price curve and increasing the price of the bonded token
+    fn swap_a_to_b(
+        &self,
+        source_amount: u128,      // amount of user's token a (collateral token)
+        swap_source_amount: u128, // swap's token a (collateral token)
+        swap_destination_amount: u128, // swap's remaining token b (bonded token)
+    ) -> Option<(u128, u128)> {
+        let a_start = DFSPreciseNumber::new(swap_source_amount)?;
+
+        // make sure we round up here so that b_start is over-estimated, which rounds down the final token b output
+        let b_start = self.b_value_with_amt_a_locked(&a_start, true)?;
+
+        // no need to return None here if this fails, can just skip this check and do real calculation below
+        if let Some(val) = self.maximum_a_remaining_for_swap_a_to_b(
+            &a_start,
+            &b_start,
+            source_amount,
+            swap_destination_amount,
+        ) {
+            return Some(val);
+        }
+
+        // otherwise, there's enough B tokens for all the A they put in, find the b_end value for the amount of A
+        // they're putting in and give them `b_end - b_start` tokens out
+        let a_end = a_start.checked_add(&(DFSPreciseNumber::new(source_amount)?))?;
+
+        let b_end = self.b_value_with_amt_a_locked(&a_end, false)?;
+
+        // b_end can be below b_start if source_amount is too small to move the curve at all
+        let (difference, is_negative) = b_end.unsigned_sub(&b_start);
+        if is_negative {
+            return Some((source_amount, 0));
+        }
+        // PreciseNumber rounds .5+ up by default, make sure to floor instead so we don't allow
+        // dust to round up for free
+        let destination_amount = difference.floor()?.to_imprecise()?;
+
+        Some((source_amount, destination_amount))
+    }
+
+    fn swap_b_to_a(
+        &self,
+        source_amount: u128,
+        _swap_source_amount: u128,
+        swap_destination_amount: u128,
+    ) -> Option<(u128, u128)> {
+        // make sure we round up here so that b_end and a_end are also over-estimated, which rounds down the final
+        // token a output
+        let b_start = self
+            .b_value_with_amt_a_locked(&(DFSPreciseNumber::new(swap_destination_amount)?), true)?;
+
+        // b_end can be negative if the user put in too many B tokens (handled below)
+        let (b_end, b_end_is_negative) =
+            b_start.unsigned_sub(&(DFSPreciseNumber::new(source_amount)?));
+
+        // if b_end < 0 (i.e. there aren't enough A tokens in the swap for all the B tokens they put in),
+        // then just give them all of the a tokens (swap_destination_amount) and only take the B tokens required to
+        // get down from b_start to 0. this only works if we assume 0 A locked at b = 0
+        if b_end_is_negative {
+            return Some((b_start.ceiling()?.to_imprecise()?, swap_destination_amount));
+        }
+
+        // make sure to use b_end.ceiling() so we don't round in favor of the user
+        let a_end = self.amt_a_locked_at_b_value(&(b_end.ceiling()?))?;
+
+        // a_end can be above swap_destination_amount if source_amount is too small to move the curve at all
+        let (difference, is_negative) =
+            DFSPreciseNumber::new(swap_destination_amount)?.unsigned_sub(&a_end);
+        if is_negative {
+            return Some((source_amount, 0));
+        }
+        // PreciseNumber rounds .5+ up by default, make sure to floor instead so we don't allow
+        // dust to round up for free
+        let destination_amount = difference.floor()?.to_imprecise()?;
+
+        Some((source_amount, destination_amount))
+    }
+
+    /// Inverse of swap_a_to_b: returns the amount of token A the user has to put in to get exactly
+    /// `destination_amount` of token B out
+    fn swap_a_to_b_exact_out(
+        &self,
+        destination_amount: u128, // amount of token b (bonded token) the user wants out
+        swap_source_amount: u128, // swap's token a (collateral token)
+        swap_destination_amount: u128, // swap's remaining token b (bonded token)
+    ) -> Option<(u128, u128)> {
+        // unlike swap_a_to_b we can't just hand out whatever's left, the user asked for an exact amount
+        if destination_amount > swap_destination_amount {
+            return None;
+        }
+
+        let a_start = DFSPreciseNumber::new(swap_source_amount)?;
+
+        // make sure we round up here so that b_end and a_end are also over-estimated, which rounds up the
+        // token a input
+        let b_start = self.b_value_with_amt_a_locked(&a_start, true)?;
+        let b_end = b_start.checked_add(&(DFSPreciseNumber::new(destination_amount)?))?;
+        let a_end = self.amt_a_locked_at_b_value(&b_end)?;
+
+        // ceiling instead of floor here, the user pays for any dust
+        let source_amount = a_end.checked_sub(&a_start)?.ceiling()?.to_imprecise()?;
+
+        Some((source_amount, destination_amount))
+    }
+
+    /// Inverse of swap_b_to_a: returns the amount of token B the user has to put in to get exactly
+    /// `destination_amount` of token A out
+    fn swap_b_to_a_exact_out(
+        &self,
+        destination_amount: u128,
+        _swap_source_amount: u128,
+        swap_destination_amount: u128,
+    ) -> Option<(u128, u128)> {
+        // a_end can't be negative, there's only swap_destination_amount of token A bonded to the curve
+        let a_end = swap_destination_amount.checked_sub(destination_amount)?;
+
+        // round b_start up and b_end down so that the token b input (b_start - b_end) is over-estimated
+        let b_start = self
+            .b_value_with_amt_a_locked(&(DFSPreciseNumber::new(swap_destination_amount)?), true)?;
+        let b_end = self.b_value_with_amt_a_locked(&(DFSPreciseNumber::new(a_end)?), false)?;
+
+        let source_amount = b_start.checked_sub(&b_end)?.ceiling()?.to_imprecise()?;
+
+        Some((source_amount, destination_amount))
+    }
+}
+
+/// Returns None if any of the parameters are out of range
+fn is_curve_param_valid(curve: &PowerPriceCurve) -> Option<()> {
+    if curve.slope_numerator == 0
+        || curve.slope_denominator == 0
+        || curve.initial_token_a_price_denominator == 0
+        || curve.exponent == 0
+        || curve.exponent > MAX_EXPONENT
+        || curve.token_b_decimals > MAX_TOKEN_B_DECIMALS
+    {
+        return None;
+    };
+
+    // since PreciseNumber only has 18 decimals, any slope < 1e-18 will be treated as 0
+    let numerator = DFSPreciseNumber::new(curve.slope_numerator.into())?;
+    let denominator = DFSPreciseNumber::new(curve.slope_denominator.into())?;
+    let minimum = DFSPreciseNumber::new(1)?
+        .checked_div(&(DFSPreciseNumber::new(1_000_000_000_000_000_000)?))?;
+
+    match numerator
+        .checked_div(&denominator)?
+        .greater_than_or_equal(&minimum)
+    {
+        true => Some(()),
+        false => None,
+    }
+}
+
+impl CurveCalculator for PowerPriceCurve {
+    /// Calculate how much destination token will be provided given an amount
+    /// of source token.
+    fn swap_without_fees(
+        &self,
+        source_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<SwapWithoutFeesResult> {
+        let (source_amount_swapped, destination_amount_swapped) = match trade_direction {
+            TradeDirection::AtoB => {
+                self.swap_a_to_b(source_amount, swap_source_amount, swap_destination_amount)?
+            }
+            TradeDirection::BtoA => {
+                self.swap_b_to_a(source_amount, swap_source_amount, swap_destination_amount)?
+            }
+        };
+        let source_amount_swapped = map_zero_to_none(source_amount_swapped)?;
+        let destination_amount_swapped = map_zero_to_none(destination_amount_swapped)?;
+        Some(SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped,
+        })
+    }
+
+    /// Calculate how much source token is required to get exactly `destination_amount` of
+    /// destination token out (rounded up in favor of the swap)
+    fn swap_without_fees_exact_out(
+        &self,
+        destination_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<SwapWithoutFeesResult> {
+        let (source_amount_swapped, destination_amount_swapped) = match trade_direction {
+            TradeDirection::AtoB => self.swap_a_to_b_exact_out(
+                destination_amount,
+                swap_source_amount,
+                swap_destination_amount,
+            )?,
+            TradeDirection::BtoA => self.swap_b_to_a_exact_out(
+                destination_amount,
+                swap_source_amount,
+                swap_destination_amount,
+            )?,
+        };
+        let source_amount_swapped = map_zero_to_none(source_amount_swapped)?;
+        let destination_amount_swapped = map_zero_to_none(destination_amount_swapped)?;
+        Some(SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped,
+        })
+    }
+
+    /// Deposits and withdrawals are disabled, same as the linear price curve
+    fn pool_tokens_to_trading_tokens(
+        &self,
+        _pool_tokens: u128,
+        _pool_token_supply: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _round_direction: RoundDirection,
+    ) -> Option<TradingTokenResult> {
+        // this causes a panic if withdraw_all_token_types is called but that's ok for now, cheap way of
+        // disabling withdrawals without having to change how SwapCurve works
+        None
+    }
+
+    /// Deposits are disabled, same as the linear price curve
+    fn deposit_single_token_type(
+        &self,
+        _source_amount: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _pool_supply: u128,
+        _trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        // this never gets called since allows_withdrawals is false (would panic otherwise so still safe)
+        None
+    }
+
+    /// Withdrawals are disabled, and fees are paid out in trading tokens instead of being converted into
+    /// pool tokens (see `collects_fees_in_trading_tokens`)
+    fn withdraw_single_token_type_exact_out(
+        &self,
+        _source_amount: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _pool_supply: u128,
+        _trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        // this causes a panic if SwapCurve.withdraw_single_token_type_exact_out instruction is called
+        // but that's ok for now, cheap way of disabling withdrawals without having to change how SwapCurve works
+        None
+    }
+
+    /// Validate that the given curve has no invalid parameters
+    /// Called on `initialize` - slope must be positive and exponent must be between 1 and MAX_EXPONENT but initial
+    /// point can be (0,0)
+    fn validate(&self) -> Result<(), SwapError> {
+        match is_curve_param_valid(self) {
+            Some(_val) => Ok(()),
+            None => Err(SwapError::InvalidCurve),
+        }
+    }
+
+    /// Validate the given supply on initialization.
+    /// We require at least some bonded token B for the curve to be useful (collateral token must be 0)
+    fn validate_supply(&self, token_a_amount: u64, token_b_amount: u64) -> Result<(), SwapError> {
+        if token_b_amount == 0 {
+            return Err(SwapError::EmptySupply);
+        }
+
+        if token_a_amount != 0 {
+            return Err(SwapError::InvalidSupply);
+        }
+        Ok(())
+    }
+
+    /// Both deposits and withdrawals are intentionally disabled
+    fn allows_deposits(&self) -> bool {
+        false
+    }
+
+    /// The curve position is derived from the amount of token A in the swap, so fees can't be left in the
+    /// swap token accounts (they'd look like bonded collateral and move the price)
+    fn collects_fees_in_trading_tokens(&self) -> bool {
+        true
+    }
+
+    /// The spot price is just the price formula `slope*x^n + a0`, where b is derived from the amount of token A
+    /// locked in the curve. Returned with a denominator of `dfs_precise_number::ONE`
+    fn spot_price(
+        &self,
+        swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+    ) -> Option<(u128, u128)> {
+        let b_value =
+            self.b_value_with_amt_a_locked(&(DFSPreciseNumber::new(swap_token_a_amount)?), false)?;
+        let price = self.price_at_b_value(&b_value)?;
+
+        // the raw DFSPreciseNumber value is the numerator over ONE, as long as it fits in a u128
+        if price.value.bits() > 128 {
+            return None;
+        }
+        Some((price.value.as_u128(), ONE))
+    }
+
+    /// The total normalized value of the power price curve adds the total
+    /// value of the token A side (as denominated in token B) to the token B side.
+    fn normalized_value(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<spl_math::precise_number::PreciseNumber> {
+        let b_value_of_a =
+            self.b_value_with_amt_a_locked(&(DFSPreciseNumber::new(swap_token_a_amount)?), false)?;
+        let total_value =
+            b_value_of_a.checked_add(&(DFSPreciseNumber::new(swap_token_b_amount)?))?;
+
+        // same as the linear price curve, truncate to 32 bits so that rounding errors (which are rounded in
+        // favor of the swap when actually swapping) don't show up as a decrease in value
+        let value_bits = total_value.value.bits();
+        let truncated_value = match value_bits > 32 {
+            true => total_value.value >> (value_bits - 32),
+            false => total_value.value,
+        };
+
+        Some(spl_math::precise_number::PreciseNumber {
+            value: truncated_value,
+        })
+    }
+}
+
+/// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
+impl IsInitialized for PowerPriceCurve {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+impl Sealed for PowerPriceCurve {}
+impl Pack for PowerPriceCurve {
+    const LEN: usize = 32;
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        (self as &dyn DynPack).pack_into_slice(output);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<PowerPriceCurve, ProgramError> {
+        let input = array_ref![input, 0, 32];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            slope_numerator,
+            slope_denominator,
+            initial_token_a_price_numerator,
+            initial_token_a_price_denominator,
+            exponent,
+            token_b_decimals,
+            _padding,
+        ) = array_refs![input, 8, 8, 8, 4, 1, 1, 2];
+        Ok(Self {
+            slope_numerator: u64::from_le_bytes(*slope_numerator),
+            slope_denominator: u64::from_le_bytes(*slope_denominator),
+            initial_token_a_price_numerator: u64::from_le_bytes(*initial_token_a_price_numerator),
+            initial_token_a_price_denominator: u32::from_le_bytes(
+                *initial_token_a_price_denominator,
+            ),
+            exponent: exponent[0],
+            token_b_decimals: token_b_decimals[0],
+        })
+    }
+}
+
+impl DynPack for PowerPriceCurve {
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 32];
+        let (
+            slope_numerator,
+            slope_denominator,
+            initial_token_a_price_numerator,
+            initial_token_a_price_denominator,
+            exponent,
+            token_b_decimals,
+            padding,
+        ) = mut_array_refs![output, 8, 8, 8, 4, 1, 1, 2];
+        *slope_numerator = self.slope_numerator.to_le_bytes();
+        *slope_denominator = self.slope_denominator.to_le_bytes();
+        *initial_token_a_price_numerator = self.initial_token_a_price_numerator.to_le_bytes();
+        *initial_token_a_price_denominator = self.initial_token_a_price_denominator.to_le_bytes();
+        exponent[0] = self.exponent;
+        token_b_decimals[0] = self.token_b_decimals;
+        *padding = [0u8; 2];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::{
+        calculator::test::check_curve_value_from_swap, linear_price::LinearPriceCurve,
+    };
+    use proptest::prelude::*;
+
+    #[test]
+    fn swap_a_to_b_basic() {
+        // same curve as linear_price::tests::swap_a_to_b_basic
+        let curve = PowerPriceCurve {
+            slope_numerator: 1,
+            slope_denominator: 2,
+            initial_token_a_price_numerator: 150,
+            initial_token_a_price_denominator: 3, // using non-1 just to test out
+            exponent: 1,
+            token_b_decimals: 0,
+        };
+
+        // put in 101 A, should get 2 B out
+        let (source_amount, destination_amount) = curve.swap_a_to_b(101, 0, 5000).unwrap();
+        assert_eq!(source_amount, 101);
+        assert_eq!(destination_amount, 2);
+
+        // put in 103 A, should get 2 more B out
+        let (source_amount, destination_amount) = curve.swap_a_to_b(103, 101, 4998).unwrap();
+        assert_eq!(source_amount, 103);
+        assert_eq!(destination_amount, 2);
+
+        // quadratic curve a = 3b^2, so the integral is just b^3
+        let curve = PowerPriceCurve {
+            slope_numerator: 3,
+            slope_denominator: 1,
+            initial_token_a_price_numerator: 0,
+            initial_token_a_price_denominator: 1,
+            exponent: 2,
+            token_b_decimals: 0,
+        };
+
+        // put in 8 A, should get 2 B out
+        let (source_amount, destination_amount) = curve.swap_a_to_b(8, 0, 5000).unwrap();
+        assert_eq!(source_amount, 8);
+        assert_eq!(destination_amount, 2);
+
+        // put in 19 A, should get 1 more B out
+        let (source_amount, destination_amount) = curve.swap_a_to_b(19, 8, 4998).unwrap();
+        assert_eq!(source_amount, 19);
+        assert_eq!(destination_amount, 1);
+
+        // putting in 1000 A @ 27 A locked/2 B remaining should give out the last 2 B and only take 98 A
+        let (source_amount, destination_amount) = curve.swap_a_to_b(1000, 27, 2).unwrap();
+        assert_eq!(source_amount, 98);
+        assert_eq!(destination_amount, 2);
+
+        // cubic curve a = 4b^3 + 1 (with b in whole tokens of 9 decimals), so the integral is
+        // b^4 * 10^9 + b (b in base units)
+        let curve = PowerPriceCurve {
+            slope_numerator: 4,
+            slope_denominator: 1,
+            initial_token_a_price_numerator: 1,
+            initial_token_a_price_denominator: 1,
+            exponent: 3,
+            token_b_decimals: 9,
+        };
+
+        // put in 16e9 + 2e9 A, should get 2 whole B out (minus a base unit of B since b_end is rounded down)
+        let (source_amount, destination_amount) = curve
+            .swap_a_to_b(18_000_000_000, 0, 1_000_000_000_000)
+            .unwrap();
+        assert_eq!(source_amount, 18_000_000_000);
+        assert_eq!(destination_amount, 1_999_999_999);
+
+        // put in 65e9 + 1e9 A, should get 1 more whole B out (minus a base unit of B again)
+        let (source_amount, destination_amount) = curve
+            .swap_a_to_b(66_000_000_000, 18_000_000_000, 998_000_000_001)
+            .unwrap();
+        assert_eq!(source_amount, 66_000_000_000);
+        assert_eq!(destination_amount, 999_999_999);
+    }
+
+    #[test]
+    fn swap_b_to_a_basic() {
+        // same curve as linear_price::tests::swap_b_to_a_basic
+        let curve = PowerPriceCurve {
+            slope_numerator: 1,
+            slope_denominator: 2,
+            initial_token_a_price_numerator: 150,
+            initial_token_a_price_denominator: 3, // using non-1 just to test out
+            exponent: 1,
+            token_b_decimals: 0,
+        };
+
+        // put in 2 B at 101 A, should get 101 A out
+        let (source_amount, destination_amount) = curve.swap_b_to_a(2, 4998, 101).unwrap();
+        assert_eq!(source_amount, 2);
+        assert_eq!(destination_amount, 101);
+
+        // put in 2 B at 204 A, should get 103 A out
+        let (source_amount, destination_amount) = curve.swap_b_to_a(2, 4996, 204).unwrap();
+        assert_eq!(source_amount, 2);
+        assert_eq!(destination_amount, 103);
+
+        // quadratic curve a = 3b^2, so the integral is just b^3
+        let curve = PowerPriceCurve {
+            slope_numerator: 3,
+            slope_denominator: 1,
+            initial_token_a_price_numerator: 0,
+            initial_token_a_price_denominator: 1,
+            exponent: 2,
+            token_b_decimals: 0,
+        };
+
+        // put in 1 B at 27 A, should get 19 A out
+        let (source_amount, destination_amount) = curve.swap_b_to_a(1, 4997, 27).unwrap();
+        assert_eq!(source_amount, 1);
+        assert_eq!(destination_amount, 19);
+
+        // put in 10 B at 27 A, should get all 27 A out and only take 3 B
+        let (source_amount, destination_amount) = curve.swap_b_to_a(10, 4997, 27).unwrap();
+        assert_eq!(source_amount, 3);
+        assert_eq!(destination_amount, 27);
+
+        // cubic curve a = 4b^3 + 1 (with b in whole tokens of 9 decimals)
+        let curve = PowerPriceCurve {
+            slope_numerator: 4,
+            slope_denominator: 1,
+            initial_token_a_price_numerator: 1,
+            initial_token_a_price_denominator: 1,
+            exponent: 3,
+            token_b_decimals: 9,
+        };
+
+        // put in 1 whole B at 84e9 A locked (b = 3), should get 65e9 + 1e9 A out, minus the price of a base unit
+        // of B (4*2^3 + 1 = 33 A) since b_end is rounded up
+        let (source_amount, destination_amount) = curve
+            .swap_b_to_a(1_000_000_000, 997_000_000_000, 84_000_000_000)
+            .unwrap();
+        assert_eq!(source_amount, 1_000_000_000);
+        assert_eq!(destination_amount, 65_999_999_966);
+    }
+
+    #[test]
+    fn swap_exact_out_basic() {
+        // quadratic curve a = 3b^2, so the integral is just b^3
+        let curve = PowerPriceCurve {
+            slope_numerator: 3,
+            slope_denominator: 1,
+            initial_token_a_price_numerator: 0,
+            initial_token_a_price_denominator: 1,
+            exponent: 2,
+            token_b_decimals: 0,
+        };
+
+        // the inverses of swap_a_to_b_basic and swap_b_to_a_basic
+
+        // get out 2 B at 0 A, should cost 8 A
+        let (source_amount, destination_amount) = curve.swap_a_to_b_exact_out(2, 0, 5000).unwrap();
+        assert_eq!(source_amount, 8);
+        assert_eq!(destination_amount, 2);
+
+        // get out 1 more B at 8 A, should cost 19 A
+        let (source_amount, destination_amount) = curve.swap_a_to_b_exact_out(1, 8, 4998).unwrap();
+        assert_eq!(source_amount, 19);
+        assert_eq!(destination_amount, 1);
+
+        // get out 19 A at 27 A, should cost 1 B
+        let (source_amount, destination_amount) =
+            curve.swap_b_to_a_exact_out(19, 4997, 27).unwrap();
+        assert_eq!(source_amount, 1);
+        assert_eq!(destination_amount, 19);
+
+        // get out 20 A at 27 A, should cost 2 B (rounded up from 1.087)
+        let (source_amount, destination_amount) =
+            curve.swap_b_to_a_exact_out(20, 4997, 27).unwrap();
+        assert_eq!(source_amount, 2);
+        assert_eq!(destination_amount, 20);
+
+        // can't get out more than what's in the swap
+        assert_eq!(curve.swap_a_to_b_exact_out(5001, 0, 5000), None);
+        assert_eq!(curve.swap_b_to_a_exact_out(28, 4997, 27), None);
+    }
+
+    #[test]
+    fn spot_price_basic() {
+        // quadratic curve a = 3b^2 + 50
+        let curve = PowerPriceCurve {
+            slope_numerator: 3,
+            slope_denominator: 1,
+            initial_token_a_price_numerator: 50,
+            initial_token_a_price_denominator: 1,
+            exponent: 2,
+            token_b_decimals: 0,
+        };
+
+        // b = 0, 2 (b^3 + 50b = 108 A locked) and 3 (b^3 + 50b = 177 A locked)
+        assert_eq!(curve.spot_price(0, 5000), Some((50 * ONE, ONE)));
+        assert_eq!(curve.spot_price(108, 4998), Some((62 * ONE, ONE)));
+        assert_eq!(curve.spot_price(177, 4997), Some((77 * ONE, ONE)));
+
+        // token B amount doesn't matter
+        assert_eq!(curve.spot_price(177, 0), Some((77 * ONE, ONE)));
+    }
+
+    #[test]
+    fn matches_linear_price_curve() {
+        let linear_curve = LinearPriceCurve {
+            slope_numerator: 1,
+            slope_denominator: 200_000_000,
+            initial_token_a_price_numerator: 150,
+            initial_token_a_price_denominator: 3,
+            initial_token_a_amount: 0,
+        };
+        let power_curve = PowerPriceCurve {
+            slope_numerator: 1,
+            slope_denominator: 200_000_000,
+            initial_token_a_price_numerator: 150,
+            initial_token_a_price_denominator: 3,
+            exponent: 1,
+            token_b_decimals: 0,
+        };
+
+        for (source_amount, swap_token_a_amount) in [
+            (10_100_000_000u128, 0u128),
+            (10_300_000_000, 10_100_000_000),
+            (590_000_000_000, 8_160_000_000_000),
+            (1, 8_160_000_000_000),
+        ] {
+            assert_eq!(
+                power_curve.swap_without_fees(
+                    source_amount,
+                    swap_token_a_amount,
+                    500_000_000_000,
+                    TradeDirection::AtoB
+                ),
+                linear_curve.swap_without_fees(
+                    source_amount,
+                    swap_token_a_amount,
+                    500_000_000_000,
+                    TradeDirection::AtoB
+                ),
+            );
+        }
+    }
+
+    #[test]
+    fn pack_curve() {
+        let curve = PowerPriceCurve {
+            slope_numerator: u64::MAX,
+            slope_denominator: u64::MAX - 1,
+            initial_token_a_price_numerator: 0,
+            initial_token_a_price_denominator: u32::MAX,
+            exponent: 3,
+            token_b_decimals: 9,
+        };
+
+        let mut packed = [0u8; PowerPriceCurve::LEN];
+        Pack::pack_into_slice(&curve, &mut packed[..]);
+        let unpacked = PowerPriceCurve::unpack(&packed).unwrap();
+        assert_eq!(curve, unpacked);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&curve.slope_numerator.to_le_bytes());
+        packed.extend_from_slice(&curve.slope_denominator.to_le_bytes());
+        packed.extend_from_slice(&curve.initial_token_a_price_numerator.to_le_bytes());
+        packed.extend_from_slice(&curve.initial_token_a_price_denominator.to_le_bytes());
+        packed.push(curve.exponent);
+        packed.push(curve.token_b_decimals);
+        packed.extend_from_slice(&[0u8; 2]);
+        let unpacked = PowerPriceCurve::unpack(&packed).unwrap();
+        assert_eq!(curve, unpacked);
+    }
+
+    #[test]
+    fn validate_curve_params() {
+        let curve = PowerPriceCurve {
+            slope_numerator: 3,
+            slope_denominator: 1,
+            initial_token_a_price_numerator: 0,
+            initial_token_a_price_denominator: 1,
+            exponent: 2,
+            token_b_decimals: 9,
+        };
+        assert!(curve.validate().is_ok());
+
+        for invalid_curve in [
+            PowerPriceCurve {
+                slope_numerator: 0,
+                ..curve.clone()
+            },
+            PowerPriceCurve {
+                slope_denominator: 0,
+                ..curve.clone()
+            },
+            PowerPriceCurve {
+                initial_token_a_price_denominator: 0,
+                ..curve.clone()
+            },
+            PowerPriceCurve {
+                exponent: 0,
+                ..curve.clone()
+            },
+            PowerPriceCurve {
+                exponent: MAX_EXPONENT + 1,
+                ..curve.clone()
+            },
+            PowerPriceCurve {
+                token_b_decimals: MAX_TOKEN_B_DECIMALS + 1,
+                ..curve.clone()
+            },
+            // slope rounds to 0
+            PowerPriceCurve {
+                slope_numerator: 1,
+                slope_denominator: u64::MAX,
+                ..curve.clone()
+            },
+        ] {
+            assert_eq!(invalid_curve.validate(), Err(SwapError::InvalidCurve));
+        }
+    }
+
+    #[test]
+    fn swap_round_trip_does_not_give_free_tokens() {
+        // cubic curve with 9 decimals
+        let curve = PowerPriceCurve {
+            slope_numerator: 7,
+            slope_denominator: 3,
+            initial_token_a_price_numerator: 1,
+            initial_token_a_price_denominator: 3,
+            exponent: 3,
+            token_b_decimals: 9,
+        };
+
+        // buying and immediately selling back should never return more A than was put in
+        let mut swap_token_a_amount = 0u128;
+        let mut swap_token_b_amount = 1_000_000_000_000u128;
+        for amount_a in [1u128, 2, 3, 1_000, 123_456_789, 10_000_000_000_000] {
+            let (source_amount, destination_amount) =
+                match curve.swap_a_to_b(amount_a, swap_token_a_amount, swap_token_b_amount) {
+                    Some((_, 0)) | None => continue,
+                    Some(result) => result,
+                };
+            let (_, amount_a_back) = curve
+                .swap_b_to_a(
+                    destination_amount,
+                    swap_token_b_amount - destination_amount,
+                    swap_token_a_amount + source_amount,
+                )
+                .unwrap();
+            assert!(amount_a_back <= source_amount);
+
+            // leave the first trade in the swap so the next one starts further up the curve
+            swap_token_a_amount += source_amount;
+            swap_token_b_amount -= destination_amount;
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_swap_a_to_b(
+            // how much a user is swapping in
+            source_token_amount in 1..u64::MAX,
+            // how much a is already in swap (determines spot price)
+            swap_source_amount in 1..u64::MAX,
+            exponent in 1..=MAX_EXPONENT,
+        ) {
+            // B with 9 decimals
+            let curve = PowerPriceCurve {
+                slope_numerator: 37,
+                slope_denominator: 14,
+                initial_token_a_price_numerator: 7,
+                initial_token_a_price_denominator: 2,
+                exponent,
+                token_b_decimals: 9,
+            };
+
+            let (_source_amount_swapped, destination_amount_swapped) = curve
+                .swap_a_to_b(
+                    source_token_amount as u128,
+                    swap_source_amount as u128,
+                    u64::MAX as u128,
+                )
+                .unwrap();
+
+            // ignore the trades where not enough source_token_amount was put in to get any b out
+            if destination_amount_swapped > 0 {
+                check_curve_value_from_swap(
+                    &curve,
+                    source_token_amount as u128,
+                    swap_source_amount as u128,
+                    // swap_destination_amount (the amount of token b in the swap) doesn't affect any of the math
+                    u64::MAX as u128,
+                    TradeDirection::AtoB
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_swap_b_to_a(
+            // how much b user is swapping in
+            source_token_amount in 1..u64::MAX,
+            // how much a is already in swap (determines spot price)
+            swap_destination_amount in 1..u64::MAX,
+            exponent in 1..=MAX_EXPONENT,
+        ) {
+            let curve = PowerPriceCurve {
+                slope_numerator: 1,
+                slope_denominator: 1_000,
+                initial_token_a_price_numerator: 0,
+                initial_token_a_price_denominator: 1,
+                exponent,
+                token_b_decimals: 9,
+            };
+
+            let (_source_amount_swapped, destination_amount_swapped) = curve
+                .swap_b_to_a(
+                    source_token_amount as u128,
+                    u64::MAX as u128,
+                    swap_destination_amount as u128,
+                )
+                .unwrap();
+
+            // ignore the trades where not enough source_token_amount was put in to get any a out
+            if destination_amount_swapped > 0 {
+                check_curve_value_from_swap(
+                    &curve,
+                    source_token_amount as u128,
+                    // swap_source_amount (the amount of token b in the swap) doesn't affect any of the math
+                    u64::MAX as u128,
+                    swap_destination_amount as u128,
+                    TradeDirection::BtoA
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_swap_exact_out(
+            // how much a user wants out
+            destination_token_amount in 1..u32::MAX,
+            // how much a is already in swap (determines spot price)
+            swap_token_a_amount in 1..u64::MAX,
+            exponent in 1..=MAX_EXPONENT,
+        ) {
+            let curve = PowerPriceCurve {
+                slope_numerator: 37,
+                slope_denominator: 14,
+                initial_token_a_price_numerator: 7,
+                initial_token_a_price_denominator: 2,
+                exponent,
+                token_b_decimals: 9,
+            };
+            let destination_token_amount = destination_token_amount as u128;
+            let swap_token_a_amount = swap_token_a_amount as u128;
+            // token b in the swap doesn't affect any of the math
+            let swap_token_b_amount = u64::MAX as u128;
+            let previous_value = curve
+                .normalized_value(swap_token_a_amount, swap_token_b_amount)
+                .unwrap();
+
+            // buying exact b
+            let result = curve
+                .swap_without_fees_exact_out(
+                    destination_token_amount,
+                    swap_token_a_amount,
+                    swap_token_b_amount,
+                    TradeDirection::AtoB,
+                )
+                .unwrap();
+            assert_eq!(result.destination_amount_swapped, destination_token_amount);
+            let new_value = curve
+                .normalized_value(
+                    swap_token_a_amount + result.source_amount_swapped,
+                    swap_token_b_amount - destination_token_amount,
+                )
+                .unwrap();
+            assert!(new_value.greater_than_or_equal(&previous_value));
+
+            // selling for exact a
+            if destination_token_amount <= swap_token_a_amount {
+                let result = curve
+                    .swap_without_fees_exact_out(
+                        destination_token_amount,
+                        swap_token_b_amount,
+                        swap_token_a_amount,
+                        TradeDirection::BtoA,
+                    )
+                    .unwrap();
+                assert_eq!(result.destination_amount_swapped, destination_token_amount);
+                let new_value = curve
+                    .normalized_value(
+                        swap_token_a_amount - destination_token_amount,
+                        swap_token_b_amount + result.source_amount_swapped,
+                    )
+                    .unwrap();
+                assert!(new_value.greater_than_or_equal(&previous_value));
+            }
+        }
+    }
+}
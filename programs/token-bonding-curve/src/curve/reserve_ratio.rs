@@ -0,0 +1,981 @@
+//! Bancor-style constant reserve ratio swap curve, reserve ratio and initial (virtual) reserve and supply set at
+//! init
+//! Works under the same assumptions as the linear price curve (see `linear_price.rs`):
+//! Deposits (except the initial deposit) are disabled
+//! The initial deposit should only have token B (the bonded token) and 0 token A (the collateral token)
+//! Trade and owner fees are taken in the source token and either sent straight to a fee token account or set aside
+//! in the swap for `claim_fees` (see `SwapV2::accrue_fees`), so they never count as liquidity or move the price
+//! (see `collects_fees_in_trading_tokens`)
+//! Withdrawals are disabled, will panic if those instructions are called
+//! Unlike the other bonding curves the price isn't a function of the swap's balances alone, it also depends on the
+//! circulating supply of token B, so swaps take token B's mint to read it from (see `CurveType::uses_token_b_supply`)
+//! and the curve can't price anything until it's been given it
+
+use {
+    crate::{
+        curve::calculator::{
+            map_zero_to_none, CurveCalculator, DynPack, RoundDirection, SwapWithoutFeesResult,
+            TradeDirection, TradingTokenResult,
+        },
+        dfs_precise_number::{DFSPreciseNumber, ONE},
+        error::SwapError,
+    },
+    arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
+    solana_program::{
+        program_error::ProgramError,
+        program_pack::{IsInitialized, Pack, Sealed},
+    },
+};
+
+/// exp and ln are only accurate to ~18 decimals, so amounts computed with them are nudged in favor of the swap
+/// by 1/ROUNDING_MARGIN_DENOMINATOR of the amount (plus the same fraction of the scale of the numbers that went
+/// into exp/ln, since the error doesn't shrink along with tiny amounts) before being rounded
+const ROUNDING_MARGIN_DENOMINATOR: u128 = 1_000_000_000_000_000;
+
+/// ReserveRatioCurve struct implementing CurveCalculator
+/// A is the "collateral" token (e.g. RLY), B is the "bonded" token (e.g. TAKI).
+/// The price of a single B token (a, denominated in amount of token A) is given by the Bancor formula
+/// `a = reserve / (supply * reserve_ratio)`
+/// where `reserve = initial_reserve + token_a_bonded` and `supply` is the circulating supply of token B (everything
+/// that isn't in the swap, see `token_b_supply`)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReserveRatioCurve {
+    /// Fraction of the market cap (supply * price) that's backed by the reserve numerator
+    pub reserve_ratio_numerator: u32,
+    /// Fraction of the market cap (supply * price) that's backed by the reserve denominator
+    pub reserve_ratio_denominator: u32,
+    /// Virtual amount of token A in the reserve before anything is swapped in, i.e. the reserve of the token
+    /// being migrated (needs to be > 0 since the Bancor formula is undefined for an empty reserve)
+    pub initial_reserve: u64,
+    /// Supply of token B the curve is set up for, i.e. the supply of the token being migrated, which should be
+    /// what's circulating when the curve starts so the initial price is
+    /// `initial_reserve / (initial_supply * reserve_ratio)`. It isn't added to the supply swaps are priced off,
+    /// that's read from token B's mint and already includes it (see `token_b_supply`)
+    pub initial_supply: u64,
+    /// Circulating supply of token B plus the swap's token B amount, which swaps don't change (so the circulating
+    /// supply at any point is this less the swap's token B amount). Not stored with the curve (packs to nothing
+    /// and unpacks as None), the processor reads it from token B's mint before every swap (see
+    /// `SwapCurve::with_token_b_supply`), and every swap fails while it's None
+    pub token_b_supply: Option<u64>,
+}
+
+/// Rounds an amount the user receives down (after subtracting the rounding margin)
+fn round_amount_out(amount: &DFSPreciseNumber, scale: &DFSPreciseNumber) -> Option<u128> {
+    let margin = amount
+        .checked_add(scale)?
+        .checked_div(&(DFSPreciseNumber::new(ROUNDING_MARGIN_DENOMINATOR)?))?;
+    let (amount, is_negative) = amount.unsigned_sub(&margin);
+    if is_negative {
+        return Some(0);
+    }
+    amount.floor()?.to_imprecise()
+}
+
+/// Rounds an amount the user pays up (after adding the rounding margin)
+fn round_amount_in(amount: &DFSPreciseNumber, scale: &DFSPreciseNumber) -> Option<u128> {
+    let margin = amount
+        .checked_add(scale)?
+        .checked_div(&(DFSPreciseNumber::new(ROUNDING_MARGIN_DENOMINATOR)?))?;
+    amount.checked_add(&margin)?.ceiling()?.to_imprecise()
+}
+
+/// Returns `x^(numerator / denominator)` for x >= 1, worked out as `e^(numerator / denominator * ln(x))`
+fn pow_ratio(x: &DFSPreciseNumber, numerator: u32, denominator: u32) -> Option<DFSPreciseNumber> {
+    x.ln()?
+        .checked_mul(&(DFSPreciseNumber::new(numerator.into())?))?
+        .checked_div(&(DFSPreciseNumber::new(denominator.into())?))?
+        .exp()
+}
+
+/// These are the standard Bancor purchase and sale formulas with a constant reserve ratio r, priced off the
+/// reserve and the supply of token B as they are right now, e.g. buying with da gives
+/// `supply * ((1 + da/reserve)^r - 1)` B and selling db gives `reserve * (1 - (1 - db/supply)^(1/r))` A
+/// The powers are always taken of a ratio >= 1 (e.g. `reserve / (reserve - da)` instead of `1 - da/reserve`, then
+/// divided by) since ln is only defined from 1 up
+///
+/// swap_a_to_b and swap_b_to_a are the key functions at the bottom
+impl ReserveRatioCurve {
+    /// Returns the total reserve (initial_reserve + token A bonded to the curve)
+    fn reserve(&self, token_a_amount: u128) -> Option<DFSPreciseNumber> {
+        DFSPreciseNumber::new(u128::from(self.initial_reserve).checked_add(token_a_amount)?)
+    }
+
+    /// Returns the circulating supply of token B when the swap has `swap_token_b_amount` of token B left, None if
+    /// `token_b_supply` was never set or nothing is circulating (the Bancor formula needs a non-zero supply)
+    fn supply(&self, swap_token_b_amount: u128) -> Option<DFSPreciseNumber> {
+        let circulating_supply =
+            u128::from(self.token_b_supply?).checked_sub(swap_token_b_amount)?;
+        if circulating_supply == 0 {
+            return None;
+        }
+        DFSPreciseNumber::new(circulating_supply)
+    }
+
+    /// Returns the amount of token B it takes to sell the reserve back down to initial_reserve (i.e. to take out
+    /// all the token A bonded to the curve) from `reserve` and `supply`,
+    /// `supply - supply / (reserve / initial_reserve)^r`
+    fn b_value_of_reserve(
+        &self,
+        reserve: &DFSPreciseNumber,
+        supply: &DFSPreciseNumber,
+    ) -> Option<DFSPreciseNumber> {
+        let supply_at_initial_reserve = supply.checked_div(&pow_ratio(
+            &(reserve.checked_div(&(DFSPreciseNumber::new(self.initial_reserve.into())?))?),
+            self.reserve_ratio_numerator,
+            self.reserve_ratio_denominator,
+        )?)?;
+        let (b_value, is_negative) = supply.unsigned_sub(&supply_at_initial_reserve);
+        // can only be negative from rounding right at the initial reserve
+        match is_negative {
+            true => DFSPreciseNumber::new(0),
+            false => Some(b_value),
+        }
+    }
+
+    /// Swap's in user's collateral token and returns out the bonded token,
+    /// moving right on the price curve and increasing the price of the bonded token
+    fn swap_a_to_b(
+        &self,
+        source_amount: u128,      // amount of user's token a (collateral token)
+        swap_source_amount: u128, // swap's token a (collateral token)
+        swap_destination_amount: u128, // swap's remaining token b (bonded token)
+    ) -> Option<(u128, u128)> {
+        // if source_amount is enough to buy all the remaining B, only take what's needed for that
+        // no need to return None here if the exact out calculation fails (it overflows for amounts too large
+        // to ever be paid for), can just skip this check and do real calculation below
+        if let Some((maximum_a_remaining, _)) = self.swap_a_to_b_exact_out(
+            swap_destination_amount,
+            swap_source_amount,
+            swap_destination_amount,
+        ) {
+            if maximum_a_remaining <= source_amount {
+                return Some((maximum_a_remaining, swap_destination_amount));
+            }
+        }
+
+        // Bancor purchase formula, db = supply * ((reserve + da) / reserve)^r - supply
+        let reserve_start = self.reserve(swap_source_amount)?;
+        let reserve_end = self.reserve(swap_source_amount.checked_add(source_amount)?)?;
+        let supply_start = self.supply(swap_destination_amount)?;
+        let supply_end = supply_start.checked_mul(&pow_ratio(
+            &(reserve_end.checked_div(&reserve_start)?),
+            self.reserve_ratio_numerator,
+            self.reserve_ratio_denominator,
+        )?)?;
+        let (b_difference, is_negative) = supply_end.unsigned_sub(&supply_start);
+        // ln can only make the supply go backwards for amounts too small to get anything out anyway
+        let destination_amount = match is_negative {
+            true => 0,
+            false => round_amount_out(&b_difference, &supply_end)?,
+        };
+
+        Some((source_amount, destination_amount))
+    }
+
+    fn swap_b_to_a(
+        &self,
+        source_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+    ) -> Option<(u128, u128)> {
+        let reserve_start = self.reserve(swap_destination_amount)?;
+        let supply_start = self.supply(swap_source_amount)?;
+
+        // selling enough B to bring the reserve back down to initial_reserve takes out all the A bonded to the
+        // curve, if the user put in more B than that then just give them all of the a tokens
+        // (swap_destination_amount) and only take the B tokens required to get there
+        let b_start = self.b_value_of_reserve(&reserve_start, &supply_start)?;
+        let maximum_b_remaining = round_amount_in(&b_start, &supply_start)?;
+        if source_amount >= maximum_b_remaining {
+            return Some((maximum_b_remaining, swap_destination_amount));
+        }
+
+        // Bancor sale formula, da = reserve - reserve / (supply / (supply - db))^(1/r)
+        let (supply_end, is_negative) =
+            supply_start.unsigned_sub(&(DFSPreciseNumber::new(source_amount)?));
+        if is_negative {
+            return Some((maximum_b_remaining, swap_destination_amount));
+        }
+        let reserve_end = reserve_start.checked_div(&pow_ratio(
+            &(supply_start.checked_div(&supply_end)?),
+            self.reserve_ratio_denominator,
+            self.reserve_ratio_numerator,
+        )?)?;
+        let (a_difference, is_negative) = reserve_start.unsigned_sub(&reserve_end);
+        let destination_amount = match is_negative {
+            true => 0,
+            false => round_amount_out(&a_difference, &reserve_start)?,
+        };
+
+        Some((source_amount, destination_amount))
+    }
+
+    /// Inverse of swap_a_to_b: returns the amount of token A the user has to put in to get exactly
+    /// `destination_amount` of token B out
+    fn swap_a_to_b_exact_out(
+        &self,
+        destination_amount: u128, // amount of token b (bonded token) the user wants out
+        swap_source_amount: u128, // swap's token a (collateral token)
+        swap_destination_amount: u128, // swap's remaining token b (bonded token)
+    ) -> Option<(u128, u128)> {
+        // unlike swap_a_to_b we can't just hand out whatever's left, the user asked for an exact amount
+        if destination_amount > swap_destination_amount {
+            return None;
+        }
+
+        // da = reserve * ((supply + db) / supply)^(1/r) - reserve
+        let reserve_start = self.reserve(swap_source_amount)?;
+        let supply_start = self.supply(swap_destination_amount)?;
+        let supply_end = supply_start.checked_add(&(DFSPreciseNumber::new(destination_amount)?))?;
+        let reserve_end = reserve_start.checked_mul(&pow_ratio(
+            &(supply_end.checked_div(&supply_start)?),
+            self.reserve_ratio_denominator,
+            self.reserve_ratio_numerator,
+        )?)?;
+        let (a_difference, is_negative) = reserve_end.unsigned_sub(&reserve_start);
+        let source_amount = match is_negative {
+            true => 0,
+            false => round_amount_in(&a_difference, &reserve_end)?,
+        };
+
+        Some((source_amount, destination_amount))
+    }
+
+    /// Inverse of swap_b_to_a: returns the amount of token B the user has to put in to get exactly
+    /// `destination_amount` of token A out
+    fn swap_b_to_a_exact_out(
+        &self,
+        destination_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+    ) -> Option<(u128, u128)> {
+        // a_end can't be negative, there's only swap_destination_amount of token A bonded to the curve
+        let a_end = swap_destination_amount.checked_sub(destination_amount)?;
+
+        // db = supply - supply / (reserve / (reserve - da))^r
+        let reserve_start = self.reserve(swap_destination_amount)?;
+        let supply_start = self.supply(swap_source_amount)?;
+        let supply_end = supply_start.checked_div(&pow_ratio(
+            &(reserve_start.checked_div(&(self.reserve(a_end)?))?),
+            self.reserve_ratio_numerator,
+            self.reserve_ratio_denominator,
+        )?)?;
+        let (b_difference, is_negative) = supply_start.unsigned_sub(&supply_end);
+        let source_amount = match is_negative {
+            true => 0,
+            false => round_amount_in(&b_difference, &supply_start)?,
+        };
+
+        Some((source_amount, destination_amount))
+    }
+}
+
+/// Returns None if the reserve ratio isn't in (0, 1] or the initial reserve or supply is 0
+fn is_curve_param_valid(curve: &ReserveRatioCurve) -> Option<()> {
+    if curve.reserve_ratio_numerator == 0
+        || curve.reserve_ratio_denominator == 0
+        || curve.reserve_ratio_numerator > curve.reserve_ratio_denominator
+        || curve.initial_reserve == 0
+        || curve.initial_supply == 0
+    {
+        return None;
+    };
+    Some(())
+}
+
+impl CurveCalculator for ReserveRatioCurve {
+    /// Calculate how much destination token will be provided given an amount
+    /// of source token.
+    fn swap_without_fees(
+        &self,
+        source_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<SwapWithoutFeesResult> {
+        let (source_amount_swapped, destination_amount_swapped) = match trade_direction {
+            TradeDirection::AtoB => {
+                self.swap_a_to_b(source_amount, swap_source_amount, swap_destination_amount)?
+            }
+            TradeDirection::BtoA => {
+                self.swap_b_to_a(source_amount, swap_source_amount, swap_destination_amount)?
+            }
+        };
+        let source_amount_swapped = map_zero_to_none(source_amount_swapped)?;
+        let destination_amount_swapped = map_zero_to_none(destination_amount_swapped)?;
+        Some(SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped,
+        })
+    }
+
+    /// Calculate how much source token is required to get exactly `destination_amount` of
+    /// destination token out (rounded up in favor of the swap)
+    fn swap_without_fees_exact_out(
+        &self,
+        destination_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<SwapWithoutFeesResult> {
+        let (source_amount_swapped, destination_amount_swapped) = match trade_direction {
+            TradeDirection::AtoB => self.swap_a_to_b_exact_out(
+                destination_amount,
+                swap_source_amount,
+                swap_destination_amount,
+            )?,
+            TradeDirection::BtoA => self.swap_b_to_a_exact_out(
+                destination_amount,
+                swap_source_amount,
+                swap_destination_amount,
+            )?,
+        };
+        let source_amount_swapped = map_zero_to_none(source_amount_swapped)?;
+        let destination_amount_swapped = map_zero_to_none(destination_amount_swapped)?;
+        Some(SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped,
+        })
+    }
+
+    /// Deposits and withdrawals are disabled, same as the linear price curve
+    fn pool_tokens_to_trading_tokens(
+        &self,
+        _pool_tokens: u128,
+        _pool_token_supply: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _round_direction: RoundDirection,
+    ) -> Option<TradingTokenResult> {
+        // this causes a panic if withdraw_all_token_types is called but that's ok for now, cheap way of
+        // disabling withdrawals without having to change how SwapCurve works
+        None
+    }
+
+    /// Deposits are disabled, same as the linear price curve
+    fn deposit_single_token_type(
+        &self,
+        _source_amount: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _pool_supply: u128,
+        _trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        // this never gets called since allows_withdrawals is false (would panic otherwise so still safe)
+        None
+    }
+
+    /// Withdrawals are disabled, and fees are paid out in trading tokens instead of being converted into
+    /// pool tokens (see `collects_fees_in_trading_tokens`)
+    fn withdraw_single_token_type_exact_out(
+        &self,
+        _source_amount: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _pool_supply: u128,
+        _trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        // this causes a panic if SwapCurve.withdraw_single_token_type_exact_out instruction is called
+        // but that's ok for now, cheap way of disabling withdrawals without having to change how SwapCurve works
+        None
+    }
+
+    /// Validate that the given curve has no invalid parameters
+    /// Called on `initialize` - reserve ratio must be in (0, 1] and the initial reserve and supply must be positive
+    fn validate(&self) -> Result<(), SwapError> {
+        match is_curve_param_valid(self) {
+            Some(_val) => Ok(()),
+            None => Err(SwapError::InvalidCurve),
+        }
+    }
+
+    /// Validate the given supply on initialization.
+    /// We require at least some bonded token B for the curve to be useful (collateral token must be 0, the
+    /// initial reserve is virtual)
+    fn validate_supply(&self, token_a_amount: u64, token_b_amount: u64) -> Result<(), SwapError> {
+        if token_b_amount == 0 {
+            return Err(SwapError::EmptySupply);
+        }
+
+        if token_a_amount != 0 {
+            return Err(SwapError::InvalidSupply);
+        }
+        Ok(())
+    }
+
+    /// Both deposits and withdrawals are intentionally disabled
+    fn allows_deposits(&self) -> bool {
+        false
+    }
+
+    /// The curve position is derived from the amount of token A in the swap, so fees can't be left in the
+    /// swap token accounts (they'd look like bonded collateral and move the price)
+    fn collects_fees_in_trading_tokens(&self) -> bool {
+        true
+    }
+
+    /// The Bancor price `reserve / (supply * reserve_ratio)`. Returned with a denominator of
+    /// `dfs_precise_number::ONE`
+    fn spot_price(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<(u128, u128)> {
+        let reserve = self.reserve(swap_token_a_amount)?;
+        let supply = self.supply(swap_token_b_amount)?;
+
+        // make sure to do division last
+        let price = reserve
+            .checked_mul(&(DFSPreciseNumber::new(self.reserve_ratio_denominator.into())?))?
+            .checked_div(
+                &(supply
+                    .checked_mul(&(DFSPreciseNumber::new(self.reserve_ratio_numerator.into())?))?),
+            )?;
+
+        // the raw DFSPreciseNumber value is the numerator over ONE, as long as it fits in a u128
+        if price.value.bits() > 128 {
+            return None;
+        }
+        Some((price.value.as_u128(), ONE))
+    }
+
+    /// The total normalized value of the reserve ratio curve adds the total
+    /// value of the token A side (as denominated in token B, the circulating supply it'd take to sell all of the
+    /// token A bonded to the curve back out) to the token B side.
+    fn normalized_value(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<spl_math::precise_number::PreciseNumber> {
+        let b_value_of_a = self.b_value_of_reserve(
+            &(self.reserve(swap_token_a_amount)?),
+            &(self.supply(swap_token_b_amount)?),
+        )?;
+        let total_value =
+            b_value_of_a.checked_add(&(DFSPreciseNumber::new(swap_token_b_amount)?))?;
+
+        // same as the linear price curve, truncate to 32 bits so that errors from ln (which are rounded in
+        // favor of the swap when actually swapping) don't show up as a decrease in value
+        let value_bits = total_value.value.bits();
+        let truncated_value = match value_bits > 32 {
+            true => total_value.value >> (value_bits - 32),
+            false => total_value.value,
+        };
+
+        Some(spl_math::precise_number::PreciseNumber {
+            value: truncated_value,
+        })
+    }
+}
+
+/// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
+impl IsInitialized for ReserveRatioCurve {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+impl Sealed for ReserveRatioCurve {}
+impl Pack for ReserveRatioCurve {
+    const LEN: usize = 32;
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        (self as &dyn DynPack).pack_into_slice(output);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<ReserveRatioCurve, ProgramError> {
+        let input = array_ref![input, 0, 32];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            reserve_ratio_numerator,
+            reserve_ratio_denominator,
+            initial_reserve,
+            initial_supply,
+            _padding,
+        ) = array_refs![input, 4, 4, 8, 8, 8];
+        Ok(Self {
+            reserve_ratio_numerator: u32::from_le_bytes(*reserve_ratio_numerator),
+            reserve_ratio_denominator: u32::from_le_bytes(*reserve_ratio_denominator),
+            initial_reserve: u64::from_le_bytes(*initial_reserve),
+            initial_supply: u64::from_le_bytes(*initial_supply),
+            token_b_supply: None,
+        })
+    }
+}
+
+impl DynPack for ReserveRatioCurve {
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 32];
+        let (
+            reserve_ratio_numerator,
+            reserve_ratio_denominator,
+            initial_reserve,
+            initial_supply,
+            padding,
+        ) = mut_array_refs![output, 4, 4, 8, 8, 8];
+        *reserve_ratio_numerator = self.reserve_ratio_numerator.to_le_bytes();
+        *reserve_ratio_denominator = self.reserve_ratio_denominator.to_le_bytes();
+        *initial_reserve = self.initial_reserve.to_le_bytes();
+        *initial_supply = self.initial_supply.to_le_bytes();
+        *padding = [0u8; 8];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::{
+        calculator::test::check_curve_value_from_swap, linear_price::LinearPriceCurve,
+    };
+    use proptest::prelude::*;
+
+    /// 50% reserve ratio starting from a reserve of 1000 A and a supply of 100 B, so the price starts at
+    /// 1000 / (100 * 0.5) = 20 A per B and the reserve grows with supply^2
+    /// 5100 B in total, so the circulating supply is 5100 less whatever's left in the swap (100 with 5000 B in it)
+    const TEST_CURVE: ReserveRatioCurve = ReserveRatioCurve {
+        reserve_ratio_numerator: 1,
+        reserve_ratio_denominator: 2,
+        initial_reserve: 1000,
+        initial_supply: 100,
+        token_b_supply: Some(5100),
+    };
+
+    /// B with 9 decimals, 20% reserve ratio starting from a reserve of 1M base A and a supply of 1000 whole B
+    /// (u64::MAX B in total, like a mint-and-burn swap)
+    const TEST_CURVE_9_DECIMALS: ReserveRatioCurve = ReserveRatioCurve {
+        reserve_ratio_numerator: 1,
+        reserve_ratio_denominator: 5,
+        initial_reserve: 1_000_000,
+        initial_supply: 1_000_000_000_000,
+        token_b_supply: Some(u64::MAX),
+    };
+
+    /// Returns the amount of token B left in the swap when `curve`'s circulating supply is its initial_supply
+    fn initial_swap_token_b_amount(curve: &ReserveRatioCurve) -> u128 {
+        u128::from(curve.token_b_supply.unwrap() - curve.initial_supply)
+    }
+
+    #[test]
+    fn swap_a_to_b_basic() {
+        let curve = TEST_CURVE;
+
+        // put in 5000 A, the reserve goes from 1000 to 6000 so the supply goes from 100 to 100 * 6^0.5 = 244.95,
+        // should get 144.95 B out (rounded down to 144)
+        let (source_amount, destination_amount) = curve.swap_a_to_b(5000, 0, 5000).unwrap();
+        assert_eq!(source_amount, 5000);
+        assert_eq!(destination_amount, 144);
+
+        // put in 5000 more A, price has gone up so should get 86.38 B out (rounded down to 86), the supply is
+        // 100 + the 144 B bought so far (the fraction of a B that was rounded off doesn't exist)
+        let (source_amount, destination_amount) = curve.swap_a_to_b(5000, 5000, 4856).unwrap();
+        assert_eq!(source_amount, 5000);
+        assert_eq!(destination_amount, 86);
+
+        // putting in 100K A @ 0 A locked/100 B remaining should give out the last 100 B and only take the
+        // 3000 A that they cost (the reserve goes from 1000 to 1000 * 2^2, rounded up to 3001 for the margin)
+        let curve = ReserveRatioCurve {
+            token_b_supply: Some(200),
+            ..TEST_CURVE
+        };
+        let (source_amount, destination_amount) = curve.swap_a_to_b(100_000, 0, 100).unwrap();
+        assert_eq!(source_amount, 3001);
+        assert_eq!(destination_amount, 100);
+    }
+
+    #[test]
+    fn swap_uses_circulating_supply() {
+        // the same reserve with twice the supply halves the price, so the same A buys twice the B
+        // (100 * (1.1^0.5 - 1) = 4.88 and 200 * (1.1^0.5 - 1) = 9.76)
+        let (_, destination_amount) = TEST_CURVE.swap_a_to_b(100, 0, 5000).unwrap();
+        assert_eq!(destination_amount, 4);
+        let (_, destination_amount) = TEST_CURVE.swap_a_to_b(100, 0, 4900).unwrap();
+        assert_eq!(destination_amount, 9);
+
+        // and the same B sells for less A (11000 * (1 - (190 / 200)^2) = 1072.5 and
+        // 11000 * (1 - (290 / 300)^2) = 721.11)
+        let (_, destination_amount) = TEST_CURVE.swap_b_to_a(10, 4900, 10_000).unwrap();
+        assert_eq!(destination_amount, 1072);
+        let (_, destination_amount) = TEST_CURVE.swap_b_to_a(10, 4800, 10_000).unwrap();
+        assert_eq!(destination_amount, 721);
+
+        // the swap can't hold more B than there is, and there's no price with nothing circulating
+        assert!(TEST_CURVE.swap_a_to_b(100, 0, 5101).is_none());
+        assert!(TEST_CURVE.spot_price(0, 5101).is_none());
+        assert!(TEST_CURVE.swap_a_to_b(100, 0, 5100).is_none());
+        assert!(TEST_CURVE.spot_price(0, 5100).is_none());
+    }
+
+    #[test]
+    fn swap_fails_without_token_b_supply() {
+        // what the curve unpacks as before the processor sets the supply from token B's mint
+        let curve = ReserveRatioCurve {
+            token_b_supply: None,
+            ..TEST_CURVE
+        };
+        for trade_direction in [TradeDirection::AtoB, TradeDirection::BtoA] {
+            assert!(curve
+                .swap_without_fees(100, 5000, 5000, trade_direction)
+                .is_none());
+            assert!(curve
+                .swap_without_fees_exact_out(10, 5000, 5000, trade_direction)
+                .is_none());
+        }
+        assert!(curve.spot_price(0, 5000).is_none());
+        assert!(curve.normalized_value(0, 5000).is_none());
+    }
+
+    #[test]
+    fn swap_b_to_a_basic() {
+        let curve = TEST_CURVE;
+
+        // pretty much the opposite cases as above
+
+        // put in 144 B at 5000 A, the supply goes from 244 to 100 so the reserve goes down to
+        // 6000 / (244 / 100)^2 = 1007.79, should get 4992.21 A out (rounded down to 4992)
+        let (source_amount, destination_amount) = curve.swap_b_to_a(144, 4856, 5000).unwrap();
+        assert_eq!(source_amount, 144);
+        assert_eq!(destination_amount, 4992);
+
+        // put in 500 B at 5000 A, should get all 5000 A out and only take the 144.39 B it takes to get the
+        // reserve back down to 1000 (rounded up to 145)
+        let (source_amount, destination_amount) = curve.swap_b_to_a(500, 4856, 5000).unwrap();
+        assert_eq!(source_amount, 145);
+        assert_eq!(destination_amount, 5000);
+
+        // nothing to get out of an empty curve
+        assert!(curve
+            .swap_without_fees(500, 5000, 0, TradeDirection::BtoA)
+            .is_none());
+    }
+
+    #[test]
+    fn swap_exact_out_basic() {
+        let curve = TEST_CURVE;
+
+        // the first 100 B cost 3000 A, rounded up to 3001
+        let (source_amount, destination_amount) =
+            curve.swap_a_to_b_exact_out(100, 0, 5000).unwrap();
+        assert_eq!(source_amount, 3001);
+        assert_eq!(destination_amount, 100);
+
+        // can't get out more B than the swap has
+        assert!(curve.swap_a_to_b_exact_out(101, 0, 100).is_none());
+
+        // getting 2500 A out @ 5000 A takes the supply from 244 down to 244 / (6000 / 3500)^0.5 = 186.36, so
+        // 57.64 B (rounded up to 58)
+        let (source_amount, destination_amount) =
+            curve.swap_b_to_a_exact_out(2500, 4856, 5000).unwrap();
+        assert_eq!(source_amount, 58);
+        assert_eq!(destination_amount, 2500);
+
+        // can't get out more A than the swap has
+        assert!(curve.swap_b_to_a_exact_out(5001, 4856, 5000).is_none());
+    }
+
+    #[test]
+    fn spot_price_basic() {
+        let curve = TEST_CURVE;
+
+        // initial price is 1000 / (100 * 0.5)
+        assert_eq!(curve.spot_price(0, 5000), Some((20 * ONE, ONE)));
+
+        // after 3000 A is bonded and 100 B bought the reserve is 4000 and the supply is 200, so the price is
+        // 4000 / (200 * 0.5)
+        assert_eq!(curve.spot_price(3000, 4900), Some((40 * ONE, ONE)));
+
+        // 100% reserve ratio is a constant price of reserve / supply, the supply grows in proportion to the
+        // reserve (100 * (1000 + a) / 1000)
+        let curve = ReserveRatioCurve {
+            reserve_ratio_numerator: 1,
+            reserve_ratio_denominator: 1,
+            token_b_supply: Some(u64::MAX),
+            ..TEST_CURVE
+        };
+        for (swap_token_a_amount, circulating_supply) in
+            [(0u128, 100u128), (10, 101), (5000, 600), (1_000_000, 100_100)]
+        {
+            assert_eq!(
+                curve.spot_price(
+                    swap_token_a_amount,
+                    u128::from(u64::MAX) - circulating_supply
+                ),
+                Some((10 * ONE, ONE))
+            );
+        }
+    }
+
+    #[test]
+    fn matches_linear_price_curve() {
+        // a 50% reserve ratio gives a price that's linear in supply: the reserve is 1000 * (supply / 100)^2, so
+        // the price is its derivative, supply / 5 = b / 5 + 20
+        let linear_curve = LinearPriceCurve {
+            slope_numerator: 1,
+            slope_denominator: 5,
+            initial_token_a_price_numerator: 20,
+            initial_token_a_price_denominator: 1,
+            initial_token_a_amount: 0,
+        };
+
+        let curve = ReserveRatioCurve {
+            token_b_supply: Some(u64::MAX),
+            ..TEST_CURVE
+        };
+
+        // only compare where b is a whole number, i.e. where 1000 + a = 1000 * (supply / 100)^2 for a whole supply,
+        // since the linear curve works out a fraction of a B that the reserve ratio curve doesn't see
+        for (source_amount, swap_token_a_amount, b) in [
+            (5000u128, 0u128, 0u128),
+            (5000, 3000, 100),
+            (123_456, 8000, 200),
+            (1, 99_000, 900),
+            (10_000_000, 0, 0),
+        ] {
+            let linear_result = linear_curve
+                .swap_without_fees(
+                    source_amount,
+                    swap_token_a_amount,
+                    u64::MAX.into(),
+                    TradeDirection::AtoB,
+                )
+                .map(|result| result.destination_amount_swapped)
+                .unwrap_or(0);
+            let reserve_ratio_result = curve
+                .swap_without_fees(
+                    source_amount,
+                    swap_token_a_amount,
+                    initial_swap_token_b_amount(&curve) - b,
+                    TradeDirection::AtoB,
+                )
+                .map(|result| result.destination_amount_swapped)
+                .unwrap_or(0);
+
+            // the rounding margin can take off at most 1 more than the linear curve's rounding
+            assert!(reserve_ratio_result <= linear_result);
+            assert!(reserve_ratio_result + 1 >= linear_result);
+        }
+    }
+
+    #[test]
+    fn swap_large_amounts() {
+        let curve = TEST_CURVE_9_DECIMALS;
+        let swap_token_b_amount = initial_swap_token_b_amount(&curve);
+
+        // the reserve grows with supply^5, so u64::MAX A only gets ~449K whole B out, shouldn't panic
+        let (source_amount, destination_amount) = curve
+            .swap_a_to_b(u64::MAX.into(), 0, swap_token_b_amount)
+            .unwrap();
+        assert_eq!(source_amount, u64::MAX.into());
+        assert!(destination_amount < 500_000_000_000_000);
+        assert!(curve
+            .swap_a_to_b_exact_out(swap_token_b_amount, 0, swap_token_b_amount)
+            .is_none());
+
+        // selling it all back shouldn't give out more than was put in
+        let (_, amount_a_back) = curve
+            .swap_b_to_a(
+                destination_amount,
+                swap_token_b_amount - destination_amount,
+                u64::MAX.into(),
+            )
+            .unwrap();
+        assert!(amount_a_back <= u64::MAX.into());
+    }
+
+    #[test]
+    fn pack_curve() {
+        let curve = ReserveRatioCurve {
+            reserve_ratio_numerator: u32::MAX - 1,
+            reserve_ratio_denominator: u32::MAX,
+            initial_reserve: u64::MAX,
+            initial_supply: u64::MAX - 1,
+            token_b_supply: None,
+        };
+
+        let mut packed = [0u8; ReserveRatioCurve::LEN];
+        Pack::pack_into_slice(&curve, &mut packed[..]);
+        let unpacked = ReserveRatioCurve::unpack(&packed).unwrap();
+        assert_eq!(curve, unpacked);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&curve.reserve_ratio_numerator.to_le_bytes());
+        packed.extend_from_slice(&curve.reserve_ratio_denominator.to_le_bytes());
+        packed.extend_from_slice(&curve.initial_reserve.to_le_bytes());
+        packed.extend_from_slice(&curve.initial_supply.to_le_bytes());
+        packed.extend_from_slice(&[0u8; 8]);
+        let unpacked = ReserveRatioCurve::unpack(&packed).unwrap();
+        assert_eq!(curve, unpacked);
+    }
+
+    #[test]
+    fn validate_curve_params() {
+        assert!(TEST_CURVE.validate().is_ok());
+        assert!(TEST_CURVE_9_DECIMALS.validate().is_ok());
+
+        // both ends of (0, 1] work
+        for (reserve_ratio_numerator, reserve_ratio_denominator) in [(1, 1), (1, u32::MAX)] {
+            let curve = ReserveRatioCurve {
+                reserve_ratio_numerator,
+                reserve_ratio_denominator,
+                ..TEST_CURVE
+            };
+            assert!(curve.validate().is_ok());
+        }
+
+        for curve in [
+            ReserveRatioCurve {
+                reserve_ratio_numerator: 0,
+                ..TEST_CURVE
+            },
+            ReserveRatioCurve {
+                reserve_ratio_denominator: 0,
+                ..TEST_CURVE
+            },
+            // reserve ratio above 1
+            ReserveRatioCurve {
+                reserve_ratio_numerator: 3,
+                reserve_ratio_denominator: 2,
+                ..TEST_CURVE
+            },
+            ReserveRatioCurve {
+                initial_reserve: 0,
+                ..TEST_CURVE
+            },
+            ReserveRatioCurve {
+                initial_supply: 0,
+                ..TEST_CURVE
+            },
+        ] {
+            assert_eq!(curve.validate(), Err(SwapError::InvalidCurve));
+        }
+    }
+
+    #[test]
+    fn swap_round_trip_does_not_give_free_tokens() {
+        for curve in [TEST_CURVE, TEST_CURVE_9_DECIMALS] {
+            // buying and immediately selling back should never return more A than was put in
+            let mut swap_token_a_amount = 0u128;
+            let mut swap_token_b_amount = initial_swap_token_b_amount(&curve);
+            for amount_a in [
+                1u128,
+                19,
+                20,
+                21,
+                1_000,
+                123_456,
+                10_000_000,
+                1_000_000_000_000,
+            ] {
+                let (source_amount, destination_amount) =
+                    match curve.swap_a_to_b(amount_a, swap_token_a_amount, swap_token_b_amount) {
+                        Some((_, 0)) | None => continue,
+                        Some(result) => result,
+                    };
+                let (_, amount_a_back) = curve
+                    .swap_b_to_a(
+                        destination_amount,
+                        swap_token_b_amount - destination_amount,
+                        swap_token_a_amount + source_amount,
+                    )
+                    .unwrap();
+                assert!(amount_a_back <= source_amount);
+
+                // leave the first trade in the swap so the next one starts further up the curve
+                swap_token_a_amount += source_amount;
+                swap_token_b_amount -= destination_amount;
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_swap_a_to_b(
+            // how much a user is swapping in
+            source_token_amount in 1..u64::MAX,
+            // how much a is already in swap (determines spot price)
+            swap_source_amount in 0..u64::MAX,
+        ) {
+            let curve = TEST_CURVE_9_DECIMALS;
+            let swap_destination_amount = initial_swap_token_b_amount(&curve);
+
+            let (_source_amount_swapped, destination_amount_swapped) = curve
+                .swap_a_to_b(
+                    source_token_amount as u128,
+                    swap_source_amount as u128,
+                    swap_destination_amount,
+                )
+                .unwrap();
+
+            // ignore the trades where not enough source_token_amount was put in to get any b out
+            if destination_amount_swapped > 0 {
+                check_curve_value_from_swap(
+                    &curve,
+                    source_token_amount as u128,
+                    swap_source_amount as u128,
+                    swap_destination_amount,
+                    TradeDirection::AtoB
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_swap_b_to_a(
+            // how much b user is swapping in
+            source_token_amount in 1..u64::MAX,
+            // how much b is already in swap (determines the circulating supply, always at least 1)
+            swap_source_amount in 0..u64::MAX,
+            // how much a is already in swap (determines spot price along with the circulating supply)
+            swap_destination_amount in 1..u64::MAX,
+        ) {
+            let curve = TEST_CURVE_9_DECIMALS;
+
+            let (_source_amount_swapped, destination_amount_swapped) = curve
+                .swap_b_to_a(
+                    source_token_amount as u128,
+                    swap_source_amount as u128,
+                    swap_destination_amount as u128,
+                )
+                .unwrap();
+
+            // ignore the trades where not enough source_token_amount was put in to get any a out
+            if destination_amount_swapped > 0 {
+                check_curve_value_from_swap(
+                    &curve,
+                    source_token_amount as u128,
+                    swap_source_amount as u128,
+                    swap_destination_amount as u128,
+                    TradeDirection::BtoA
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn swap_round_trip_does_not_decrease_curve_value(
+            // how much a user is swapping in
+            source_token_amount in 1..u64::MAX,
+            // how much a is already in swap (determines spot price)
+            swap_token_a_amount in 0..u64::MAX,
+        ) {
+            let curve = TEST_CURVE_9_DECIMALS;
+            let swap_token_b_amount = initial_swap_token_b_amount(&curve);
+
+            if let Some((source_amount, destination_amount)) = curve.swap_a_to_b(
+                source_token_amount as u128,
+                swap_token_a_amount as u128,
+                swap_token_b_amount,
+            ) {
+                if destination_amount > 0 {
+                    let (_, amount_a_back) = curve
+                        .swap_b_to_a(
+                            destination_amount,
+                            swap_token_b_amount - destination_amount,
+                            swap_token_a_amount as u128 + source_amount,
+                        )
+                        .unwrap();
+                    assert!(amount_a_back <= source_amount);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,939 @@
+//! Sigmoid (logistic) price swap curve, max price, steepness and midpoint set at init
+//! Unlike the other bonding curves the price doesn't grow forever, it levels off at `max_price`
+//! Works under the same assumptions as the linear price curve (see `linear_price.rs`):
+//! Deposits (except the initial deposit) are disabled
+//! The initial deposit should only have token B (the bonded token) and 0 token A (the collateral token)
+//! Trade and owner fees are taken in the source token and either sent straight to a fee token account or set aside
+//! in the swap for `claim_fees` (see `SwapV2::accrue_fees`), so they never count as liquidity or move the price
+//! (see `collects_fees_in_trading_tokens`)
+//! Withdrawals are disabled, will panic if those instructions are called
+
+use {
+    crate::{
+        curve::calculator::{
+            map_zero_to_none, CurveCalculator, DynPack, RoundDirection, SwapWithoutFeesResult,
+            TradeDirection, TradingTokenResult,
+        },
+        dfs_precise_number::{DFSPreciseNumber, ONE},
+        error::SwapError,
+    },
+    arrayref::{array_mut_ref, array_ref},
+    solana_program::{
+        program_error::ProgramError,
+        program_pack::{IsInitialized, Pack, Sealed},
+    },
+};
+
+/// exp and ln are only accurate to ~18 decimals, so amounts computed with them are nudged in favor of the swap
+/// by 1/ROUNDING_MARGIN_DENOMINATOR of the amount (plus the same fraction of the scale of the numbers that went
+/// into exp/ln, since the error doesn't shrink along with tiny amounts) before being rounded
+const ROUNDING_MARGIN_DENOMINATOR: u128 = 1_000_000_000_000_000;
+
+/// SigmoidPriceCurve struct implementing CurveCalculator
+/// A is the "collateral" token (e.g. RLY), B is the "bonded" token (e.g. TAKI).
+/// The price of a single B token (a, denominated in amount of token A) is defined by
+/// `a = max_price / (1 + e^(-k*(b - midpoint)))` with `k = steepness`
+/// where b is the amount of token B that's been swapped out of this curve
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SigmoidPriceCurve {
+    /// The price of token B (denominated in A) that the curve levels off at numerator
+    pub max_price_numerator: u64,
+    /// The price of token B (denominated in A) that the curve levels off at denominator
+    pub max_price_denominator: u32,
+    /// How quickly the price moves from 0 to max_price around the midpoint (k, per base unit of token B)
+    /// numerator
+    pub steepness_numerator: u32,
+    /// How quickly the price moves from 0 to max_price around the midpoint (k, per base unit of token B)
+    /// denominator, e.g. 10^decimals of token B for k to be per whole token
+    pub steepness_denominator: u64,
+    /// Amount of token B that has to be swapped out of the curve for the price to reach half of max_price
+    pub midpoint: u64,
+}
+
+/// Rounds an amount the user receives down (after subtracting the rounding margin)
+fn round_amount_out(amount: &DFSPreciseNumber, scale: &DFSPreciseNumber) -> Option<u128> {
+    let margin = amount
+        .checked_add(scale)?
+        .checked_div(&(DFSPreciseNumber::new(ROUNDING_MARGIN_DENOMINATOR)?))?;
+    let (amount, is_negative) = amount.unsigned_sub(&margin);
+    if is_negative {
+        return Some(0);
+    }
+    amount.floor()?.to_imprecise()
+}
+
+/// Rounds an amount the user pays up (after adding the rounding margin)
+fn round_amount_in(amount: &DFSPreciseNumber, scale: &DFSPreciseNumber) -> Option<u128> {
+    let margin = amount
+        .checked_add(scale)?
+        .checked_div(&(DFSPreciseNumber::new(ROUNDING_MARGIN_DENOMINATOR)?))?;
+    amount.checked_add(&margin)?.ceiling()?.to_imprecise()
+}
+
+/// Returns ln(1 + e^-x), i.e. softplus(-x), for x >= 0
+/// (written with 1/e^x since DFSPreciseNumber can't hold negative exponents, once e^x overflows this is 0)
+fn softplus_of_negative(x: &DFSPreciseNumber) -> Option<DFSPreciseNumber> {
+    let one = DFSPreciseNumber::new(1)?;
+    match x.exp() {
+        Some(growth) => one.checked_add(&(one.checked_div(&growth)?))?.ln(),
+        None => DFSPreciseNumber::new(0),
+    }
+}
+
+/// The liquidity integral of the sigmoid price is the softplus function `softplus(x) = ln(1 + e^x)`:
+/// `token_a_bonded = (P/k) * (softplus(k*(b - m)) - softplus(-k*m))` where P = max_price and m = midpoint
+/// (integration constant is chosen so there's 0 token A bonded at b = 0)
+/// Inverting it gives `b = m + ln(e^S - 1) / k` with `S = token_a_bonded * k/P + softplus(-k*m)`
+/// Since DFSPreciseNumber is unsigned, every value that can be negative (b - m, ln(e^S - 1)) is worked out
+/// as a magnitude and a sign
+///
+/// swap_a_to_b and swap_b_to_a are the key functions at the bottom
+impl SigmoidPriceCurve {
+    /// Converts an amount of token B into the dimensionless k*amount that goes into exp/ln
+    /// (k is never computed on its own since it can be too small to represent with 18 decimals)
+    fn to_curve_offset(&self, token_b_amount: &DFSPreciseNumber) -> Option<DFSPreciseNumber> {
+        token_b_amount
+            .checked_mul(&(DFSPreciseNumber::new(self.steepness_numerator.into())?))?
+            .checked_div(&(DFSPreciseNumber::new(self.steepness_denominator.into())?))
+    }
+
+    /// Inverse of to_curve_offset, converts k*amount back into an amount of token B
+    fn to_token_b_amount(&self, curve_offset: &DFSPreciseNumber) -> Option<DFSPreciseNumber> {
+        curve_offset
+            .checked_mul(&(DFSPreciseNumber::new(self.steepness_denominator.into())?))?
+            .checked_div(&(DFSPreciseNumber::new(self.steepness_numerator.into())?))
+    }
+
+    /// Returns 1/k, the amount of token B it takes to move the curve offset by 1
+    fn token_b_scale(&self) -> Option<DFSPreciseNumber> {
+        self.to_token_b_amount(&(DFSPreciseNumber::new(1)?))
+    }
+
+    /// Returns P/k, the amount of token A it takes to move the softplus value by 1
+    fn token_a_scale(&self) -> Option<DFSPreciseNumber> {
+        // make sure to do division last
+        DFSPreciseNumber::new(self.max_price_numerator.into())?
+            .checked_mul(&(DFSPreciseNumber::new(self.steepness_denominator.into())?))?
+            .checked_div(
+                &(DFSPreciseNumber::new(self.max_price_denominator.into())?
+                    .checked_mul(&(DFSPreciseNumber::new(self.steepness_numerator.into())?))?),
+            )
+    }
+
+    /// Returns softplus(k*(b - m)) at the given b value
+    fn softplus_at_b_value(&self, b_value: &DFSPreciseNumber) -> Option<DFSPreciseNumber> {
+        let (distance, is_below_midpoint) =
+            b_value.unsigned_sub(&(DFSPreciseNumber::new(self.midpoint.into())?));
+        let curve_offset = self.to_curve_offset(&distance)?;
+
+        // softplus(x) = x + softplus(-x), so the positive side is just shifted by x
+        let tail = softplus_of_negative(&curve_offset)?;
+        match is_below_midpoint {
+            true => Some(tail),
+            false => curve_offset.checked_add(&tail),
+        }
+    }
+
+    /// Returns S = token_a_bonded * k/P + softplus(-k*m), i.e. softplus(k*(b - m)) at the b value that has
+    /// `token_a_amount` locked
+    fn softplus_with_amt_a_locked(
+        &self,
+        token_a_amount: &DFSPreciseNumber,
+    ) -> Option<DFSPreciseNumber> {
+        token_a_amount
+            .checked_div(&(self.token_a_scale()?))?
+            .checked_add(&(self.softplus_at_b_value(&(DFSPreciseNumber::new(0)?))?))
+    }
+
+    /// Returns the amount of A token locked at a given b_value (by plugging b_value into the integral function)
+    fn amt_a_locked_at_b_value(&self, b_value: &DFSPreciseNumber) -> Option<DFSPreciseNumber> {
+        let (softplus_difference, is_negative) = self
+            .softplus_at_b_value(b_value)?
+            .unsigned_sub(&(self.softplus_at_b_value(&(DFSPreciseNumber::new(0)?))?));
+        // can only be negative from rounding right at b = 0
+        if is_negative {
+            return DFSPreciseNumber::new(0);
+        }
+        softplus_difference.checked_mul(&(self.token_a_scale()?))
+    }
+
+    /// Returns the b value (amount of token B swapped out of the curve) given the amount of token A locked
+    /// b = m + ln(e^S - 1) / k
+    fn b_value_with_amt_a_locked(
+        &self,
+        token_a_amount: &DFSPreciseNumber,
+    ) -> Option<DFSPreciseNumber> {
+        let one = DFSPreciseNumber::new(1)?;
+        let midpoint = DFSPreciseNumber::new(self.midpoint.into())?;
+        let softplus_value = self.softplus_with_amt_a_locked(token_a_amount)?;
+
+        // once e^S overflows, ln(e^S - 1) = S to way more than 18 decimals
+        let growth = match softplus_value.exp() {
+            Some(growth) => growth,
+            None => return midpoint.checked_add(&(self.to_token_b_amount(&softplus_value)?)),
+        };
+        let (growth_minus_one, _) = growth.unsigned_sub(&one);
+        if growth_minus_one.value.is_zero() {
+            return DFSPreciseNumber::new(0);
+        }
+
+        // above the midpoint e^S - 1 >= 1, so ln(e^S - 1) is positive
+        if growth_minus_one.greater_than_or_equal(&one) {
+            return midpoint.checked_add(&(self.to_token_b_amount(&(growth_minus_one.ln()?))?));
+        }
+
+        // below the midpoint ln(e^S - 1) = -ln(1 / (e^S - 1)), and b can't go below 0
+        let distance_below_midpoint =
+            self.to_token_b_amount(&(one.checked_div(&growth_minus_one)?.ln()?))?;
+        let (b_value, is_negative) = midpoint.unsigned_sub(&distance_below_midpoint);
+        match is_negative {
+            true => DFSPreciseNumber::new(0),
+            false => Some(b_value),
+        }
+    }
+
+    /// Swap's in user's collateral token and returns out the bonded token,
+    /// moving right on the price curve and increasing the price of the bonded token
+    fn swap_a_to_b(
+        &self,
+        source_amount: u128,      // amount of user's token a (collateral token)
+        swap_source_amount: u128, // swap's token a (collateral token)
+        swap_destination_amount: u128, // swap's remaining token b (bonded token)
+    ) -> Option<(u128, u128)> {
+        // if source_amount is enough to buy all the remaining B, only take what's needed for that
+        // no need to return None here if the exact out calculation fails (it overflows for amounts too large
+        // to ever be paid for), can just skip this check and do real calculation below
+        if let Some((maximum_a_remaining, _)) = self.swap_a_to_b_exact_out(
+            swap_destination_amount,
+            swap_source_amount,
+            swap_destination_amount,
+        ) {
+            if maximum_a_remaining <= source_amount {
+                return Some((maximum_a_remaining, swap_destination_amount));
+            }
+        }
+
+        let a_start = DFSPreciseNumber::new(swap_source_amount)?;
+        let a_end = a_start.checked_add(&(DFSPreciseNumber::new(source_amount)?))?;
+        let (b_difference, is_negative) = self
+            .b_value_with_amt_a_locked(&a_end)?
+            .unsigned_sub(&(self.b_value_with_amt_a_locked(&a_start)?));
+        // ln can only make b go backwards for amounts too small to get anything out anyway
+        let destination_amount = match is_negative {
+            true => 0,
+            false => round_amount_out(&b_difference, &(self.token_b_scale()?))?,
+        };
+
+        Some((source_amount, destination_amount))
+    }
+
+    fn swap_b_to_a(
+        &self,
+        source_amount: u128,
+        _swap_source_amount: u128,
+        swap_destination_amount: u128,
+    ) -> Option<(u128, u128)> {
+        let token_b_scale = self.token_b_scale()?;
+        let a_start = DFSPreciseNumber::new(swap_destination_amount)?;
+
+        // if the user put in more B tokens than it takes to get back down to b = 0, then just give them all of
+        // the a tokens (swap_destination_amount) and only take the B tokens required to get down to 0
+        let b_start = self.b_value_with_amt_a_locked(&a_start)?;
+        let maximum_b_remaining = round_amount_in(&b_start, &token_b_scale)?;
+        if source_amount >= maximum_b_remaining {
+            return Some((maximum_b_remaining, swap_destination_amount));
+        }
+
+        // source_amount can only be above b_start by less than the rounding margin, treat that as going to b = 0
+        let (b_end, is_negative) = b_start.unsigned_sub(&(DFSPreciseNumber::new(source_amount)?));
+        let b_end = match is_negative {
+            true => DFSPreciseNumber::new(0)?,
+            false => b_end,
+        };
+        let (a_difference, is_negative) =
+            a_start.unsigned_sub(&(self.amt_a_locked_at_b_value(&b_end)?));
+        let destination_amount = match is_negative {
+            true => 0,
+            false => round_amount_out(&a_difference, &(self.token_a_scale()?))?,
+        };
+
+        Some((source_amount, destination_amount))
+    }
+
+    /// Inverse of swap_a_to_b: returns the amount of token A the user has to put in to get exactly
+    /// `destination_amount` of token B out
+    fn swap_a_to_b_exact_out(
+        &self,
+        destination_amount: u128, // amount of token b (bonded token) the user wants out
+        swap_source_amount: u128, // swap's token a (collateral token)
+        swap_destination_amount: u128, // swap's remaining token b (bonded token)
+    ) -> Option<(u128, u128)> {
+        // unlike swap_a_to_b we can't just hand out whatever's left, the user asked for an exact amount
+        if destination_amount > swap_destination_amount {
+            return None;
+        }
+
+        let a_start = DFSPreciseNumber::new(swap_source_amount)?;
+        let b_end = self
+            .b_value_with_amt_a_locked(&a_start)?
+            .checked_add(&(DFSPreciseNumber::new(destination_amount)?))?;
+        let (a_difference, is_negative) =
+            self.amt_a_locked_at_b_value(&b_end)?.unsigned_sub(&a_start);
+        let source_amount = match is_negative {
+            true => 0,
+            false => round_amount_in(&a_difference, &(self.token_a_scale()?))?,
+        };
+
+        Some((source_amount, destination_amount))
+    }
+
+    /// Inverse of swap_b_to_a: returns the amount of token B the user has to put in to get exactly
+    /// `destination_amount` of token A out
+    fn swap_b_to_a_exact_out(
+        &self,
+        destination_amount: u128,
+        _swap_source_amount: u128,
+        swap_destination_amount: u128,
+    ) -> Option<(u128, u128)> {
+        // a_end can't be negative, there's only swap_destination_amount of token A bonded to the curve
+        let a_end = swap_destination_amount.checked_sub(destination_amount)?;
+
+        let b_difference = self
+            .b_value_with_amt_a_locked(&(DFSPreciseNumber::new(swap_destination_amount)?))?
+            .checked_sub(&(self.b_value_with_amt_a_locked(&(DFSPreciseNumber::new(a_end)?))?))?;
+        let source_amount = round_amount_in(&b_difference, &(self.token_b_scale()?))?;
+
+        Some((source_amount, destination_amount))
+    }
+}
+
+/// Returns None if any of the parameters are 0 or the steepness is too small to be represented with
+/// PreciseNumber
+fn is_curve_param_valid(curve: &SigmoidPriceCurve) -> Option<()> {
+    if curve.max_price_numerator == 0
+        || curve.max_price_denominator == 0
+        || curve.steepness_numerator == 0
+        || curve.steepness_denominator == 0
+    {
+        return None;
+    };
+
+    // since PreciseNumber only has 18 decimals, any steepness < 1e-18 will be treated as 0
+    if curve
+        .to_curve_offset(&(DFSPreciseNumber::new(1)?))?
+        .value
+        .is_zero()
+    {
+        return None;
+    }
+    match curve.token_a_scale()?.value.is_zero() {
+        true => None,
+        false => Some(()),
+    }
+}
+
+impl CurveCalculator for SigmoidPriceCurve {
+    /// Calculate how much destination token will be provided given an amount
+    /// of source token.
+    fn swap_without_fees(
+        &self,
+        source_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<SwapWithoutFeesResult> {
+        let (source_amount_swapped, destination_amount_swapped) = match trade_direction {
+            TradeDirection::AtoB => {
+                self.swap_a_to_b(source_amount, swap_source_amount, swap_destination_amount)?
+            }
+            TradeDirection::BtoA => {
+                self.swap_b_to_a(source_amount, swap_source_amount, swap_destination_amount)?
+            }
+        };
+        let source_amount_swapped = map_zero_to_none(source_amount_swapped)?;
+        let destination_amount_swapped = map_zero_to_none(destination_amount_swapped)?;
+        Some(SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped,
+        })
+    }
+
+    /// Calculate how much source token is required to get exactly `destination_amount` of
+    /// destination token out (rounded up in favor of the swap)
+    fn swap_without_fees_exact_out(
+        &self,
+        destination_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<SwapWithoutFeesResult> {
+        let (source_amount_swapped, destination_amount_swapped) = match trade_direction {
+            TradeDirection::AtoB => self.swap_a_to_b_exact_out(
+                destination_amount,
+                swap_source_amount,
+                swap_destination_amount,
+            )?,
+            TradeDirection::BtoA => self.swap_b_to_a_exact_out(
+                destination_amount,
+                swap_source_amount,
+                swap_destination_amount,
+            )?,
+        };
+        let source_amount_swapped = map_zero_to_none(source_amount_swapped)?;
+        let destination_amount_swapped = map_zero_to_none(destination_amount_swapped)?;
+        Some(SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped,
+        })
+    }
+
+    /// Deposits and withdrawals are disabled, same as the linear price curve
+    fn pool_tokens_to_trading_tokens(
+        &self,
+        _pool_tokens: u128,
+        _pool_token_supply: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _round_direction: RoundDirection,
+    ) -> Option<TradingTokenResult> {
+        // this causes a panic if withdraw_all_token_types is called but that's ok for now, cheap way of
+        // disabling withdrawals without having to change how SwapCurve works
+        None
+    }
+
+    /// Deposits are disabled, same as the linear price curve
+    fn deposit_single_token_type(
+        &self,
+        _source_amount: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _pool_supply: u128,
+        _trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        // this never gets called since allows_withdrawals is false (would panic otherwise so still safe)
+        None
+    }
+
+    /// Withdrawals are disabled, and fees are paid out in trading tokens instead of being converted into
+    /// pool tokens (see `collects_fees_in_trading_tokens`)
+    fn withdraw_single_token_type_exact_out(
+        &self,
+        _source_amount: u128,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+        _pool_supply: u128,
+        _trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        // this causes a panic if SwapCurve.withdraw_single_token_type_exact_out instruction is called
+        // but that's ok for now, cheap way of disabling withdrawals without having to change how SwapCurve works
+        None
+    }
+
+    /// Validate that the given curve has no invalid parameters
+    /// Called on `initialize` - max price and steepness must be positive, the midpoint can be anything
+    fn validate(&self) -> Result<(), SwapError> {
+        match is_curve_param_valid(self) {
+            Some(_val) => Ok(()),
+            None => Err(SwapError::InvalidCurve),
+        }
+    }
+
+    /// Validate the given supply on initialization.
+    /// We require at least some bonded token B for the curve to be useful (collateral token must be 0)
+    fn validate_supply(&self, token_a_amount: u64, token_b_amount: u64) -> Result<(), SwapError> {
+        if token_b_amount == 0 {
+            return Err(SwapError::EmptySupply);
+        }
+
+        if token_a_amount != 0 {
+            return Err(SwapError::InvalidSupply);
+        }
+        Ok(())
+    }
+
+    /// Both deposits and withdrawals are intentionally disabled
+    fn allows_deposits(&self) -> bool {
+        false
+    }
+
+    /// The curve position is derived from the amount of token A in the swap, so fees can't be left in the
+    /// swap token accounts (they'd look like bonded collateral and move the price)
+    fn collects_fees_in_trading_tokens(&self) -> bool {
+        true
+    }
+
+    /// Since `softplus(x) = ln(1 + e^x)`, `e^S = 1 + e^(k*(b - m))`, so the spot price
+    /// `P * e^(k*(b - m)) / (1 + e^(k*(b - m)))` simplifies to `P - P/e^S` without having to find b first.
+    /// Returned with a denominator of `dfs_precise_number::ONE`
+    fn spot_price(
+        &self,
+        swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+    ) -> Option<(u128, u128)> {
+        let max_price = DFSPreciseNumber::new(self.max_price_numerator.into())?
+            .checked_div(&(DFSPreciseNumber::new(self.max_price_denominator.into())?))?;
+        let softplus_value =
+            self.softplus_with_amt_a_locked(&(DFSPreciseNumber::new(swap_token_a_amount)?))?;
+
+        // once e^S overflows the price is max_price to way more than 18 decimals
+        let price = match softplus_value.exp() {
+            Some(growth) => max_price.unsigned_sub(&(max_price.checked_div(&growth)?)).0,
+            None => max_price,
+        };
+
+        // the raw DFSPreciseNumber value is the numerator over ONE, as long as it fits in a u128
+        if price.value.bits() > 128 {
+            return None;
+        }
+        Some((price.value.as_u128(), ONE))
+    }
+
+    /// The total normalized value of the sigmoid price curve adds the total
+    /// value of the token A side (as denominated in token B) to the token B side.
+    fn normalized_value(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<spl_math::precise_number::PreciseNumber> {
+        let b_value_of_a =
+            self.b_value_with_amt_a_locked(&(DFSPreciseNumber::new(swap_token_a_amount)?))?;
+        let total_value =
+            b_value_of_a.checked_add(&(DFSPreciseNumber::new(swap_token_b_amount)?))?;
+
+        // same as the linear price curve, truncate to 32 bits so that errors from ln (which are rounded in
+        // favor of the swap when actually swapping) don't show up as a decrease in value
+        let value_bits = total_value.value.bits();
+        let truncated_value = match value_bits > 32 {
+            true => total_value.value >> (value_bits - 32),
+            false => total_value.value,
+        };
+
+        Some(spl_math::precise_number::PreciseNumber {
+            value: truncated_value,
+        })
+    }
+}
+
+/// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
+impl IsInitialized for SigmoidPriceCurve {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+impl Sealed for SigmoidPriceCurve {}
+impl Pack for SigmoidPriceCurve {
+    const LEN: usize = 32;
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        (self as &dyn DynPack).pack_into_slice(output);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<SigmoidPriceCurve, ProgramError> {
+        let max_price_numerator = array_ref![input, 0, 8];
+        let max_price_denominator = array_ref![input, 8, 4];
+        let steepness_numerator = array_ref![input, 12, 4];
+        let steepness_denominator = array_ref![input, 16, 8];
+        let midpoint = array_ref![input, 24, 8];
+        Ok(Self {
+            max_price_numerator: u64::from_le_bytes(*max_price_numerator),
+            max_price_denominator: u32::from_le_bytes(*max_price_denominator),
+            steepness_numerator: u32::from_le_bytes(*steepness_numerator),
+            steepness_denominator: u64::from_le_bytes(*steepness_denominator),
+            midpoint: u64::from_le_bytes(*midpoint),
+        })
+    }
+}
+
+impl DynPack for SigmoidPriceCurve {
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let max_price_numerator = array_mut_ref![output, 0, 8];
+        *max_price_numerator = self.max_price_numerator.to_le_bytes();
+        let max_price_denominator = array_mut_ref![output, 8, 4];
+        *max_price_denominator = self.max_price_denominator.to_le_bytes();
+        let steepness_numerator = array_mut_ref![output, 12, 4];
+        *steepness_numerator = self.steepness_numerator.to_le_bytes();
+        let steepness_denominator = array_mut_ref![output, 16, 8];
+        *steepness_denominator = self.steepness_denominator.to_le_bytes();
+        let midpoint = array_mut_ref![output, 24, 8];
+        *midpoint = self.midpoint.to_le_bytes();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::calculator::test::check_curve_value_from_swap;
+    use proptest::prelude::*;
+
+    /// Price levels off at 100 A per B, reaches 50 A per B after 1000 B have been swapped out and moves from
+    /// ~12% to ~88% of the max price between 800 and 1200 B
+    const TEST_CURVE: SigmoidPriceCurve = SigmoidPriceCurve {
+        max_price_numerator: 100,
+        max_price_denominator: 1,
+        steepness_numerator: 1,
+        steepness_denominator: 100,
+        midpoint: 1000,
+    };
+
+    /// B with 9 decimals, price levels off at 1 base A per base B, reaches half of that after 10,000 whole B
+    /// and k is 1/1000 per whole B
+    const TEST_CURVE_9_DECIMALS: SigmoidPriceCurve = SigmoidPriceCurve {
+        max_price_numerator: 1,
+        max_price_denominator: 1,
+        steepness_numerator: 1,
+        steepness_denominator: 1_000_000_000_000,
+        midpoint: 10_000_000_000_000,
+    };
+
+    #[test]
+    fn swap_a_to_b_basic() {
+        let curve = TEST_CURVE;
+
+        // put in 5000 A, should get 956.74 B out (rounded down to 956)
+        let (source_amount, destination_amount) = curve.swap_a_to_b(5000, 0, 5000).unwrap();
+        assert_eq!(source_amount, 5000);
+        assert_eq!(destination_amount, 956);
+
+        // put in 5000 more A, price has gone up so should get 97.40 B out (rounded down to 97)
+        let (source_amount, destination_amount) = curve.swap_a_to_b(5000, 5000, 4044).unwrap();
+        assert_eq!(source_amount, 5000);
+        assert_eq!(destination_amount, 97);
+
+        // putting in 100K A @ 0 A locked/1500 B remaining should give out the last 1500 B and only take the
+        // 50066.70 A that they cost (rounded up to 50067)
+        let (source_amount, destination_amount) = curve.swap_a_to_b(100_000, 0, 1500).unwrap();
+        assert_eq!(source_amount, 50067);
+        assert_eq!(destination_amount, 1500);
+
+        // way past the midpoint (200K A locked, b = 3000) the price is within 0.000001% of the max price, so
+        // 10K A gets 100.0000002 B (rounded down to 100)
+        let (source_amount, destination_amount) =
+            curve.swap_a_to_b(10_000, 200_000, 10_000).unwrap();
+        assert_eq!(source_amount, 10_000);
+        assert_eq!(destination_amount, 100);
+    }
+
+    #[test]
+    fn swap_b_to_a_basic() {
+        let curve = TEST_CURVE;
+
+        // pretty much the opposite cases as above
+
+        // put in 956 B at 5000 A, should get 4999.99 A out (rounded down to 4999)
+        let (source_amount, destination_amount) = curve.swap_b_to_a(956, 44, 5000).unwrap();
+        assert_eq!(source_amount, 956);
+        assert_eq!(destination_amount, 4999);
+
+        // put in 2000 B at 5000 A, should get all 5000 A out and only take the 956.74 B it took to get there
+        // (rounded up to 957)
+        let (source_amount, destination_amount) = curve.swap_b_to_a(2000, 44, 5000).unwrap();
+        assert_eq!(source_amount, 957);
+        assert_eq!(destination_amount, 5000);
+
+        // nothing to get out of an empty curve
+        assert!(curve
+            .swap_without_fees(2000, 5000, 0, TradeDirection::BtoA)
+            .is_none());
+    }
+
+    #[test]
+    fn swap_exact_out_basic() {
+        let curve = TEST_CURVE;
+
+        // the first 1500 B cost 50066.70 A, rounded up to 50067
+        let (source_amount, destination_amount) =
+            curve.swap_a_to_b_exact_out(1500, 0, 5000).unwrap();
+        assert_eq!(source_amount, 50067);
+        assert_eq!(destination_amount, 1500);
+
+        // can't get out more B than the swap has
+        assert!(curve.swap_a_to_b_exact_out(1501, 0, 1500).is_none());
+
+        // getting 5000 A out @ 100K A (b = 2000) takes 50.0012 B, rounded up to 51
+        let (source_amount, destination_amount) =
+            curve.swap_b_to_a_exact_out(5000, 3000, 100_000).unwrap();
+        assert_eq!(source_amount, 51);
+        assert_eq!(destination_amount, 5000);
+
+        // can't get out more A than the swap has
+        assert!(curve
+            .swap_b_to_a_exact_out(100_001, 3000, 100_000)
+            .is_none());
+    }
+
+    #[test]
+    fn spot_price_basic() {
+        let curve = TEST_CURVE;
+
+        // initial price is 100 / (1 + e^10) = 0.004539786870243439
+        let (numerator, denominator) = curve.spot_price(0, 5000).unwrap();
+        assert_eq!(denominator, ONE);
+        let expected = 4539786870243439;
+        assert!(numerator.max(expected) - numerator.min(expected) <= 10_000);
+
+        // 10000 * (ln(2) - ln(1 + e^-10)) = 6931.0178 A is locked by the midpoint, where the price is half the
+        // max price
+        let (numerator, _) = curve.spot_price(6931, 0).unwrap();
+        assert!(numerator < 50 * ONE);
+        let (numerator, _) = curve.spot_price(6932, 0).unwrap();
+        assert!(numerator > 50 * ONE);
+
+        // after 100K A is bonded (b = 2000) the price is 100 / (1 + e^-10) = 99.99546021312975
+        let (numerator, _) = curve.spot_price(100_000, 0).unwrap();
+        let expected = 99_995460213129756000;
+        assert!(numerator.max(expected) - numerator.min(expected) <= 10_000);
+    }
+
+    #[test]
+    fn spot_price_does_not_exceed_max_price() {
+        let curve = TEST_CURVE;
+
+        let mut previous_price = 0;
+        for swap_token_a_amount in [0u128, 1, 1_000, 1_000_000, u64::MAX.into(), u128::MAX / ONE] {
+            let (numerator, denominator) = curve.spot_price(swap_token_a_amount, 0).unwrap();
+            assert_eq!(denominator, ONE);
+            assert!(numerator >= previous_price);
+            assert!(numerator <= 100 * ONE);
+            previous_price = numerator;
+        }
+
+        // far enough past the midpoint the price is exactly the max price
+        assert_eq!(previous_price, 100 * ONE);
+    }
+
+    #[test]
+    fn swap_large_amounts() {
+        let curve = TEST_CURVE;
+
+        // e^S overflows long before u64::MAX A is bonded, at that point the price is just the max price so
+        // u64::MAX A gets u64::MAX / 100 B (minus the part of the curve below the max price)
+        let (source_amount, destination_amount) = curve
+            .swap_a_to_b(u64::MAX.into(), 0, u64::MAX.into())
+            .unwrap();
+        assert_eq!(source_amount, u64::MAX.into());
+        assert!(destination_amount > u128::from(u64::MAX) / 100);
+        assert!(destination_amount < u128::from(u64::MAX) / 100 + 2000);
+
+        // since the price never goes above the max price, buying all u64::MAX B doesn't overflow either, it
+        // just costs way more A than could ever be paid
+        let (source_amount, _) = curve
+            .swap_a_to_b_exact_out(u64::MAX.into(), 0, u64::MAX.into())
+            .unwrap();
+        assert!(source_amount > 99 * u128::from(u64::MAX));
+
+        // selling it all back shouldn't give out more than was put in
+        let (_, amount_a_back) = curve
+            .swap_b_to_a(
+                destination_amount,
+                u128::from(u64::MAX) - destination_amount,
+                u64::MAX.into(),
+            )
+            .unwrap();
+        assert!(amount_a_back <= u64::MAX.into());
+    }
+
+    #[test]
+    fn pack_curve() {
+        let curve = SigmoidPriceCurve {
+            max_price_numerator: u64::MAX,
+            max_price_denominator: u32::MAX - 1,
+            steepness_numerator: u32::MAX,
+            steepness_denominator: u64::MAX - 1,
+            midpoint: 1_000_000_000,
+        };
+
+        let mut packed = [0u8; SigmoidPriceCurve::LEN];
+        Pack::pack_into_slice(&curve, &mut packed[..]);
+        let unpacked = SigmoidPriceCurve::unpack(&packed).unwrap();
+        assert_eq!(curve, unpacked);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&curve.max_price_numerator.to_le_bytes());
+        packed.extend_from_slice(&curve.max_price_denominator.to_le_bytes());
+        packed.extend_from_slice(&curve.steepness_numerator.to_le_bytes());
+        packed.extend_from_slice(&curve.steepness_denominator.to_le_bytes());
+        packed.extend_from_slice(&curve.midpoint.to_le_bytes());
+        let unpacked = SigmoidPriceCurve::unpack(&packed).unwrap();
+        assert_eq!(curve, unpacked);
+    }
+
+    #[test]
+    fn validate_curve_params() {
+        assert!(TEST_CURVE.validate().is_ok());
+        assert!(TEST_CURVE_9_DECIMALS.validate().is_ok());
+
+        // the midpoint can be anywhere, including at b = 0
+        let curve = SigmoidPriceCurve {
+            midpoint: 0,
+            ..TEST_CURVE
+        };
+        assert!(curve.validate().is_ok());
+
+        // smallest possible steepness still works
+        let curve = SigmoidPriceCurve {
+            steepness_numerator: 1,
+            steepness_denominator: 1_000_000_000_000_000_000,
+            ..TEST_CURVE
+        };
+        assert!(curve.validate().is_ok());
+
+        for curve in [
+            SigmoidPriceCurve {
+                max_price_numerator: 0,
+                ..TEST_CURVE
+            },
+            SigmoidPriceCurve {
+                max_price_denominator: 0,
+                ..TEST_CURVE
+            },
+            SigmoidPriceCurve {
+                steepness_numerator: 0,
+                ..TEST_CURVE
+            },
+            SigmoidPriceCurve {
+                steepness_denominator: 0,
+                ..TEST_CURVE
+            },
+            // too flat to represent
+            SigmoidPriceCurve {
+                steepness_numerator: 1,
+                steepness_denominator: u64::MAX,
+                ..TEST_CURVE
+            },
+        ] {
+            assert_eq!(curve.validate(), Err(SwapError::InvalidCurve));
+        }
+    }
+
+    #[test]
+    fn swap_round_trip_does_not_give_free_tokens() {
+        for curve in [TEST_CURVE, TEST_CURVE_9_DECIMALS] {
+            // buying and immediately selling back should never return more A than was put in, starting from
+            // the bottom of the curve, through the midpoint and up to where the price levels off
+            let mut swap_token_a_amount = 0u128;
+            let mut swap_token_b_amount = u64::MAX as u128;
+            for amount_a in [
+                1u128,
+                49,
+                50,
+                51,
+                1_000,
+                123_456,
+                10_000_000,
+                1_000_000_000_000,
+                100_000_000_000_000,
+            ] {
+                let (source_amount, destination_amount) =
+                    match curve.swap_a_to_b(amount_a, swap_token_a_amount, swap_token_b_amount) {
+                        Some((_, 0)) | None => continue,
+                        Some(result) => result,
+                    };
+                let (_, amount_a_back) = curve
+                    .swap_b_to_a(
+                        destination_amount,
+                        swap_token_b_amount - destination_amount,
+                        swap_token_a_amount + source_amount,
+                    )
+                    .unwrap();
+                assert!(amount_a_back <= source_amount);
+
+                // leave the first trade in the swap so the next one starts further up the curve
+                swap_token_a_amount += source_amount;
+                swap_token_b_amount -= destination_amount;
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_swap_a_to_b(
+            // how much a user is swapping in
+            source_token_amount in 1..u64::MAX,
+            // how much a is already in swap (determines spot price)
+            swap_source_amount in 0..u64::MAX,
+        ) {
+            let curve = TEST_CURVE_9_DECIMALS;
+
+            let (_source_amount_swapped, destination_amount_swapped) = curve
+                .swap_a_to_b(
+                    source_token_amount as u128,
+                    swap_source_amount as u128,
+                    u64::MAX as u128,
+                )
+                .unwrap();
+
+            // ignore the trades where not enough source_token_amount was put in to get any b out
+            if destination_amount_swapped > 0 {
+                check_curve_value_from_swap(
+                    &curve,
+                    source_token_amount as u128,
+                    swap_source_amount as u128,
+                    // swap_destination_amount (the amount of token b in the swap) doesn't affect any of the math
+                    u64::MAX as u128,
+                    TradeDirection::AtoB
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_swap_b_to_a(
+            // how much b user is swapping in
+            source_token_amount in 1..u64::MAX,
+            // how much a is already in swap (determines spot price)
+            swap_destination_amount in 1..u64::MAX,
+        ) {
+            let curve = TEST_CURVE_9_DECIMALS;
+
+            let (_source_amount_swapped, destination_amount_swapped) = curve
+                .swap_b_to_a(
+                    source_token_amount as u128,
+                    u64::MAX as u128,
+                    swap_destination_amount as u128,
+                )
+                .unwrap();
+
+            // ignore the trades where not enough source_token_amount was put in to get any a out
+            if destination_amount_swapped > 0 {
+                check_curve_value_from_swap(
+                    &curve,
+                    source_token_amount as u128,
+                    // swap_source_amount (the amount of token b in the swap) doesn't affect any of the math
+                    u64::MAX as u128,
+                    swap_destination_amount as u128,
+                    TradeDirection::BtoA
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn swap_round_trip_does_not_decrease_curve_value(
+            // how much a user is swapping in
+            source_token_amount in 1..u64::MAX,
+            // how much a is already in swap (determines spot price)
+            swap_token_a_amount in 0..u64::MAX,
+        ) {
+            let curve = TEST_CURVE;
+            let swap_token_b_amount = u64::MAX as u128;
+
+            if let Some((source_amount, destination_amount)) = curve.swap_a_to_b(
+                source_token_amount as u128,
+                swap_token_a_amount as u128,
+                swap_token_b_amount,
+            ) {
+                if destination_amount > 0 {
+                    let (_, amount_a_back) = curve
+                        .swap_b_to_a(
+                            destination_amount,
+                            swap_token_b_amount - destination_amount,
+                            swap_token_a_amount as u128 + source_amount,
+                        )
+                        .unwrap();
+                    assert!(amount_a_back <= source_amount);
+                }
+            }
+        }
+    }
+}
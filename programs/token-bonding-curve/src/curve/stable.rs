@@ -132,6 +132,36 @@ fn compute_new_destination_amount(
     u128::try_from(y).ok()
 }
 
+/// Compute the spot price of token B denominated in token A
+/// Differentiating the invariant:
+/// f(x, y) = leverage * (x + y) + D - leverage * D - D**3 / (4 * x * y)
+/// gives -dx / dy = (∂f / ∂y) / (∂f / ∂x) = x * (y + k) / (y * (x + k))
+/// where k = D**3 / (4 * leverage * x * y)
+fn compute_spot_price(leverage: u64, amount_a: u128, amount_b: u128) -> Option<(u128, u128)> {
+    let d_val: U256 = compute_d(leverage, amount_a, amount_b)?.into();
+    let amount_a: U256 = amount_a.into();
+    let amount_b: U256 = amount_b.into();
+
+    let k = checked_u8_power(&d_val, N_COINS.checked_add(1)?)?.checked_div(
+        checked_u8_mul(&amount_a, N_COINS_SQUARED)?
+            .checked_mul(leverage.into())?
+            .checked_mul(amount_b)?,
+    )?;
+    let mut numerator = amount_a.checked_mul(amount_b.checked_add(k)?)?;
+    let mut denominator = amount_b.checked_mul(amount_a.checked_add(k)?)?;
+
+    // scale both down to fit in a u128, only loses precision on extremely large values
+    let excess_bits = std::cmp::max(numerator.bits(), denominator.bits()).saturating_sub(128);
+    if excess_bits > 0 {
+        numerator >>= excess_bits;
+        denominator >>= excess_bits;
+    }
+    Some((
+        u128::try_from(numerator).ok()?,
+        u128::try_from(denominator).ok()?,
+    ))
+}
+
 impl CurveCalculator for StableCurve {
     /// Stable curve
     fn swap_without_fees(
@@ -309,6 +339,15 @@ impl CurveCalculator for StableCurve {
         }
     }
 
+    fn spot_price(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<(u128, u128)> {
+        let leverage = self.amp.checked_mul(N_COINS as u64)?;
+        compute_spot_price(leverage, swap_token_a_amount, swap_token_b_amount)
+    }
+
     fn validate(&self) -> Result<(), SwapError> {
         // TODO are all amps valid?
         Ok(())
@@ -458,6 +497,34 @@ mod tests {
         assert_eq!(curve, unpacked);
     }
 
+    #[test]
+    fn spot_price() {
+        let curve = StableCurve { amp: 100 };
+
+        // balanced pool is 1:1
+        let (numerator, denominator) = curve.spot_price(1_000_000, 1_000_000).unwrap();
+        assert_eq!(numerator, denominator);
+
+        // more token B makes token B cheaper, but not as cheap as constant product
+        let (numerator, denominator) = curve.spot_price(1_000_000, 4_000_000).unwrap();
+        assert!(numerator < denominator);
+        assert!(numerator * 4 > denominator);
+
+        // the marginal price matches a tiny swap
+        let result = curve
+            .swap_without_fees(1_000, 4_000_000, 1_000_000, TradeDirection::BtoA)
+            .unwrap();
+        let swap_price = result.destination_amount_swapped as f64 / 1_000f64;
+        let spot_price = numerator as f64 / denominator as f64;
+        assert!((swap_price - spot_price).abs() < 0.01);
+
+        // large amounts still fit
+        assert!(curve.spot_price(u64::MAX as u128, 1).is_some());
+
+        // empty pool has no price
+        assert_eq!(curve.spot_price(0, 1_000_000), None);
+    }
+
     proptest! {
         #[test]
         fn curve_value_does_not_decrease_from_deposit(
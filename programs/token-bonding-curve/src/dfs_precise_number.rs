@@ -5,6 +5,8 @@
 //! since we're only ever operating on wrapped u64 type numbers
 //! Also fixes some quirks from PreciseNumber around to_imprecise and removes pow/root
 //! since we don't need those (could add them back in if we did more testing around precision)
+//! exp/ln are added for the exponential price curve, they're computed internally with 36 decimals so
+//! the series don't accumulate rounding errors at 18 decimals
 
 use spl_math::uint::U256;
 
@@ -18,6 +20,13 @@ type InnerUint = U256;
 pub const ONE: u128 = 1_000000_000000_000000;
 /// Used for sqrt_u64 to correct precision calculation
 pub const SQRT_ONE: u128 = 1000_000000;
+/// ONE squared, the extra precision exp and ln use internally for their series
+const ONE_SQUARED: u128 = ONE * ONE;
+/// ln(2) with 36 decimals (i.e. scaled by ONE_SQUARED), used for range reduction in exp and ln
+const LN_2: u128 = 693147180559945309_417232121458176568;
+/// Newton's method converges quadratically once it's close to the root, so if it hasn't converged after
+/// this many iterations the initial guess was way off (newton_root gives up and returns None)
+const MAX_NEWTON_ITERATIONS: usize = 64;
 
 /// Struct encapsulating a fixed-point number that allows for decimal calculations
 #[derive(Clone, Debug, PartialEq)]
@@ -189,6 +198,194 @@ impl DFSPreciseNumber {
         spl_number.checked_add(&decimals)
     }
 
+    /// e^self
+    /// Reduces the exponent to `self = k*ln(2) + r` with 0 <= r < ln(2) so that the taylor series for e^r
+    /// converges quickly, then multiplies by 2^k with a bit shift
+    /// Returns None if the result would overflow
+    pub fn exp(&self) -> Option<Self> {
+        let one_squared = InnerUint::from(ONE_SQUARED);
+        let ln_2 = InnerUint::from(LN_2);
+
+        // k = floor(self / ln(2)), self.value is scaled by ONE so scale it up to match LN_2
+        let k = self.value.checked_mul(one())?.checked_div(ln_2)?;
+        // e^r is < 2, which takes up 61 bits once scaled by ONE, so anything above this overflows anyway
+        if k > InnerUint::from(256 - 61) {
+            return None;
+        }
+        let k = k.as_usize();
+
+        // r = self - k*ln(2) with 36 decimals
+        let r = self
+            .value
+            .checked_mul(one())?
+            .checked_sub(ln_2.checked_mul(InnerUint::from(k))?)?;
+
+        // e^r = 1 + r + r^2/2! + r^3/3! + ..., stop once the terms are too small to affect the 18 decimal result
+        let mut sum = one_squared;
+        let mut term = one_squared;
+        let mut n = 1u64;
+        while term >= one() {
+            term = term
+                .checked_mul(r)?
+                .checked_div(one_squared.checked_mul(InnerUint::from(n))?)?;
+            sum = sum.checked_add(term)?;
+            n += 1;
+        }
+
+        // back down to 18 decimals (rounding to nearest like the rest of DFSPreciseNumber) then multiply by 2^k
+        let value = sum
+            .checked_add(Self::rounding_correction())?
+            .checked_div(one())?;
+        if value.bits() + k > 256 {
+            return None;
+        }
+        Some(Self { value: value << k })
+    }
+
+    /// Natural log of self
+    /// Since DFSPreciseNumber is unsigned, this is only defined for self >= 1 (returns None otherwise)
+    /// Reduces self to `2^k * m` with 1 <= m < 2 and uses the series
+    /// `ln(m) = 2 * (s + s^3/3 + s^5/5 + ...)` where `s = (m - 1) / (m + 1)` (so s < 1/3)
+    pub fn ln(&self) -> Option<Self> {
+        if self.value < one() {
+            return None;
+        }
+        let one_squared = InnerUint::from(ONE_SQUARED);
+
+        // k = floor(log2(self)), i.e. the position of the highest bit of the integer part
+        let k = self.value.checked_div(one())?.bits() - 1;
+
+        // m = self / 2^k with 36 decimals (if self is too big to scale up first, it has plenty of bits to
+        // shift off before scaling without losing precision)
+        let m = match self.value.checked_mul(one()) {
+            Some(value) => value >> k,
+            None => (self.value >> k).checked_mul(one())?,
+        };
+
+        let s = m
+            .checked_sub(one_squared)?
+            .checked_mul(one_squared)?
+            .checked_div(m.checked_add(one_squared)?)?;
+        let s_squared = s.checked_mul(s)?.checked_div(one_squared)?;
+
+        // stop once the terms are too small to affect the 18 decimal result
+        let mut sum = s;
+        let mut term = s;
+        let mut n = 1u64;
+        while term >= one() {
+            term = term.checked_mul(s_squared)?.checked_div(one_squared)?;
+            sum = sum.checked_add(term.checked_div(InnerUint::from(2 * n + 1))?)?;
+            n += 1;
+        }
+
+        let value = InnerUint::from(LN_2)
+            .checked_mul(InnerUint::from(k))?
+            .checked_add(sum.checked_mul(InnerUint::from(2))?)?
+            .checked_add(Self::rounding_correction())?
+            .checked_div(one())?;
+        Some(Self { value })
+    }
+
+    /// Solves `f(x) = target` for x >= 0 with Newton's method, where f is increasing and convex with f(0) <= target
+    /// (e.g. the integral of a bonding curve's price), and f_prime is its derivative
+    /// Starting from an initial guess that's above the root, each Newton step lands closer to the root without
+    /// overshooting it, so this just steps down until the steps become too small to represent. If the initial
+    /// guess is below the root, it's doubled until it isn't
+    /// If should_round_up, the result always has `f(x) >= target`, otherwise it always has `f(x) <= target`
+    /// Returns None if any of the calculations overflow or it doesn't converge within MAX_NEWTON_ITERATIONS
+    pub fn newton_root<F, D>(
+        target: &Self,
+        initial_guess: Self,
+        f: F,
+        f_prime: D,
+        should_round_up: bool,
+    ) -> Option<Self>
+    where
+        F: Fn(&Self) -> Option<Self>,
+        D: Fn(&Self) -> Option<Self>,
+    {
+        // f(0) <= target so f(0) == target
+        if target.value.is_zero() {
+            return Some(Self::zero());
+        }
+        let mut x = match initial_guess.value.is_zero() {
+            true => Self::new(1)?,
+            false => initial_guess,
+        };
+        let mut f_x = f(&x)?;
+        let mut iterations = 0;
+        while f_x.less_than(target) {
+            x = x.checked_add(&x)?;
+            f_x = f(&x)?;
+            iterations += 1;
+            if iterations > MAX_NEWTON_ITERATIONS {
+                return None;
+            }
+        }
+
+        // x is now always at or above the root (f(x) >= target)
+        let mut converged = false;
+        for _ in 0..MAX_NEWTON_ITERATIONS {
+            if f_x.eq(target) {
+                converged = true;
+                break;
+            }
+            let step = f_x.checked_sub(target)?.checked_div(&(f_prime(&x)?))?;
+            if step.value.is_zero() {
+                converged = true;
+                break;
+            }
+            let (next_x, is_negative) = x.unsigned_sub(&step);
+            let next_x = match is_negative {
+                true => Self::zero(),
+                false => next_x,
+            };
+            let f_next_x = f(&next_x)?;
+            if f_next_x.less_than(target) {
+                // only possible due to rounding, so we're within rounding distance of the root
+                if !should_round_up {
+                    return Some(next_x);
+                }
+                converged = true;
+                break;
+            }
+            // away from the root each step at least halves the distance to the target (for the polynomials this
+            // is used for), if it doesn't then f has lost precision internally and we can't get any closer
+            let made_progress = f_next_x
+                .checked_sub(target)?
+                .checked_add(&(f_next_x.checked_sub(target)?))?
+                .less_than_or_equal(&(f_x.checked_sub(target)?));
+            x = next_x;
+            f_x = f_next_x;
+            if !made_progress {
+                converged = true;
+                break;
+            }
+        }
+        if !converged {
+            return None;
+        }
+        if should_round_up || f_x.eq(target) {
+            return Some(x);
+        }
+
+        // step down until we're at or below the root, the root is within a few precise units of x so this
+        // doesn't take long
+        let mut step = Self {
+            value: InnerUint::from(1),
+        };
+        loop {
+            let (next_x, is_negative) = x.unsigned_sub(&step);
+            if is_negative {
+                return Some(Self::zero());
+            }
+            if f(&next_x)?.less_than_or_equal(target) {
+                return Some(next_x);
+            }
+            step = step.checked_add(&step)?;
+        }
+    }
+
     /// Babylonian sqrt method
     /// Note this will round up to the nearest int depending on `should_round_up`
     fn sqrt_babylonian(x: u64, should_round_up: bool) -> Option<u64> {
@@ -618,6 +815,235 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_exp() {
+        // e^0 = 1 exactly
+        let number = DFSPreciseNumber::new(0).unwrap();
+        assert_eq!(number.exp().unwrap(), DFSPreciseNumber::new(1).unwrap());
+
+        // e^1 = 2.718281828459045235
+        let number = DFSPreciseNumber::new(1).unwrap();
+        let expected = DFSPreciseNumber {
+            value: InnerUint::from(2_718281828_459045235u128),
+        };
+        assert!(
+            number
+                .exp()
+                .unwrap()
+                .almost_eq(&expected, InnerUint::from(2)),
+            "exp {:?} not equal to expected {:?}",
+            number.exp().unwrap(),
+            expected,
+        );
+
+        // e^0.000000001 = 1.000000001000000001
+        let number = DFSPreciseNumber {
+            value: InnerUint::from(1_000_000_000u128),
+        };
+        let expected = DFSPreciseNumber {
+            value: InnerUint::from(1_000000001_000000001u128),
+        };
+        assert!(
+            number
+                .exp()
+                .unwrap()
+                .almost_eq(&expected, InnerUint::from(2)),
+            "exp {:?} not equal to expected {:?}",
+            number.exp().unwrap(),
+            expected,
+        );
+
+        // e^50 = 5184705528587072464087.453322933485384827 (first 18 digits are accurate)
+        let number = DFSPreciseNumber::new(50).unwrap();
+        let expected = DFSPreciseNumber::new(5184705528587072464087).unwrap();
+        assert!(
+            number.exp().unwrap().almost_eq(
+                &expected,
+                InnerUint::from(ONE)
+                    .checked_mul(InnerUint::from(10_000))
+                    .unwrap()
+            ),
+            "exp {:?} not equal to expected {:?}",
+            number.exp().unwrap(),
+            expected,
+        );
+
+        // too large to fit
+        let number = DFSPreciseNumber::new(200).unwrap();
+        assert!(number.exp().is_none());
+    }
+
+    #[test]
+    fn test_ln() {
+        // ln(1) = 0 exactly
+        let number = DFSPreciseNumber::new(1).unwrap();
+        assert_eq!(number.ln().unwrap(), DFSPreciseNumber::new(0).unwrap());
+
+        // ln(2) = 0.693147180559945309
+        let number = DFSPreciseNumber::new(2).unwrap();
+        let expected = DFSPreciseNumber {
+            value: InnerUint::from(693147180559945309u128),
+        };
+        assert!(
+            number
+                .ln()
+                .unwrap()
+                .almost_eq(&expected, InnerUint::from(2)),
+            "ln {:?} not equal to expected {:?}",
+            number.ln().unwrap(),
+            expected,
+        );
+
+        // ln(1.000000001) = 0.000000000999999999500000000333333333
+        let number = DFSPreciseNumber {
+            value: InnerUint::from(1_000000001_000000000u128),
+        };
+        let expected = DFSPreciseNumber {
+            value: InnerUint::from(999999999u128),
+        };
+        assert!(
+            number
+                .ln()
+                .unwrap()
+                .almost_eq(&expected, InnerUint::from(2)),
+            "ln {:?} not equal to expected {:?}",
+            number.ln().unwrap(),
+            expected,
+        );
+
+        // ln(10^30) = 69.077552789821370520
+        let number = DFSPreciseNumber::new(10u128.pow(30)).unwrap();
+        let expected = DFSPreciseNumber {
+            value: InnerUint::from(69_077552789_821370520u128),
+        };
+        assert!(
+            number
+                .ln()
+                .unwrap()
+                .almost_eq(&expected, InnerUint::from(2)),
+            "ln {:?} not equal to expected {:?}",
+            number.ln().unwrap(),
+            expected,
+        );
+
+        // too large to scale up by ONE before reducing (10^45)
+        let number = DFSPreciseNumber::new(10u128.pow(30))
+            .unwrap()
+            .checked_mul(&(DFSPreciseNumber::new(10u128.pow(15)).unwrap()))
+            .unwrap();
+        assert!(number.value.checked_mul(one()).is_none());
+        let expected = DFSPreciseNumber {
+            value: InnerUint::from(103_616329184_732055781u128),
+        };
+        assert!(
+            number
+                .ln()
+                .unwrap()
+                .almost_eq(&expected, InnerUint::from(2)),
+            "ln {:?} not equal to expected {:?}",
+            number.ln().unwrap(),
+            expected,
+        );
+
+        // ln is only defined for numbers >= 1
+        let number = DFSPreciseNumber::new(1)
+            .unwrap()
+            .checked_div(&(DFSPreciseNumber::new(2).unwrap()))
+            .unwrap();
+        assert!(number.ln().is_none());
+    }
+
+    #[test]
+    fn test_exp_ln_round_trip() {
+        for value in [1u128, 7, 1_000, 123_456_789, 10u128.pow(20)] {
+            let number = DFSPreciseNumber::new(value).unwrap();
+            let round_trip = number.ln().unwrap().exp().unwrap();
+            // ln is accurate to 18 decimals so exp(ln(x)) is accurate to ~18 significant digits of x
+            let precision = number
+                .value
+                .checked_div(InnerUint::from(10u128.pow(16)))
+                .unwrap()
+                .checked_add(InnerUint::from(10))
+                .unwrap();
+            assert!(
+                round_trip.almost_eq(&number, precision),
+                "exp(ln({:?})) = {:?}",
+                number,
+                round_trip,
+            );
+        }
+    }
+
+    #[test]
+    fn test_newton_root() {
+        // x^3 = 27
+        let cube = |x: &DFSPreciseNumber| x.checked_mul(x)?.checked_mul(x);
+        let cube_prime = |x: &DFSPreciseNumber| {
+            x.checked_mul(x)?
+                .checked_mul(&(DFSPreciseNumber::new(3).unwrap()))
+        };
+        let target = DFSPreciseNumber::new(27).unwrap();
+        let expected = DFSPreciseNumber::new(3).unwrap();
+        for initial_guess in [0u128, 1, 3, 4, 1_000_000] {
+            for should_round_up in [true, false] {
+                let root = DFSPreciseNumber::newton_root(
+                    &target,
+                    DFSPreciseNumber::new(initial_guess).unwrap(),
+                    cube,
+                    cube_prime,
+                    should_round_up,
+                )
+                .unwrap();
+                assert!(
+                    root.almost_eq(&expected, InnerUint::from(10)),
+                    "root {:?} not equal to expected {:?}",
+                    root,
+                    expected,
+                );
+            }
+        }
+
+        // x^3 = 2, cube root is 1.259921049894873164767...
+        let target = DFSPreciseNumber::new(2).unwrap();
+        let root_up = DFSPreciseNumber::newton_root(
+            &target,
+            DFSPreciseNumber::new(2).unwrap(),
+            cube,
+            cube_prime,
+            true,
+        )
+        .unwrap();
+        let root_down = DFSPreciseNumber::newton_root(
+            &target,
+            DFSPreciseNumber::new(2).unwrap(),
+            cube,
+            cube_prime,
+            false,
+        )
+        .unwrap();
+        let expected = DFSPreciseNumber {
+            value: InnerUint::from(1_259921049894873165u128),
+        };
+        assert!(root_up.almost_eq(&expected, InnerUint::from(10)));
+        assert!(root_down.almost_eq(&expected, InnerUint::from(10)));
+        // rounding is always in the right direction
+        assert!(cube(&root_up).unwrap().greater_than_or_equal(&target));
+        assert!(cube(&root_down).unwrap().less_than_or_equal(&target));
+        assert!(root_down.less_than_or_equal(&root_up));
+
+        // root of 0 is 0
+        let target = DFSPreciseNumber::new(0).unwrap();
+        let root = DFSPreciseNumber::newton_root(
+            &target,
+            DFSPreciseNumber::new(5).unwrap(),
+            cube,
+            cube_prime,
+            false,
+        )
+        .unwrap();
+        assert_eq!(root, target);
+    }
+
     #[test]
     fn test_floor() {
         let whole_number = DFSPreciseNumber::new(2).unwrap();
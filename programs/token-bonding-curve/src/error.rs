@@ -1,112 +1,160 @@
 //! Error types
 
+use anchor_lang::prelude::*;
 use num_derive::FromPrimitive;
-use solana_program::{decode_error::DecodeError, program_error::ProgramError};
-use thiserror::Error;
+use solana_program::decode_error::DecodeError;
 
 /// Errors that may be returned by the TokenSwap program.
-#[derive(Clone, Debug, Eq, Error, FromPrimitive, PartialEq)]
+/// These are the program's Anchor error codes (offset by `ERROR_CODE_OFFSET`, so `AlreadyInUse` is 6000) and are
+/// published in the IDL, so variants must only ever be added at the end to keep the codes stable
+#[error]
+#[derive(Eq, FromPrimitive, PartialEq)]
 pub enum SwapError {
     // 0.
     /// The account cannot be initialized because it is already being used.
-    #[error("Swap account already in use")]
+    #[msg("Swap account already in use")]
     AlreadyInUse,
     /// The program address provided doesn't match the value generated by the program.
-    #[error("Invalid program address generated from bump seed and key")]
+    #[msg("Invalid program address generated from bump seed and key")]
     InvalidProgramAddress,
     /// The owner of the input isn't set to the program address generated by the program.
-    #[error("Input account owner is not the program address")]
+    #[msg("Input account owner is not the program address")]
     InvalidOwner,
     /// The owner of the pool token output is set to the program address generated by the program.
-    #[error("Output pool account owner cannot be the program address")]
+    #[msg("Output pool account owner cannot be the program address")]
     InvalidOutputOwner,
     /// The deserialization of the account returned something besides State::Mint.
-    #[error("Deserialized account is not an SPL Token mint")]
+    #[msg("Deserialized account is not an SPL Token mint")]
     ExpectedMint,
 
     // 5.
     /// The deserialization of the account returned something besides State::Account.
-    #[error("Deserialized account is not an SPL Token account")]
+    #[msg("Deserialized account is not an SPL Token account")]
     ExpectedAccount,
     /// The input token account is empty.
-    #[error("Input token account empty")]
+    #[msg("Input token account empty")]
     EmptySupply,
     /// The pool token mint has a non-zero supply.
-    #[error("Pool token mint has a non-zero supply")]
+    #[msg("Pool token mint has a non-zero supply")]
     InvalidSupply,
     /// The provided token account has a delegate.
-    #[error("Token account has a delegate")]
+    #[msg("Token account has a delegate")]
     InvalidDelegate,
     /// The input token is invalid for swap.
-    #[error("InvalidInput")]
+    #[msg("InvalidInput")]
     InvalidInput,
 
     // 10.
     /// Address of the provided swap token account is incorrect.
-    #[error("Address of the provided swap token account is incorrect")]
+    #[msg("Address of the provided swap token account is incorrect")]
     IncorrectSwapAccount,
     /// Address of the provided pool token mint is incorrect
-    #[error("Address of the provided pool token mint is incorrect")]
+    #[msg("Address of the provided pool token mint is incorrect")]
     IncorrectPoolMint,
     /// The output token is invalid for swap.
-    #[error("InvalidOutput")]
+    #[msg("InvalidOutput")]
     InvalidOutput,
     /// General calculation failure due to overflow or underflow
-    #[error("General calculation failure due to overflow or underflow")]
+    #[msg("General calculation failure due to overflow or underflow")]
     CalculationFailure,
     /// Invalid instruction number passed in.
-    #[error("Invalid instruction")]
+    #[msg("Invalid instruction")]
     InvalidInstruction,
 
     // 15.
     /// Swap input token accounts have the same mint
-    #[error("Swap input token accounts have the same mint")]
+    #[msg("Swap input token accounts have the same mint")]
     RepeatedMint,
     /// Swap instruction exceeds desired slippage limit
-    #[error("Swap instruction exceeds desired slippage limit")]
+    #[msg("Swap instruction exceeds desired slippage limit")]
     ExceededSlippage,
     /// The provided token account has a close authority.
-    #[error("Token account has a close authority")]
+    #[msg("Token account has a close authority")]
     InvalidCloseAuthority,
     /// The pool token mint has a freeze authority.
-    #[error("Pool token mint has a freeze authority")]
+    #[msg("Pool token mint has a freeze authority")]
     InvalidFreezeAuthority,
     /// The pool fee token account is incorrect
-    #[error("Pool fee token account incorrect")]
+    #[msg("Pool fee token account incorrect")]
     IncorrectFeeAccount,
 
     // 20.
     /// Given pool token amount results in zero trading tokens
-    #[error("Given pool token amount results in zero trading tokens")]
+    #[msg("Given pool token amount results in zero trading tokens")]
     ZeroTradingTokens,
     /// The fee calculation failed due to overflow, underflow, or unexpected 0
-    #[error("Fee calculation failed due to overflow, underflow, or unexpected 0")]
+    #[msg("Fee calculation failed due to overflow, underflow, or unexpected 0")]
     FeeCalculationFailure,
     /// ConversionFailure
-    #[error("Conversion to u64 failed with an overflow or underflow")]
+    #[msg("Conversion to u64 failed with an overflow or underflow")]
     ConversionFailure,
     /// The provided fee does not match the program owner's constraints
-    #[error("The provided fee does not match the program owner's constraints")]
+    #[msg("The provided fee does not match the program owner's constraints")]
     InvalidFee,
     /// The provided token program does not match the token program expected by the swap
-    #[error("The provided token program does not match the token program expected by the swap")]
+    #[msg("The provided token program does not match the token program expected by the swap")]
     IncorrectTokenProgramId,
 
     // 25.
     /// The provided curve type is not supported by the program owner
-    #[error("The provided curve type is not supported by the program owner")]
+    #[msg("The provided curve type is not supported by the program owner")]
     UnsupportedCurveType,
     /// The provided curve parameters are invalid
-    #[error("The provided curve parameters are invalid")]
+    #[msg("The provided curve parameters are invalid")]
     InvalidCurve,
     /// The operation cannot be performed on the given curve
-    #[error("The operation cannot be performed on the given curve")]
+    #[msg("The operation cannot be performed on the given curve")]
     UnsupportedCurveOperation,
-}
-impl From<SwapError> for ProgramError {
-    fn from(e: SwapError) -> Self {
-        ProgramError::Custom(e as u32)
-    }
+    /// The provided curve params account does not match the one stored in the swap
+    #[msg("The provided curve params account does not match the one stored in the swap")]
+    IncorrectCurveParamsAccount,
+    /// The provided token B mint does not match the one stored in the swap
+    #[msg("The provided token B mint does not match the one stored in the swap")]
+    IncorrectTokenBMint,
+
+    // 30.
+    /// The signer is not the swap's admin (or pending admin, when accepting the admin role)
+    #[msg("The signer is not the swap's admin")]
+    InvalidAdmin,
+    /// The swap has been paused by its admin
+    #[msg("The swap is paused")]
+    Paused,
+    /// The swap account isn't on the version the instruction expects, SwapV1 swaps have to be migrated with
+    /// `migrate_swap_v1_to_v2` before they can be used
+    #[msg("The swap account's version isn't supported by the instruction")]
+    IncorrectSwapVersion,
+    /// The swap has been put in emergency mode by its admin, which permanently disables it
+    #[msg("The swap is in emergency mode")]
+    EmergencyMode,
+    /// The instruction is only available once the admin has put the swap in emergency mode
+    #[msg("The swap isn't in emergency mode")]
+    NotEmergencyMode,
+
+    // 35.
+    /// The swap can't be closed while its token accounts still hold tokens
+    #[msg("The swap's token accounts aren't empty")]
+    SwapNotEmpty,
+    /// The swap doesn't have enough of the destination token left for the trade, e.g. a bonding curve that has
+    /// sold all of its token B
+    #[msg("The swap doesn't have enough of the destination token left")]
+    CurveSoldOut,
+    /// The amount is too small to trade, it's either used up by fees or worth less than the smallest unit of the
+    /// other token
+    #[msg("The amount is below the minimum tradable size")]
+    AmountBelowMinimum,
+    /// The curve's calculation overflowed for the given amounts
+    #[msg("The curve calculation overflowed")]
+    CurveCalculationOverflow,
+    /// The registry or registry page account doesn't match the swap's collateral mint and the registry's last
+    /// page
+    #[msg("Incorrect registry account provided")]
+    IncorrectRegistryAccount,
+
+    // 40.
+    /// A swap closed without the admin's signature has to return everything to its creator, the recipient or a
+    /// destination account that's drained to doesn't belong to them
+    #[msg("The close recipient isn't the swap's creator")]
+    IncorrectCloseRecipient,
 }
 impl<T> DecodeError<T> for SwapError {
     fn type_of() -> &'static str {
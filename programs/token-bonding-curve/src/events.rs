@@ -0,0 +1,218 @@
+//! Events emitted by the instruction handlers, so clients and indexers can follow a swap's state from the
+//! transaction logs without re-reading its accounts
+
+use anchor_lang::prelude::*;
+
+/// Emitted by `initialize_*` once the swap is set up
+#[event]
+pub struct InitializeEvent {
+    /// Token-swap account
+    pub swap: Pubkey,
+    /// Mint of token A
+    pub token_a_mint: Pubkey,
+    /// Mint of token B
+    pub token_b_mint: Pubkey,
+    /// Curve type, see `CurveType`
+    pub curve_type: u8,
+    /// Curve parameters, packed the same way as the calculator's slot in the swap account
+    pub curve_parameters: Vec<u8>,
+    /// Curve parameters kept in the swap's curve params account, packed the same way as that account's data
+    /// (empty for curves that store everything in the swap, see `CurveType::curve_params_len`)
+    pub curve_params_account_data: Vec<u8>,
+    /// Whether token B is minted on buys and burned on sells
+    pub token_b_mint_and_burn: bool,
+    /// Token A the curve is seeded with
+    pub bonded_token_a_amount: u64,
+}
+
+/// Emitted by `swap` and `swap_exact_amount_out`, spot prices are in token A per token B and are (0, 0) if the
+/// curve can't calculate them (see `CurveCalculator::spot_price`)
+#[event]
+pub struct SwapEvent {
+    /// Token-swap account
+    pub swap: Pubkey,
+    /// True when trading token A for token B (a buy), false when trading token B for token A (a sell)
+    pub a_to_b: bool,
+    /// Source tokens taken from the user, including fees
+    pub amount_in: u64,
+    /// Destination tokens paid out to the user
+    pub amount_out: u64,
+    /// Trade fee, in source tokens
+    pub trade_fee: u64,
+    /// Owner trade fee, in source tokens
+    pub owner_fee: u64,
+    /// Spot price numerator before the swap
+    pub spot_price_before_numerator: u128,
+    /// Spot price denominator before the swap
+    pub spot_price_before_denominator: u128,
+    /// Spot price numerator after the swap
+    pub spot_price_after_numerator: u128,
+    /// Spot price denominator after the swap
+    pub spot_price_after_denominator: u128,
+    /// Token A collateral backing the curve after the swap, see `SwapV2::bonded_token_a_amount`
+    pub bonded_token_a_amount: u64,
+}
+
+/// Emitted by `quote_swap`, with the same fields the corresponding `SwapEvent` would have if the swap went
+/// through
+#[event]
+pub struct QuoteSwapEvent {
+    /// Token-swap account
+    pub swap: Pubkey,
+    /// True when quoting token A for token B, false when quoting token B for token A
+    pub a_to_b: bool,
+    /// Source tokens the swap would take, including fees
+    pub amount_in: u64,
+    /// Destination tokens the swap would pay out
+    pub amount_out: u64,
+    /// Trade fee, in source tokens
+    pub trade_fee: u64,
+    /// Owner trade fee, in source tokens
+    pub owner_fee: u64,
+    /// Spot price numerator before the swap
+    pub spot_price_before_numerator: u128,
+    /// Spot price denominator before the swap
+    pub spot_price_before_denominator: u128,
+    /// Spot price numerator after the swap
+    pub spot_price_after_numerator: u128,
+    /// Spot price denominator after the swap
+    pub spot_price_after_denominator: u128,
+}
+
+/// Emitted by `deposit_all_token_types` and `deposit_single_token_type_exact_amount_in`
+#[event]
+pub struct DepositEvent {
+    /// Token-swap account
+    pub swap: Pubkey,
+    /// Token A deposited
+    pub token_a_amount: u64,
+    /// Token B deposited
+    pub token_b_amount: u64,
+    /// Pool tokens minted to the user
+    pub pool_token_amount: u64,
+    /// Token A collateral backing the curve after the deposit
+    pub bonded_token_a_amount: u64,
+}
+
+/// Emitted by `withdraw_all_token_types` and `withdraw_single_token_type_exact_amount_out`
+#[event]
+pub struct WithdrawEvent {
+    /// Token-swap account
+    pub swap: Pubkey,
+    /// Token A withdrawn
+    pub token_a_amount: u64,
+    /// Token B withdrawn
+    pub token_b_amount: u64,
+    /// Pool tokens burned from the user, including the withdraw fee
+    pub pool_token_amount: u64,
+    /// Withdraw fee, in pool tokens sent to the pool fee account
+    pub withdraw_fee: u64,
+    /// Token A collateral backing the curve after the withdrawal
+    pub bonded_token_a_amount: u64,
+}
+
+/// Emitted by `set_admin`, `accept_admin` and `renounce_admin`
+#[event]
+pub struct AdminEvent {
+    /// Token-swap account
+    pub swap: Pubkey,
+    /// Admin after the instruction, None once renounced
+    pub admin: Option<Pubkey>,
+    /// Pending admin after the instruction, set by `set_admin` until it's accepted
+    pub pending_admin: Option<Pubkey>,
+}
+
+/// Emitted by `pause` and `resume`
+#[event]
+pub struct PauseEvent {
+    /// Token-swap account
+    pub swap: Pubkey,
+    /// Whether the swap is paused after the instruction
+    pub paused: bool,
+}
+
+/// Emitted by `set_accrue_fees`
+#[event]
+pub struct AccrueFeesEvent {
+    /// Token-swap account
+    pub swap: Pubkey,
+    /// Whether fees build up in the swap after the instruction
+    pub accrue_fees: bool,
+}
+
+/// Emitted by `sweep_surplus`
+#[event]
+pub struct SweepSurplusEvent {
+    /// Token-swap account
+    pub swap: Pubkey,
+    /// Token A swept to the destination
+    pub token_a_amount: u64,
+}
+
+/// Emitted by `claim_fees`
+#[event]
+pub struct ClaimFeesEvent {
+    /// Token-swap account
+    pub swap: Pubkey,
+    /// Token A fees claimed
+    pub token_a_amount: u64,
+    /// Token B fees claimed
+    pub token_b_amount: u64,
+}
+
+/// Emitted by `migrate_swap_v1_to_v2`
+#[event]
+pub struct MigrateSwapEvent {
+    /// Token-swap account
+    pub swap: Pubkey,
+    /// Token A collateral the migrated swap starts bonding with
+    pub bonded_token_a_amount: u64,
+    /// Token B the migrated swap starts out having sold
+    pub sold_token_b_amount: u64,
+}
+
+/// Emitted by `enable_emergency_mode`
+#[event]
+pub struct EmergencyModeEvent {
+    /// Token-swap account
+    pub swap: Pubkey,
+    /// Token B in circulation that can be redeemed for the bonded token A
+    pub circulating_token_b_amount: u64,
+    /// Token A collateral redeemable pro rata
+    pub bonded_token_a_amount: u64,
+}
+
+/// Emitted by `recover_token_b`
+#[event]
+pub struct RecoverTokenBEvent {
+    /// Token-swap account
+    pub swap: Pubkey,
+    /// Token B moved out of the swap's token B account
+    pub token_b_amount: u64,
+}
+
+/// Emitted by `emergency_redeem`
+#[event]
+pub struct EmergencyRedeemEvent {
+    /// Token-swap account
+    pub swap: Pubkey,
+    /// Token B burned from the user
+    pub token_b_amount: u64,
+    /// Token A paid out to the user
+    pub token_a_amount: u64,
+    /// Token A collateral left to redeem
+    pub bonded_token_a_amount: u64,
+}
+
+/// Emitted by `close_swap`
+#[event]
+pub struct CloseSwapEvent {
+    /// Token-swap account, closed by the instruction
+    pub swap: Pubkey,
+    /// Token A drained from the swap in emergency mode
+    pub token_a_amount: u64,
+    /// Token B drained from the swap in emergency mode
+    pub token_b_amount: u64,
+    /// Recipient of the reclaimed rent
+    pub recipient: Pubkey,
+}
@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+
+use crate::error::SwapError;
+use crate::processor;
+use crate::state::SwapV2;
+use crate::token::{Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct ClaimFees<'info> {
+    ///   0. `[writable]` Token-swap
+    #[account(
+        mut,
+        constraint = token_swap.pool_fee_account == pool_fee.key() @ SwapError::IncorrectFeeAccount,
+        constraint = token_swap.token_a == swap_token_a.key() @ SwapError::IncorrectSwapAccount,
+        constraint = token_swap.token_b == swap_token_b.key() @ SwapError::IncorrectSwapAccount,
+    )]
+    pub token_swap: Box<Account<'info, SwapV2>>,
+    ///   1. `[]` swap authority
+    #[account(seeds = [token_swap.key().as_ref()], bump = token_swap.bump_seed)]
+    pub swap_authority: AccountInfo<'info>,
+    ///   2. `[signer]` Pool owner, must own the pool fee account
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+    ///   3. `[]` Pool fee account stored in the swap
+    #[account(has_one = owner @ SwapError::InvalidOwner)]
+    pub pool_fee: Box<Account<'info, TokenAccount>>,
+    ///   4. `[writable]` token_a Swap Account to claim the token A fees FROM.
+    #[account(mut)]
+    pub swap_token_a: Box<Account<'info, TokenAccount>>,
+    ///   5. `[writable]` token_b Swap Account to claim the token B fees FROM.
+    #[account(mut)]
+    pub swap_token_b: Box<Account<'info, TokenAccount>>,
+    ///   6. `[writable]` token_a DESTINATION Account to credit the token A fees to.
+    #[account(mut, constraint = destination_a.key() != swap_token_a.key() @ SwapError::InvalidInput)]
+    pub destination_a: Box<Account<'info, TokenAccount>>,
+    ///   7. `[writable]` token_b DESTINATION Account to credit the token B fees to.
+    #[account(mut, constraint = destination_b.key() != swap_token_b.key() @ SwapError::InvalidInput)]
+    pub destination_b: Box<Account<'info, TokenAccount>>,
+    ///   8. '[]` Token program id
+    pub token_program: Program<'info, Token>,
+}
+
+///   Claim the trading token fees that built up in the swap (see `set_accrue_fees`) for the pool owner.
+pub fn handler(ctx: Context<ClaimFees>) -> ProgramResult {
+    let accounts = [
+        ctx.accounts.token_swap.to_account_info(),
+        ctx.accounts.swap_authority.clone(),
+        ctx.accounts.owner.clone(),
+        ctx.accounts.pool_fee.to_account_info(),
+        ctx.accounts.swap_token_a.to_account_info(),
+        ctx.accounts.swap_token_b.to_account_info(),
+        ctx.accounts.destination_a.to_account_info(),
+        ctx.accounts.destination_b.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+    ];
+
+    let event = processor::Processor::process_claim_fees(&accounts)?;
+    emit!(event);
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+
+use crate::error::SwapError;
+use crate::processor;
+use crate::state::SwapV2;
+use crate::token::{Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct CloseSwap<'info> {
+    ///   0. `[writable]` Token-swap to close
+    #[account(
+        mut,
+        constraint = token_swap.token_a == swap_token_a.key() @ SwapError::IncorrectSwapAccount,
+        constraint = token_swap.token_b == swap_token_b.key() @ SwapError::IncorrectSwapAccount,
+    )]
+    pub token_swap: Box<Account<'info, SwapV2>>,
+    ///   1. `[]` swap authority
+    #[account(seeds = [token_swap.key().as_ref()], bump = token_swap.bump_seed)]
+    pub swap_authority: AccountInfo<'info>,
+    ///   2. `[]` Admin of the swap, which has to sign to close it for anyone but its creator
+    pub admin: AccountInfo<'info>,
+    ///   3. `[writable]` token_a Swap Account to close, must be empty unless the swap is in emergency mode and
+    ///      fully redeemed
+    #[account(mut)]
+    pub swap_token_a: Box<Account<'info, TokenAccount>>,
+    ///   4. `[writable]` token_b Swap Account to close, must be empty unless the swap is in emergency mode and
+    ///      fully redeemed
+    #[account(mut)]
+    pub swap_token_b: Box<Account<'info, TokenAccount>>,
+    ///   5. `[writable]` token_a DESTINATION Account to drain any token A left after a full emergency redemption to,
+    ///      owned by the swap's creator unless the admin signs.
+    #[account(mut, constraint = destination_a.key() != swap_token_a.key() @ SwapError::InvalidInput)]
+    pub destination_a: Box<Account<'info, TokenAccount>>,
+    ///   6. `[writable]` token_b DESTINATION Account to drain any token B left after a full emergency redemption to,
+    ///      owned by the swap's creator unless the admin signs.
+    #[account(mut, constraint = destination_b.key() != swap_token_b.key() @ SwapError::InvalidInput)]
+    pub destination_b: Box<Account<'info, TokenAccount>>,
+    ///   7. `[writable]` Recipient of the closed accounts' lamports, the swap's creator unless the admin signs
+    #[account(mut, constraint = recipient.key() != token_swap.key() @ SwapError::InvalidInput)]
+    pub recipient: AccountInfo<'info>,
+    ///   8. `[writable]` Registry of token_a's mint, the swap is removed from it
+    #[account(mut)]
+    pub registry: AccountInfo<'info>,
+    ///   9. `[writable]` Page of the registry the swap is recorded in
+    #[account(mut)]
+    pub registry_page: AccountInfo<'info>,
+    ///   10. '[]` Token program id
+    pub token_program: Program<'info, Token>,
+    // remaining accounts, passed through to the processor as-is:
+    //   11 `[writable]` Curve params account stored in the swap, only for curves that keep their parameters in a
+    //     separate account (e.g. PiecewiseLinear), closed along with the swap
+}
+
+///   Close a swap whose token accounts are empty (or drain them first once it's been fully redeemed in emergency
+///   mode), along with its token accounts (and curve params account, if any), returning all of their lamports to
+///   the recipient, and remove it from the registry. Anyone can close the swap for its creator, only the admin can
+///   close it for someone else.
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, CloseSwap<'info>>) -> ProgramResult {
+    let mut accounts = vec![
+        ctx.accounts.token_swap.to_account_info(),
+        ctx.accounts.swap_authority.clone(),
+        ctx.accounts.admin.clone(),
+        ctx.accounts.swap_token_a.to_account_info(),
+        ctx.accounts.swap_token_b.to_account_info(),
+        ctx.accounts.destination_a.to_account_info(),
+        ctx.accounts.destination_b.to_account_info(),
+        ctx.accounts.recipient.clone(),
+        ctx.accounts.registry.clone(),
+        ctx.accounts.registry_page.clone(),
+        ctx.accounts.token_program.to_account_info(),
+    ];
+
+    // curve params account, see the account list above (the processor validates it)
+    accounts.extend(ctx.remaining_accounts.iter().cloned());
+    let event = processor::Processor::process_close_swap(ctx.program_id, &accounts)?;
+    emit!(event);
+
+    Ok(())
+}
@@ -1,36 +1,46 @@
 use anchor_lang::prelude::*;
 
+use crate::error::SwapError;
 use crate::processor;
+use crate::state::SwapV2;
+use crate::token::{Mint, Token, TokenAccount};
 
 #[derive(Accounts)]
 pub struct DepositAllTokenTypes<'info> {
-    ///   0. `[]` Token-swap
-    pub token_swap: AccountInfo<'info>,
+    ///   0. `[writable]` Token-swap, the bonded token A amount is updated
+    #[account(
+        mut,
+        has_one = token_a @ SwapError::IncorrectSwapAccount,
+        has_one = token_b @ SwapError::IncorrectSwapAccount,
+        has_one = pool_mint @ SwapError::IncorrectPoolMint,
+    )]
+    pub token_swap: Box<Account<'info, SwapV2>>,
     ///   1. `[]` swap authority
+    #[account(seeds = [token_swap.key().as_ref()], bump = token_swap.bump_seed)]
     pub swap_authority: AccountInfo<'info>,
     ///   2. `[signer]` user transfer authority
     #[account(signer)]
     pub user_transfer_authority: AccountInfo<'info>,
     ///   3. `[writable]` token_a user transfer authority can transfer amount,
-    #[account(mut)]
-    pub source_a: AccountInfo<'info>,
+    #[account(mut, constraint = source_a.key() != token_a.key() @ SwapError::InvalidInput)]
+    pub source_a: Box<Account<'info, TokenAccount>>,
     ///   4. `[writable]` token_b user transfer authority can transfer amount,
-    #[account(mut)]
-    pub source_b: AccountInfo<'info>,
+    #[account(mut, constraint = source_b.key() != token_b.key() @ SwapError::InvalidInput)]
+    pub source_b: Box<Account<'info, TokenAccount>>,
     ///   5. `[writable]` token_a Base Account to deposit into.
     #[account(mut)]
-    pub token_a: AccountInfo<'info>,
+    pub token_a: Box<Account<'info, TokenAccount>>,
     ///   6. `[writable]` token_b Base Account to deposit into.
     #[account(mut)]
-    pub token_b: AccountInfo<'info>,
+    pub token_b: Box<Account<'info, TokenAccount>>,
     ///   7. `[writable]` Pool MINT account, swap authority is the owner.
     #[account(mut)]
-    pub pool_mint: AccountInfo<'info>,
+    pub pool_mint: Box<Account<'info, Mint>>,
     ///   8. `[writable]` Pool Account to deposit the generated tokens, user is the owner.
     #[account(mut)]
-    pub destination: AccountInfo<'info>,
+    pub destination: Box<Account<'info, TokenAccount>>,
     ///   9. '[]` Token program id
-    pub token_program: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 ///   Deposit both types of tokens into the pool.  The output is a "pool"
@@ -43,23 +53,25 @@ pub fn handler(
     maximum_token_b_amount: u64,
 ) -> ProgramResult {
     let accounts = [
-        ctx.accounts.token_swap.clone(),
+        ctx.accounts.token_swap.to_account_info(),
         ctx.accounts.swap_authority.clone(),
         ctx.accounts.user_transfer_authority.clone(),
-        ctx.accounts.source_a.clone(),
-        ctx.accounts.source_b.clone(),
-        ctx.accounts.token_a.clone(),
-        ctx.accounts.token_b.clone(),
-        ctx.accounts.pool_mint.clone(),
-        ctx.accounts.destination.clone(),
-        ctx.accounts.token_program.clone(),
+        ctx.accounts.source_a.to_account_info(),
+        ctx.accounts.source_b.to_account_info(),
+        ctx.accounts.token_a.to_account_info(),
+        ctx.accounts.token_b.to_account_info(),
+        ctx.accounts.pool_mint.to_account_info(),
+        ctx.accounts.destination.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
     ];
 
-    processor::Processor::process_deposit_all_token_types(
-        ctx.program_id,
+    let event = processor::Processor::process_deposit_all_token_types(
         pool_token_amount,
         maximum_token_a_amount,
         maximum_token_b_amount,
         &accounts,
-    )
+    )?;
+    emit!(event);
+
+    Ok(())
 }
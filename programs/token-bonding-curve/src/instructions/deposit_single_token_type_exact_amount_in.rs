@@ -1,33 +1,47 @@
 use anchor_lang::prelude::*;
 
+use crate::error::SwapError;
 use crate::processor;
+use crate::state::SwapV2;
+use crate::token::{Mint, Token, TokenAccount};
 
 #[derive(Accounts)]
 pub struct DepositSingleTokenTypeExactAmountIn<'info> {
-    ///   0. `[]` Token-swap
-    pub token_swap: AccountInfo<'info>,
+    ///   0. `[writable]` Token-swap, the bonded token A amount is updated
+    #[account(
+        mut,
+        has_one = pool_mint @ SwapError::IncorrectPoolMint,
+        constraint = token_swap.token_a == swap_token_a.key() @ SwapError::IncorrectSwapAccount,
+        constraint = token_swap.token_b == swap_token_b.key() @ SwapError::IncorrectSwapAccount,
+    )]
+    pub token_swap: Box<Account<'info, SwapV2>>,
     ///   1. `[]` swap authority
+    #[account(seeds = [token_swap.key().as_ref()], bump = token_swap.bump_seed)]
     pub swap_authority: AccountInfo<'info>,
     ///   2. `[signer]` user transfer authority
     #[account(signer)]
     pub user_transfer_authority: AccountInfo<'info>,
     ///   3. `[writable]` token_(A|B) SOURCE Account, amount is transferable by user transfer authority,
-    #[account(mut)]
-    pub source_token: AccountInfo<'info>,
+    #[account(
+        mut,
+        constraint = source_token.key() != swap_token_a.key() && source_token.key() != swap_token_b.key()
+            @ SwapError::InvalidInput,
+    )]
+    pub source_token: Box<Account<'info, TokenAccount>>,
     ///   4. `[writable]` token_a Swap Account, may deposit INTO.
     #[account(mut)]
-    pub swap_token_a: AccountInfo<'info>,
+    pub swap_token_a: Box<Account<'info, TokenAccount>>,
     ///   5. `[writable]` token_b Swap Account, may deposit INTO.
     #[account(mut)]
-    pub swap_token_b: AccountInfo<'info>,
+    pub swap_token_b: Box<Account<'info, TokenAccount>>,
     ///   6. `[writable]` Pool MINT account, swap authority is the owner.
     #[account(mut)]
-    pub pool_mint: AccountInfo<'info>,
+    pub pool_mint: Box<Account<'info, Mint>>,
     ///   7. `[writable]` Pool Account to deposit the generated tokens, user is the owner.
     #[account(mut)]
-    pub destination: AccountInfo<'info>,
+    pub destination: Box<Account<'info, TokenAccount>>,
     ///   8. '[]` Token program id
-    pub token_program: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 ///   Deposit one type of tokens into the pool.  The output is a "pool" token
@@ -41,21 +55,23 @@ pub fn handler(
     // TODO: maybe not the best way to do this probably, kind of defeating the purpose of
     // anchor, but lets us just use process_foo directly
     let accounts = [
-        ctx.accounts.token_swap.clone(),
+        ctx.accounts.token_swap.to_account_info(),
         ctx.accounts.swap_authority.clone(),
         ctx.accounts.user_transfer_authority.clone(),
-        ctx.accounts.source_token.clone(),
-        ctx.accounts.swap_token_a.clone(),
-        ctx.accounts.swap_token_b.clone(),
-        ctx.accounts.pool_mint.clone(),
-        ctx.accounts.destination.clone(),
-        ctx.accounts.token_program.clone(),
+        ctx.accounts.source_token.to_account_info(),
+        ctx.accounts.swap_token_a.to_account_info(),
+        ctx.accounts.swap_token_b.to_account_info(),
+        ctx.accounts.pool_mint.to_account_info(),
+        ctx.accounts.destination.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
     ];
 
-    processor::Processor::process_deposit_single_token_type_exact_amount_in(
-        ctx.program_id,
+    let event = processor::Processor::process_deposit_single_token_type_exact_amount_in(
         source_token_amount,
         minimum_pool_token_amount,
         &accounts,
-    )
+    )?;
+    emit!(event);
+
+    Ok(())
 }
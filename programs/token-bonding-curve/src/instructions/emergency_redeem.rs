@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::error::SwapError;
+use crate::processor;
+use crate::state::SwapV2;
+use crate::token::{Mint, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct EmergencyRedeem<'info> {
+    ///   0. `[writable]` Token-swap, must be in emergency mode
+    #[account(
+        mut,
+        has_one = token_b_mint @ SwapError::IncorrectTokenBMint,
+        constraint = token_swap.token_a == swap_token_a.key() @ SwapError::IncorrectSwapAccount,
+    )]
+    pub token_swap: Box<Account<'info, SwapV2>>,
+    ///   1. `[]` swap authority
+    #[account(seeds = [token_swap.key().as_ref()], bump = token_swap.bump_seed)]
+    pub swap_authority: AccountInfo<'info>,
+    ///   2. `[signer]` user transfer authority
+    #[account(signer)]
+    pub user_transfer_authority: AccountInfo<'info>,
+    ///   3. `[writable]` token_b SOURCE Account to burn the redeemed token B from, amount is burnable by user
+    ///      transfer authority
+    #[account(mut)]
+    pub source: Box<Account<'info, TokenAccount>>,
+    ///   4. `[writable]` token_b Mint
+    #[account(mut)]
+    pub token_b_mint: Box<Account<'info, Mint>>,
+    ///   5. `[writable]` token_a Swap Account to pay the redemption FROM.
+    #[account(mut)]
+    pub swap_token_a: Box<Account<'info, TokenAccount>>,
+    ///   6. `[writable]` token_a DESTINATION Account assigned to USER as the owner.
+    #[account(mut, constraint = destination.key() != swap_token_a.key() @ SwapError::InvalidInput)]
+    pub destination: Box<Account<'info, TokenAccount>>,
+    ///   7. '[]` Token program id
+    pub token_program: Program<'info, Token>,
+}
+
+///   Burn `token_b_amount` of token B in exchange for the same share of the swap's bonded token A, once the swap is
+///   in emergency mode.
+pub fn handler(ctx: Context<EmergencyRedeem>, token_b_amount: u64) -> ProgramResult {
+    let accounts = [
+        ctx.accounts.token_swap.to_account_info(),
+        ctx.accounts.swap_authority.clone(),
+        ctx.accounts.user_transfer_authority.clone(),
+        ctx.accounts.source.to_account_info(),
+        ctx.accounts.token_b_mint.to_account_info(),
+        ctx.accounts.swap_token_a.to_account_info(),
+        ctx.accounts.destination.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+    ];
+
+    let event = processor::Processor::process_emergency_redeem(token_b_amount, &accounts)?;
+    emit!(event);
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+use crate::processor;
+use crate::state::SwapV2;
+
+#[derive(Accounts)]
+pub struct EnableEmergencyMode<'info> {
+    ///   0. `[writable]` Token-swap
+    #[account(mut)]
+    pub token_swap: Box<Account<'info, SwapV2>>,
+    ///   1. `[signer]` Admin of the swap
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+}
+
+///   Put the swap in emergency mode, permanently disabling swaps so token B holders can redeem it for their share
+///   of the bonded token A with `emergency_redeem`.
+pub fn handler(ctx: Context<EnableEmergencyMode>) -> ProgramResult {
+    let accounts = [
+        ctx.accounts.token_swap.to_account_info(),
+        ctx.accounts.admin.clone(),
+    ];
+
+    let event = processor::Processor::process_enable_emergency_mode(&accounts)?;
+    emit!(event);
+
+    Ok(())
+}
@@ -1,57 +1,126 @@
 use anchor_lang::prelude::*;
+use solana_program::program_option::COption;
 
 use crate::constraints::SWAP_CONSTRAINTS;
 use crate::curve::{base::SwapCurve, fees::Fees};
+use crate::error::SwapError;
 use crate::processor;
-
-// TODO: we're just using AccountInfo below for token_swap but in theory we should make it a ProgramAccount and rewrite SwapV1 to derive from anchor
-// (this will make it backward incompatible though with regular spl-token-swap since anchor will init data into those accounts)
+use crate::state::SwapVersion;
+use crate::token::{Mint, Token, TokenAccount};
 
 ///   Initializes a new swap
+///   The swap itself is created here (by the processor) so it's still untyped, see `SwapV2` for how the other
+///   instructions take it
 #[derive(Accounts)]
 pub struct Initialize<'info> {
     ///   0. `[writable, signer]` New Token-swap to create.
-    #[account(mut, signer)]
+    #[account(
+        mut,
+        signer,
+        constraint = !SwapVersion::is_initialized(&token_swap.data.borrow()) @ SwapError::AlreadyInUse,
+    )]
     pub token_swap: AccountInfo<'info>,
     ///   1. `[]` swap authority derived from `create_program_address(&[Token-swap account])`
+    #[account(seeds = [token_swap.key.as_ref()], bump)]
     pub swap_authority: AccountInfo<'info>,
     ///   2. `[]` token_a Account. Must be non zero, owned by swap authority.
-    pub token_a: AccountInfo<'info>,
+    #[account(
+        constraint = token_a.owner == swap_authority.key() @ SwapError::InvalidOwner,
+        constraint = token_a.mint != token_b.mint @ SwapError::RepeatedMint,
+        constraint = token_a.delegate.is_none() @ SwapError::InvalidDelegate,
+        constraint = token_a.close_authority.is_none() @ SwapError::InvalidCloseAuthority,
+    )]
+    pub token_a: Box<Account<'info, TokenAccount>>,
     ///   3. `[]` token_b Account. Must be non zero, owned by swap authority.
-    pub token_b: AccountInfo<'info>,
+    #[account(
+        constraint = token_b.owner == swap_authority.key() @ SwapError::InvalidOwner,
+        constraint = token_b.delegate.is_none() @ SwapError::InvalidDelegate,
+        constraint = token_b.close_authority.is_none() @ SwapError::InvalidCloseAuthority,
+    )]
+    pub token_b: Box<Account<'info, TokenAccount>>,
     ///   4. `[writable]` Pool Token Mint. Must be empty, owned by swap authority. Freeze authority must be null.
-    #[account(mut)]
-    pub pool: AccountInfo<'info>,
+    #[account(
+        mut,
+        constraint = pool.mint_authority == COption::Some(swap_authority.key()) @ SwapError::InvalidOwner,
+        constraint = pool.supply == 0 @ SwapError::InvalidSupply,
+        constraint = pool.freeze_authority.is_none() @ SwapError::InvalidFreezeAuthority,
+    )]
+    pub pool: Box<Account<'info, Mint>>,
     ///   5. `[]` Pool Token Account to deposit trading and withdraw fees.
-    ///   Must be empty, not owned by swap authority
-    pub fee: AccountInfo<'info>,
+    ///      Must be empty, not owned by swap authority
+    #[account(
+        constraint = fee.owner != swap_authority.key() @ SwapError::InvalidOutputOwner,
+        constraint = fee.mint == pool.key() @ SwapError::IncorrectPoolMint,
+    )]
+    pub fee: Box<Account<'info, TokenAccount>>,
     ///   6. `[writable]` Pool Token Account to deposit the initial pool token
-    ///   supply.  Must be empty, not owned by swap authority.
-    #[account(mut)]
-    pub destination: AccountInfo<'info>,
+    ///      supply.  Must be empty, not owned by swap authority.
+    #[account(
+        mut,
+        constraint = destination.owner != swap_authority.key() @ SwapError::InvalidOutputOwner,
+    )]
+    pub destination: Box<Account<'info, TokenAccount>>,
     ///   7. '[]` Token program id
-    pub token_program: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    ///   8. `[signer]` Admin authority of the new swap, can be transferred with `set_admin`/`accept_admin`
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    ///   9. `[writable]` Registry of token_a's mint, the PDA from `Processor::registry_address`, created with
+    ///      the mint's first swap
+    #[account(mut)]
+    pub registry: AccountInfo<'info>,
+    ///   10. `[writable]` Last page of the registry, or the next page (created here) if the last one is full, the
+    ///       PDA from `Processor::registry_page_address`
+    #[account(mut)]
+    pub registry_page: AccountInfo<'info>,
+    ///   11. `[writable, signer]` Payer of the rent of the registry and curve params accounts created here
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+    ///   12. `[]` System program
+    pub system_program: Program<'info, System>,
+    // remaining accounts, passed through to the processor as-is:
+    //   13 `[writable]` Curve params account, only for curves that keep their parameters in a separate account
+    //     (e.g. PiecewiseLinear, see `CurveType::curve_params_len`). The PDA from
+    //     `Processor::curve_params_address`, which is created here
+    //   13 (or 14 after the curve params account) `[]` Token B mint, only for mint-and-burn swaps where the swap
+    //     authority is token B's mint authority (token B is then minted on buys and burned on sells, and the
+    //     token_b account must be empty)
 }
 
 ///   Initializes a new swap
 ///   Note that SwapCurve has a dynamic trait so can't be borsh serialized easily, so lib.rs just handles
 ///   creating the SwapCurve based on the primitives passed into the different instructions
-pub fn handler(ctx: Context<Initialize>, fees: Fees, swap_curve: SwapCurve) -> ProgramResult {
-    let accounts = [
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, Initialize<'info>>,
+    fees: Fees,
+    swap_curve: SwapCurve,
+) -> ProgramResult {
+    let mut accounts = vec![
         ctx.accounts.token_swap.clone(),
         ctx.accounts.swap_authority.clone(),
-        ctx.accounts.token_a.clone(),
-        ctx.accounts.token_b.clone(),
-        ctx.accounts.pool.clone(),
-        ctx.accounts.fee.clone(),
-        ctx.accounts.destination.clone(),
-        ctx.accounts.token_program.clone(),
+        ctx.accounts.token_a.to_account_info(),
+        ctx.accounts.token_b.to_account_info(),
+        ctx.accounts.pool.to_account_info(),
+        ctx.accounts.fee.to_account_info(),
+        ctx.accounts.destination.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.admin.clone(),
+        ctx.accounts.registry.clone(),
+        ctx.accounts.registry_page.clone(),
+        ctx.accounts.payer.clone(),
+        ctx.accounts.system_program.to_account_info(),
     ];
-    processor::Processor::process_initialize(
+
+    // curve params and token B mint accounts, see the account list above (the processor validates it)
+    accounts.extend(ctx.remaining_accounts.iter().cloned());
+    let event = processor::Processor::process_initialize(
         ctx.program_id,
         fees,
         swap_curve,
         &accounts,
         &SWAP_CONSTRAINTS,
-    )
+    )?;
+    emit!(event);
+
+    Ok(())
 }
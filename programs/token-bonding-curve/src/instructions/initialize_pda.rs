@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+
+use crate::constraints::SWAP_CONSTRAINTS;
+use crate::curve::{base::SwapCurve, fees::Fees};
+use crate::error::SwapError;
+use crate::processor;
+use crate::token::{Mint, Token, TokenAccount};
+
+///   Creates a new swap at a PDA of its token mints and an index, along with its pool mint and token accounts,
+///   and initializes it in the same instruction
+#[derive(Accounts)]
+pub struct InitializePda<'info> {
+    ///   0. `[writable]` New Token-swap to create, the PDA from `Processor::swap_address`
+    #[account(mut)]
+    pub token_swap: AccountInfo<'info>,
+    ///   1. `[]` swap authority derived from `create_program_address(&[Token-swap account])`
+    #[account(seeds = [token_swap.key.as_ref()], bump)]
+    pub swap_authority: AccountInfo<'info>,
+    ///   2. `[]` token_a Mint
+    #[account(constraint = token_a_mint.key() != token_b_mint.key() @ SwapError::RepeatedMint)]
+    pub token_a_mint: Box<Account<'info, Mint>>,
+    ///   3. `[]` token_b Mint
+    pub token_b_mint: Box<Account<'info, Mint>>,
+    ///   4. `[writable]` token_a Account to create, the `TOKEN_A_SEED` PDA from `Processor::swap_account_address`
+    #[account(mut)]
+    pub token_a: AccountInfo<'info>,
+    ///   5. `[writable]` token_b Account to create, the `TOKEN_B_SEED` PDA from `Processor::swap_account_address`
+    #[account(mut)]
+    pub token_b: AccountInfo<'info>,
+    ///   6. `[writable]` Pool Token Mint to create, the `POOL_MINT_SEED` PDA from `Processor::swap_account_address`.
+    ///      It gets the same decimals as token_a's Mint
+    #[account(mut)]
+    pub pool: AccountInfo<'info>,
+    ///   7. `[writable]` Pool Token Account to create, the `POOL_FEE_SEED` PDA from `Processor::swap_account_address`.
+    ///      Owned by the admin, it gets the trading and withdraw fees and the initial pool token supply
+    #[account(mut)]
+    pub fee: AccountInfo<'info>,
+    ///   8. `[writable]` token_a Account to fund the swap's token_a Account from, owned by the payer
+    #[account(mut)]
+    pub source_token_a: Box<Account<'info, TokenAccount>>,
+    ///   9. `[writable]` token_b Account to fund the swap's token_b Account from, owned by the payer
+    #[account(mut)]
+    pub source_token_b: Box<Account<'info, TokenAccount>>,
+    ///   10. `[signer]` Admin authority of the new swap, can be transferred with `set_admin`/`accept_admin`
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    ///   11. `[writable]` Registry of token_a's mint, see `Initialize`
+    #[account(mut)]
+    pub registry: AccountInfo<'info>,
+    ///   12. `[writable]` Last or next page of the registry, see `Initialize`
+    #[account(mut)]
+    pub registry_page: AccountInfo<'info>,
+    ///   13. `[writable, signer]` Payer of the new accounts' rent and of the tokens funding the swap
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+    ///   14. '[]` Token program id
+    pub token_program: Program<'info, Token>,
+    ///   15. `[]` System program
+    pub system_program: Program<'info, System>,
+    ///   16. `[]` Rent sysvar
+    pub rent: Sysvar<'info, Rent>,
+    // remaining accounts, passed through to the processor as-is:
+    //   17 `[writable]` Curve params account, only for curves that keep their parameters in a separate account
+    //     (e.g. PiecewiseLinear, see `CurveType::curve_params_len`). The PDA from
+    //     `Processor::curve_params_address`, which is created here
+}
+
+///   Creates a new swap at `Processor::swap_address(token_a_mint, token_b_mint, index)` and initializes it
+///   The token_a and token_b Accounts start with `token_a_amount` and `token_b_amount` from the source accounts,
+///   and with `token_b_mint_and_burn` token B is minted and burned by the swap instead (the swap authority must be
+///   token B's mint authority, and `token_b_amount` must be 0)
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, InitializePda<'info>>,
+    index: u64,
+    fees: Fees,
+    swap_curve: SwapCurve,
+    token_a_amount: u64,
+    token_b_amount: u64,
+    token_b_mint_and_burn: bool,
+) -> ProgramResult {
+    let accounts = [
+        ctx.accounts.token_swap.clone(),
+        ctx.accounts.swap_authority.clone(),
+        ctx.accounts.token_a_mint.to_account_info(),
+        ctx.accounts.token_b_mint.to_account_info(),
+        ctx.accounts.token_a.clone(),
+        ctx.accounts.token_b.clone(),
+        ctx.accounts.pool.clone(),
+        ctx.accounts.fee.clone(),
+        ctx.accounts.source_token_a.to_account_info(),
+        ctx.accounts.source_token_b.to_account_info(),
+        ctx.accounts.admin.clone(),
+        ctx.accounts.payer.clone(),
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.system_program.to_account_info(),
+        ctx.accounts.rent.to_account_info(),
+    ];
+    processor::Processor::process_create_swap_accounts(
+        ctx.program_id,
+        index,
+        token_a_amount,
+        token_b_amount,
+        &accounts,
+    )?;
+
+    // the rest is a regular `initialize`, with the fee account getting the initial pool token supply too
+    let mut accounts = vec![
+        ctx.accounts.token_swap.clone(),
+        ctx.accounts.swap_authority.clone(),
+        ctx.accounts.token_a.clone(),
+        ctx.accounts.token_b.clone(),
+        ctx.accounts.pool.clone(),
+        ctx.accounts.fee.clone(),
+        ctx.accounts.fee.clone(),
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.admin.clone(),
+        ctx.accounts.registry.clone(),
+        ctx.accounts.registry_page.clone(),
+        ctx.accounts.payer.clone(),
+        ctx.accounts.system_program.to_account_info(),
+    ];
+    // curve params account, see the account list above (the processor validates it)
+    accounts.extend(ctx.remaining_accounts.iter().cloned());
+    if token_b_mint_and_burn {
+        accounts.push(ctx.accounts.token_b_mint.to_account_info());
+    }
+    let event = processor::Processor::process_initialize(
+        ctx.program_id,
+        fees,
+        swap_curve,
+        &accounts,
+        &SWAP_CONSTRAINTS,
+    )?;
+    emit!(event);
+
+    Ok(())
+}
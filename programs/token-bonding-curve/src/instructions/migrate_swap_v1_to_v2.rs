@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+use crate::processor;
+use crate::token::{Mint, TokenAccount};
+
+#[derive(Accounts)]
+pub struct MigrateSwapV1ToV2<'info> {
+    ///   0. `[writable]` Token-swap, still on SwapV1
+    #[account(mut, owner = crate::ID @ ProgramError::IncorrectProgramId)]
+    pub token_swap: AccountInfo<'info>,
+    ///   1. `[]` token_a Swap Account, its balance becomes the bonded token A amount of bonding curves
+    pub swap_token_a: Box<Account<'info, TokenAccount>>,
+    ///   2. `[]` Pool fee account stored in the swap, its owner becomes the admin
+    pub pool_fee: Box<Account<'info, TokenAccount>>,
+    ///   3. `[writable, signer]` Payer of the rent for the larger account
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+    ///   4. `[]` System program
+    pub system_program: Program<'info, System>,
+    ///   5. `[writable]` Registry of token_a's mint the swap is recorded in, see `Initialize`
+    #[account(mut)]
+    pub registry: AccountInfo<'info>,
+    ///   6. `[writable]` Last or next page of the registry, see `Initialize`
+    #[account(mut)]
+    pub registry_page: AccountInfo<'info>,
+    ///   7. `[]` token_b Swap Account, its balance isn't counted in the token B bonding curves start out having sold
+    pub swap_token_b: Box<Account<'info, TokenAccount>>,
+    ///   8. `[]` token_b Mint
+    pub token_b_mint: Box<Account<'info, Mint>>,
+}
+
+///   Migrate a swap created before SwapV2, reallocating its account to the latest size and rewriting it in place,
+///   and record it in the registry of its collateral mint.
+pub fn handler(ctx: Context<MigrateSwapV1ToV2>) -> ProgramResult {
+    let accounts = [
+        ctx.accounts.token_swap.clone(),
+        ctx.accounts.swap_token_a.to_account_info(),
+        ctx.accounts.pool_fee.to_account_info(),
+        ctx.accounts.payer.clone(),
+        ctx.accounts.system_program.to_account_info(),
+        ctx.accounts.registry.clone(),
+        ctx.accounts.registry_page.clone(),
+        ctx.accounts.swap_token_b.to_account_info(),
+        ctx.accounts.token_b_mint.to_account_info(),
+    ];
+
+    let event = processor::Processor::process_migrate_swap_v1_to_v2(ctx.program_id, &accounts)?;
+    emit!(event);
+
+    Ok(())
+}
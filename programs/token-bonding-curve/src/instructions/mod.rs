@@ -1,13 +1,40 @@
+pub mod accept_admin;
+pub mod claim_fees;
+pub mod close_swap;
 pub mod deposit_all_token_types;
 pub mod deposit_single_token_type_exact_amount_in;
+pub mod emergency_redeem;
+pub mod enable_emergency_mode;
 pub mod initialize;
+pub mod initialize_pda;
+pub mod migrate_swap_v1_to_v2;
+pub mod pause;
+pub mod quote_swap;
+pub mod recover_token_b;
+pub mod renounce_admin;
+pub mod resume;
+pub mod set_accrue_fees;
+pub mod set_admin;
 pub mod swap;
+pub mod swap_exact_amount_out;
+pub mod sweep_surplus;
 pub mod withdraw_all_token_types;
 pub mod withdraw_single_token_type_exact_amount_out;
 
+pub use claim_fees::*;
+pub use close_swap::*;
 pub use deposit_all_token_types::*;
 pub use deposit_single_token_type_exact_amount_in::*;
+pub use emergency_redeem::*;
+pub use enable_emergency_mode::*;
 pub use initialize::*;
+pub use initialize_pda::*;
+pub use migrate_swap_v1_to_v2::*;
+pub use pause::*;
+pub use quote_swap::*;
+pub use recover_token_b::*;
+pub use set_admin::*;
 pub use swap::*;
+pub use sweep_surplus::*;
 pub use withdraw_all_token_types::*;
 pub use withdraw_single_token_type_exact_amount_out::*;
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::processor;
+use crate::state::SwapV2;
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    ///   0. `[writable]` Token-swap
+    #[account(mut)]
+    pub token_swap: Box<Account<'info, SwapV2>>,
+    ///   1. `[signer]` Admin of the swap
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    // remaining accounts, passed through to the processor as-is:
+    //   2 `[writable]` Optional page of the registry of token_a's mint the swap is recorded in, its entry is marked
+    //     paused or resumed along with the swap
+}
+
+///   Pause swaps, deposits and withdrawals on the swap until it's resumed, quoting still works.
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, SetPaused<'info>>) -> ProgramResult {
+    let mut accounts = vec![
+        ctx.accounts.token_swap.to_account_info(),
+        ctx.accounts.admin.clone(),
+    ];
+
+    // registry page, see the account list above (the processor validates it)
+    accounts.extend(ctx.remaining_accounts.iter().cloned());
+
+    let event = processor::Processor::process_pause(ctx.program_id, &accounts)?;
+    emit!(event);
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::error::SwapError;
+use crate::processor;
+use crate::state::SwapV2;
+use crate::token::TokenAccount;
+
+#[derive(Accounts)]
+pub struct QuoteSwap<'info> {
+    ///   0. `[]` Token-swap
+    pub token_swap: Box<Account<'info, SwapV2>>,
+    ///   1. `[]` token_(A|B) Base Account to swap INTO.  Must be the SOURCE token.
+    #[account(
+        constraint = swap_source.key() == token_swap.token_a || swap_source.key() == token_swap.token_b
+            @ SwapError::IncorrectSwapAccount,
+    )]
+    pub swap_source: Box<Account<'info, TokenAccount>>,
+    ///   2. `[]` token_(A|B) Base Account to swap FROM.  Must be the DESTINATION token.
+    #[account(
+        constraint = swap_destination.key() == token_swap.token_a || swap_destination.key() == token_swap.token_b
+            @ SwapError::IncorrectSwapAccount,
+        constraint = swap_destination.key() != swap_source.key() @ SwapError::InvalidInput,
+    )]
+    pub swap_destination: Box<Account<'info, TokenAccount>>,
+    // remaining accounts, passed through to the processor as-is:
+    //   3 `[]` Curve params account stored in the swap, only for curves that keep their parameters in a
+    //     separate account (e.g. PiecewiseLinear)
+    //   3 (or 4 after the curve params account) `[]` Token B mint, only for mint-and-burn swaps and curves
+    //     priced off token B's supply (e.g. ReserveRatio)
+}
+
+///   Quote a swap of `amount_in` against the pool's current balances without moving any tokens.
+///   The packed `SwapQuote` (see curve/base.rs) is returned through the return data syscall, so
+///   clients can get it by simulating the transaction.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, QuoteSwap<'info>>,
+    amount_in: u64,
+) -> ProgramResult {
+    let mut accounts = vec![
+        ctx.accounts.token_swap.to_account_info(),
+        ctx.accounts.swap_source.to_account_info(),
+        ctx.accounts.swap_destination.to_account_info(),
+    ];
+
+    // curve params and token B mint accounts, see the account list above (the processor validates it)
+    accounts.extend(ctx.remaining_accounts.iter().cloned());
+
+    let event = processor::Processor::process_quote_swap(ctx.program_id, amount_in, &accounts)?;
+    emit!(event);
+
+    Ok(())
+}
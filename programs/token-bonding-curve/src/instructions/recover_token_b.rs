@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+use crate::error::SwapError;
+use crate::processor;
+use crate::state::SwapV2;
+use crate::token::{Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct RecoverTokenB<'info> {
+    ///   0. `[]` Token-swap, must be in emergency mode
+    #[account(constraint = token_swap.token_b == swap_token_b.key() @ SwapError::IncorrectSwapAccount)]
+    pub token_swap: Box<Account<'info, SwapV2>>,
+    ///   1. `[]` swap authority
+    #[account(seeds = [token_swap.key().as_ref()], bump = token_swap.bump_seed)]
+    pub swap_authority: AccountInfo<'info>,
+    ///   2. `[signer]` Admin of the swap
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    ///   3. `[writable]` token_b Swap Account to recover FROM.
+    #[account(mut)]
+    pub swap_token_b: Box<Account<'info, TokenAccount>>,
+    ///   4. `[writable]` token_b DESTINATION Account to credit the unsold inventory to.
+    #[account(mut, constraint = destination.key() != swap_token_b.key() @ SwapError::InvalidInput)]
+    pub destination: Box<Account<'info, TokenAccount>>,
+    ///   5. '[]` Token program id
+    pub token_program: Program<'info, Token>,
+}
+
+///   Recover the swap's unsold token B inventory once the swap is in emergency mode.
+pub fn handler(ctx: Context<RecoverTokenB>) -> ProgramResult {
+    let accounts = [
+        ctx.accounts.token_swap.to_account_info(),
+        ctx.accounts.swap_authority.clone(),
+        ctx.accounts.admin.clone(),
+        ctx.accounts.swap_token_b.to_account_info(),
+        ctx.accounts.destination.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+    ];
+
+    let event = processor::Processor::process_recover_token_b(&accounts)?;
+    emit!(event);
+
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use anchor_lang::prelude::*;
+
+use crate::instructions::set_admin::UpdateAdmin;
+use crate::processor;
+
+///   Give up the admin role, leaving the swap without an admin for good.
+///   Takes the same accounts as `set_admin`.
+pub fn handler(ctx: Context<UpdateAdmin>) -> ProgramResult {
+    let accounts = [
+        ctx.accounts.token_swap.to_account_info(),
+        ctx.accounts.admin.clone(),
+    ];
+
+    let event = processor::Processor::process_renounce_admin(&accounts)?;
+    emit!(event);
+
+    Ok(())
+}
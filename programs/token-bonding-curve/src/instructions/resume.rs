@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+use crate::instructions::pause::SetPaused;
+use crate::processor;
+
+///   Resume swaps, deposits and withdrawals on a paused swap.
+///   Takes the same accounts as `pause`, signed by the admin.
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, SetPaused<'info>>) -> ProgramResult {
+    let mut accounts = vec![
+        ctx.accounts.token_swap.to_account_info(),
+        ctx.accounts.admin.clone(),
+    ];
+
+    // registry page, see the account list in `SetPaused` (the processor validates it)
+    accounts.extend(ctx.remaining_accounts.iter().cloned());
+
+    let event = processor::Processor::process_resume(ctx.program_id, &accounts)?;
+    emit!(event);
+
+    Ok(())
+}
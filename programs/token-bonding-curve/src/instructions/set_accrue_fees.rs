@@ -0,0 +1,18 @@
+use anchor_lang::prelude::*;
+
+use crate::instructions::set_admin::UpdateAdmin;
+use crate::processor;
+
+///   Set whether the swap's trading token fees build up in the swap for the pool owner to claim with `claim_fees`,
+///   or are sent to a fee account on every swap. Takes the same accounts as `set_admin`, signed by the admin.
+pub fn handler(ctx: Context<UpdateAdmin>, accrue_fees: bool) -> ProgramResult {
+    let accounts = [
+        ctx.accounts.token_swap.to_account_info(),
+        ctx.accounts.admin.clone(),
+    ];
+
+    let event = processor::Processor::process_set_accrue_fees(accrue_fees, &accounts)?;
+    emit!(event);
+
+    Ok(())
+}
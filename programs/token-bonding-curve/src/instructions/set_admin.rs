@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+use crate::processor;
+use crate::state::SwapV2;
+
+#[derive(Accounts)]
+pub struct UpdateAdmin<'info> {
+    ///   0. `[writable]` Token-swap
+    #[account(mut)]
+    pub token_swap: Box<Account<'info, SwapV2>>,
+    ///   1. `[signer]` Current admin of the swap (or the pending admin for `accept_admin`)
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+}
+
+///   Propose a new admin for the swap, who has to accept it with `accept_admin` before taking over.
+pub fn handler(ctx: Context<UpdateAdmin>, new_admin: Pubkey) -> ProgramResult {
+    let accounts = [
+        ctx.accounts.token_swap.to_account_info(),
+        ctx.accounts.admin.clone(),
+    ];
+
+    let event = processor::Processor::process_set_admin(new_admin, &accounts)?;
+    emit!(event);
+
+    Ok(())
+}
@@ -1,59 +1,101 @@
 use anchor_lang::prelude::*;
 
+use crate::error::SwapError;
 use crate::processor;
+use crate::state::SwapV2;
+use crate::token::{Mint, Token, TokenAccount};
 
 #[derive(Accounts)]
 pub struct Swap<'info> {
-    ///   0. `[]` Token-swap
-    pub token_swap: AccountInfo<'info>,
+    ///   0. `[writable]` Token-swap, the bonded token A amount is updated
+    #[account(
+        mut,
+        has_one = pool_mint @ SwapError::IncorrectPoolMint,
+        constraint = token_swap.pool_fee_account == pool_fee.key() @ SwapError::IncorrectFeeAccount,
+    )]
+    pub token_swap: Box<Account<'info, SwapV2>>,
     ///   1. `[]` swap authority
+    #[account(seeds = [token_swap.key().as_ref()], bump = token_swap.bump_seed)]
     pub swap_authority: AccountInfo<'info>,
     ///   2. `[signer]` user transfer authority
     #[account(signer)]
     pub user_transfer_authority: AccountInfo<'info>,
     ///   3. `[writable]` token_(A|B) SOURCE Account, amount is transferable by user transfer authority,
-    #[account(mut)]
-    pub source: AccountInfo<'info>,
+    #[account(mut, constraint = source.key() != swap_source.key() @ SwapError::InvalidInput)]
+    pub source: Box<Account<'info, TokenAccount>>,
     ///   4. `[writable]` token_(A|B) Base Account to swap INTO.  Must be the SOURCE token.
-    #[account(mut)]
-    pub swap_source: AccountInfo<'info>,
+    #[account(
+        mut,
+        constraint = swap_source.key() == token_swap.token_a || swap_source.key() == token_swap.token_b
+            @ SwapError::IncorrectSwapAccount,
+    )]
+    pub swap_source: Box<Account<'info, TokenAccount>>,
     ///   5. `[writable]` token_(A|B) Base Account to swap FROM.  Must be the DESTINATION token.
-    #[account(mut)]
-    pub swap_destination: AccountInfo<'info>,
+    #[account(
+        mut,
+        constraint = swap_destination.key() == token_swap.token_a || swap_destination.key() == token_swap.token_b
+            @ SwapError::IncorrectSwapAccount,
+        constraint = swap_destination.key() != swap_source.key() @ SwapError::InvalidInput,
+    )]
+    pub swap_destination: Box<Account<'info, TokenAccount>>,
     ///   6. `[writable]` token_(A|B) DESTINATION Account assigned to USER as the owner.
-    #[account(mut)]
-    pub destination: AccountInfo<'info>,
+    #[account(mut, constraint = destination.key() != swap_destination.key() @ SwapError::InvalidInput)]
+    pub destination: Box<Account<'info, TokenAccount>>,
     ///   7. `[writable]` Pool token mint, to generate trading fees
     #[account(mut)]
-    pub pool_mint: AccountInfo<'info>,
+    pub pool_mint: Box<Account<'info, Mint>>,
     ///   8. `[writable]` Fee account, to receive trading fees
     #[account(mut)]
-    pub pool_fee: AccountInfo<'info>,
+    pub pool_fee: Box<Account<'info, TokenAccount>>,
     ///   9. '[]` Token program id
-    pub token_program: AccountInfo<'info>,
-    // TODO:     ///   10 `[optional, writable]` Host fee account to receive additional trading fees
+    pub token_program: Program<'info, Token>,
+    // remaining accounts, passed through to the processor as-is:
+    //   10 `[optional, writable]` Host fee account to receive additional trading fees, a pool token account that
+    //      gets `host_fee_numerator / host_fee_denominator` of the owner fee's pool tokens (e.g. so a frontend hosting
+    //      the swap can earn a cut, see `constraints.rs`)
+    // or, for curves that collect fees in trading tokens (e.g. LinearPrice):
+    //   10 `[writable]` Source token fee account, owned by the owner of the pool fee account
+    //      (only required if the swap charges fees)
+    //   11 `[optional, writable]` Source token host fee account to receive additional trading fees
+    // curves that keep their parameters in a separate account (e.g. PiecewiseLinear) take that account
+    // first, before any of the fee accounts above:
+    //   10 `[]` Curve params account stored in the swap
+    // mint-and-burn swaps, and curves priced off token B's supply (e.g. ReserveRatio), take token B's mint next
+    // (after the curve params account, before the fee accounts):
+    //   10 `[writable]` Token B mint, token B is minted to the destination on buys and burned from the source on
+    //      sells for mint-and-burn swaps (only read for the others)
 }
 
 ///   Swap the tokens in the pool.
 
-pub fn handler(ctx: Context<Swap>, amount_in: u64, minimum_amount_out: u64) -> ProgramResult {
-    let accounts = vec![
-        ctx.accounts.token_swap.clone(),
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, Swap<'info>>,
+    amount_in: u64,
+    minimum_amount_out: u64,
+) -> ProgramResult {
+    let mut accounts = vec![
+        ctx.accounts.token_swap.to_account_info(),
         ctx.accounts.swap_authority.clone(),
         ctx.accounts.user_transfer_authority.clone(),
-        ctx.accounts.source.clone(),
-        ctx.accounts.swap_source.clone(),
-        ctx.accounts.swap_destination.clone(),
-        ctx.accounts.destination.clone(),
-        ctx.accounts.pool_mint.clone(),
-        ctx.accounts.pool_fee.clone(),
-        ctx.accounts.token_program.clone(),
+        ctx.accounts.source.to_account_info(),
+        ctx.accounts.swap_source.to_account_info(),
+        ctx.accounts.swap_destination.to_account_info(),
+        ctx.accounts.destination.to_account_info(),
+        ctx.accounts.pool_mint.to_account_info(),
+        ctx.accounts.pool_fee.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
     ];
 
-    // TODO: figure out optional remaining accounts handling
-    // if ctx.remaining_accounts.len() > 0 {
-    //     accounts.push(ctx.remaining_accounts[0].clone());
-    // }
+    // curve params, token B mint and optional fee accounts, see the account list above (the processor validates them)
+    accounts.extend(ctx.remaining_accounts.iter().cloned());
+
+    let event = processor::Processor::process_swap(
+        ctx.program_id,
+        amount_in,
+        minimum_amount_out,
+        &accounts,
+    )?;
+    emit!(event);
 
-    processor::Processor::process_swap(ctx.program_id, amount_in, minimum_amount_out, &accounts)
+    Ok(())
 }
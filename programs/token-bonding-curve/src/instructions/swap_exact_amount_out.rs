@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+use crate::instructions::swap::Swap;
+use crate::processor;
+
+///   Swap the tokens in the pool, getting out an exact amount of the destination token.
+///   Takes the same accounts as `swap`.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, Swap<'info>>,
+    amount_out: u64,
+    maximum_amount_in: u64,
+) -> ProgramResult {
+    let mut accounts = vec![
+        ctx.accounts.token_swap.to_account_info(),
+        ctx.accounts.swap_authority.clone(),
+        ctx.accounts.user_transfer_authority.clone(),
+        ctx.accounts.source.to_account_info(),
+        ctx.accounts.swap_source.to_account_info(),
+        ctx.accounts.swap_destination.to_account_info(),
+        ctx.accounts.destination.to_account_info(),
+        ctx.accounts.pool_mint.to_account_info(),
+        ctx.accounts.pool_fee.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+    ];
+
+    // curve params, token B mint and optional fee accounts, see the account list in swap.rs (the processor validates them)
+    accounts.extend(ctx.remaining_accounts.iter().cloned());
+
+    let event = processor::Processor::process_swap_exact_amount_out(
+        ctx.program_id,
+        amount_out,
+        maximum_amount_in,
+        &accounts,
+    )?;
+    emit!(event);
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::error::SwapError;
+use crate::processor;
+use crate::state::SwapV2;
+use crate::token::{Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct SweepSurplus<'info> {
+    ///   0. `[]` Token-swap
+    #[account(
+        constraint = token_swap.pool_fee_account == pool_fee.key() @ SwapError::IncorrectFeeAccount,
+        constraint = token_swap.token_a == swap_token_a.key() @ SwapError::IncorrectSwapAccount,
+    )]
+    pub token_swap: Box<Account<'info, SwapV2>>,
+    ///   1. `[]` swap authority
+    #[account(seeds = [token_swap.key().as_ref()], bump = token_swap.bump_seed)]
+    pub swap_authority: AccountInfo<'info>,
+    ///   2. `[signer]` Pool owner, must own the pool fee account
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+    ///   3. `[]` Pool fee account stored in the swap
+    #[account(has_one = owner @ SwapError::InvalidOwner)]
+    pub pool_fee: Box<Account<'info, TokenAccount>>,
+    ///   4. `[writable]` token_a Swap Account to sweep FROM.
+    #[account(mut)]
+    pub swap_token_a: Box<Account<'info, TokenAccount>>,
+    ///   5. `[writable]` token_a DESTINATION Account to credit the surplus to.
+    #[account(mut, constraint = destination.key() != swap_token_a.key() @ SwapError::InvalidInput)]
+    pub destination: Box<Account<'info, TokenAccount>>,
+    ///   6. '[]` Token program id
+    pub token_program: Program<'info, Token>,
+}
+
+///   Sweep any token A in the swap that isn't bonded to the curve (e.g. tokens transferred straight into the swap's
+///   token A account) out to the pool owner.
+pub fn handler(ctx: Context<SweepSurplus>) -> ProgramResult {
+    let accounts = [
+        ctx.accounts.token_swap.to_account_info(),
+        ctx.accounts.swap_authority.clone(),
+        ctx.accounts.owner.clone(),
+        ctx.accounts.pool_fee.to_account_info(),
+        ctx.accounts.swap_token_a.to_account_info(),
+        ctx.accounts.destination.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+    ];
+
+    let event = processor::Processor::process_sweep_surplus(&accounts)?;
+    emit!(event);
+
+    Ok(())
+}
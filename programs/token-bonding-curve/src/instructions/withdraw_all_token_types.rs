@@ -1,39 +1,56 @@
 use anchor_lang::prelude::*;
 
+use crate::error::SwapError;
 use crate::processor;
+use crate::state::SwapV2;
+use crate::token::{Mint, Token, TokenAccount};
 
 #[derive(Accounts)]
 pub struct WithdrawAllTokenTypes<'info> {
-    ///   0. `[]` Token-swap
-    pub token_swap: AccountInfo<'info>,
+    ///   0. `[writable]` Token-swap, the bonded token A amount is updated
+    #[account(
+        mut,
+        has_one = pool_mint @ SwapError::IncorrectPoolMint,
+        constraint = token_swap.token_a == swap_token_a.key() @ SwapError::IncorrectSwapAccount,
+        constraint = token_swap.token_b == swap_token_b.key() @ SwapError::IncorrectSwapAccount,
+        constraint = token_swap.pool_fee_account == fee_account.key() @ SwapError::IncorrectFeeAccount,
+    )]
+    pub token_swap: Box<Account<'info, SwapV2>>,
     ///   1. `[]` swap authority
+    #[account(seeds = [token_swap.key().as_ref()], bump = token_swap.bump_seed)]
     pub swap_authority: AccountInfo<'info>,
     ///   2. `[signer]` user transfer authority
     #[account(signer)]
     pub user_transfer_authority: AccountInfo<'info>,
     ///   3. `[writable]` Pool mint account, swap authority is the owner
     #[account(mut)]
-    pub pool_mint: AccountInfo<'info>,
+    pub pool_mint: Box<Account<'info, Mint>>,
     ///   4. `[writable]` SOURCE Pool account, amount is transferable by user transfer authority.
     #[account(mut)]
-    pub source: AccountInfo<'info>,
+    pub source: Box<Account<'info, TokenAccount>>,
     ///   5. `[writable]` token_a Swap Account to withdraw FROM.
     #[account(mut)]
-    pub swap_token_a: AccountInfo<'info>,
+    pub swap_token_a: Box<Account<'info, TokenAccount>>,
     ///   6. `[writable]` token_b Swap Account to withdraw FROM.
     #[account(mut)]
-    pub swap_token_b: AccountInfo<'info>,
+    pub swap_token_b: Box<Account<'info, TokenAccount>>,
     ///   7. `[writable]` token_a user Account to credit.
-    #[account(mut)]
-    pub destination_token_a: AccountInfo<'info>,
+    #[account(
+        mut,
+        constraint = destination_token_a.key() != swap_token_a.key() @ SwapError::InvalidInput,
+    )]
+    pub destination_token_a: Box<Account<'info, TokenAccount>>,
     ///   8. `[writable]` token_b user Account to credit.
-    #[account(mut)]
-    pub destination_token_b: AccountInfo<'info>,
+    #[account(
+        mut,
+        constraint = destination_token_b.key() != swap_token_b.key() @ SwapError::InvalidInput,
+    )]
+    pub destination_token_b: Box<Account<'info, TokenAccount>>,
     ///   9. `[writable]` Fee account, to receive withdrawal fees
     #[account(mut)]
-    pub fee_account: AccountInfo<'info>,
+    pub fee_account: Box<Account<'info, TokenAccount>>,
     ///   10 '[]` Token program id
-    pub token_program: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 ///   Withdraw both types of tokens from the pool at the current ratio, given
@@ -46,24 +63,26 @@ pub fn handler(
     minimum_token_b_amount: u64,
 ) -> ProgramResult {
     let accounts = [
-        ctx.accounts.token_swap.clone(),
+        ctx.accounts.token_swap.to_account_info(),
         ctx.accounts.swap_authority.clone(),
         ctx.accounts.user_transfer_authority.clone(),
-        ctx.accounts.pool_mint.clone(),
-        ctx.accounts.source.clone(),
-        ctx.accounts.swap_token_a.clone(),
-        ctx.accounts.swap_token_b.clone(),
-        ctx.accounts.destination_token_a.clone(),
-        ctx.accounts.destination_token_b.clone(),
-        ctx.accounts.fee_account.clone(),
-        ctx.accounts.token_program.clone(),
+        ctx.accounts.pool_mint.to_account_info(),
+        ctx.accounts.source.to_account_info(),
+        ctx.accounts.swap_token_a.to_account_info(),
+        ctx.accounts.swap_token_b.to_account_info(),
+        ctx.accounts.destination_token_a.to_account_info(),
+        ctx.accounts.destination_token_b.to_account_info(),
+        ctx.accounts.fee_account.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
     ];
 
-    processor::Processor::process_withdraw_all_token_types(
-        ctx.program_id,
+    let event = processor::Processor::process_withdraw_all_token_types(
         pool_token_amount,
         minimum_token_a_amount,
         minimum_token_b_amount,
         &accounts,
-    )
+    )?;
+    emit!(event);
+
+    Ok(())
 }
@@ -1,36 +1,51 @@
 use anchor_lang::prelude::*;
 
+use crate::error::SwapError;
 use crate::processor;
+use crate::state::SwapV2;
+use crate::token::{Mint, Token, TokenAccount};
 
 #[derive(Accounts)]
 pub struct WithdrawSingleTokenTypeExactAmountOut<'info> {
-    ///   0. `[]` Token-swap
-    pub token_swap: AccountInfo<'info>,
+    ///   0. `[writable]` Token-swap, the bonded token A amount is updated
+    #[account(
+        mut,
+        has_one = pool_mint @ SwapError::IncorrectPoolMint,
+        has_one = pool_fee_account @ SwapError::IncorrectFeeAccount,
+        constraint = token_swap.token_a == swap_token_a.key() @ SwapError::IncorrectSwapAccount,
+        constraint = token_swap.token_b == swap_token_b.key() @ SwapError::IncorrectSwapAccount,
+    )]
+    pub token_swap: Box<Account<'info, SwapV2>>,
     ///   1. `[]` swap authority
+    #[account(seeds = [token_swap.key().as_ref()], bump = token_swap.bump_seed)]
     pub swap_authority: AccountInfo<'info>,
     ///   2. `[signer]` user transfer authority
     #[account(signer)]
     pub user_transfer_authority: AccountInfo<'info>,
     ///   3. `[writable]` Pool mint account, swap authority is the owner
     #[account(mut)]
-    pub pool_mint: AccountInfo<'info>,
+    pub pool_mint: Box<Account<'info, Mint>>,
     ///   4. `[writable]` SOURCE Pool account, amount is transferable by user transfer authority.
     #[account(mut)]
-    pub pool_token_source: AccountInfo<'info>,
+    pub pool_token_source: Box<Account<'info, TokenAccount>>,
     ///   5. `[writable]` token_a Swap Account to potentially withdraw from.
     #[account(mut)]
-    pub swap_token_a: AccountInfo<'info>,
+    pub swap_token_a: Box<Account<'info, TokenAccount>>,
     ///   6. `[writable]` token_b Swap Account to potentially withdraw from.
     #[account(mut)]
-    pub swap_token_b: AccountInfo<'info>,
+    pub swap_token_b: Box<Account<'info, TokenAccount>>,
     ///   7. `[writable]` token_(A|B) User Account to credit
-    #[account(mut)]
-    pub destination: AccountInfo<'info>,
+    #[account(
+        mut,
+        constraint = destination.key() != swap_token_a.key() && destination.key() != swap_token_b.key()
+            @ SwapError::InvalidInput,
+    )]
+    pub destination: Box<Account<'info, TokenAccount>>,
     ///   8. `[writable]` Fee account, to receive withdrawal fees
     #[account(mut)]
-    pub pool_fee_account: AccountInfo<'info>,
+    pub pool_fee_account: Box<Account<'info, TokenAccount>>,
     ///   9. '[]` Token program id
-    pub token_program: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 ///   Withdraw one token type from the pool at the current ratio given the
@@ -43,24 +58,26 @@ pub fn handler(
     // TODO: maybe not the best way to do this probably, kind of defeating the purpose of
     // anchor, but lets us just use process_foo directly
     let accounts = [
-        ctx.accounts.token_swap.clone(),
+        ctx.accounts.token_swap.to_account_info(),
         ctx.accounts.swap_authority.clone(),
         ctx.accounts.user_transfer_authority.clone(),
-        ctx.accounts.pool_mint.clone(),
-        ctx.accounts.pool_token_source.clone(),
-        ctx.accounts.swap_token_a.clone(),
-        ctx.accounts.swap_token_b.clone(),
-        ctx.accounts.destination.clone(),
-        ctx.accounts.pool_fee_account.clone(),
-        ctx.accounts.token_program.clone(),
+        ctx.accounts.pool_mint.to_account_info(),
+        ctx.accounts.pool_token_source.to_account_info(),
+        ctx.accounts.swap_token_a.to_account_info(),
+        ctx.accounts.swap_token_b.to_account_info(),
+        ctx.accounts.destination.to_account_info(),
+        ctx.accounts.pool_fee_account.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
     ];
 
-    processor::Processor::process_withdraw_single_token_type_exact_amount_out(
-        ctx.program_id,
+    let event = processor::Processor::process_withdraw_single_token_type_exact_amount_out(
         destination_token_amount,
         maximum_pool_token_amount,
         &accounts,
-    )
+    )?;
+    emit!(event);
+
+    Ok(())
 }
 
 /*
@@ -10,10 +10,14 @@ pub mod constraints;
 pub mod curve;
 pub mod dfs_precise_number;
 pub mod error;
+pub mod events;
 pub mod processor;
 pub mod state;
+pub mod token;
 
+use curve::base::CurveParams;
 use curve::fees::Fees;
+use curve::piecewise_linear::PiecewiseLinearSegment;
 use instructions::*;
 
 security_txt! {
@@ -32,12 +36,46 @@ declare_id!("TBCwReYDDw8SvwVVKJHgatzeXKrLHnaTPyDGwkUoBsq");
 mod token_bonding_curve {
     use super::*;
 
+    ///   Creates an 'initialize' instruction with any curve type, see `CurveParams` for each curve's parameters
+    ///   `initialize_constant_price` and `initialize_linear_price` below take their parameters as loose arguments
+    ///   and the same accounts, see `Initialize` for the account list
+    ///   `CurveParams::PiecewiseLinear` writes its segments to the swap's curve params account (a PDA, see
+    ///   `Processor::curve_params_address`), which is passed in after the regular accounts
+    pub fn initialize<'info>(
+        ctx: Context<'_, '_, '_, 'info, Initialize<'info>>,
+        fees: Fees,
+        curve_params: CurveParams,
+    ) -> ProgramResult {
+        instructions::initialize::handler(ctx, fees, curve_params.into())
+    }
+
+    ///   Creates a swap at a PDA of its token mints and `index` (see `Processor::swap_address`), along with
+    ///   its pool mint and token accounts, funds it and initializes it like `initialize`, all in one instruction
+    pub fn initialize_pda<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitializePda<'info>>,
+        index: u64,
+        fees: Fees,
+        curve_params: CurveParams,
+        token_a_amount: u64,
+        token_b_amount: u64,
+        token_b_mint_and_burn: bool,
+    ) -> ProgramResult {
+        instructions::initialize_pda::handler(
+            ctx,
+            index,
+            fees,
+            curve_params.into(),
+            token_a_amount,
+            token_b_amount,
+            token_b_mint_and_burn,
+        )
+    }
+
     ///   Creates an 'initialize' instruction with ConstantPrice curve
     ///   Note that SwapCurve has a dynamic trait so can't be borsh serialized easily, so we just handles
     ///   creating the SwapCurve based on the primitives passed into the different instructions
-    pub fn initialize_constant_price(
-        ctx: Context<Initialize>,
-        // TODO: should be able to just accept Fees in here instead of all these but wasn't working, not sure why
+    pub fn initialize_constant_price<'info>(
+        ctx: Context<'_, '_, '_, 'info, Initialize<'info>>,
         trade_fee_numerator: u64,
         trade_fee_denominator: u64,
         owner_trade_fee_numerator: u64,
@@ -70,15 +108,16 @@ mod token_bonding_curve {
     ///   Creates an 'initialize' instruction with LinearPrice curve
     ///   Note that SwapCurve has a dynamic trait so can't be borsh serialized easily, so we just handles
     ///   creating the SwapCurve based on the primitives passed into the different instructions
-    pub fn initialize_linear_price(
-        ctx: Context<Initialize>,
+    ///   Fees are hardcoded to 0 here to keep the instruction's original arguments, use `initialize` with
+    ///   `CurveParams::LinearPrice` for a linear curve with trade and owner fees or seeded with initial collateral
+    ///   (the curve always starts at b = 0 here)
+    pub fn initialize_linear_price<'info>(
+        ctx: Context<'_, '_, '_, 'info, Initialize<'info>>,
         slope_numerator: u64,
         slope_denominator: u64,
         initial_token_a_price_numerator: u64,
         initial_token_a_price_denominator: u64,
     ) -> ProgramResult {
-        // just hardcode fees to 0 for linear curve, we don't support those right now (would require implementing
-        // some withdraw logic to calculate the fees during swap)
         instructions::initialize::handler(
             ctx,
             Fees {
@@ -98,16 +137,160 @@ mod token_bonding_curve {
                     slope_denominator,
                     initial_token_a_price_numerator,
                     initial_token_a_price_denominator,
+                    initial_token_a_amount: 0,
                 }),
             },
         )
     }
 
+    ///   Creates an 'initialize' instruction with ExponentialPrice curve, same as `initialize` with
+    ///   `CurveParams::ExponentialPrice`
+    pub fn initialize_exponential_price<'info>(
+        ctx: Context<'_, '_, '_, 'info, Initialize<'info>>,
+        fees: Fees,
+        initial_token_a_price_numerator: u64,
+        initial_token_a_price_denominator: u64,
+        growth_rate_numerator: u32,
+        growth_rate_denominator: u32,
+        growth_interval: u64,
+    ) -> ProgramResult {
+        instructions::initialize::handler(
+            ctx,
+            fees,
+            CurveParams::ExponentialPrice {
+                initial_token_a_price_numerator,
+                initial_token_a_price_denominator,
+                growth_rate_numerator,
+                growth_rate_denominator,
+                growth_interval,
+            }
+            .into(),
+        )
+    }
+
+    ///   Creates an 'initialize' instruction with PowerPrice curve, same as `initialize` with
+    ///   `CurveParams::PowerPrice`
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_power_price<'info>(
+        ctx: Context<'_, '_, '_, 'info, Initialize<'info>>,
+        fees: Fees,
+        slope_numerator: u64,
+        slope_denominator: u64,
+        initial_token_a_price_numerator: u64,
+        initial_token_a_price_denominator: u32,
+        exponent: u8,
+        token_b_decimals: u8,
+    ) -> ProgramResult {
+        instructions::initialize::handler(
+            ctx,
+            fees,
+            CurveParams::PowerPrice {
+                slope_numerator,
+                slope_denominator,
+                initial_token_a_price_numerator,
+                initial_token_a_price_denominator,
+                exponent,
+                token_b_decimals,
+            }
+            .into(),
+        )
+    }
+
+    ///   Creates an 'initialize' instruction with PiecewiseLinear curve, same as `initialize` with
+    ///   `CurveParams::PiecewiseLinear` (so the curve params account is passed in after the regular accounts)
+    pub fn initialize_piecewise_linear<'info>(
+        ctx: Context<'_, '_, '_, 'info, Initialize<'info>>,
+        fees: Fees,
+        initial_token_a_price_numerator: u64,
+        initial_token_a_price_denominator: u64,
+        segments: Vec<PiecewiseLinearSegment>,
+    ) -> ProgramResult {
+        instructions::initialize::handler(
+            ctx,
+            fees,
+            CurveParams::PiecewiseLinear {
+                initial_token_a_price_numerator,
+                initial_token_a_price_denominator,
+                segments,
+            }
+            .into(),
+        )
+    }
+
+    ///   Creates an 'initialize' instruction with SigmoidPrice curve, same as `initialize` with
+    ///   `CurveParams::SigmoidPrice`
+    pub fn initialize_sigmoid_price<'info>(
+        ctx: Context<'_, '_, '_, 'info, Initialize<'info>>,
+        fees: Fees,
+        max_price_numerator: u64,
+        max_price_denominator: u32,
+        steepness_numerator: u32,
+        steepness_denominator: u64,
+        midpoint: u64,
+    ) -> ProgramResult {
+        instructions::initialize::handler(
+            ctx,
+            fees,
+            CurveParams::SigmoidPrice {
+                max_price_numerator,
+                max_price_denominator,
+                steepness_numerator,
+                steepness_denominator,
+                midpoint,
+            }
+            .into(),
+        )
+    }
+
+    ///   Creates an 'initialize' instruction with ReserveRatio curve, same as `initialize` with
+    ///   `CurveParams::ReserveRatio`
+    pub fn initialize_reserve_ratio<'info>(
+        ctx: Context<'_, '_, '_, 'info, Initialize<'info>>,
+        fees: Fees,
+        reserve_ratio_numerator: u32,
+        reserve_ratio_denominator: u32,
+        initial_reserve: u64,
+        initial_supply: u64,
+    ) -> ProgramResult {
+        instructions::initialize::handler(
+            ctx,
+            fees,
+            CurveParams::ReserveRatio {
+                reserve_ratio_numerator,
+                reserve_ratio_denominator,
+                initial_reserve,
+                initial_supply,
+            }
+            .into(),
+        )
+    }
+
     /// Creates a 'swap' instruction.
-    pub fn swap(ctx: Context<Swap>, amount_in: u64, minimum_amount_out: u64) -> ProgramResult {
+    pub fn swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, Swap<'info>>,
+        amount_in: u64,
+        minimum_amount_out: u64,
+    ) -> ProgramResult {
         instructions::swap::handler(ctx, amount_in, minimum_amount_out)
     }
 
+    /// Creates a 'swap_exact_amount_out' instruction.
+    pub fn swap_exact_amount_out<'info>(
+        ctx: Context<'_, '_, '_, 'info, Swap<'info>>,
+        amount_out: u64,
+        maximum_amount_in: u64,
+    ) -> ProgramResult {
+        instructions::swap_exact_amount_out::handler(ctx, amount_out, maximum_amount_in)
+    }
+
+    /// Creates a 'quote_swap' instruction.
+    pub fn quote_swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, QuoteSwap<'info>>,
+        amount_in: u64,
+    ) -> ProgramResult {
+        instructions::quote_swap::handler(ctx, amount_in)
+    }
+
     /// Creates a 'deposit_all_token_types' instruction.
     pub fn deposit_all_token_types(
         ctx: Context<DepositAllTokenTypes>,
@@ -163,4 +346,69 @@ mod token_bonding_curve {
             maximum_pool_token_amount,
         )
     }
+
+    /// Creates a 'sweep_surplus' instruction.
+    pub fn sweep_surplus(ctx: Context<SweepSurplus>) -> ProgramResult {
+        instructions::sweep_surplus::handler(ctx)
+    }
+
+    /// Creates a 'claim_fees' instruction.
+    pub fn claim_fees(ctx: Context<ClaimFees>) -> ProgramResult {
+        instructions::claim_fees::handler(ctx)
+    }
+
+    /// Creates a 'set_admin' instruction.
+    pub fn set_admin(ctx: Context<UpdateAdmin>, new_admin: Pubkey) -> ProgramResult {
+        instructions::set_admin::handler(ctx, new_admin)
+    }
+
+    /// Creates an 'accept_admin' instruction.
+    pub fn accept_admin(ctx: Context<UpdateAdmin>) -> ProgramResult {
+        instructions::accept_admin::handler(ctx)
+    }
+
+    /// Creates a 'renounce_admin' instruction.
+    pub fn renounce_admin(ctx: Context<UpdateAdmin>) -> ProgramResult {
+        instructions::renounce_admin::handler(ctx)
+    }
+
+    /// Creates a 'pause' instruction.
+    pub fn pause<'info>(ctx: Context<'_, '_, '_, 'info, SetPaused<'info>>) -> ProgramResult {
+        instructions::pause::handler(ctx)
+    }
+
+    /// Creates a 'resume' instruction.
+    pub fn resume<'info>(ctx: Context<'_, '_, '_, 'info, SetPaused<'info>>) -> ProgramResult {
+        instructions::resume::handler(ctx)
+    }
+
+    /// Creates a 'set_accrue_fees' instruction.
+    pub fn set_accrue_fees(ctx: Context<UpdateAdmin>, accrue_fees: bool) -> ProgramResult {
+        instructions::set_accrue_fees::handler(ctx, accrue_fees)
+    }
+
+    /// Creates a 'migrate_swap_v1_to_v2' instruction.
+    pub fn migrate_swap_v1_to_v2(ctx: Context<MigrateSwapV1ToV2>) -> ProgramResult {
+        instructions::migrate_swap_v1_to_v2::handler(ctx)
+    }
+
+    /// Creates an 'enable_emergency_mode' instruction.
+    pub fn enable_emergency_mode(ctx: Context<EnableEmergencyMode>) -> ProgramResult {
+        instructions::enable_emergency_mode::handler(ctx)
+    }
+
+    /// Creates a 'recover_token_b' instruction.
+    pub fn recover_token_b(ctx: Context<RecoverTokenB>) -> ProgramResult {
+        instructions::recover_token_b::handler(ctx)
+    }
+
+    /// Creates an 'emergency_redeem' instruction.
+    pub fn emergency_redeem(ctx: Context<EmergencyRedeem>, token_b_amount: u64) -> ProgramResult {
+        instructions::emergency_redeem::handler(ctx, token_b_amount)
+    }
+
+    /// Creates a 'close_swap' instruction.
+    pub fn close_swap<'info>(ctx: Context<'_, '_, '_, 'info, CloseSwap<'info>>) -> ProgramResult {
+        instructions::close_swap::handler(ctx)
+    }
 }
@@ -3,24 +3,34 @@
 use crate::constraints::SwapConstraints;
 use crate::{
     curve::{
-        base::SwapCurve,
+        base::{SwapCurve, SwapQuote, SwapResult},
         calculator::{RoundDirection, TradeDirection},
         fees::Fees,
     },
     error::SwapError,
-    state::{SwapState, SwapV1, SwapVersion},
+    events::{
+        AccrueFeesEvent, AdminEvent, ClaimFeesEvent, CloseSwapEvent, DepositEvent,
+        EmergencyModeEvent, EmergencyRedeemEvent, InitializeEvent, MigrateSwapEvent, PauseEvent,
+        QuoteSwapEvent, RecoverTokenBEvent, SwapEvent, SweepSurplusEvent, WithdrawEvent,
+    },
+    state::{
+        Registry, RegistryEntry, RegistryPage, SwapExtensionType, SwapState, SwapV2, SwapVersion,
+    },
 };
 use num_traits::FromPrimitive;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     decode_error::DecodeError,
     entrypoint::ProgramResult,
+    instruction::Instruction,
     msg,
-    program::invoke_signed,
+    program::{invoke, invoke_signed, set_return_data},
     program_error::{PrintProgramError, ProgramError},
     program_option::COption,
     program_pack::Pack,
     pubkey::Pubkey,
+    system_instruction,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
 };
 use std::convert::TryInto;
 
@@ -63,6 +73,78 @@ impl Processor {
             .or(Err(SwapError::InvalidProgramAddress))
     }
 
+    /// Seed, after the swap's address, of the PDA holding the swap's curve params (see
+    /// `CurveType::curve_params_len`)
+    pub const CURVE_PARAMS_SEED: &'static [u8] = b"curve_params";
+
+    /// Finds the address and bump seed of the swap's curve params account
+    pub fn curve_params_address(program_id: &Pubkey, swap: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[swap.as_ref(), Self::CURVE_PARAMS_SEED], program_id)
+    }
+
+    /// Seed, before the token mints and index, of the swaps `initialize_pda` creates
+    pub const SWAP_SEED: &'static [u8] = b"swap";
+    /// Seed, after the swap's address, of the token A account `initialize_pda` creates
+    pub const TOKEN_A_SEED: &'static [u8] = b"token_a";
+    /// Seed, after the swap's address, of the token B account `initialize_pda` creates
+    pub const TOKEN_B_SEED: &'static [u8] = b"token_b";
+    /// Seed, after the swap's address, of the pool mint `initialize_pda` creates
+    pub const POOL_MINT_SEED: &'static [u8] = b"pool_mint";
+    /// Seed, after the swap's address, of the pool fee account `initialize_pda` creates
+    pub const POOL_FEE_SEED: &'static [u8] = b"pool_fee";
+
+    /// Finds the address and bump seed of the swap `initialize_pda` creates for a token pair, there can be any
+    /// number of swaps for the same pair with different indexes. Anyone can take the next free index, so the
+    /// swap's admin (recorded in the swap and its registry entry) is what tells the curves for a pair apart
+    pub fn swap_address(
+        program_id: &Pubkey,
+        token_a_mint: &Pubkey,
+        token_b_mint: &Pubkey,
+        index: u64,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                Self::SWAP_SEED,
+                token_a_mint.as_ref(),
+                token_b_mint.as_ref(),
+                &index.to_le_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Finds the address and bump seed of one of the accounts `initialize_pda` creates for a swap, `seed` is
+    /// `TOKEN_A_SEED`, `TOKEN_B_SEED`, `POOL_MINT_SEED` or `POOL_FEE_SEED`
+    pub fn swap_account_address(program_id: &Pubkey, swap: &Pubkey, seed: &[u8]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[swap.as_ref(), seed], program_id)
+    }
+
+    /// Seed, before the collateral mint, of the `Registry` of the swaps initialized with that mint
+    pub const REGISTRY_SEED: &'static [u8] = b"registry";
+    /// Seed, before the collateral mint and page index, of a `RegistryPage`
+    pub const REGISTRY_PAGE_SEED: &'static [u8] = b"registry_page";
+
+    /// Finds the address and bump seed of the registry of the swaps with the collateral (token A) mint
+    pub fn registry_address(program_id: &Pubkey, collateral_mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::REGISTRY_SEED, collateral_mint.as_ref()], program_id)
+    }
+
+    /// Finds the address and bump seed of a page of the registry of the swaps with the collateral mint
+    pub fn registry_page_address(
+        program_id: &Pubkey,
+        collateral_mint: &Pubkey,
+        index: u32,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                Self::REGISTRY_PAGE_SEED,
+                collateral_mint.as_ref(),
+                &index.to_le_bytes(),
+            ],
+            program_id,
+        )
+    }
+
     /// Issue a spl_token `Burn` instruction.
     pub fn token_burn<'a>(
         swap: &Pubkey,
@@ -146,58 +228,454 @@ impl Processor {
         )
     }
 
-    #[allow(clippy::too_many_arguments)]
-    fn check_accounts(
-        token_swap: &dyn SwapState,
-        program_id: &Pubkey,
-        swap_account_info: &AccountInfo,
-        authority_info: &AccountInfo,
-        token_a_info: &AccountInfo,
-        token_b_info: &AccountInfo,
-        pool_mint_info: &AccountInfo,
-        token_program_info: &AccountInfo,
-        user_token_a_info: Option<&AccountInfo>,
-        user_token_b_info: Option<&AccountInfo>,
-        pool_fee_account_info: Option<&AccountInfo>,
+    /// Issue a spl_token `CloseAccount` instruction.
+    pub fn token_close_account<'a>(
+        swap: &Pubkey,
+        token_program: AccountInfo<'a>,
+        account: AccountInfo<'a>,
+        destination: AccountInfo<'a>,
+        authority: AccountInfo<'a>,
+        bump_seed: u8,
+    ) -> Result<(), ProgramError> {
+        let swap_bytes = swap.to_bytes();
+        let authority_signature_seeds = [&swap_bytes[..32], &[bump_seed]];
+        let signers = &[&authority_signature_seeds[..]];
+        let ix = spl_token::instruction::close_account(
+            token_program.key,
+            account.key,
+            destination.key,
+            authority.key,
+            &[],
+        )?;
+        invoke_signed(
+            &ix,
+            &[account, destination, authority, token_program],
+            signers,
+        )
+    }
+
+    /// Moves all of a program owned account's lamports to the destination and zeroes its data, so the runtime
+    /// garbage collects it at the end of the transaction
+    fn close_program_account(
+        account_info: &AccountInfo,
+        destination_info: &AccountInfo,
     ) -> ProgramResult {
-        if swap_account_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
+        let lamports = account_info.lamports();
+        **destination_info.lamports.borrow_mut() = destination_info
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(SwapError::CalculationFailure)?;
+        **account_info.lamports.borrow_mut() = 0;
+        account_info.data.borrow_mut().fill(0);
+        Ok(())
+    }
+
+    /// Creates a rent exempt account at a PDA of this program, paid for by the payer
+    fn create_pda_account<'a>(
+        payer: &AccountInfo<'a>,
+        account_info: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        space: usize,
+        owner: &Pubkey,
+        signer_seeds: &[&[u8]],
+    ) -> ProgramResult {
+        for instruction in Self::create_pda_account_instructions(
+            payer.key,
+            account_info.key,
+            account_info.lamports(),
+            Rent::get()?.minimum_balance(space),
+            space,
+            owner,
+        ) {
+            invoke_signed(
+                &instruction,
+                &[payer.clone(), account_info.clone(), system_program.clone()],
+                &[signer_seeds],
+            )?;
         }
-        if *authority_info.key
-            != Self::authority_id(program_id, swap_account_info.key, token_swap.bump_seed())?
+        Ok(())
+    }
+
+    /// System program instructions that create the account at `address` (see `create_pda_account`). Anyone can send
+    /// lamports to a PDA before it's created, which would make `create_account` fail, so an account that already
+    /// holds `lamports` is topped up to rent exemption, allocated and assigned instead
+    fn create_pda_account_instructions(
+        payer: &Pubkey,
+        address: &Pubkey,
+        lamports: u64,
+        rent_exempt_lamports: u64,
+        space: usize,
+        owner: &Pubkey,
+    ) -> Vec<Instruction> {
+        if lamports == 0 {
+            return vec![system_instruction::create_account(
+                payer,
+                address,
+                rent_exempt_lamports,
+                space as u64,
+                owner,
+            )];
+        }
+        let mut instructions = vec![];
+        let missing_lamports = rent_exempt_lamports.saturating_sub(lamports);
+        if missing_lamports > 0 {
+            instructions.push(system_instruction::transfer(
+                payer,
+                address,
+                missing_lamports,
+            ));
+        }
+        instructions.push(system_instruction::allocate(address, space as u64));
+        instructions.push(system_instruction::assign(address, owner));
+        instructions
+    }
+
+    /// Loads the swap curve's parameters from the next account if the curve keeps them in a separate account
+    /// (see `CurveType::curve_params_len`), returns None for every other curve
+    fn load_swap_curve(
+        program_id: &Pubkey,
+        swap_info: &AccountInfo,
+        swap_curve: &SwapCurve,
+        account_info_iter: &mut std::slice::Iter<AccountInfo>,
+    ) -> Result<Option<SwapCurve>, ProgramError> {
+        if swap_curve.curve_type.curve_params_len().is_none() {
+            return Ok(None);
+        }
+        let curve_params_info = next_account_info(account_info_iter)?;
+        if SwapVersion::get_extension(&swap_info.data.borrow(), SwapExtensionType::CurveParams)?
+            != Some(curve_params_info.key.as_ref())
         {
-            return Err(SwapError::InvalidProgramAddress.into());
-        }
-        if *token_a_info.key != *token_swap.token_a_account() {
-            return Err(SwapError::IncorrectSwapAccount.into());
+            return Err(SwapError::IncorrectCurveParamsAccount.into());
         }
-        if *token_b_info.key != *token_swap.token_b_account() {
-            return Err(SwapError::IncorrectSwapAccount.into());
+        if curve_params_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
         }
-        if *pool_mint_info.key != *token_swap.pool_mint() {
-            return Err(SwapError::IncorrectPoolMint.into());
+        Ok(Some(
+            swap_curve.load_curve_params(&curve_params_info.data.borrow())?,
+        ))
+    }
+
+    /// Creates the swap's curve params PDA (see `CurveType::curve_params_len`) and packs the curve's parameters
+    /// into it, returns its address to link from the swap
+    fn create_curve_params_account<'a>(
+        program_id: &Pubkey,
+        swap_info: &AccountInfo<'a>,
+        curve_params_info: &AccountInfo<'a>,
+        payer_info: &AccountInfo<'a>,
+        system_program_info: &AccountInfo<'a>,
+        swap_curve: &SwapCurve,
+        curve_params_len: usize,
+    ) -> Result<Pubkey, ProgramError> {
+        let (curve_params_account, curve_params_bump_seed) =
+            Self::curve_params_address(program_id, swap_info.key);
+        if *curve_params_info.key != curve_params_account {
+            return Err(SwapError::IncorrectCurveParamsAccount.into());
+        }
+
+        Self::create_pda_account(
+            payer_info,
+            curve_params_info,
+            system_program_info,
+            curve_params_len,
+            program_id,
+            &[
+                swap_info.key.as_ref(),
+                Self::CURVE_PARAMS_SEED,
+                &[curve_params_bump_seed],
+            ],
+        )?;
+        swap_curve
+            .calculator
+            .pack_curve_params(&mut curve_params_info.data.borrow_mut())?;
+        Ok(curve_params_account)
+    }
+
+    /// Loads token B's mint from the next account if the swap mints and burns token B (see
+    /// `SwapV2::token_b_mint_and_burn`) or its curve is priced off token B's supply (see
+    /// `CurveType::uses_token_b_supply`), returns None for every other swap
+    fn load_token_b_mint<'a, 'b>(
+        token_swap: &SwapV2,
+        account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+    ) -> Result<Option<(&'a AccountInfo<'b>, spl_token::state::Mint)>, ProgramError> {
+        if !token_swap.token_b_mint_and_burn
+            && !token_swap.swap_curve().curve_type.uses_token_b_supply()
+        {
+            return Ok(None);
         }
-        if *token_program_info.key != *token_swap.token_program_id() {
-            return Err(SwapError::IncorrectTokenProgramId.into());
+        let token_b_mint_info = next_account_info(account_info_iter)?;
+        if *token_b_mint_info.key != *token_swap.token_b_mint() {
+            return Err(SwapError::IncorrectTokenBMint.into());
         }
-        if let Some(user_token_a_info) = user_token_a_info {
-            if token_a_info.key == user_token_a_info.key {
-                return Err(SwapError::InvalidInput.into());
+        let token_b_mint = Self::unpack_mint(token_b_mint_info, token_swap.token_program_id())?;
+        Ok(Some((token_b_mint_info, token_b_mint)))
+    }
+
+    /// Returns a copy of the swap curve priced off token B's supply for curves that use it (see
+    /// `CurveType::uses_token_b_supply`), None for every other curve. The curve is given the circulating supply
+    /// plus the swap's token B amount, which swaps don't change: the mint's supply, or the mint's supply plus
+    /// whatever's still mintable for a mint-and-burn swap
+    fn with_token_b_supply(
+        token_swap: &SwapV2,
+        swap_curve: &SwapCurve,
+        token_b_mint: Option<&spl_token::state::Mint>,
+    ) -> Result<Option<SwapCurve>, ProgramError> {
+        if !swap_curve.curve_type.uses_token_b_supply() {
+            return Ok(None);
+        }
+        let token_b_mint = token_b_mint.ok_or(SwapError::IncorrectTokenBMint)?;
+        let token_b_supply = if token_swap.token_b_mint_and_burn {
+            token_b_mint.supply + Self::mintable_token_b_amount(token_b_mint)
+        } else {
+            token_b_mint.supply
+        };
+        Ok(Some(swap_curve.with_token_b_supply(token_b_supply)?))
+    }
+
+    /// Amount of token B a mint-and-burn swap can still mint, which the curve sees in place of the token B
+    /// account's balance since there's no inventory to run out of
+    fn mintable_token_b_amount(token_b_mint: &spl_token::state::Mint) -> u64 {
+        u64::MAX - token_b_mint.supply
+    }
+
+    /// Appends a newly initialized swap to the registry of its collateral mint, creating the registry on its first
+    /// swap and a new page whenever the last one is full (the registry page passed has to be the last page, or
+    /// the next one if the last page is full)
+    fn register_swap<'a>(
+        program_id: &Pubkey,
+        registry_info: &AccountInfo<'a>,
+        registry_page_info: &AccountInfo<'a>,
+        payer_info: &AccountInfo<'a>,
+        system_program_info: &AccountInfo<'a>,
+        collateral_mint: &Pubkey,
+        entry: RegistryEntry,
+    ) -> ProgramResult {
+        let (registry_address, registry_bump_seed) =
+            Self::registry_address(program_id, collateral_mint);
+        if *registry_info.key != registry_address {
+            return Err(SwapError::IncorrectRegistryAccount.into());
+        }
+        let mut registry = if registry_info.data_is_empty() {
+            Self::create_pda_account(
+                payer_info,
+                registry_info,
+                system_program_info,
+                Registry::LEN,
+                program_id,
+                &[
+                    Self::REGISTRY_SEED,
+                    collateral_mint.as_ref(),
+                    &[registry_bump_seed],
+                ],
+            )?;
+            Registry {
+                is_initialized: true,
+                collateral_mint: *collateral_mint,
+                ..Registry::default()
             }
-        }
-        if let Some(user_token_b_info) = user_token_b_info {
-            if token_b_info.key == user_token_b_info.key {
-                return Err(SwapError::InvalidInput.into());
+        } else {
+            if registry_info.owner != program_id {
+                return Err(ProgramError::IncorrectProgramId);
             }
-        }
-        if let Some(pool_fee_account_info) = pool_fee_account_info {
-            if *pool_fee_account_info.key != *token_swap.pool_fee_account() {
-                return Err(SwapError::IncorrectFeeAccount.into());
+            Registry::unpack(&registry_info.data.borrow())?
+        };
+
+        let mut registry_page = if !Self::registry_needs_new_page(&registry) {
+            if registry.last_page != COption::Some(*registry_page_info.key) {
+                return Err(SwapError::IncorrectRegistryAccount.into());
             }
-        }
+            RegistryPage::unpack(&registry_page_info.data.borrow())?
+        } else {
+            let (registry_page_address, registry_page_bump_seed) =
+                Self::registry_page_address(program_id, collateral_mint, registry.page_count);
+            if *registry_page_info.key != registry_page_address {
+                return Err(SwapError::IncorrectRegistryAccount.into());
+            }
+            Self::create_pda_account(
+                payer_info,
+                registry_page_info,
+                system_program_info,
+                RegistryPage::LEN,
+                program_id,
+                &[
+                    Self::REGISTRY_PAGE_SEED,
+                    collateral_mint.as_ref(),
+                    &registry.page_count.to_le_bytes(),
+                    &[registry_page_bump_seed],
+                ],
+            )?;
+            Self::new_registry_page(&mut registry, &registry_address, &registry_page_address)?
+        };
+
+        Self::push_registry_entry(&mut registry, &mut registry_page, entry)?;
+        RegistryPage::pack(registry_page, &mut registry_page_info.data.borrow_mut())?;
+        Registry::pack(registry, &mut registry_info.data.borrow_mut())
+    }
+
+    /// Returns whether the next swap recorded in the registry goes in a new page, because the registry doesn't have
+    /// a page yet or its last page is full
+    fn registry_needs_new_page(registry: &Registry) -> bool {
+        registry.last_page.is_none()
+            || usize::from(registry.last_page_entry_count) == RegistryPage::CAPACITY
+    }
+
+    /// Starts the registry's next page at `registry_page_address`, linked back to its current last page
+    fn new_registry_page(
+        registry: &mut Registry,
+        registry_address: &Pubkey,
+        registry_page_address: &Pubkey,
+    ) -> Result<RegistryPage, SwapError> {
+        let registry_page = RegistryPage {
+            is_initialized: true,
+            registry: *registry_address,
+            index: registry.page_count,
+            prev_page: registry.last_page,
+            entries: vec![],
+        };
+        registry.page_count = registry
+            .page_count
+            .checked_add(1)
+            .ok_or(SwapError::CalculationFailure)?;
+        registry.last_page = COption::Some(*registry_page_address);
+        registry.last_page_entry_count = 0;
+        Ok(registry_page)
+    }
+
+    /// Appends a swap to the registry's last page
+    fn push_registry_entry(
+        registry: &mut Registry,
+        registry_page: &mut RegistryPage,
+        entry: RegistryEntry,
+    ) -> Result<(), SwapError> {
+        registry_page.entries.push(entry);
+        registry.last_page_entry_count = registry_page.entries.len() as u8;
+        registry.swap_count = registry
+            .swap_count
+            .checked_add(1)
+            .ok_or(SwapError::CalculationFailure)?;
         Ok(())
     }
 
+    /// Removes a closed swap from its page of the registry and returns its entry. The page isn't refilled unless
+    /// it's the last one
+    fn remove_registry_entry(
+        registry: &mut Registry,
+        registry_page: &mut RegistryPage,
+        registry_page_address: &Pubkey,
+        swap: &Pubkey,
+    ) -> Result<RegistryEntry, SwapError> {
+        let position = registry_page
+            .entries
+            .iter()
+            .position(|entry| entry.swap == *swap)
+            .ok_or(SwapError::IncorrectRegistryAccount)?;
+        let entry = registry_page.entries.remove(position);
+        if registry.last_page == COption::Some(*registry_page_address) {
+            registry.last_page_entry_count = registry_page.entries.len() as u8;
+        }
+        registry.swap_count = registry
+            .swap_count
+            .checked_sub(1)
+            .ok_or(SwapError::CalculationFailure)?;
+        Ok(entry)
+    }
+
+    /// Unpacks the registry page a swap is recorded in, which has to be a page of the registry of the swap's
+    /// collateral mint
+    fn unpack_registry_page(
+        program_id: &Pubkey,
+        registry_page_info: &AccountInfo,
+        collateral_mint: &Pubkey,
+    ) -> Result<RegistryPage, ProgramError> {
+        let registry_page = RegistryPage::unpack(&registry_page_info.data.borrow())?;
+        if *registry_page_info.key
+            != Self::registry_page_address(program_id, collateral_mint, registry_page.index).0
+        {
+            return Err(SwapError::IncorrectRegistryAccount.into());
+        }
+        Ok(registry_page)
+    }
+
+    /// Returns the swap's token A amount to run swaps, deposits and withdrawals against: the bonded amount for
+    /// curves that collect fees in trading tokens (see `SwapV2::bonded_token_a_amount`), or the token A account's
+    /// balance for the AMM curves, which price against their whole balance like spl token swap
+    fn swap_token_a_amount(token_swap: &SwapV2, token_a: &spl_token::state::Account) -> u64 {
+        if token_swap
+            .swap_curve
+            .calculator
+            .collects_fees_in_trading_tokens()
+        {
+            token_swap.bonded_token_a_amount
+        } else {
+            token_a.amount
+        }
+    }
+
+    /// Records the swap's new bonded token A amount (see `SwapV2::bonded_token_a_amount`), AMM curves don't track
+    /// one so it's left at 0 for them
+    fn update_bonded_token_a_amount(
+        swap_info: &AccountInfo,
+        bonded_token_a_amount: u128,
+    ) -> ProgramResult {
+        let mut token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        if !token_swap
+            .swap_curve
+            .calculator
+            .collects_fees_in_trading_tokens()
+        {
+            return Ok(());
+        }
+        token_swap.bonded_token_a_amount = to_u64(bonded_token_a_amount)?;
+        SwapVersion::pack(
+            SwapVersion::SwapV2(token_swap),
+            &mut swap_info.data.borrow_mut(),
+        )
+    }
+
+    /// Records the token B the curve sold (or bought back) in a swap in the swap's sold token B amount (see
+    /// `SwapV2::sold_token_b_amount`). Token B minted outside the curve can be sold to it too (e.g. against a seeded
+    /// price floor), so the amount sold bottoms out at 0
+    fn update_sold_token_b_amount(
+        swap_info: &AccountInfo,
+        trade_direction: TradeDirection,
+        token_b_amount: u64,
+    ) -> ProgramResult {
+        let mut token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        token_swap.sold_token_b_amount = match trade_direction {
+            TradeDirection::AtoB => token_swap
+                .sold_token_b_amount
+                .checked_add(token_b_amount)
+                .ok_or(SwapError::CalculationFailure)?,
+            TradeDirection::BtoA => token_swap
+                .sold_token_b_amount
+                .saturating_sub(token_b_amount),
+        };
+        SwapVersion::pack(
+            SwapVersion::SwapV2(token_swap),
+            &mut swap_info.data.borrow_mut(),
+        )
+    }
+
+    /// Adds fees that build up in the swap (see `SwapV2::accrue_fees`) to the swap's accrued fees of the source
+    /// token
+    fn accrue_fees(
+        swap_info: &AccountInfo,
+        trade_direction: TradeDirection,
+        fee_amount: u128,
+    ) -> ProgramResult {
+        let mut token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        let accrued_fees = match trade_direction {
+            TradeDirection::AtoB => &mut token_swap.accrued_token_a_fees,
+            TradeDirection::BtoA => &mut token_swap.accrued_token_b_fees,
+        };
+        *accrued_fees = accrued_fees
+            .checked_add(to_u64(fee_amount)?)
+            .ok_or(SwapError::FeeCalculationFailure)?;
+        SwapVersion::pack(
+            SwapVersion::SwapV2(token_swap),
+            &mut swap_info.data.borrow_mut(),
+        )
+    }
+
     /// Processes an [Initialize](enum.Instruction.html).
     pub fn process_initialize(
         program_id: &Pubkey,
@@ -205,7 +683,7 @@ impl Processor {
         swap_curve: SwapCurve,
         accounts: &[AccountInfo],
         swap_constraints: &Option<SwapConstraints>,
-    ) -> ProgramResult {
+    ) -> Result<InitializeEvent, ProgramError> {
         let account_info_iter = &mut accounts.iter();
         let swap_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
@@ -215,11 +693,21 @@ impl Processor {
         let fee_account_info = next_account_info(account_info_iter)?;
         let destination_info = next_account_info(account_info_iter)?;
         let token_program_info = next_account_info(account_info_iter)?;
-
+        let admin_info = next_account_info(account_info_iter)?;
+        let registry_info = next_account_info(account_info_iter)?;
+        let registry_page_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        // `initialize_pda` runs this on the accounts it just created rather than through the `Initialize`
+        // constraints, so the accounts are checked here too
         let token_program_id = *token_program_info.key;
         if SwapVersion::is_initialized(&swap_info.data.borrow()) {
             return Err(SwapError::AlreadyInUse.into());
         }
+        if !admin_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
 
         let (swap_authority, bump_seed) =
             Pubkey::find_program_address(&[&swap_info.key.to_bytes()], program_id);
@@ -250,9 +738,6 @@ impl Processor {
         if token_a.mint == token_b.mint {
             return Err(SwapError::RepeatedMint.into());
         }
-        swap_curve
-            .calculator
-            .validate_supply(token_a.amount, token_b.amount)?;
         if token_a.delegate.is_some() {
             return Err(SwapError::InvalidDelegate.into());
         }
@@ -290,6 +775,73 @@ impl Processor {
         fees.validate()?;
         swap_curve.calculator.validate()?;
 
+        // curves whose parameters don't fit in the swap keep them in a PDA of the swap, created here and linked
+        // from the swap's extension area (the swap account grows to fit the link if it wasn't created big enough)
+        let curve_params_account = match swap_curve.curve_type.curve_params_len() {
+            Some(curve_params_len) => {
+                let curve_params_account = Self::create_curve_params_account(
+                    program_id,
+                    swap_info,
+                    next_account_info(account_info_iter)?,
+                    payer_info,
+                    system_program_info,
+                    &swap_curve,
+                    curve_params_len,
+                )?;
+
+                let swap_len = SwapVersion::LATEST_LEN + SwapVersion::extension_len(32);
+                if swap_info.data_len() < swap_len {
+                    let missing_lamports = Rent::get()?
+                        .minimum_balance(swap_len)
+                        .saturating_sub(swap_info.lamports());
+                    if missing_lamports > 0 {
+                        invoke(
+                            &system_instruction::transfer(
+                                payer_info.key,
+                                swap_info.key,
+                                missing_lamports,
+                            ),
+                            &[
+                                payer_info.clone(),
+                                swap_info.clone(),
+                                system_program_info.clone(),
+                            ],
+                        )?;
+                    }
+                    swap_info.realloc(swap_len, true)?;
+                }
+                Some(curve_params_account)
+            }
+            None => None,
+        };
+
+        // passing token B's mint next puts the swap in mint-and-burn mode, where token B starts empty and the curve
+        // sees however much the swap can still mint as its token B amount (see `SwapV2::token_b_mint_and_burn`)
+        let (token_b_mint_and_burn, swap_token_b_amount) =
+            match next_account_info(account_info_iter) {
+                Ok(token_b_mint_info) => {
+                    if *token_b_mint_info.key != token_b.mint {
+                        return Err(SwapError::IncorrectTokenBMint.into());
+                    }
+                    let token_b_mint = Self::unpack_mint(token_b_mint_info, &token_program_id)?;
+                    if COption::Some(*authority_info.key) != token_b_mint.mint_authority {
+                        return Err(SwapError::InvalidOwner.into());
+                    }
+                    // only the bonding curves (which never hold token B for liquidity providers) can mint and burn
+                    if !swap_curve.calculator.collects_fees_in_trading_tokens() {
+                        return Err(SwapError::UnsupportedCurveOperation.into());
+                    }
+                    if token_b.amount != 0 {
+                        return Err(SwapError::InvalidSupply.into());
+                    }
+                    (true, Self::mintable_token_b_amount(&token_b_mint))
+                }
+                Err(_) => (false, token_b.amount),
+            };
+        swap_curve
+            .calculator
+            .validate_supply(token_a.amount, swap_token_b_amount)?;
+
         let initial_amount = swap_curve.calculator.new_pool_supply();
 
         Self::token_mint_to(
@@ -302,7 +854,30 @@ impl Processor {
             to_u64(initial_amount)?,
         )?;
 
-        let obj = SwapVersion::SwapV1(SwapV1 {
+        let bonded_token_a_amount = if swap_curve.calculator.collects_fees_in_trading_tokens() {
+            token_a.amount
+        } else {
+            0
+        };
+        let mut packed_swap_curve = [0u8; SwapCurve::LEN];
+        swap_curve.pack_into_slice(&mut packed_swap_curve);
+        let mut curve_params_account_data =
+            vec![0u8; swap_curve.curve_type.curve_params_len().unwrap_or(0)];
+        swap_curve
+            .calculator
+            .pack_curve_params(&mut curve_params_account_data)?;
+        let event = InitializeEvent {
+            swap: *swap_info.key,
+            token_a_mint: token_a.mint,
+            token_b_mint: token_b.mint,
+            curve_type: swap_curve.curve_type as u8,
+            curve_parameters: packed_swap_curve[1..].to_vec(),
+            curve_params_account_data,
+            token_b_mint_and_burn,
+            bonded_token_a_amount,
+        };
+
+        let obj = SwapVersion::SwapV2(SwapV2 {
             is_initialized: true,
             bump_seed,
             token_program_id,
@@ -314,106 +889,688 @@ impl Processor {
             pool_fee_account: *fee_account_info.key,
             fees,
             swap_curve,
+            bonded_token_a_amount,
+            token_b_mint_and_burn,
+            admin: COption::Some(*admin_info.key),
+            pending_admin: COption::None,
+            paused: false,
+            emergency_mode: false,
+            emergency_circulating_token_b_amount: 0,
+            accrue_fees: false,
+            accrued_token_a_fees: 0,
+            accrued_token_b_fees: 0,
+            sold_token_b_amount: 0,
         });
         SwapVersion::pack(obj, &mut swap_info.data.borrow_mut())?;
-        Ok(())
+        if let Some(curve_params_account) = curve_params_account {
+            SwapVersion::set_extension(
+                &mut swap_info.data.borrow_mut(),
+                SwapExtensionType::CurveParams,
+                curve_params_account.as_ref(),
+            )?;
+        }
+        Self::register_swap(
+            program_id,
+            registry_info,
+            registry_page_info,
+            payer_info,
+            system_program_info,
+            &event.token_a_mint,
+            RegistryEntry {
+                swap: *swap_info.key,
+                bonded_mint: event.token_b_mint,
+                curve_type: event.curve_type,
+                creation_slot: Clock::get()?.slot,
+                creator: *admin_info.key,
+                paused: false,
+            },
+        )?;
+        Ok(event)
     }
 
-    /// Processes an [Swap](enum.Instruction.html).
-    pub fn process_swap(
+    /// Creates the accounts of a swap for `initialize_pda`, before it's set up with `process_initialize`: the swap
+    /// itself at `swap_address`, and its pool mint and token A, token B and pool fee accounts at
+    /// `swap_account_address`. The pool fee account is owned by the admin, the pool mint has the same decimals as
+    /// token A's mint, and the token A and token B accounts are funded from the payer's source accounts. The
+    /// addresses are predictable, so any of them may already hold lamports (see `create_pda_account_instructions`)
+    pub fn process_create_swap_accounts(
         program_id: &Pubkey,
-        amount_in: u64,
-        minimum_amount_out: u64,
+        index: u64,
+        token_a_amount: u64,
+        token_b_amount: u64,
         accounts: &[AccountInfo],
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         let swap_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
-        let user_transfer_authority_info = next_account_info(account_info_iter)?;
-        let source_info = next_account_info(account_info_iter)?;
-        let swap_source_info = next_account_info(account_info_iter)?;
-        let swap_destination_info = next_account_info(account_info_iter)?;
-        let destination_info = next_account_info(account_info_iter)?;
+        let token_a_mint_info = next_account_info(account_info_iter)?;
+        let token_b_mint_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
         let pool_mint_info = next_account_info(account_info_iter)?;
-        let pool_fee_account_info = next_account_info(account_info_iter)?;
+        let fee_account_info = next_account_info(account_info_iter)?;
+        let source_token_a_info = next_account_info(account_info_iter)?;
+        let source_token_b_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
         let token_program_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
 
-        if swap_info.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId);
-        }
-        let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
-
-        if *authority_info.key
-            != Self::authority_id(program_id, swap_info.key, token_swap.bump_seed())?
-        {
+        let (swap_address, swap_bump_seed) = Self::swap_address(
+            program_id,
+            token_a_mint_info.key,
+            token_b_mint_info.key,
+            index,
+        );
+        if *swap_info.key != swap_address {
             return Err(SwapError::InvalidProgramAddress.into());
         }
-        if !(*swap_source_info.key == *token_swap.token_a_account()
-            || *swap_source_info.key == *token_swap.token_b_account())
-        {
-            return Err(SwapError::IncorrectSwapAccount.into());
-        }
-        if !(*swap_destination_info.key == *token_swap.token_a_account()
-            || *swap_destination_info.key == *token_swap.token_b_account())
-        {
-            return Err(SwapError::IncorrectSwapAccount.into());
-        }
-        if *swap_source_info.key == *swap_destination_info.key {
-            return Err(SwapError::InvalidInput.into());
-        }
-        if swap_source_info.key == source_info.key {
-            return Err(SwapError::InvalidInput.into());
-        }
-        if swap_destination_info.key == destination_info.key {
-            return Err(SwapError::InvalidInput.into());
-        }
-        if *pool_mint_info.key != *token_swap.pool_mint() {
-            return Err(SwapError::IncorrectPoolMint.into());
-        }
-        if *pool_fee_account_info.key != *token_swap.pool_fee_account() {
-            return Err(SwapError::IncorrectFeeAccount.into());
-        }
-        if *token_program_info.key != *token_swap.token_program_id() {
-            return Err(SwapError::IncorrectTokenProgramId.into());
-        }
-
-        let source_account =
-            Self::unpack_token_account(swap_source_info, token_swap.token_program_id())?;
-        let dest_account =
-            Self::unpack_token_account(swap_destination_info, token_swap.token_program_id())?;
-        let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
+        Self::create_pda_account(
+            payer_info,
+            swap_info,
+            system_program_info,
+            SwapVersion::LATEST_LEN,
+            program_id,
+            &[
+                Self::SWAP_SEED,
+                token_a_mint_info.key.as_ref(),
+                token_b_mint_info.key.as_ref(),
+                &index.to_le_bytes(),
+                &[swap_bump_seed],
+            ],
+        )?;
 
-        let trade_direction = if *swap_source_info.key == *token_swap.token_a_account() {
-            TradeDirection::AtoB
-        } else {
-            TradeDirection::BtoA
-        };
-        let result = token_swap
-            .swap_curve()
-            .swap(
-                to_u128(amount_in)?,
-                to_u128(source_account.amount)?,
-                to_u128(dest_account.amount)?,
-                trade_direction,
-                token_swap.fees(),
-            )
-            .ok_or(SwapError::ZeroTradingTokens)?;
-        if result.destination_amount_swapped < to_u128(minimum_amount_out)? {
-            return Err(SwapError::ExceededSlippage.into());
+        let token_a_mint = Self::unpack_mint(token_a_mint_info, token_program_info.key)?;
+        let (pool_mint_address, pool_mint_bump_seed) =
+            Self::swap_account_address(program_id, swap_info.key, Self::POOL_MINT_SEED);
+        if *pool_mint_info.key != pool_mint_address {
+            return Err(SwapError::InvalidProgramAddress.into());
         }
+        Self::create_pda_account(
+            payer_info,
+            pool_mint_info,
+            system_program_info,
+            spl_token::state::Mint::LEN,
+            token_program_info.key,
+            &[
+                swap_info.key.as_ref(),
+                Self::POOL_MINT_SEED,
+                &[pool_mint_bump_seed],
+            ],
+        )?;
+        invoke(
+            &spl_token::instruction::initialize_mint(
+                token_program_info.key,
+                pool_mint_info.key,
+                authority_info.key,
+                None,
+                token_a_mint.decimals,
+            )?,
+            &[
+                pool_mint_info.clone(),
+                rent_info.clone(),
+                token_program_info.clone(),
+            ],
+        )?;
 
-        let (swap_token_a_amount, swap_token_b_amount) = match trade_direction {
-            TradeDirection::AtoB => (
-                result.new_swap_source_amount,
-                result.new_swap_destination_amount,
+        for (account_info, seed, mint_info, owner_info) in [
+            (
+                token_a_info,
+                Self::TOKEN_A_SEED,
+                token_a_mint_info,
+                authority_info,
             ),
-            TradeDirection::BtoA => (
-                result.new_swap_destination_amount,
-                result.new_swap_source_amount,
+            (
+                token_b_info,
+                Self::TOKEN_B_SEED,
+                token_b_mint_info,
+                authority_info,
             ),
-        };
-
-        Self::token_transfer(
+            (
+                fee_account_info,
+                Self::POOL_FEE_SEED,
+                pool_mint_info,
+                admin_info,
+            ),
+        ] {
+            let (address, bump_seed) = Self::swap_account_address(program_id, swap_info.key, seed);
+            if *account_info.key != address {
+                return Err(SwapError::InvalidProgramAddress.into());
+            }
+            Self::create_pda_account(
+                payer_info,
+                account_info,
+                system_program_info,
+                spl_token::state::Account::LEN,
+                token_program_info.key,
+                &[swap_info.key.as_ref(), seed, &[bump_seed]],
+            )?;
+            invoke(
+                &spl_token::instruction::initialize_account(
+                    token_program_info.key,
+                    account_info.key,
+                    mint_info.key,
+                    owner_info.key,
+                )?,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    owner_info.clone(),
+                    rent_info.clone(),
+                    token_program_info.clone(),
+                ],
+            )?;
+        }
+
+        for (source_info, destination_info, amount) in [
+            (source_token_a_info, token_a_info, token_a_amount),
+            (source_token_b_info, token_b_info, token_b_amount),
+        ] {
+            if amount > 0 {
+                invoke(
+                    &spl_token::instruction::transfer(
+                        token_program_info.key,
+                        source_info.key,
+                        destination_info.key,
+                        payer_info.key,
+                        &[],
+                        amount,
+                    )?,
+                    &[
+                        source_info.clone(),
+                        destination_info.clone(),
+                        payer_info.clone(),
+                        token_program_info.clone(),
+                    ],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Processes an [Swap](enum.Instruction.html).
+    pub fn process_swap(
+        program_id: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64,
+        accounts: &[AccountInfo],
+    ) -> Result<SwapEvent, ProgramError> {
+        Self::process_swap_with(
+            program_id,
+            accounts,
+            |swap_curve, swap_source_amount, swap_destination_amount, trade_direction, fees| {
+                let amount_in = to_u128(amount_in)?;
+                let result = swap_curve
+                    .swap(
+                        amount_in,
+                        swap_source_amount,
+                        swap_destination_amount,
+                        trade_direction,
+                        fees,
+                    )
+                    .ok_or_else(|| {
+                        swap_curve.swap_error(
+                            amount_in,
+                            swap_source_amount,
+                            swap_destination_amount,
+                            trade_direction,
+                            fees,
+                        )
+                    })?;
+                if result.destination_amount_swapped < to_u128(minimum_amount_out)? {
+                    return Err(SwapError::ExceededSlippage.into());
+                }
+                Ok(result)
+            },
+        )
+    }
+
+    /// Processes a [SwapExactAmountOut](enum.Instruction.html).
+    pub fn process_swap_exact_amount_out(
+        program_id: &Pubkey,
+        amount_out: u64,
+        maximum_amount_in: u64,
+        accounts: &[AccountInfo],
+    ) -> Result<SwapEvent, ProgramError> {
+        Self::process_swap_with(
+            program_id,
+            accounts,
+            |swap_curve, swap_source_amount, swap_destination_amount, trade_direction, fees| {
+                let amount_out = to_u128(amount_out)?;
+                let result = swap_curve
+                    .swap_exact_out(
+                        amount_out,
+                        swap_source_amount,
+                        swap_destination_amount,
+                        trade_direction,
+                        fees,
+                    )
+                    .ok_or_else(|| {
+                        swap_curve.swap_exact_out_error(
+                            amount_out,
+                            swap_source_amount,
+                            swap_destination_amount,
+                            trade_direction,
+                        )
+                    })?;
+                if result.source_amount_swapped > to_u128(maximum_amount_in)? {
+                    return Err(SwapError::ExceededSlippage.into());
+                }
+                Ok(result)
+            },
+        )
+    }
+
+    /// Processes a [QuoteSwap](enum.Instruction.html).
+    /// Calculates the swap against the swap's current balances without moving any tokens and returns the
+    /// packed `SwapQuote` as return data
+    pub fn process_quote_swap(
+        program_id: &Pubkey,
+        amount_in: u64,
+        accounts: &[AccountInfo],
+    ) -> Result<QuoteSwapEvent, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let swap_source_info = next_account_info(account_info_iter)?;
+        let swap_destination_info = next_account_info(account_info_iter)?;
+
+        // the accounts are validated by the `QuoteSwap` constraints
+        let token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        if token_swap.emergency_mode {
+            return Err(SwapError::EmergencyMode.into());
+        }
+
+        let source_account =
+            Self::unpack_token_account(swap_source_info, token_swap.token_program_id())?;
+        let dest_account =
+            Self::unpack_token_account(swap_destination_info, token_swap.token_program_id())?;
+
+        let trade_direction = if *swap_source_info.key == *token_swap.token_a_account() {
+            TradeDirection::AtoB
+        } else {
+            TradeDirection::BtoA
+        };
+        let loaded_swap_curve = Self::load_swap_curve(
+            program_id,
+            swap_info,
+            token_swap.swap_curve(),
+            account_info_iter,
+        )?;
+        let token_b_mint = Self::load_token_b_mint(&token_swap, account_info_iter)?;
+        let (swap_source_amount, swap_destination_amount) = Self::swap_trading_token_amounts(
+            &token_swap,
+            &source_account,
+            &dest_account,
+            token_b_mint.as_ref().map(|(_, token_b_mint)| token_b_mint),
+            trade_direction,
+        )?;
+        let swap_curve = loaded_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| token_swap.swap_curve());
+        let supply_swap_curve = Self::with_token_b_supply(
+            &token_swap,
+            swap_curve,
+            token_b_mint.as_ref().map(|(_, token_b_mint)| token_b_mint),
+        )?;
+        let swap_curve = supply_swap_curve.as_ref().unwrap_or(swap_curve);
+        let amount_in = to_u128(amount_in)?;
+        let quote = swap_curve
+            .quote_swap(
+                amount_in,
+                swap_source_amount,
+                swap_destination_amount,
+                trade_direction,
+                token_swap.fees(),
+            )
+            .ok_or_else(|| {
+                swap_curve.swap_error(
+                    amount_in,
+                    swap_source_amount,
+                    swap_destination_amount,
+                    trade_direction,
+                    token_swap.fees(),
+                )
+            })?;
+
+        let mut quote_data = [0u8; SwapQuote::LEN];
+        quote.pack_into_slice(&mut quote_data);
+        set_return_data(&quote_data);
+
+        Ok(QuoteSwapEvent {
+            swap: *swap_info.key,
+            a_to_b: trade_direction == TradeDirection::AtoB,
+            amount_in: to_u64(quote.swap_result.source_amount_swapped)?,
+            amount_out: to_u64(quote.swap_result.destination_amount_swapped)?,
+            trade_fee: to_u64(quote.swap_result.trade_fee)?,
+            owner_fee: to_u64(quote.swap_result.owner_fee)?,
+            spot_price_before_numerator: quote.spot_price_before.0,
+            spot_price_before_denominator: quote.spot_price_before.1,
+            spot_price_after_numerator: quote.spot_price_after.0,
+            spot_price_after_denominator: quote.spot_price_after.1,
+        })
+    }
+
+    /// Returns the swap's source and destination token amounts to run a swap against, token A is the amount from
+    /// `swap_token_a_amount`, and token B is the mintable amount if `token_b_mint` is passed in for a mint-and-burn swap, or the token B
+    /// account's balance less any fees built up in it otherwise
+    fn swap_trading_token_amounts(
+        token_swap: &SwapV2,
+        source_account: &spl_token::state::Account,
+        dest_account: &spl_token::state::Account,
+        token_b_mint: Option<&spl_token::state::Mint>,
+        trade_direction: TradeDirection,
+    ) -> Result<(u128, u128), SwapError> {
+        let token_a_amount =
+            |token_a_account| to_u128(Self::swap_token_a_amount(token_swap, token_a_account));
+        let token_b_amount = |token_b_account: &spl_token::state::Account| match token_b_mint {
+            Some(token_b_mint) if token_swap.token_b_mint_and_burn => {
+                to_u128(Self::mintable_token_b_amount(token_b_mint))
+            }
+            _ => to_u128(
+                token_b_account
+                    .amount
+                    .checked_sub(token_swap.accrued_token_b_fees)
+                    .ok_or(SwapError::CalculationFailure)?,
+            ),
+        };
+        match trade_direction {
+            TradeDirection::AtoB => Ok((
+                token_a_amount(source_account)?,
+                token_b_amount(dest_account)?,
+            )),
+            TradeDirection::BtoA => Ok((
+                token_b_amount(source_account)?,
+                token_a_amount(dest_account)?,
+            )),
+        }
+    }
+
+    /// Validates the swap accounts, calculates the swap result with `calculate_swap` (given the swap curve, the
+    /// swap's source and destination token amounts, the trade direction and fees) and moves the tokens
+    fn process_swap_with<F>(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        calculate_swap: F,
+    ) -> Result<SwapEvent, ProgramError>
+    where
+        F: FnOnce(
+            &SwapCurve,
+            u128,
+            u128,
+            TradeDirection,
+            &Fees,
+        ) -> Result<SwapResult, ProgramError>,
+    {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let swap_source_info = next_account_info(account_info_iter)?;
+        let swap_destination_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let pool_fee_account_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        // the accounts are validated by the `Swap` constraints
+        let token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        if token_swap.paused {
+            return Err(SwapError::Paused.into());
+        }
+        if token_swap.emergency_mode {
+            return Err(SwapError::EmergencyMode.into());
+        }
+
+        let source_account =
+            Self::unpack_token_account(swap_source_info, token_swap.token_program_id())?;
+        let dest_account =
+            Self::unpack_token_account(swap_destination_info, token_swap.token_program_id())?;
+        let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
+
+        let trade_direction = if *swap_source_info.key == *token_swap.token_a_account() {
+            TradeDirection::AtoB
+        } else {
+            TradeDirection::BtoA
+        };
+        let loaded_swap_curve = Self::load_swap_curve(
+            program_id,
+            swap_info,
+            token_swap.swap_curve(),
+            account_info_iter,
+        )?;
+        let swap_curve = loaded_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| token_swap.swap_curve());
+        let token_b_mint = Self::load_token_b_mint(&token_swap, account_info_iter)?;
+        let (swap_source_amount, swap_destination_amount) = Self::swap_trading_token_amounts(
+            &token_swap,
+            &source_account,
+            &dest_account,
+            token_b_mint.as_ref().map(|(_, token_b_mint)| token_b_mint),
+            trade_direction,
+        )?;
+        let supply_swap_curve = Self::with_token_b_supply(
+            &token_swap,
+            swap_curve,
+            token_b_mint.as_ref().map(|(_, token_b_mint)| token_b_mint),
+        )?;
+        let swap_curve = supply_swap_curve.as_ref().unwrap_or(swap_curve);
+        let result = calculate_swap(
+            swap_curve,
+            swap_source_amount,
+            swap_destination_amount,
+            trade_direction,
+            token_swap.fees(),
+        )?;
+
+        let (swap_token_a_amount, swap_token_b_amount) = match trade_direction {
+            TradeDirection::AtoB => (
+                result.new_swap_source_amount,
+                result.new_swap_destination_amount,
+            ),
+            TradeDirection::BtoA => (
+                result.new_swap_destination_amount,
+                result.new_swap_source_amount,
+            ),
+        };
+        Self::update_bonded_token_a_amount(swap_info, swap_token_a_amount)?;
+
+        let spot_price = |swap_token_a_amount, swap_token_b_amount| {
+            swap_curve
+                .calculator
+                .spot_price(swap_token_a_amount, swap_token_b_amount)
+                .unwrap_or((0, 0))
+        };
+        let (spot_price_before_numerator, spot_price_before_denominator) = match trade_direction {
+            TradeDirection::AtoB => spot_price(swap_source_amount, swap_destination_amount),
+            TradeDirection::BtoA => spot_price(swap_destination_amount, swap_source_amount),
+        };
+        let (spot_price_after_numerator, spot_price_after_denominator) =
+            spot_price(swap_token_a_amount, swap_token_b_amount);
+        let event = SwapEvent {
+            swap: *swap_info.key,
+            a_to_b: trade_direction == TradeDirection::AtoB,
+            amount_in: to_u64(result.source_amount_swapped)?,
+            amount_out: to_u64(result.destination_amount_swapped)?,
+            trade_fee: to_u64(result.trade_fee)?,
+            owner_fee: to_u64(result.owner_fee)?,
+            spot_price_before_numerator,
+            spot_price_before_denominator,
+            spot_price_after_numerator,
+            spot_price_after_denominator,
+            bonded_token_a_amount: to_u64(swap_token_a_amount)?,
+        };
+
+        if swap_curve.calculator.collects_fees_in_trading_tokens() {
+            let total_fees = result
+                .trade_fee
+                .checked_add(result.owner_fee)
+                .ok_or(SwapError::FeeCalculationFailure)?;
+            if total_fees > 0 {
+                let mut fee_amount = total_fees;
+                // fees that build up in the swap don't need a fee account, otherwise the fee token account must
+                // hold the source token and belong to whoever owns the pool fee account, since that's the only fee
+                // recipient recorded in the swap
+                let fee_account_info = if token_swap.accrue_fees {
+                    None
+                } else {
+                    let pool_fee_account = Self::unpack_token_account(
+                        pool_fee_account_info,
+                        token_swap.token_program_id(),
+                    )?;
+                    let fee_account_info = next_account_info(account_info_iter)
+                        .map_err(|_| SwapError::IncorrectFeeAccount)?;
+                    let fee_account = Self::unpack_token_account(
+                        fee_account_info,
+                        token_swap.token_program_id(),
+                    )?;
+                    if fee_account.mint != source_account.mint
+                        || fee_account.owner != pool_fee_account.owner
+                    {
+                        return Err(SwapError::IncorrectFeeAccount.into());
+                    }
+                    Some(fee_account_info)
+                };
+
+                // Allow error to fall through
+                if let Ok(host_fee_account_info) = next_account_info(account_info_iter) {
+                    let host_fee_account = Self::unpack_token_account(
+                        host_fee_account_info,
+                        token_swap.token_program_id(),
+                    )?;
+                    if host_fee_account.mint != source_account.mint {
+                        return Err(SwapError::IncorrectFeeAccount.into());
+                    }
+                    let host_fee = token_swap
+                        .fees()
+                        .host_fee(result.owner_fee)
+                        .ok_or(SwapError::FeeCalculationFailure)?;
+                    if host_fee > 0 {
+                        fee_amount = fee_amount
+                            .checked_sub(host_fee)
+                            .ok_or(SwapError::FeeCalculationFailure)?;
+                        Self::token_transfer(
+                            swap_info.key,
+                            token_program_info.clone(),
+                            source_info.clone(),
+                            host_fee_account_info.clone(),
+                            user_transfer_authority_info.clone(),
+                            token_swap.bump_seed(),
+                            to_u64(host_fee)?,
+                        )?;
+                    }
+                }
+                match fee_account_info {
+                    Some(fee_account_info) => Self::token_transfer(
+                        swap_info.key,
+                        token_program_info.clone(),
+                        source_info.clone(),
+                        fee_account_info.clone(),
+                        user_transfer_authority_info.clone(),
+                        token_swap.bump_seed(),
+                        to_u64(fee_amount)?,
+                    )?,
+                    None => {
+                        Self::accrue_fees(swap_info, trade_direction, fee_amount)?;
+                        Self::token_transfer(
+                            swap_info.key,
+                            token_program_info.clone(),
+                            source_info.clone(),
+                            swap_source_info.clone(),
+                            user_transfer_authority_info.clone(),
+                            token_swap.bump_seed(),
+                            to_u64(fee_amount)?,
+                        )?
+                    }
+                }
+            }
+
+            let source_amount = to_u64(
+                result
+                    .source_amount_swapped
+                    .checked_sub(total_fees)
+                    .ok_or(SwapError::FeeCalculationFailure)?,
+            )?;
+            // token B fees don't go into the curve, they're paid out or built up in the swap and count as
+            // circulating once emergency mode is enabled
+            Self::update_sold_token_b_amount(
+                swap_info,
+                trade_direction,
+                match trade_direction {
+                    TradeDirection::AtoB => to_u64(result.destination_amount_swapped)?,
+                    TradeDirection::BtoA => source_amount,
+                },
+            )?;
+            match token_b_mint {
+                // mint-and-burn swaps burn the token B that's sold instead of holding on to it
+                Some((token_b_mint_info, _))
+                    if token_swap.token_b_mint_and_burn
+                        && trade_direction == TradeDirection::BtoA =>
+                {
+                    Self::token_burn(
+                        swap_info.key,
+                        token_program_info.clone(),
+                        source_info.clone(),
+                        token_b_mint_info.clone(),
+                        user_transfer_authority_info.clone(),
+                        token_swap.bump_seed(),
+                        source_amount,
+                    )?
+                }
+                _ => Self::token_transfer(
+                    swap_info.key,
+                    token_program_info.clone(),
+                    source_info.clone(),
+                    swap_source_info.clone(),
+                    user_transfer_authority_info.clone(),
+                    token_swap.bump_seed(),
+                    source_amount,
+                )?,
+            }
+            match token_b_mint {
+                // and mint the token B that's bought instead of paying it out of the token B account
+                Some((token_b_mint_info, _))
+                    if token_swap.token_b_mint_and_burn
+                        && trade_direction == TradeDirection::AtoB =>
+                {
+                    Self::token_mint_to(
+                        swap_info.key,
+                        token_program_info.clone(),
+                        token_b_mint_info.clone(),
+                        destination_info.clone(),
+                        authority_info.clone(),
+                        token_swap.bump_seed(),
+                        to_u64(result.destination_amount_swapped)?,
+                    )?
+                }
+                _ => Self::token_transfer(
+                    swap_info.key,
+                    token_program_info.clone(),
+                    swap_destination_info.clone(),
+                    destination_info.clone(),
+                    authority_info.clone(),
+                    token_swap.bump_seed(),
+                    to_u64(result.destination_amount_swapped)?,
+                )?,
+            }
+
+            return Ok(event);
+        }
+
+        // the optional host fee account gets a cut of the owner fee, which these curves pay out in pool tokens, so
+        // it has to be a pool token account even if this swap doesn't end up paying it anything
+        let host_fee_account_info = next_account_info(account_info_iter).ok();
+        if let Some(host_fee_account_info) = host_fee_account_info {
+            let host_fee_account =
+                Self::unpack_token_account(host_fee_account_info, token_swap.token_program_id())?;
+            if *pool_mint_info.key != host_fee_account.mint {
+                return Err(SwapError::IncorrectPoolMint.into());
+            }
+        }
+
+        Self::token_transfer(
             swap_info.key,
             token_program_info.clone(),
             source_info.clone(),
@@ -423,8 +1580,7 @@ impl Processor {
             to_u64(result.source_amount_swapped)?,
         )?;
 
-        let mut pool_token_amount = token_swap
-            .swap_curve()
+        let mut pool_token_amount = swap_curve
             .withdraw_single_token_type_exact_out(
                 result.owner_fee,
                 swap_token_a_amount,
@@ -436,15 +1592,7 @@ impl Processor {
             .ok_or(SwapError::FeeCalculationFailure)?;
 
         if pool_token_amount > 0 {
-            // Allow error to fall through
-            if let Ok(host_fee_account_info) = next_account_info(account_info_iter) {
-                let host_fee_account = Self::unpack_token_account(
-                    host_fee_account_info,
-                    token_swap.token_program_id(),
-                )?;
-                if *pool_mint_info.key != host_fee_account.mint {
-                    return Err(SwapError::IncorrectPoolMint.into());
-                }
+            if let Some(host_fee_account_info) = host_fee_account_info {
                 let host_fee = token_swap
                     .fees()
                     .host_fee(pool_token_amount)
@@ -485,17 +1633,16 @@ impl Processor {
             to_u64(result.destination_amount_swapped)?,
         )?;
 
-        Ok(())
+        Ok(event)
     }
 
     /// Processes an [DepositAllTokenTypes](enum.Instruction.html).
     pub fn process_deposit_all_token_types(
-        program_id: &Pubkey,
         pool_token_amount: u64,
         maximum_token_a_amount: u64,
         maximum_token_b_amount: u64,
         accounts: &[AccountInfo],
-    ) -> ProgramResult {
+    ) -> Result<DepositEvent, ProgramError> {
         let account_info_iter = &mut accounts.iter();
         let swap_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
@@ -508,28 +1655,23 @@ impl Processor {
         let dest_info = next_account_info(account_info_iter)?;
         let token_program_info = next_account_info(account_info_iter)?;
 
-        let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        let token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        if token_swap.paused {
+            return Err(SwapError::Paused.into());
+        }
+        if token_swap.emergency_mode {
+            return Err(SwapError::EmergencyMode.into());
+        }
         let calculator = &token_swap.swap_curve().calculator;
         if !calculator.allows_deposits() {
             return Err(SwapError::UnsupportedCurveOperation.into());
         }
-        Self::check_accounts(
-            token_swap.as_ref(),
-            program_id,
-            swap_info,
-            authority_info,
-            token_a_info,
-            token_b_info,
-            pool_mint_info,
-            token_program_info,
-            Some(source_a_info),
-            Some(source_b_info),
-            None,
-        )?;
+        // the accounts are validated by the `DepositAllTokenTypes` constraints
 
         let token_a = Self::unpack_token_account(token_a_info, token_swap.token_program_id())?;
         let token_b = Self::unpack_token_account(token_b_info, token_swap.token_program_id())?;
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
+        let bonded_token_a_amount = to_u128(Self::swap_token_a_amount(&token_swap, &token_a))?;
         let current_pool_mint_supply = to_u128(pool_mint.supply)?;
         let (pool_token_amount, pool_mint_supply) = if current_pool_mint_supply > 0 {
             (to_u128(pool_token_amount)?, current_pool_mint_supply)
@@ -541,7 +1683,7 @@ impl Processor {
             .pool_tokens_to_trading_tokens(
                 pool_token_amount,
                 pool_mint_supply,
-                to_u128(token_a.amount)?,
+                bonded_token_a_amount,
                 to_u128(token_b.amount)?,
                 RoundDirection::Ceiling,
             )
@@ -563,6 +1705,10 @@ impl Processor {
 
         let pool_token_amount = to_u64(pool_token_amount)?;
 
+        let new_bonded_token_a_amount = bonded_token_a_amount
+            .checked_add(results.token_a_amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        Self::update_bonded_token_a_amount(swap_info, new_bonded_token_a_amount)?;
         Self::token_transfer(
             swap_info.key,
             token_program_info.clone(),
@@ -591,17 +1737,22 @@ impl Processor {
             pool_token_amount,
         )?;
 
-        Ok(())
+        Ok(DepositEvent {
+            swap: *swap_info.key,
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount,
+            bonded_token_a_amount: to_u64(new_bonded_token_a_amount)?,
+        })
     }
 
     /// Processes an [WithdrawAllTokenTypes](enum.Instruction.html).
     pub fn process_withdraw_all_token_types(
-        program_id: &Pubkey,
         pool_token_amount: u64,
         minimum_token_a_amount: u64,
         minimum_token_b_amount: u64,
         accounts: &[AccountInfo],
-    ) -> ProgramResult {
+    ) -> Result<WithdrawEvent, ProgramError> {
         let account_info_iter = &mut accounts.iter();
         let swap_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
@@ -615,24 +1766,19 @@ impl Processor {
         let pool_fee_account_info = next_account_info(account_info_iter)?;
         let token_program_info = next_account_info(account_info_iter)?;
 
-        let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
-        Self::check_accounts(
-            token_swap.as_ref(),
-            program_id,
-            swap_info,
-            authority_info,
-            token_a_info,
-            token_b_info,
-            pool_mint_info,
-            token_program_info,
-            Some(dest_token_a_info),
-            Some(dest_token_b_info),
-            Some(pool_fee_account_info),
-        )?;
+        let token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        if token_swap.paused {
+            return Err(SwapError::Paused.into());
+        }
+        if token_swap.emergency_mode {
+            return Err(SwapError::EmergencyMode.into());
+        }
+        // the accounts are validated by the `WithdrawAllTokenTypes` constraints
 
         let token_a = Self::unpack_token_account(token_a_info, token_swap.token_program_id())?;
         let token_b = Self::unpack_token_account(token_b_info, token_swap.token_program_id())?;
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
+        let bonded_token_a_amount = Self::swap_token_a_amount(&token_swap, &token_a);
 
         let calculator = &token_swap.swap_curve().calculator;
 
@@ -653,17 +1799,17 @@ impl Processor {
             .pool_tokens_to_trading_tokens(
                 pool_token_amount,
                 to_u128(pool_mint.supply)?,
-                to_u128(token_a.amount)?,
+                to_u128(bonded_token_a_amount)?,
                 to_u128(token_b.amount)?,
                 RoundDirection::Floor,
             )
             .ok_or(SwapError::ZeroTradingTokens)?;
         let token_a_amount = to_u64(results.token_a_amount)?;
-        let token_a_amount = std::cmp::min(token_a.amount, token_a_amount);
+        let token_a_amount = std::cmp::min(bonded_token_a_amount, token_a_amount);
         if token_a_amount < minimum_token_a_amount {
             return Err(SwapError::ExceededSlippage.into());
         }
-        if token_a_amount == 0 && token_a.amount != 0 {
+        if token_a_amount == 0 && bonded_token_a_amount != 0 {
             return Err(SwapError::ZeroTradingTokens.into());
         }
         let token_b_amount = to_u64(results.token_b_amount)?;
@@ -675,6 +1821,10 @@ impl Processor {
             return Err(SwapError::ZeroTradingTokens.into());
         }
 
+        let new_bonded_token_a_amount = bonded_token_a_amount
+            .checked_sub(token_a_amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        Self::update_bonded_token_a_amount(swap_info, to_u128(new_bonded_token_a_amount)?)?;
         if withdraw_fee > 0 {
             Self::token_transfer(
                 swap_info.key,
@@ -718,16 +1868,26 @@ impl Processor {
                 token_b_amount,
             )?;
         }
-        Ok(())
+        Ok(WithdrawEvent {
+            swap: *swap_info.key,
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount: to_u64(
+                pool_token_amount
+                    .checked_add(withdraw_fee)
+                    .ok_or(SwapError::CalculationFailure)?,
+            )?,
+            withdraw_fee: to_u64(withdraw_fee)?,
+            bonded_token_a_amount: new_bonded_token_a_amount,
+        })
     }
 
     /// Processes DepositSingleTokenTypeExactAmountIn
     pub fn process_deposit_single_token_type_exact_amount_in(
-        program_id: &Pubkey,
         source_token_amount: u64,
         minimum_pool_token_amount: u64,
         accounts: &[AccountInfo],
-    ) -> ProgramResult {
+    ) -> Result<DepositEvent, ProgramError> {
         let account_info_iter = &mut accounts.iter();
         let swap_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
@@ -739,7 +1899,13 @@ impl Processor {
         let destination_info = next_account_info(account_info_iter)?;
         let token_program_info = next_account_info(account_info_iter)?;
 
-        let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        let token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        if token_swap.paused {
+            return Err(SwapError::Paused.into());
+        }
+        if token_swap.emergency_mode {
+            return Err(SwapError::EmergencyMode.into());
+        }
         let source_account =
             Self::unpack_token_account(source_info, token_swap.token_program_id())?;
         let swap_token_a =
@@ -754,34 +1920,17 @@ impl Processor {
         } else {
             return Err(SwapError::IncorrectSwapAccount.into());
         };
-
-        let (source_a_info, source_b_info) = match trade_direction {
-            TradeDirection::AtoB => (Some(source_info), None),
-            TradeDirection::BtoA => (None, Some(source_info)),
-        };
-
-        Self::check_accounts(
-            token_swap.as_ref(),
-            program_id,
-            swap_info,
-            authority_info,
-            swap_token_a_info,
-            swap_token_b_info,
-            pool_mint_info,
-            token_program_info,
-            source_a_info,
-            source_b_info,
-            None,
-        )?;
+        // the rest of the accounts are validated by the `DepositSingleTokenTypeExactAmountIn` constraints
 
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
         let pool_mint_supply = to_u128(pool_mint.supply)?;
+        let bonded_token_a_amount = to_u128(Self::swap_token_a_amount(&token_swap, &swap_token_a))?;
         let pool_token_amount = if pool_mint_supply > 0 {
             token_swap
                 .swap_curve()
                 .deposit_single_token_type(
                     to_u128(source_token_amount)?,
-                    to_u128(swap_token_a.amount)?,
+                    bonded_token_a_amount,
                     to_u128(swap_token_b.amount)?,
                     pool_mint_supply,
                     trade_direction,
@@ -800,8 +1949,16 @@ impl Processor {
             return Err(SwapError::ZeroTradingTokens.into());
         }
 
+        let (token_a_amount, token_b_amount) = match trade_direction {
+            TradeDirection::AtoB => (source_token_amount, 0),
+            TradeDirection::BtoA => (0, source_token_amount),
+        };
+        let new_bonded_token_a_amount = bonded_token_a_amount
+            .checked_add(to_u128(token_a_amount)?)
+            .ok_or(SwapError::CalculationFailure)?;
         match trade_direction {
             TradeDirection::AtoB => {
+                Self::update_bonded_token_a_amount(swap_info, new_bonded_token_a_amount)?;
                 Self::token_transfer(
                     swap_info.key,
                     token_program_info.clone(),
@@ -834,16 +1991,21 @@ impl Processor {
             pool_token_amount,
         )?;
 
-        Ok(())
+        Ok(DepositEvent {
+            swap: *swap_info.key,
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount,
+            bonded_token_a_amount: to_u64(new_bonded_token_a_amount)?,
+        })
     }
 
     /// Processes a [WithdrawSingleTokenTypeExactAmountOut](enum.Instruction.html).
     pub fn process_withdraw_single_token_type_exact_amount_out(
-        program_id: &Pubkey,
         destination_token_amount: u64,
         maximum_pool_token_amount: u64,
         accounts: &[AccountInfo],
-    ) -> ProgramResult {
+    ) -> Result<WithdrawEvent, ProgramError> {
         let account_info_iter = &mut accounts.iter();
         let swap_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
@@ -856,7 +2018,13 @@ impl Processor {
         let pool_fee_account_info = next_account_info(account_info_iter)?;
         let token_program_info = next_account_info(account_info_iter)?;
 
-        let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        let token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        if token_swap.paused {
+            return Err(SwapError::Paused.into());
+        }
+        if token_swap.emergency_mode {
+            return Err(SwapError::EmergencyMode.into());
+        }
         let destination_account =
             Self::unpack_token_account(destination_info, token_swap.token_program_id())?;
         let swap_token_a =
@@ -871,28 +2039,11 @@ impl Processor {
         } else {
             return Err(SwapError::IncorrectSwapAccount.into());
         };
-
-        let (destination_a_info, destination_b_info) = match trade_direction {
-            TradeDirection::AtoB => (Some(destination_info), None),
-            TradeDirection::BtoA => (None, Some(destination_info)),
-        };
-        Self::check_accounts(
-            token_swap.as_ref(),
-            program_id,
-            swap_info,
-            authority_info,
-            swap_token_a_info,
-            swap_token_b_info,
-            pool_mint_info,
-            token_program_info,
-            destination_a_info,
-            destination_b_info,
-            Some(pool_fee_account_info),
-        )?;
+        // the rest of the accounts are validated by the `WithdrawSingleTokenTypeExactAmountOut` constraints
 
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
         let pool_mint_supply = to_u128(pool_mint.supply)?;
-        let swap_token_a_amount = to_u128(swap_token_a.amount)?;
+        let swap_token_a_amount = to_u128(Self::swap_token_a_amount(&token_swap, &swap_token_a))?;
         let swap_token_b_amount = to_u128(swap_token_b.amount)?;
 
         let burn_pool_token_amount = token_swap
@@ -948,8 +2099,16 @@ impl Processor {
             to_u64(burn_pool_token_amount)?,
         )?;
 
+        let (token_a_amount, token_b_amount) = match trade_direction {
+            TradeDirection::AtoB => (destination_token_amount, 0),
+            TradeDirection::BtoA => (0, destination_token_amount),
+        };
+        let new_bonded_token_a_amount = swap_token_a_amount
+            .checked_sub(to_u128(token_a_amount)?)
+            .ok_or(SwapError::CalculationFailure)?;
         match trade_direction {
             TradeDirection::AtoB => {
+                Self::update_bonded_token_a_amount(swap_info, new_bonded_token_a_amount)?;
                 Self::token_transfer(
                     swap_info.key,
                     token_program_info.clone(),
@@ -973,14 +2132,743 @@ impl Processor {
             }
         }
 
+        Ok(WithdrawEvent {
+            swap: *swap_info.key,
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount: to_u64(pool_token_amount)?,
+            withdraw_fee: to_u64(withdraw_fee)?,
+            bonded_token_a_amount: to_u64(new_bonded_token_a_amount)?,
+        })
+    }
+
+    /// Checks that `admin_info` signed and is `admin`
+    fn check_admin(admin: &COption<Pubkey>, admin_info: &AccountInfo) -> ProgramResult {
+        if COption::Some(*admin_info.key) != *admin {
+            return Err(SwapError::InvalidAdmin.into());
+        }
+        if !admin_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
         Ok(())
     }
-}
 
-impl PrintProgramError for SwapError {
-    fn print<E>(&self)
-    where
-        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
+    /// Processes a [SetAdmin](enum.Instruction.html).
+    /// Proposes `new_admin` as the swap's admin, who has to accept it with `accept_admin` before taking over (so
+    /// the admin can't be handed to an address nobody controls)
+    pub fn process_set_admin(
+        new_admin: Pubkey,
+        accounts: &[AccountInfo],
+    ) -> Result<AdminEvent, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        Self::check_admin(&token_swap.admin, admin_info)?;
+
+        token_swap.pending_admin = COption::Some(new_admin);
+        let event = AdminEvent {
+            swap: *swap_info.key,
+            admin: token_swap.admin.into(),
+            pending_admin: token_swap.pending_admin.into(),
+        };
+        SwapVersion::pack(
+            SwapVersion::SwapV2(token_swap),
+            &mut swap_info.data.borrow_mut(),
+        )?;
+        Ok(event)
+    }
+
+    /// Processes an [AcceptAdmin](enum.Instruction.html).
+    /// The pending admin proposed with `set_admin` becomes the swap's admin
+    pub fn process_accept_admin(accounts: &[AccountInfo]) -> Result<AdminEvent, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let pending_admin_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        Self::check_admin(&token_swap.pending_admin, pending_admin_info)?;
+
+        token_swap.admin = COption::Some(*pending_admin_info.key);
+        token_swap.pending_admin = COption::None;
+        let event = AdminEvent {
+            swap: *swap_info.key,
+            admin: token_swap.admin.into(),
+            pending_admin: token_swap.pending_admin.into(),
+        };
+        SwapVersion::pack(
+            SwapVersion::SwapV2(token_swap),
+            &mut swap_info.data.borrow_mut(),
+        )?;
+        Ok(event)
+    }
+
+    /// Processes a [RenounceAdmin](enum.Instruction.html).
+    /// Leaves the swap without an admin (cancelling any pending admin), nobody can manage it after this
+    pub fn process_renounce_admin(accounts: &[AccountInfo]) -> Result<AdminEvent, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        Self::check_admin(&token_swap.admin, admin_info)?;
+
+        token_swap.admin = COption::None;
+        token_swap.pending_admin = COption::None;
+        let event = AdminEvent {
+            swap: *swap_info.key,
+            admin: token_swap.admin.into(),
+            pending_admin: token_swap.pending_admin.into(),
+        };
+        SwapVersion::pack(
+            SwapVersion::SwapV2(token_swap),
+            &mut swap_info.data.borrow_mut(),
+        )?;
+        Ok(event)
+    }
+
+    /// Sets the swap's paused flag, only the admin can pause or resume. The paused flag of the swap's entry in the
+    /// registry is only a mirror for listing swaps, so it's updated if the registry page is passed and left alone
+    /// otherwise, pausing never depends on the registry
+    fn set_paused(
+        program_id: &Pubkey,
+        paused: bool,
+        accounts: &[AccountInfo],
+    ) -> Result<PauseEvent, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+        let registry_page_info = account_info_iter.next();
+
+        let mut token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        Self::check_admin(&token_swap.admin, admin_info)?;
+
+        if let Some(registry_page_info) = registry_page_info {
+            let mut registry_page = Self::unpack_registry_page(
+                program_id,
+                registry_page_info,
+                &token_swap.token_a_mint,
+            )?;
+            registry_page
+                .entries
+                .iter_mut()
+                .find(|entry| entry.swap == *swap_info.key)
+                .ok_or(SwapError::IncorrectRegistryAccount)?
+                .paused = paused;
+            RegistryPage::pack(registry_page, &mut registry_page_info.data.borrow_mut())?;
+        }
+
+        token_swap.paused = paused;
+        SwapVersion::pack(
+            SwapVersion::SwapV2(token_swap),
+            &mut swap_info.data.borrow_mut(),
+        )?;
+        Ok(PauseEvent {
+            swap: *swap_info.key,
+            paused,
+        })
+    }
+
+    /// Processes a [Pause](enum.Instruction.html).
+    /// Swaps, deposits and withdrawals fail with `SwapError::Paused` until the swap is resumed, quotes still work
+    pub fn process_pause(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> Result<PauseEvent, ProgramError> {
+        Self::set_paused(program_id, true, accounts)
+    }
+
+    /// Processes a [Resume](enum.Instruction.html).
+    pub fn process_resume(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> Result<PauseEvent, ProgramError> {
+        Self::set_paused(program_id, false, accounts)
+    }
+
+    /// Processes a [SetAccrueFees](enum.Instruction.html).
+    /// Sets whether the swap's trading token fees build up in the swap (see `SwapV2::accrue_fees`) or are sent to a
+    /// fee account on every swap, only the admin can change it. Fees that already built up stay claimable
+    pub fn process_set_accrue_fees(
+        accrue_fees: bool,
+        accounts: &[AccountInfo],
+    ) -> Result<AccrueFeesEvent, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        Self::check_admin(&token_swap.admin, admin_info)?;
+        if !token_swap
+            .swap_curve
+            .calculator
+            .collects_fees_in_trading_tokens()
+        {
+            return Err(SwapError::UnsupportedCurveOperation.into());
+        }
+
+        token_swap.accrue_fees = accrue_fees;
+        SwapVersion::pack(
+            SwapVersion::SwapV2(token_swap),
+            &mut swap_info.data.borrow_mut(),
+        )?;
+        Ok(AccrueFeesEvent {
+            swap: *swap_info.key,
+            accrue_fees,
+        })
+    }
+
+    /// Processes a [SweepSurplus](enum.Instruction.html).
+    /// Transfers any token A in the swap above the bonded amount (see `SwapV2::bonded_token_a_amount`) and the fees
+    /// built up in it to the destination, only the owner of the pool fee account can sweep. The AMM curves don't
+    /// track a bonded amount, so they have no surplus to sweep
+    pub fn process_sweep_surplus(
+        accounts: &[AccountInfo],
+    ) -> Result<SweepSurplusEvent, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let _owner_info = next_account_info(account_info_iter)?;
+        let _pool_fee_account_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        // the accounts are validated by the `SweepSurplus` constraints
+        let token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        if !token_swap
+            .swap_curve
+            .calculator
+            .collects_fees_in_trading_tokens()
+        {
+            return Err(SwapError::UnsupportedCurveOperation.into());
+        }
+
+        let token_a = Self::unpack_token_account(token_a_info, token_swap.token_program_id())?;
+        let surplus = token_a
+            .amount
+            .checked_sub(token_swap.bonded_token_a_amount)
+            .and_then(|amount| amount.checked_sub(token_swap.accrued_token_a_fees))
+            .ok_or(SwapError::CalculationFailure)?;
+        if surplus > 0 {
+            Self::token_transfer(
+                swap_info.key,
+                token_program_info.clone(),
+                token_a_info.clone(),
+                destination_info.clone(),
+                authority_info.clone(),
+                token_swap.bump_seed(),
+                surplus,
+            )?;
+        }
+
+        Ok(SweepSurplusEvent {
+            swap: *swap_info.key,
+            token_a_amount: surplus,
+        })
+    }
+
+    /// Processes a [ClaimFees](enum.Instruction.html).
+    /// Transfers the fees built up in the swap's token A and token B accounts (see `SwapV2::accrue_fees`) to the
+    /// destinations, only the owner of the pool fee account can claim them. Works in emergency mode too, so the
+    /// token accounts can be emptied before the swap is closed
+    pub fn process_claim_fees(accounts: &[AccountInfo]) -> Result<ClaimFeesEvent, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let _owner_info = next_account_info(account_info_iter)?;
+        let _pool_fee_account_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let destination_a_info = next_account_info(account_info_iter)?;
+        let destination_b_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        // the accounts are validated by the `ClaimFees` constraints
+        let mut token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        let event = ClaimFeesEvent {
+            swap: *swap_info.key,
+            token_a_amount: token_swap.accrued_token_a_fees,
+            token_b_amount: token_swap.accrued_token_b_fees,
+        };
+        let bump_seed = token_swap.bump_seed();
+        token_swap.accrued_token_a_fees = 0;
+        token_swap.accrued_token_b_fees = 0;
+        SwapVersion::pack(
+            SwapVersion::SwapV2(token_swap),
+            &mut swap_info.data.borrow_mut(),
+        )?;
+
+        for (token_info, destination_info, amount) in [
+            (token_a_info, destination_a_info, event.token_a_amount),
+            (token_b_info, destination_b_info, event.token_b_amount),
+        ] {
+            if amount > 0 {
+                Self::token_transfer(
+                    swap_info.key,
+                    token_program_info.clone(),
+                    token_info.clone(),
+                    destination_info.clone(),
+                    authority_info.clone(),
+                    bump_seed,
+                    amount,
+                )?;
+            }
+        }
+
+        Ok(event)
+    }
+
+    /// Processes a [MigrateSwapV1ToV2](enum.Instruction.html).
+    /// Reallocs a SwapV1 swap account to the latest size (the payer covers any extra rent) and rewrites it in place
+    /// as a SwapV2. Swaps didn't track the bonded token A amount or have an admin before SwapV2, so the bonded
+    /// amount of curves that track one starts at the token A account's balance (which is what SwapV1 curves ran
+    /// against) and the owner of the pool fee account becomes the admin. They didn't track the token B sold either,
+    /// so it starts at token B's supply minus the token B account's balance. The swap is also recorded in the
+    /// registry of its collateral mint, which didn't exist before SwapV2. Anyone can migrate a swap since nothing
+    /// else changes
+    pub fn process_migrate_swap_v1_to_v2(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> Result<MigrateSwapEvent, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let pool_fee_account_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let registry_info = next_account_info(account_info_iter)?;
+        let registry_page_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let token_b_mint_info = next_account_info(account_info_iter)?;
+
+        let token_swap = match SwapVersion::unpack_versioned(&swap_info.data.borrow())? {
+            SwapVersion::SwapV1(token_swap) => token_swap,
+            SwapVersion::SwapV2(_) => return Err(SwapError::IncorrectSwapVersion.into()),
+        };
+        if *token_a_info.key != token_swap.token_a {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *pool_fee_account_info.key != token_swap.pool_fee_account {
+            return Err(SwapError::IncorrectFeeAccount.into());
+        }
+        if *token_b_info.key != token_swap.token_b {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *token_b_mint_info.key != token_swap.token_b_mint {
+            return Err(SwapError::IncorrectTokenBMint.into());
+        }
+        let token_a = Self::unpack_token_account(token_a_info, &token_swap.token_program_id)?;
+        let pool_fee_account =
+            Self::unpack_token_account(pool_fee_account_info, &token_swap.token_program_id)?;
+        let token_b = Self::unpack_token_account(token_b_info, &token_swap.token_program_id)?;
+        let token_b_mint = Self::unpack_mint(token_b_mint_info, &token_swap.token_program_id)?;
+
+        let rent_exempt_lamports = Rent::get()?.minimum_balance(SwapVersion::LATEST_LEN);
+        let missing_lamports = rent_exempt_lamports.saturating_sub(swap_info.lamports());
+        if missing_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(payer_info.key, swap_info.key, missing_lamports),
+                &[
+                    payer_info.clone(),
+                    swap_info.clone(),
+                    system_program_info.clone(),
+                ],
+            )?;
+        }
+        swap_info.realloc(SwapVersion::LATEST_LEN, true)?;
+
+        let (bonded_token_a_amount, sold_token_b_amount) = if token_swap
+            .swap_curve
+            .calculator
+            .collects_fees_in_trading_tokens()
+        {
+            (
+                token_a.amount,
+                token_b_mint
+                    .supply
+                    .checked_sub(token_b.amount)
+                    .ok_or(SwapError::CalculationFailure)?,
+            )
+        } else {
+            (0, 0)
+        };
+
+        // swaps created before SwapV2 were never recorded in the registry
+        let registry_entry = RegistryEntry {
+            swap: *swap_info.key,
+            bonded_mint: token_swap.token_b_mint,
+            curve_type: token_swap.swap_curve.curve_type as u8,
+            creation_slot: Clock::get()?.slot,
+            creator: pool_fee_account.owner,
+            paused: false,
+        };
+        let obj = SwapVersion::SwapV2(SwapV2 {
+            is_initialized: token_swap.is_initialized,
+            bump_seed: token_swap.bump_seed,
+            token_program_id: token_swap.token_program_id,
+            token_a: token_swap.token_a,
+            token_b: token_swap.token_b,
+            pool_mint: token_swap.pool_mint,
+            token_a_mint: token_swap.token_a_mint,
+            token_b_mint: token_swap.token_b_mint,
+            pool_fee_account: token_swap.pool_fee_account,
+            fees: token_swap.fees,
+            swap_curve: token_swap.swap_curve,
+            bonded_token_a_amount,
+            token_b_mint_and_burn: false,
+            admin: COption::Some(pool_fee_account.owner),
+            pending_admin: COption::None,
+            paused: false,
+            emergency_mode: false,
+            emergency_circulating_token_b_amount: 0,
+            accrue_fees: false,
+            accrued_token_a_fees: 0,
+            accrued_token_b_fees: 0,
+            sold_token_b_amount,
+        });
+        SwapVersion::pack(obj, &mut swap_info.data.borrow_mut())?;
+        Self::register_swap(
+            program_id,
+            registry_info,
+            registry_page_info,
+            payer_info,
+            system_program_info,
+            &token_swap.token_a_mint,
+            registry_entry,
+        )?;
+
+        Ok(MigrateSwapEvent {
+            swap: *swap_info.key,
+            bonded_token_a_amount,
+            sold_token_b_amount,
+        })
+    }
+
+    /// Processes an [EnableEmergencyMode](enum.Instruction.html).
+    /// Permanently disables swaps, deposits and withdrawals (there's no way back, unlike `pause`) and records how
+    /// much token B the curve put in circulation, so holders can redeem it for their share of the bonded token A
+    /// with `emergency_redeem`. Only the admin can enable it, and only on bonding curves
+    pub fn process_enable_emergency_mode(
+        accounts: &[AccountInfo],
+    ) -> Result<EmergencyModeEvent, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        Self::check_admin(&token_swap.admin, admin_info)?;
+        if token_swap.emergency_mode {
+            return Err(SwapError::EmergencyMode.into());
+        }
+        if !token_swap
+            .swap_curve
+            .calculator
+            .collects_fees_in_trading_tokens()
+        {
+            return Err(SwapError::UnsupportedCurveOperation.into());
+        }
+
+        token_swap.emergency_mode = true;
+        // only the token B sold by the curve is backed by the bonded token A, token B's supply can include token B
+        // minted outside the curve. Token B fees built up in the swap belong to the owner of the pool fee account,
+        // so they count as circulating
+        token_swap.emergency_circulating_token_b_amount = token_swap
+            .sold_token_b_amount
+            .checked_add(token_swap.accrued_token_b_fees)
+            .ok_or(SwapError::CalculationFailure)?;
+        let event = EmergencyModeEvent {
+            swap: *swap_info.key,
+            circulating_token_b_amount: token_swap.emergency_circulating_token_b_amount,
+            bonded_token_a_amount: token_swap.bonded_token_a_amount,
+        };
+        SwapVersion::pack(
+            SwapVersion::SwapV2(token_swap),
+            &mut swap_info.data.borrow_mut(),
+        )?;
+        Ok(event)
+    }
+
+    /// Processes a [RecoverTokenB](enum.Instruction.html).
+    /// Transfers the swap's unsold token B inventory (leaving any fees built up in it to be claimed) to the
+    /// destination once the swap is in emergency mode, only the admin can recover it. The recovered token B wasn't
+    /// circulating when emergency mode was enabled, so it isn't backed by any of the bonded token A and redeeming it
+    /// would eat into the other holders' share
+    pub fn process_recover_token_b(
+        accounts: &[AccountInfo],
+    ) -> Result<RecoverTokenBEvent, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        // the accounts are validated by the `RecoverTokenB` constraints
+        let token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        Self::check_admin(&token_swap.admin, admin_info)?;
+        if !token_swap.emergency_mode {
+            return Err(SwapError::NotEmergencyMode.into());
+        }
+
+        let token_b = Self::unpack_token_account(token_b_info, token_swap.token_program_id())?;
+        let inventory = token_b
+            .amount
+            .checked_sub(token_swap.accrued_token_b_fees)
+            .ok_or(SwapError::CalculationFailure)?;
+        if inventory > 0 {
+            Self::token_transfer(
+                swap_info.key,
+                token_program_info.clone(),
+                token_b_info.clone(),
+                destination_info.clone(),
+                authority_info.clone(),
+                token_swap.bump_seed(),
+                inventory,
+            )?;
+        }
+
+        Ok(RecoverTokenBEvent {
+            swap: *swap_info.key,
+            token_b_amount: inventory,
+        })
+    }
+
+    /// Returns the bonded token A paid out for redeeming `token_b_amount` in emergency mode, rounded down so the
+    /// swap never pays out more than its bonded token A
+    fn emergency_redeem_token_a_amount(
+        token_swap: &SwapV2,
+        token_b_amount: u64,
+    ) -> Result<u64, SwapError> {
+        to_u64(
+            to_u128(token_swap.bonded_token_a_amount)?
+                .checked_mul(to_u128(token_b_amount)?)
+                .ok_or(SwapError::CalculationFailure)?
+                .checked_div(to_u128(token_swap.emergency_circulating_token_b_amount)?)
+                .ok_or(SwapError::CalculationFailure)?,
+        )
+    }
+
+    /// Processes an [EmergencyRedeem](enum.Instruction.html).
+    /// Burns `token_b_amount` of the user's token B and pays out the same share of the bonded token A, i.e.
+    /// `bonded_token_a_amount * token_b_amount / emergency_circulating_token_b_amount`, so every holder gets the
+    /// same price no matter when they redeem
+    pub fn process_emergency_redeem(
+        token_b_amount: u64,
+        accounts: &[AccountInfo],
+    ) -> Result<EmergencyRedeemEvent, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let token_b_mint_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        // the accounts are validated by the `EmergencyRedeem` constraints
+        let mut token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        if !token_swap.emergency_mode {
+            return Err(SwapError::NotEmergencyMode.into());
+        }
+
+        let token_a_amount = Self::emergency_redeem_token_a_amount(&token_swap, token_b_amount)?;
+        if token_a_amount == 0 {
+            return Err(SwapError::AmountBelowMinimum.into());
+        }
+
+        let bump_seed = token_swap.bump_seed();
+        let event = EmergencyRedeemEvent {
+            swap: *swap_info.key,
+            token_b_amount,
+            token_a_amount,
+            bonded_token_a_amount: token_swap
+                .bonded_token_a_amount
+                .checked_sub(token_a_amount)
+                .ok_or(SwapError::CalculationFailure)?,
+        };
+        token_swap.bonded_token_a_amount = event.bonded_token_a_amount;
+        token_swap.emergency_circulating_token_b_amount = token_swap
+            .emergency_circulating_token_b_amount
+            .checked_sub(token_b_amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        SwapVersion::pack(
+            SwapVersion::SwapV2(token_swap),
+            &mut swap_info.data.borrow_mut(),
+        )?;
+
+        Self::token_burn(
+            swap_info.key,
+            token_program_info.clone(),
+            source_info.clone(),
+            token_b_mint_info.clone(),
+            user_transfer_authority_info.clone(),
+            bump_seed,
+            token_b_amount,
+        )?;
+        Self::token_transfer(
+            swap_info.key,
+            token_program_info.clone(),
+            token_a_info.clone(),
+            destination_info.clone(),
+            authority_info.clone(),
+            bump_seed,
+            token_a_amount,
+        )?;
+
+        Ok(event)
+    }
+
+    /// Returns the token A and token B left in a swap that's being closed, to drain to the admin's destination
+    /// accounts. The swap has to be empty, unless it's in emergency mode and every circulating token B has been
+    /// redeemed, in which case whatever's left (rounding dust and token B that was never sold) is drained instead.
+    /// The token A backing token B that hasn't been redeemed yet belongs to its holders, so it never goes to the
+    /// admin. Fees built up in the swap belong to the owner of the pool fee account, so they have to be claimed
+    /// first either way
+    fn close_swap_amounts(
+        token_swap: &SwapV2,
+        token_a: &spl_token::state::Account,
+        token_b: &spl_token::state::Account,
+    ) -> Result<(u64, u64), SwapError> {
+        if token_swap.accrued_token_a_fees != 0 || token_swap.accrued_token_b_fees != 0 {
+            return Err(SwapError::SwapNotEmpty);
+        }
+        let fully_redeemed =
+            token_swap.emergency_mode && token_swap.emergency_circulating_token_b_amount == 0;
+        if !fully_redeemed && (token_a.amount != 0 || token_b.amount != 0) {
+            return Err(SwapError::SwapNotEmpty);
+        }
+        Ok((token_a.amount, token_b.amount))
+    }
+
+    /// Processes a [CloseSwap](enum.Instruction.html).
+    /// Tears down a swap whose token accounts are empty, or drains them to the destination accounts first if the
+    /// swap is in emergency mode and fully redeemed (see `close_swap_amounts`): closes the token A and token B accounts through the
+    /// swap authority, closes the swap account and its curve params account (if the curve has one), and sends all
+    /// of their lamports to the recipient. The swap is removed from the registry of its collateral mint. The admin
+    /// can close the swap for any recipient, anyone else (e.g. once the admin has been renounced) can only close it
+    /// for its creator recorded in the registry, who has to be the recipient and own the destination accounts
+    /// anything's drained to
+    /// The pool mint and the pool fee account are left behind: spl token mints can't be closed, and the pool fee
+    /// account belongs to the pool owner rather than the swap authority, so only its owner can close it
+    pub fn process_close_swap(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> Result<CloseSwapEvent, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let destination_a_info = next_account_info(account_info_iter)?;
+        let destination_b_info = next_account_info(account_info_iter)?;
+        let recipient_info = next_account_info(account_info_iter)?;
+        let registry_info = next_account_info(account_info_iter)?;
+        let registry_page_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        // the accounts are validated by the `CloseSwap` constraints
+        let token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        let closed_by_admin = Self::check_admin(&token_swap.admin, admin_info).is_ok();
+
+        if *registry_info.key != Self::registry_address(program_id, &token_swap.token_a_mint).0 {
+            return Err(SwapError::IncorrectRegistryAccount.into());
+        }
+        let mut registry = Registry::unpack(&registry_info.data.borrow())?;
+        let mut registry_page =
+            Self::unpack_registry_page(program_id, registry_page_info, &token_swap.token_a_mint)?;
+        let registry_entry = Self::remove_registry_entry(
+            &mut registry,
+            &mut registry_page,
+            registry_page_info.key,
+            swap_info.key,
+        )?;
+
+        let token_a = Self::unpack_token_account(token_a_info, token_swap.token_program_id())?;
+        let token_b = Self::unpack_token_account(token_b_info, token_swap.token_program_id())?;
+        let (token_a_amount, token_b_amount) =
+            Self::close_swap_amounts(&token_swap, &token_a, &token_b)?;
+        if !closed_by_admin {
+            if *recipient_info.key != registry_entry.creator {
+                return Err(SwapError::IncorrectCloseRecipient.into());
+            }
+            for (destination_info, amount) in [
+                (destination_a_info, token_a_amount),
+                (destination_b_info, token_b_amount),
+            ] {
+                if amount > 0
+                    && Self::unpack_token_account(destination_info, token_swap.token_program_id())?
+                        .owner
+                        != registry_entry.creator
+                {
+                    return Err(SwapError::IncorrectCloseRecipient.into());
+                }
+            }
+        }
+        RegistryPage::pack(registry_page, &mut registry_page_info.data.borrow_mut())?;
+        Registry::pack(registry, &mut registry_info.data.borrow_mut())?;
+
+        if token_swap
+            .swap_curve
+            .curve_type
+            .curve_params_len()
+            .is_some()
+        {
+            let curve_params_info = next_account_info(account_info_iter)?;
+            if SwapVersion::get_extension(&swap_info.data.borrow(), SwapExtensionType::CurveParams)?
+                != Some(curve_params_info.key.as_ref())
+            {
+                return Err(SwapError::IncorrectCurveParamsAccount.into());
+            }
+            if curve_params_info.owner != program_id {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            Self::close_program_account(curve_params_info, recipient_info)?;
+        }
+
+        for (token_info, destination_info, amount) in [
+            (token_a_info, destination_a_info, token_a_amount),
+            (token_b_info, destination_b_info, token_b_amount),
+        ] {
+            if amount > 0 {
+                Self::token_transfer(
+                    swap_info.key,
+                    token_program_info.clone(),
+                    token_info.clone(),
+                    destination_info.clone(),
+                    authority_info.clone(),
+                    token_swap.bump_seed(),
+                    amount,
+                )?;
+            }
+            Self::token_close_account(
+                swap_info.key,
+                token_program_info.clone(),
+                token_info.clone(),
+                recipient_info.clone(),
+                authority_info.clone(),
+                token_swap.bump_seed(),
+            )?;
+        }
+        Self::close_program_account(swap_info, recipient_info)?;
+
+        Ok(CloseSwapEvent {
+            swap: *swap_info.key,
+            token_a_amount,
+            token_b_amount,
+            recipient: *recipient_info.key,
+        })
+    }
+}
+
+impl PrintProgramError for SwapError {
+    fn print<E>(&self)
+    where
+        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
     {
         match self {
             SwapError::AlreadyInUse => msg!("Error: Swap account already in use"),
@@ -1038,20 +2926,1149 @@ impl PrintProgramError for SwapError {
             SwapError::InvalidCurve => {
                 msg!("Error: The provided curve parameters are invalid")
             }
-            SwapError::UnsupportedCurveOperation => {
-                msg!("Error: The operation cannot be performed on the given curve")
+            SwapError::UnsupportedCurveOperation => {
+                msg!("Error: The operation cannot be performed on the given curve")
+            }
+            SwapError::IncorrectCurveParamsAccount => {
+                msg!("Error: The provided curve params account does not match the one stored in the swap")
+            }
+            SwapError::IncorrectTokenBMint => {
+                msg!("Error: The provided token B mint does not match the one stored in the swap")
+            }
+            SwapError::InvalidAdmin => msg!("Error: The signer is not the swap's admin"),
+            SwapError::Paused => msg!("Error: The swap is paused"),
+            SwapError::IncorrectSwapVersion => {
+                msg!("Error: The swap account's version isn't supported by the instruction")
+            }
+            SwapError::EmergencyMode => msg!("Error: The swap is in emergency mode"),
+            SwapError::NotEmergencyMode => msg!("Error: The swap isn't in emergency mode"),
+            SwapError::SwapNotEmpty => msg!("Error: The swap's token accounts aren't empty"),
+            SwapError::CurveSoldOut => {
+                msg!("Error: The swap doesn't have enough of the destination token left")
+            }
+            SwapError::AmountBelowMinimum => {
+                msg!("Error: The amount is below the minimum tradable size")
+            }
+            SwapError::CurveCalculationOverflow => msg!("Error: The curve calculation overflowed"),
+            SwapError::IncorrectRegistryAccount => {
+                msg!("Error: The provided registry account does not match the swap's collateral mint")
+            }
+            SwapError::IncorrectCloseRecipient => {
+                msg!("Error: The close recipient isn't the swap's creator")
+            }
+        }
+    }
+}
+
+fn to_u128(val: u64) -> Result<u128, SwapError> {
+    val.try_into().map_err(|_| SwapError::ConversionFailure)
+}
+
+fn to_u64(val: u128) -> Result<u64, SwapError> {
+    val.try_into().map_err(|_| SwapError::ConversionFailure)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        curve::{
+            base::CurveType,
+            linear_price::LinearPriceCurve,
+            piecewise_linear::{
+                PiecewiseLinearCurve, PiecewiseLinearSegment, PiecewiseLinearSegments,
+            },
+        },
+        state::SwapV1,
+    };
+    use solana_program::program_stubs;
+
+    const TEST_BONDED_TOKEN_A_AMOUNT: u64 = 1_000;
+    const TEST_TOKEN_A_AMOUNT: u64 = 1_500;
+
+    fn linear_price_swap() -> SwapV2 {
+        SwapV2 {
+            is_initialized: true,
+            swap_curve: SwapCurve {
+                curve_type: CurveType::LinearPrice,
+                calculator: Box::new(LinearPriceCurve {
+                    slope_numerator: 1,
+                    slope_denominator: 1,
+                    initial_token_a_price_numerator: 1,
+                    initial_token_a_price_denominator: 1,
+                    initial_token_a_amount: 0,
+                }),
+            },
+            bonded_token_a_amount: TEST_BONDED_TOKEN_A_AMOUNT,
+            ..Default::default()
+        }
+    }
+
+    fn constant_product_swap() -> SwapV2 {
+        SwapV2 {
+            is_initialized: true,
+            ..Default::default()
+        }
+    }
+
+    fn token_a_account() -> spl_token::state::Account {
+        spl_token::state::Account {
+            amount: TEST_TOKEN_A_AMOUNT,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        }
+    }
+
+    struct TestSyscallStubs {}
+    impl program_stubs::SyscallStubs for TestSyscallStubs {
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe {
+                *(var_addr as *mut Rent) = Rent::default();
+            }
+            solana_program::entrypoint::SUCCESS
+        }
+
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe {
+                *(var_addr as *mut Clock) = Clock::default();
             }
+            solana_program::entrypoint::SUCCESS
         }
     }
-}
 
-fn to_u128(val: u64) -> Result<u128, SwapError> {
-    val.try_into().map_err(|_| SwapError::ConversionFailure)
-}
+    /// Packs an initialized registry without any page yet for the collateral mint
+    fn empty_registry_data(collateral_mint: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0u8; Registry::LEN];
+        Registry::pack(
+            Registry {
+                is_initialized: true,
+                collateral_mint: *collateral_mint,
+                ..Registry::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        data
+    }
 
-fn to_u64(val: u128) -> Result<u64, SwapError> {
-    val.try_into().map_err(|_| SwapError::ConversionFailure)
+    /// Packs `token_swap` into a swap account and runs `f` with its account info
+    fn with_swap_info<F: FnOnce(&AccountInfo)>(token_swap: SwapV2, f: F) {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0u8; SwapVersion::LATEST_LEN];
+        SwapVersion::pack(SwapVersion::SwapV2(token_swap), &mut data).unwrap();
+        let swap_info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &crate::ID,
+            false,
+            0,
+        );
+        f(&swap_info);
+    }
+
+    #[test]
+    fn migrate_swap_v1_to_v2() {
+        program_stubs::set_syscall_stubs(Box::new(TestSyscallStubs {}));
+
+        let token_a_key = Pubkey::new_unique();
+        let pool_fee_key = Pubkey::new_unique();
+        let pool_fee_owner = Pubkey::new_unique();
+        let token_a_mint = Pubkey::new_unique();
+        let token_b_key = Pubkey::new_unique();
+        let token_b_mint_key = Pubkey::new_unique();
+        let token_swap = SwapV1 {
+            is_initialized: true,
+            token_program_id: spl_token::id(),
+            token_a: token_a_key,
+            token_b: token_b_key,
+            token_a_mint,
+            token_b_mint: token_b_mint_key,
+            pool_fee_account: pool_fee_key,
+            swap_curve: linear_price_swap().swap_curve,
+            ..Default::default()
+        };
+
+        // realloc writes the new length in front of the account data like the runtime's serialized input, so the
+        // account data has to sit behind an 8 byte length in a buffer big enough for the latest version
+        let mut buffer = vec![0u64; 1 + SwapVersion::LATEST_LEN / 8 + 1];
+        let buffer = unsafe {
+            std::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, buffer.len() * 8)
+        };
+        let swap_data = &mut buffer[8..8 + 1 + SwapV1::LEN];
+        SwapVersion::pack(SwapVersion::SwapV1(token_swap), swap_data).unwrap();
+        let swap_key = Pubkey::new_unique();
+        // already rent exempt at the latest size, so the payer isn't charged
+        let mut swap_lamports = Rent::default().minimum_balance(SwapVersion::LATEST_LEN);
+        let swap_info = AccountInfo::new(
+            &swap_key,
+            false,
+            true,
+            &mut swap_lamports,
+            swap_data,
+            &crate::ID,
+            false,
+            0,
+        );
+
+        let mut token_a_data = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(token_a_account(), &mut token_a_data).unwrap();
+        let mut token_a_lamports = 0;
+        let token_program_id = spl_token::id();
+        let token_a_info = AccountInfo::new(
+            &token_a_key,
+            false,
+            false,
+            &mut token_a_lamports,
+            &mut token_a_data,
+            &token_program_id,
+            false,
+            0,
+        );
+        let mut pool_fee_data = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(
+            spl_token::state::Account {
+                owner: pool_fee_owner,
+                state: spl_token::state::AccountState::Initialized,
+                ..Default::default()
+            },
+            &mut pool_fee_data,
+        )
+        .unwrap();
+        let mut pool_fee_lamports = 0;
+        let pool_fee_info = AccountInfo::new(
+            &pool_fee_key,
+            false,
+            false,
+            &mut pool_fee_lamports,
+            &mut pool_fee_data,
+            &token_program_id,
+            false,
+            0,
+        );
+        let payer_key = Pubkey::new_unique();
+        let mut payer_lamports = 0;
+        let mut payer_data = vec![];
+        let system_program_id = solana_program::system_program::id();
+        let payer_info = AccountInfo::new(
+            &payer_key,
+            true,
+            true,
+            &mut payer_lamports,
+            &mut payer_data,
+            &system_program_id,
+            false,
+            0,
+        );
+        let mut system_program_lamports = 0;
+        let mut system_program_data = vec![];
+        let system_program_info = AccountInfo::new(
+            &system_program_id,
+            false,
+            false,
+            &mut system_program_lamports,
+            &mut system_program_data,
+            &system_program_id,
+            true,
+            0,
+        );
+        let (registry_key, _) = Processor::registry_address(&crate::ID, &token_a_mint);
+        let mut registry_lamports = 0;
+        let mut registry_data = empty_registry_data(&token_a_mint);
+        let registry_info = AccountInfo::new(
+            &registry_key,
+            false,
+            true,
+            &mut registry_lamports,
+            &mut registry_data,
+            &crate::ID,
+            false,
+            0,
+        );
+        // the stubs skip creating the first page, so it's already allocated
+        let (registry_page_key, _) = Processor::registry_page_address(&crate::ID, &token_a_mint, 0);
+        let mut registry_page_lamports = 0;
+        let mut registry_page_data = vec![0u8; RegistryPage::LEN];
+        let registry_page_info = AccountInfo::new(
+            &registry_page_key,
+            false,
+            true,
+            &mut registry_page_lamports,
+            &mut registry_page_data,
+            &crate::ID,
+            false,
+            0,
+        );
+        // SwapV1 didn't track the token B sold, so it's whatever of token B's supply isn't in the swap
+        let mut token_b_data = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(
+            spl_token::state::Account {
+                amount: 400,
+                state: spl_token::state::AccountState::Initialized,
+                ..Default::default()
+            },
+            &mut token_b_data,
+        )
+        .unwrap();
+        let mut token_b_lamports = 0;
+        let token_b_info = AccountInfo::new(
+            &token_b_key,
+            false,
+            false,
+            &mut token_b_lamports,
+            &mut token_b_data,
+            &token_program_id,
+            false,
+            0,
+        );
+        let mut token_b_mint_data = vec![0u8; spl_token::state::Mint::LEN];
+        spl_token::state::Mint::pack(
+            spl_token::state::Mint {
+                supply: 1_000,
+                is_initialized: true,
+                ..Default::default()
+            },
+            &mut token_b_mint_data,
+        )
+        .unwrap();
+        let mut token_b_mint_lamports = 0;
+        let token_b_mint_info = AccountInfo::new(
+            &token_b_mint_key,
+            false,
+            false,
+            &mut token_b_mint_lamports,
+            &mut token_b_mint_data,
+            &token_program_id,
+            false,
+            0,
+        );
+
+        let accounts = [
+            swap_info,
+            token_a_info,
+            pool_fee_info,
+            payer_info,
+            system_program_info,
+            registry_info,
+            registry_page_info,
+            token_b_info,
+            token_b_mint_info,
+        ];
+
+        let event = Processor::process_migrate_swap_v1_to_v2(&crate::ID, &accounts).unwrap();
+        assert_eq!(event.bonded_token_a_amount, TEST_TOKEN_A_AMOUNT);
+        assert_eq!(event.sold_token_b_amount, 600);
+
+        assert_eq!(accounts[0].data_len(), SwapVersion::LATEST_LEN);
+        let token_swap = SwapVersion::unpack_latest(&accounts[0].data.borrow()).unwrap();
+        assert_eq!(token_swap.token_a, token_a_key);
+        assert_eq!(token_swap.swap_curve, linear_price_swap().swap_curve);
+        assert_eq!(token_swap.bonded_token_a_amount, TEST_TOKEN_A_AMOUNT);
+        assert_eq!(token_swap.sold_token_b_amount, 600);
+        assert_eq!(token_swap.admin, COption::Some(pool_fee_owner));
+        assert!(!token_swap.paused);
+        let registry_page = RegistryPage::unpack(&accounts[6].data.borrow()).unwrap();
+        assert_eq!(registry_page.entries.len(), 1);
+        assert_eq!(registry_page.entries[0].swap, swap_key);
+        assert_eq!(registry_page.entries[0].creator, pool_fee_owner);
+
+        // a swap can only be migrated once
+        let err = Processor::process_migrate_swap_v1_to_v2(&crate::ID, &accounts).err();
+        assert_eq!(err, Some(SwapError::IncorrectSwapVersion.into()));
+    }
+
+    #[test]
+    fn register_swap() {
+        program_stubs::set_syscall_stubs(Box::new(TestSyscallStubs {}));
+
+        let collateral_mint = Pubkey::new_unique();
+        let (registry_key, _) = Processor::registry_address(&crate::ID, &collateral_mint);
+        let mut registry_lamports = 0;
+        let mut registry_data = empty_registry_data(&collateral_mint);
+        let registry_info = AccountInfo::new(
+            &registry_key,
+            false,
+            true,
+            &mut registry_lamports,
+            &mut registry_data,
+            &crate::ID,
+            false,
+            0,
+        );
+        // the stubs skip creating the pages, so they're already allocated
+        let (first_page_key, _) = Processor::registry_page_address(&crate::ID, &collateral_mint, 0);
+        let mut first_page_lamports = 0;
+        let mut first_page_data = vec![0u8; RegistryPage::LEN];
+        let first_page_info = AccountInfo::new(
+            &first_page_key,
+            false,
+            true,
+            &mut first_page_lamports,
+            &mut first_page_data,
+            &crate::ID,
+            false,
+            0,
+        );
+        let (second_page_key, _) =
+            Processor::registry_page_address(&crate::ID, &collateral_mint, 1);
+        let mut second_page_lamports = 0;
+        let mut second_page_data = vec![0u8; RegistryPage::LEN];
+        let second_page_info = AccountInfo::new(
+            &second_page_key,
+            false,
+            true,
+            &mut second_page_lamports,
+            &mut second_page_data,
+            &crate::ID,
+            false,
+            0,
+        );
+        // stands in for the payer too, neither is used without the system program
+        let system_program_id = solana_program::system_program::id();
+        let mut system_program_lamports = 0;
+        let mut system_program_data = vec![];
+        let system_program_info = AccountInfo::new(
+            &system_program_id,
+            false,
+            false,
+            &mut system_program_lamports,
+            &mut system_program_data,
+            &system_program_id,
+            true,
+            0,
+        );
+        let registry_page_infos = [first_page_info, second_page_info];
+        let register = |page: usize, entry: RegistryEntry| {
+            Processor::register_swap(
+                &crate::ID,
+                &registry_info,
+                &registry_page_infos[page],
+                &system_program_info,
+                &system_program_info,
+                &collateral_mint,
+                entry,
+            )
+        };
+        let entries: Vec<RegistryEntry> = (0..RegistryPage::CAPACITY + 2)
+            .map(|_| RegistryEntry {
+                swap: Pubkey::new_unique(),
+                ..RegistryEntry::default()
+            })
+            .collect();
+
+        for entry in &entries[..RegistryPage::CAPACITY] {
+            register(0, *entry).unwrap();
+        }
+        // the first page is full, so the next swap goes in the second one
+        assert_eq!(
+            register(0, entries[RegistryPage::CAPACITY]),
+            Err(SwapError::IncorrectRegistryAccount.into())
+        );
+        register(1, entries[RegistryPage::CAPACITY]).unwrap();
+
+        let mut registry = Registry::unpack(&registry_info.data.borrow()).unwrap();
+        assert_eq!(registry.page_count, 2);
+        assert_eq!(registry.swap_count, RegistryPage::CAPACITY as u64 + 1);
+        assert_eq!(registry.last_page, COption::Some(second_page_key));
+        assert_eq!(registry.last_page_entry_count, 1);
+        let mut first_page = RegistryPage::unpack(&registry_page_infos[0].data.borrow()).unwrap();
+        assert_eq!(first_page.index, 0);
+        assert_eq!(first_page.prev_page, COption::None);
+        assert_eq!(first_page.entries, entries[..RegistryPage::CAPACITY]);
+        let second_page = RegistryPage::unpack(&registry_page_infos[1].data.borrow()).unwrap();
+        assert_eq!(second_page.index, 1);
+        assert_eq!(second_page.prev_page, COption::Some(first_page_key));
+        assert_eq!(second_page.entries, entries[RegistryPage::CAPACITY..][..1]);
+
+        // closing a swap leaves a gap in its page, the next swap still goes in the last page
+        assert_eq!(
+            Processor::remove_registry_entry(
+                &mut registry,
+                &mut first_page,
+                &first_page_key,
+                &entries[RegistryPage::CAPACITY].swap,
+            ),
+            Err(SwapError::IncorrectRegistryAccount)
+        );
+        Processor::remove_registry_entry(
+            &mut registry,
+            &mut first_page,
+            &first_page_key,
+            &entries[0].swap,
+        )
+        .unwrap();
+        assert_eq!(registry.swap_count, RegistryPage::CAPACITY as u64);
+        assert_eq!(registry.last_page_entry_count, 1);
+        assert_eq!(first_page.entries, entries[1..RegistryPage::CAPACITY]);
+        RegistryPage::pack(first_page, &mut registry_page_infos[0].data.borrow_mut()).unwrap();
+        Registry::pack(registry, &mut registry_info.data.borrow_mut()).unwrap();
+        assert_eq!(
+            register(0, entries[RegistryPage::CAPACITY + 1]),
+            Err(SwapError::IncorrectRegistryAccount.into())
+        );
+        register(1, entries[RegistryPage::CAPACITY + 1]).unwrap();
+
+        // the last page fills up again once swaps in it are closed
+        let mut registry = Registry::unpack(&registry_info.data.borrow()).unwrap();
+        let mut second_page = RegistryPage::unpack(&registry_page_infos[1].data.borrow()).unwrap();
+        assert_eq!(registry.last_page_entry_count, 2);
+        Processor::remove_registry_entry(
+            &mut registry,
+            &mut second_page,
+            &second_page_key,
+            &entries[RegistryPage::CAPACITY].swap,
+        )
+        .unwrap();
+        assert_eq!(registry.last_page_entry_count, 1);
+        assert_eq!(second_page.entries, entries[RegistryPage::CAPACITY + 1..]);
+    }
+
+    #[test]
+    fn register_swap_prefunded_page() {
+        program_stubs::set_syscall_stubs(Box::new(TestSyscallStubs {}));
+
+        let collateral_mint = Pubkey::new_unique();
+        let (registry_key, _) = Processor::registry_address(&crate::ID, &collateral_mint);
+        let mut registry_lamports = 0;
+        let mut registry_data = empty_registry_data(&collateral_mint);
+        let registry_info = AccountInfo::new(
+            &registry_key,
+            false,
+            true,
+            &mut registry_lamports,
+            &mut registry_data,
+            &crate::ID,
+            false,
+            0,
+        );
+        // anyone can send lamports to the next page's address before it's created, the stubs skip creating it so
+        // it's already allocated
+        let system_program_id = solana_program::system_program::id();
+        let (page_key, _) = Processor::registry_page_address(&crate::ID, &collateral_mint, 0);
+        let mut page_lamports = 1;
+        let mut page_data = vec![0u8; RegistryPage::LEN];
+        let page_info = AccountInfo::new(
+            &page_key,
+            false,
+            true,
+            &mut page_lamports,
+            &mut page_data,
+            &system_program_id,
+            false,
+            0,
+        );
+        // stands in for the payer too, neither is used without the system program
+        let mut system_program_lamports = 0;
+        let mut system_program_data = vec![];
+        let system_program_info = AccountInfo::new(
+            &system_program_id,
+            false,
+            false,
+            &mut system_program_lamports,
+            &mut system_program_data,
+            &system_program_id,
+            true,
+            0,
+        );
+        let entry = RegistryEntry {
+            swap: Pubkey::new_unique(),
+            ..RegistryEntry::default()
+        };
+
+        Processor::register_swap(
+            &crate::ID,
+            &registry_info,
+            &page_info,
+            &system_program_info,
+            &system_program_info,
+            &collateral_mint,
+            entry,
+        )
+        .unwrap();
+        let registry = Registry::unpack(&registry_info.data.borrow()).unwrap();
+        assert_eq!(registry.last_page, COption::Some(page_key));
+        let page = RegistryPage::unpack(&page_info.data.borrow()).unwrap();
+        assert_eq!(page.entries, vec![entry]);
+    }
+
+    #[test]
+    fn create_pda_account_instructions() {
+        let payer = Pubkey::new_unique();
+        let address = Pubkey::new_unique();
+        let space = RegistryPage::LEN;
+        let rent_exempt_lamports = Rent::default().minimum_balance(space);
+        let create = |lamports| {
+            Processor::create_pda_account_instructions(
+                &payer,
+                &address,
+                lamports,
+                rent_exempt_lamports,
+                space,
+                &crate::ID,
+            )
+        };
+
+        assert_eq!(
+            create(0),
+            vec![system_instruction::create_account(
+                &payer,
+                &address,
+                rent_exempt_lamports,
+                space as u64,
+                &crate::ID,
+            )]
+        );
+        // create_account fails on an address that already holds lamports, so it's topped up, allocated and
+        // assigned instead
+        assert_eq!(
+            create(1),
+            vec![
+                system_instruction::transfer(&payer, &address, rent_exempt_lamports - 1),
+                system_instruction::allocate(&address, space as u64),
+                system_instruction::assign(&address, &crate::ID),
+            ]
+        );
+        assert_eq!(
+            create(rent_exempt_lamports + 1),
+            vec![
+                system_instruction::allocate(&address, space as u64),
+                system_instruction::assign(&address, &crate::ID),
+            ]
+        );
+    }
+
+    #[test]
+    fn create_prefunded_curve_params_account() {
+        program_stubs::set_syscall_stubs(Box::new(TestSyscallStubs {}));
+
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::PiecewiseLinear,
+            calculator: Box::new(PiecewiseLinearCurve {
+                segments: PiecewiseLinearSegments {
+                    is_initialized: true,
+                    initial_token_a_price_numerator: 1,
+                    initial_token_a_price_denominator: 1,
+                    segments: vec![PiecewiseLinearSegment {
+                        breakpoint: 0,
+                        slope_numerator: 1,
+                        slope_denominator: 1,
+                    }],
+                },
+            }),
+        };
+        let curve_params_len = CurveType::PiecewiseLinear.curve_params_len().unwrap();
+        let swap_key = Pubkey::new_unique();
+        let mut swap_lamports = 0;
+        let mut swap_data = vec![0u8; SwapVersion::LATEST_LEN];
+        let swap_info = AccountInfo::new(
+            &swap_key,
+            false,
+            true,
+            &mut swap_lamports,
+            &mut swap_data,
+            &crate::ID,
+            false,
+            0,
+        );
+        // anyone can send lamports to the curve params address before the swap is initialized, the stubs skip
+        // creating it so it's already allocated
+        let system_program_id = solana_program::system_program::id();
+        let (curve_params_key, _) = Processor::curve_params_address(&crate::ID, &swap_key);
+        let mut curve_params_lamports = 1;
+        let mut curve_params_data = vec![0u8; curve_params_len];
+        let curve_params_info = AccountInfo::new(
+            &curve_params_key,
+            false,
+            true,
+            &mut curve_params_lamports,
+            &mut curve_params_data,
+            &system_program_id,
+            false,
+            0,
+        );
+        // stands in for the payer too, neither is used without the system program
+        let mut system_program_lamports = 0;
+        let mut system_program_data = vec![];
+        let system_program_info = AccountInfo::new(
+            &system_program_id,
+            false,
+            false,
+            &mut system_program_lamports,
+            &mut system_program_data,
+            &system_program_id,
+            true,
+            0,
+        );
+        let create = |curve_params_info| {
+            Processor::create_curve_params_account(
+                &crate::ID,
+                &swap_info,
+                curve_params_info,
+                &system_program_info,
+                &system_program_info,
+                &swap_curve,
+                curve_params_len,
+            )
+        };
+
+        assert_eq!(
+            create(&system_program_info),
+            Err(SwapError::IncorrectCurveParamsAccount.into())
+        );
+        assert_eq!(create(&curve_params_info), Ok(curve_params_key));
+        assert_eq!(
+            swap_curve
+                .load_curve_params(&curve_params_info.data.borrow())
+                .unwrap(),
+            swap_curve
+        );
+    }
+
+    #[test]
+    fn emergency_redeem_token_a_amount() {
+        let token_swap = SwapV2 {
+            emergency_mode: true,
+            emergency_circulating_token_b_amount: 300,
+            ..linear_price_swap()
+        };
+
+        // bonded * amount / circulating
+        assert_eq!(
+            Processor::emergency_redeem_token_a_amount(&token_swap, 30).unwrap(),
+            100
+        );
+        // rounded down
+        assert_eq!(
+            Processor::emergency_redeem_token_a_amount(&token_swap, 1).unwrap(),
+            3
+        );
+        // all of the circulating token B gets all of the bonded token A
+        assert_eq!(
+            Processor::emergency_redeem_token_a_amount(&token_swap, 300).unwrap(),
+            TEST_BONDED_TOKEN_A_AMOUNT
+        );
+
+        // holders get the same price no matter when they redeem
+        let mut token_swap = token_swap;
+        let token_a_amount = Processor::emergency_redeem_token_a_amount(&token_swap, 100).unwrap();
+        token_swap.bonded_token_a_amount -= token_a_amount;
+        token_swap.emergency_circulating_token_b_amount -= 100;
+        assert_eq!(
+            Processor::emergency_redeem_token_a_amount(&token_swap, 100).unwrap(),
+            token_a_amount
+        );
+
+        // large amounts don't overflow
+        let token_swap = SwapV2 {
+            bonded_token_a_amount: u64::MAX,
+            emergency_circulating_token_b_amount: u64::MAX,
+            ..linear_price_swap()
+        };
+        assert_eq!(
+            Processor::emergency_redeem_token_a_amount(&token_swap, u64::MAX).unwrap(),
+            u64::MAX
+        );
+
+        let token_swap = SwapV2 {
+            emergency_circulating_token_b_amount: 0,
+            ..linear_price_swap()
+        };
+        assert_eq!(
+            Processor::emergency_redeem_token_a_amount(&token_swap, 1),
+            Err(SwapError::CalculationFailure)
+        );
+    }
+
+    #[test]
+    fn close_swap_amounts() {
+        let empty_token_account = spl_token::state::Account {
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let token_b_account = spl_token::state::Account {
+            amount: 200,
+            ..empty_token_account
+        };
+
+        // an empty swap can always be closed
+        assert_eq!(
+            Processor::close_swap_amounts(
+                &linear_price_swap(),
+                &empty_token_account,
+                &empty_token_account
+            ),
+            Ok((0, 0))
+        );
+
+        // outside of emergency mode neither token account can hold anything
+        assert_eq!(
+            Processor::close_swap_amounts(
+                &linear_price_swap(),
+                &token_a_account(),
+                &empty_token_account
+            ),
+            Err(SwapError::SwapNotEmpty)
+        );
+        assert_eq!(
+            Processor::close_swap_amounts(
+                &linear_price_swap(),
+                &empty_token_account,
+                &token_b_account
+            ),
+            Err(SwapError::SwapNotEmpty)
+        );
+
+        // in emergency mode the token A backing unredeemed token B can't be drained
+        let token_swap = SwapV2 {
+            emergency_mode: true,
+            emergency_circulating_token_b_amount: 100,
+            ..linear_price_swap()
+        };
+        assert_eq!(
+            Processor::close_swap_amounts(&token_swap, &token_a_account(), &token_b_account),
+            Err(SwapError::SwapNotEmpty)
+        );
+        assert_eq!(
+            Processor::close_swap_amounts(&token_swap, &empty_token_account, &token_b_account),
+            Err(SwapError::SwapNotEmpty)
+        );
+
+        // once it's all been redeemed whatever's left is drained
+        let token_swap = SwapV2 {
+            emergency_circulating_token_b_amount: 0,
+            ..token_swap
+        };
+        assert_eq!(
+            Processor::close_swap_amounts(&token_swap, &token_a_account(), &token_b_account),
+            Ok((TEST_TOKEN_A_AMOUNT, 200))
+        );
+
+        // fees built up in the swap have to be claimed first
+        let token_swap = SwapV2 {
+            accrued_token_b_fees: 10,
+            ..token_swap
+        };
+        assert_eq!(
+            Processor::close_swap_amounts(&token_swap, &token_a_account(), &token_b_account),
+            Err(SwapError::SwapNotEmpty)
+        );
+    }
+
+    #[test]
+    fn close_swap_renounced_admin() {
+        program_stubs::set_syscall_stubs(Box::new(TestSyscallStubs {}));
+
+        let token_program_id = spl_token::id();
+        let system_program_id = solana_program::system_program::id();
+        let swap_key = Pubkey::new_unique();
+        let token_a_key = Pubkey::new_unique();
+        let token_b_key = Pubkey::new_unique();
+        let token_a_mint = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let mut swap_data = vec![0u8; SwapVersion::LATEST_LEN];
+        SwapVersion::pack(
+            SwapVersion::SwapV2(SwapV2 {
+                token_program_id,
+                token_a: token_a_key,
+                token_b: token_b_key,
+                token_a_mint,
+                admin: COption::None,
+                ..linear_price_swap()
+            }),
+            &mut swap_data,
+        )
+        .unwrap();
+        let mut empty_token_account_data = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(
+            spl_token::state::Account {
+                state: spl_token::state::AccountState::Initialized,
+                ..Default::default()
+            },
+            &mut empty_token_account_data,
+        )
+        .unwrap();
+        let (registry_key, _) = Processor::registry_address(&crate::ID, &token_a_mint);
+        let (registry_page_key, _) = Processor::registry_page_address(&crate::ID, &token_a_mint, 0);
+        let mut registry_data = vec![0u8; Registry::LEN];
+        Registry::pack(
+            Registry {
+                is_initialized: true,
+                collateral_mint: token_a_mint,
+                page_count: 1,
+                swap_count: 1,
+                last_page: COption::Some(registry_page_key),
+                last_page_entry_count: 1,
+            },
+            &mut registry_data,
+        )
+        .unwrap();
+        let mut registry_page_data = vec![0u8; RegistryPage::LEN];
+        RegistryPage::pack(
+            RegistryPage {
+                is_initialized: true,
+                registry: registry_key,
+                index: 0,
+                prev_page: COption::None,
+                entries: vec![RegistryEntry {
+                    swap: swap_key,
+                    creator,
+                    ..RegistryEntry::default()
+                }],
+            },
+            &mut registry_page_data,
+        )
+        .unwrap();
+
+        // nobody can sign for a renounced admin, so anyone can close the empty swap for its creator
+        let close = |recipient| {
+            let mut accounts = vec![
+                (swap_key, 1_000, swap_data.clone(), crate::ID),
+                (Pubkey::new_unique(), 0, vec![], system_program_id),
+                (Pubkey::new_unique(), 0, vec![], system_program_id),
+                (
+                    token_a_key,
+                    0,
+                    empty_token_account_data.clone(),
+                    token_program_id,
+                ),
+                (
+                    token_b_key,
+                    0,
+                    empty_token_account_data.clone(),
+                    token_program_id,
+                ),
+                (Pubkey::new_unique(), 0, vec![], token_program_id),
+                (Pubkey::new_unique(), 0, vec![], token_program_id),
+                (recipient, 0, vec![], system_program_id),
+                (registry_key, 0, registry_data.clone(), crate::ID),
+                (registry_page_key, 0, registry_page_data.clone(), crate::ID),
+                (token_program_id, 0, vec![], system_program_id),
+            ];
+            let account_infos: Vec<AccountInfo> = accounts
+                .iter_mut()
+                .map(|(key, lamports, data, owner)| {
+                    AccountInfo::new(key, false, true, lamports, data, owner, false, 0)
+                })
+                .collect();
+            Processor::process_close_swap(&crate::ID, &account_infos).map(|event| {
+                assert_eq!(account_infos[7].lamports(), 1_000);
+                assert_eq!(event.recipient, recipient);
+                let registry_page = RegistryPage::unpack(&account_infos[9].data.borrow()).unwrap();
+                assert!(registry_page.entries.is_empty());
+            })
+        };
+
+        assert_eq!(
+            close(Pubkey::new_unique()),
+            Err(SwapError::IncorrectCloseRecipient.into())
+        );
+        close(creator).unwrap();
+    }
+
+    #[test]
+    fn swap_address() {
+        let token_a_mint = Pubkey::new_unique();
+        let token_b_mint = Pubkey::new_unique();
+        let (address, _) = Processor::swap_address(&crate::ID, &token_a_mint, &token_b_mint, 0);
+
+        let (same_address, _) =
+            Processor::swap_address(&crate::ID, &token_a_mint, &token_b_mint, 0);
+        assert_eq!(address, same_address);
+        let (other_index_address, _) =
+            Processor::swap_address(&crate::ID, &token_a_mint, &token_b_mint, 1);
+        assert_ne!(address, other_index_address);
+        let (swapped_mints_address, _) =
+            Processor::swap_address(&crate::ID, &token_b_mint, &token_a_mint, 0);
+        assert_ne!(address, swapped_mints_address);
+    }
+
+    #[test]
+    fn create_prefunded_swap_accounts() {
+        program_stubs::set_syscall_stubs(Box::new(TestSyscallStubs {}));
+
+        let token_program_id = spl_token::id();
+        let system_program_id = solana_program::system_program::id();
+        let token_a_mint = Pubkey::new_unique();
+        let token_b_mint = Pubkey::new_unique();
+        let (swap_key, _) = Processor::swap_address(&crate::ID, &token_a_mint, &token_b_mint, 0);
+        let swap_account_key =
+            |seed| Processor::swap_account_address(&crate::ID, &swap_key, seed).0;
+        let mut token_a_mint_data = vec![0u8; spl_token::state::Mint::LEN];
+        spl_token::state::Mint::pack(
+            spl_token::state::Mint {
+                decimals: 6,
+                is_initialized: true,
+                ..Default::default()
+            },
+            &mut token_a_mint_data,
+        )
+        .unwrap();
+
+        // anyone can send lamports to the swap's addresses before it's created, the stubs skip creating them and
+        // initializing the token accounts so they're already allocated
+        let mut accounts = vec![
+            (
+                swap_key,
+                1,
+                vec![0u8; SwapVersion::LATEST_LEN],
+                system_program_id,
+            ),
+            (Pubkey::new_unique(), 0, vec![], system_program_id),
+            (token_a_mint, 0, token_a_mint_data, token_program_id),
+            (token_b_mint, 0, vec![], token_program_id),
+            (
+                swap_account_key(Processor::TOKEN_A_SEED),
+                1,
+                vec![0u8; spl_token::state::Account::LEN],
+                system_program_id,
+            ),
+            (
+                swap_account_key(Processor::TOKEN_B_SEED),
+                1,
+                vec![0u8; spl_token::state::Account::LEN],
+                system_program_id,
+            ),
+            (
+                swap_account_key(Processor::POOL_MINT_SEED),
+                1,
+                vec![0u8; spl_token::state::Mint::LEN],
+                system_program_id,
+            ),
+            (
+                swap_account_key(Processor::POOL_FEE_SEED),
+                1,
+                vec![0u8; spl_token::state::Account::LEN],
+                system_program_id,
+            ),
+            (Pubkey::new_unique(), 0, vec![], token_program_id),
+            (Pubkey::new_unique(), 0, vec![], token_program_id),
+            (Pubkey::new_unique(), 0, vec![], system_program_id),
+            (Pubkey::new_unique(), 0, vec![], system_program_id),
+            (token_program_id, 0, vec![], system_program_id),
+            (system_program_id, 0, vec![], system_program_id),
+            (
+                solana_program::sysvar::rent::id(),
+                0,
+                vec![],
+                system_program_id,
+            ),
+        ];
+        let account_infos: Vec<AccountInfo> = accounts
+            .iter_mut()
+            .map(|(key, lamports, data, owner)| {
+                AccountInfo::new(key, false, true, lamports, data, owner, false, 0)
+            })
+            .collect();
+
+        Processor::process_create_swap_accounts(&crate::ID, 0, 100, 100, &account_infos).unwrap();
+        // the token accounts have to be the swap's PDAs
+        let mut wrong_account_infos = account_infos.clone();
+        wrong_account_infos.swap(4, 5);
+        assert_eq!(
+            Processor::process_create_swap_accounts(&crate::ID, 0, 100, 100, &wrong_account_infos),
+            Err(SwapError::InvalidProgramAddress.into())
+        );
+    }
+
+    #[test]
+    fn swap_token_a_amount() {
+        // bonding curves run against the bonded amount, surplus in the token A account doesn't move the price
+        assert_eq!(
+            Processor::swap_token_a_amount(&linear_price_swap(), &token_a_account()),
+            TEST_BONDED_TOKEN_A_AMOUNT
+        );
+        // AMM curves run against the whole balance like spl token swap
+        assert_eq!(
+            Processor::swap_token_a_amount(&constant_product_swap(), &token_a_account()),
+            TEST_TOKEN_A_AMOUNT
+        );
+    }
+
+    #[test]
+    fn update_bonded_token_a_amount() {
+        with_swap_info(linear_price_swap(), |swap_info| {
+            Processor::update_bonded_token_a_amount(swap_info, 2_000).unwrap();
+            let token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow()).unwrap();
+            assert_eq!(token_swap.bonded_token_a_amount, 2_000);
+        });
+
+        // AMM curves don't track a bonded amount
+        with_swap_info(constant_product_swap(), |swap_info| {
+            Processor::update_bonded_token_a_amount(swap_info, 2_000).unwrap();
+            let token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow()).unwrap();
+            assert_eq!(token_swap.bonded_token_a_amount, 0);
+        });
+    }
+
+    #[test]
+    fn update_sold_token_b_amount() {
+        with_swap_info(linear_price_swap(), |swap_info| {
+            Processor::update_sold_token_b_amount(swap_info, TradeDirection::AtoB, 500).unwrap();
+            Processor::update_sold_token_b_amount(swap_info, TradeDirection::BtoA, 200).unwrap();
+            let token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow()).unwrap();
+            assert_eq!(token_swap.sold_token_b_amount, 300);
+
+            // token B minted outside the curve can be sold to it as well
+            Processor::update_sold_token_b_amount(swap_info, TradeDirection::BtoA, 1_000).unwrap();
+            let token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow()).unwrap();
+            assert_eq!(token_swap.sold_token_b_amount, 0);
+        });
+    }
+
+    #[test]
+    fn enable_emergency_mode_with_token_b_minted_outside_the_curve() {
+        let admin_key = Pubkey::new_unique();
+        // the curve sold 300 token B and built up 10 in fees, whatever else was minted (e.g. a supply the creator
+        // kept) isn't backed by the bonded token A
+        let token_swap = SwapV2 {
+            admin: COption::Some(admin_key),
+            sold_token_b_amount: 300,
+            accrued_token_b_fees: 10,
+            ..linear_price_swap()
+        };
+        let swap_key = Pubkey::new_unique();
+        let mut swap_lamports = 0;
+        let mut swap_data = vec![0u8; SwapVersion::LATEST_LEN];
+        SwapVersion::pack(SwapVersion::SwapV2(token_swap), &mut swap_data).unwrap();
+        let swap_info = AccountInfo::new(
+            &swap_key,
+            false,
+            true,
+            &mut swap_lamports,
+            &mut swap_data,
+            &crate::ID,
+            false,
+            0,
+        );
+        let mut admin_lamports = 0;
+        let mut admin_data = vec![];
+        let system_program_id = solana_program::system_program::id();
+        let admin_info = AccountInfo::new(
+            &admin_key,
+            true,
+            false,
+            &mut admin_lamports,
+            &mut admin_data,
+            &system_program_id,
+            false,
+            0,
+        );
+        let accounts = [swap_info, admin_info];
+
+        let event = Processor::process_enable_emergency_mode(&accounts).unwrap();
+        assert_eq!(event.circulating_token_b_amount, 310);
+        assert_eq!(event.bonded_token_a_amount, TEST_BONDED_TOKEN_A_AMOUNT);
+        let token_swap = SwapVersion::unpack_latest(&accounts[0].data.borrow()).unwrap();
+        assert!(token_swap.emergency_mode);
+        assert_eq!(token_swap.emergency_circulating_token_b_amount, 310);
+        let err = Processor::process_enable_emergency_mode(&accounts).err();
+        assert_eq!(err, Some(SwapError::EmergencyMode.into()));
+    }
 }
+
 /*
 #[cfg(test)]
 mod tests {
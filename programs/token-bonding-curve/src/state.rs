@@ -1,13 +1,19 @@
 //! State transition types
 
-use crate::curve::{base::SwapCurve, fees::Fees};
+use crate::{
+    curve::{base::SwapCurve, fees::Fees},
+    error::SwapError,
+};
+use anchor_lang::{AccountDeserialize, AccountSerialize, Owner};
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use enum_dispatch::enum_dispatch;
 use solana_program::{
     program_error::ProgramError,
+    program_option::COption,
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::Pubkey,
 };
+use std::convert::TryInto;
 
 /// Trait representing access to program state across all versions
 #[enum_dispatch]
@@ -42,8 +48,11 @@ pub trait SwapState {
 /// All versions of SwapState
 #[enum_dispatch(SwapState)]
 pub enum SwapVersion {
-    /// Latest version, used for all new swaps
+    /// Original version, which swaps created before SwapV2 still use until they're migrated with
+    /// `migrate_swap_v1_to_v2`
     SwapV1,
+    /// Latest version, used for all new swaps
+    SwapV2,
 }
 
 /// SwapVersion does not implement program_pack::Pack because there are size
@@ -51,30 +60,61 @@ pub enum SwapVersion {
 /// special implementations are provided here
 impl SwapVersion {
     /// Size of the latest version of the SwapState
-    pub const LATEST_LEN: usize = 1 + SwapV1::LEN; // add one for the version enum
+    pub const LATEST_LEN: usize = 1 + SwapV2::LEN; // add one for the version enum
 
     /// Pack a swap into a byte array, based on its version
     pub fn pack(src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        let (version, len) = match src {
+            Self::SwapV1(_) => (1, SwapV1::LEN),
+            Self::SwapV2(_) => (2, SwapV2::LEN),
+        };
+        let dst = dst
+            .get_mut(..1 + len)
+            .ok_or(ProgramError::AccountDataTooSmall)?;
+        dst[0] = version;
         match src {
-            Self::SwapV1(swap_info) => {
-                dst[0] = 1;
-                SwapV1::pack(swap_info, &mut dst[1..])
-            }
+            Self::SwapV1(swap_info) => SwapV1::pack(swap_info, &mut dst[1..]),
+            Self::SwapV2(swap_info) => SwapV2::pack(swap_info, &mut dst[1..]),
         }
     }
 
     /// Unpack the swap account based on its version, returning the result as a
     /// SwapState trait object
     pub fn unpack(input: &[u8]) -> Result<Box<dyn SwapState>, ProgramError> {
+        Ok(match Self::unpack_versioned(input)? {
+            Self::SwapV1(swap_info) => Box::new(swap_info),
+            Self::SwapV2(swap_info) => Box::new(swap_info),
+        })
+    }
+
+    /// Unpack the swap account based on its version, returning the SwapVersion itself so that it can be updated
+    /// and packed back with `pack`
+    pub fn unpack_versioned(input: &[u8]) -> Result<Self, ProgramError> {
         let (&version, rest) = input
             .split_first()
             .ok_or(ProgramError::InvalidAccountData)?;
         match version {
-            1 => Ok(Box::new(SwapV1::unpack(rest)?)),
+            1 => Ok(Self::SwapV1(SwapV1::unpack(
+                rest.get(..SwapV1::LEN)
+                    .ok_or(ProgramError::InvalidAccountData)?,
+            )?)),
+            2 => Ok(Self::SwapV2(SwapV2::unpack(
+                rest.get(..SwapV2::LEN)
+                    .ok_or(ProgramError::InvalidAccountData)?,
+            )?)),
             _ => Err(ProgramError::UninitializedAccount),
         }
     }
 
+    /// Unpack a swap account that's on the latest version, the only one that instructions other than
+    /// `migrate_swap_v1_to_v2` accept
+    pub fn unpack_latest(input: &[u8]) -> Result<SwapV2, ProgramError> {
+        match Self::unpack_versioned(input)? {
+            Self::SwapV2(swap_info) => Ok(swap_info),
+            Self::SwapV1(_) => Err(SwapError::IncorrectSwapVersion.into()),
+        }
+    }
+
     /// Special check to be done before any instruction processing, works for
     /// all versions
     pub fn is_initialized(input: &[u8]) -> bool {
@@ -83,9 +123,132 @@ impl SwapVersion {
             Err(_) => false,
         }
     }
+
+    /// Size of a TLV extension entry holding a value of `value_len` bytes, to add to `LATEST_LEN` when sizing a
+    /// swap account that stores extensions
+    pub const fn extension_len(value_len: usize) -> usize {
+        EXTENSION_HEADER_LEN + value_len
+    }
+
+    /// Finds the value of the given extension in the TLV extension area of a swap account, None if the swap
+    /// doesn't have it (which is always the case for SwapV1 swaps)
+    pub fn get_extension(
+        input: &[u8],
+        extension_type: SwapExtensionType,
+    ) -> Result<Option<&[u8]>, ProgramError> {
+        if input.first() != Some(&2) {
+            return Ok(None);
+        }
+        get_extension_value(
+            input.get(Self::LATEST_LEN..).unwrap_or(&[]),
+            extension_type as u16,
+        )
+    }
+
+    /// Writes the value of the given extension into the TLV extension area of a SwapV2 swap account, replacing
+    /// its current value (which must be the same size) or appending it after the existing extensions
+    pub fn set_extension(
+        dst: &mut [u8],
+        extension_type: SwapExtensionType,
+        value: &[u8],
+    ) -> Result<(), ProgramError> {
+        if dst.first() != Some(&2) {
+            return Err(SwapError::IncorrectSwapVersion.into());
+        }
+        if extension_type == SwapExtensionType::Uninitialized {
+            return Err(ProgramError::InvalidArgument);
+        }
+        set_extension_value(
+            dst.get_mut(Self::LATEST_LEN..)
+                .ok_or(ProgramError::AccountDataTooSmall)?,
+            extension_type as u16,
+            value,
+        )
+    }
+}
+
+/// Optional features stored in the TLV extension area after a SwapV2's fixed fields, each entry being a
+/// little-endian u16 type, a little-endian u16 length and the value itself
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SwapExtensionType {
+    /// Marks the start of the unused part of the extension area
+    Uninitialized,
+    /// Address of the swap's curve params account, for curves whose parameters don't fit in the swap (see
+    /// `CurveType::curve_params_len`)
+    CurveParams,
+}
+
+/// Size of the type and length that precede every extension's value
+const EXTENSION_HEADER_LEN: usize = 4;
+
+/// Walks the TLV entries of an extension area until `extension_type` or the unused part of the area is reached,
+/// returning the offset the walk stopped at and, if found, the entry's value offset and length. Entries of other
+/// types (including ones this version of the program doesn't know about) are skipped over
+fn find_extension(
+    extensions: &[u8],
+    extension_type: u16,
+) -> Result<(usize, Option<(usize, usize)>), ProgramError> {
+    let mut offset = 0;
+    while offset + EXTENSION_HEADER_LEN <= extensions.len() {
+        let header = array_ref![extensions, offset, EXTENSION_HEADER_LEN];
+        let (entry_type, len) = array_refs![header, 2, 2];
+        let entry_type = u16::from_le_bytes(*entry_type);
+        if entry_type == SwapExtensionType::Uninitialized as u16 {
+            break;
+        }
+        let value_offset = offset + EXTENSION_HEADER_LEN;
+        let len = u16::from_le_bytes(*len) as usize;
+        if value_offset + len > extensions.len() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if entry_type == extension_type {
+            return Ok((offset, Some((value_offset, len))));
+        }
+        offset = value_offset + len;
+    }
+    Ok((offset, None))
+}
+
+fn get_extension_value(
+    extensions: &[u8],
+    extension_type: u16,
+) -> Result<Option<&[u8]>, ProgramError> {
+    Ok(find_extension(extensions, extension_type)?
+        .1
+        .map(|(offset, len)| &extensions[offset..offset + len]))
+}
+
+fn set_extension_value(
+    extensions: &mut [u8],
+    extension_type: u16,
+    value: &[u8],
+) -> Result<(), ProgramError> {
+    match find_extension(extensions, extension_type)? {
+        (_, Some((offset, len))) => {
+            if len != value.len() {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            extensions[offset..offset + len].copy_from_slice(value);
+        }
+        (offset, None) => {
+            let len: u16 = value
+                .len()
+                .try_into()
+                .map_err(|_| ProgramError::InvalidArgument)?;
+            let entry = extensions
+                .get_mut(offset..offset + SwapVersion::extension_len(value.len()))
+                .ok_or(ProgramError::AccountDataTooSmall)?;
+            let (header, entry_value) = entry.split_at_mut(EXTENSION_HEADER_LEN);
+            header[..2].copy_from_slice(&extension_type.to_le_bytes());
+            header[2..].copy_from_slice(&len.to_le_bytes());
+            entry_value.copy_from_slice(value);
+        }
+    }
+    Ok(())
 }
 
-/// Program states.
+/// Original program state, kept so that swaps created before SwapV2 can still be read and migrated
 #[repr(C)]
 #[derive(Debug, Default, PartialEq)]
 pub struct SwapV1 {
@@ -122,7 +285,8 @@ pub struct SwapV1 {
     pub fees: Fees,
 
     /// Swap curve parameters, to be unpacked and used by the SwapCurve, which
-    /// calculates swaps, deposits, and withdrawals
+    /// calculates swaps, deposits, and withdrawals. SwapV1 only has room for 32
+    /// bytes of calculator parameters, the rest of the calculator is zeroed
     pub swap_curve: SwapCurve,
 }
 
@@ -207,7 +371,9 @@ impl Pack for SwapV1 {
         token_b_mint.copy_from_slice(self.token_b_mint.as_ref());
         pool_fee_account.copy_from_slice(self.pool_fee_account.as_ref());
         self.fees.pack_into_slice(&mut fees[..]);
-        self.swap_curve.pack_into_slice(&mut swap_curve[..]);
+        let mut packed_swap_curve = [0u8; SwapCurve::LEN];
+        self.swap_curve.pack_into_slice(&mut packed_swap_curve);
+        swap_curve.copy_from_slice(&packed_swap_curve[..33]);
     }
 
     /// Unpacks a byte buffer into a [SwapV1](struct.SwapV1.html).
@@ -227,6 +393,279 @@ impl Pack for SwapV1 {
             fees,
             swap_curve,
         ) = array_refs![input, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 33];
+        let mut packed_swap_curve = [0u8; SwapCurve::LEN];
+        packed_swap_curve[..33].copy_from_slice(swap_curve);
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            bump_seed: bump_seed[0],
+            token_program_id: Pubkey::new_from_array(*token_program_id),
+            token_a: Pubkey::new_from_array(*token_a),
+            token_b: Pubkey::new_from_array(*token_b),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            token_a_mint: Pubkey::new_from_array(*token_a_mint),
+            token_b_mint: Pubkey::new_from_array(*token_b_mint),
+            pool_fee_account: Pubkey::new_from_array(*pool_fee_account),
+            fees: Fees::unpack_from_slice(fees)?,
+            swap_curve: SwapCurve::unpack_from_slice(&packed_swap_curve)?,
+        })
+    }
+}
+
+/// Program states, followed in the account by a TLV extension area (see `SwapExtensionType`) for optional features
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SwapV2 {
+    /// Initialized state.
+    pub is_initialized: bool,
+    /// Bump seed used in program address.
+    /// The program address is created deterministically with the bump seed,
+    /// swap program id, and swap account pubkey.  This program address has
+    /// authority over the swap's token A account, token B account, and pool
+    /// token mint.
+    pub bump_seed: u8,
+
+    /// Program ID of the tokens being exchanged.
+    pub token_program_id: Pubkey,
+
+    /// Token A
+    pub token_a: Pubkey,
+    /// Token B
+    pub token_b: Pubkey,
+
+    /// Pool tokens are issued when A or B tokens are deposited.
+    /// Pool tokens can be withdrawn back to the original A or B token.
+    pub pool_mint: Pubkey,
+
+    /// Mint information for token A
+    pub token_a_mint: Pubkey,
+    /// Mint information for token B
+    pub token_b_mint: Pubkey,
+
+    /// Pool token account to receive trading and / or withdrawal fees
+    pub pool_fee_account: Pubkey,
+
+    /// All fee information
+    pub fees: Fees,
+
+    /// Swap curve parameters, to be unpacked and used by the SwapCurve, which
+    /// calculates swaps, deposits, and withdrawals
+    pub swap_curve: SwapCurve,
+
+    /// Amount of token A bonded to the curve, updated on every swap, deposit and withdrawal. The curve position is
+    /// derived from this instead of the token A account's balance (anyone can transfer tokens into that), and any
+    /// balance above it is surplus that doesn't move the price and can be swept by the pool owner
+    /// Only tracked for curves that collect fees in trading tokens, the AMM curves price against the token A
+    /// account's balance like spl token swap and leave it at 0
+    pub bonded_token_a_amount: u64,
+
+    /// Token B is minted on buys and burned on sells by the swap authority (which must be token B's mint authority)
+    /// rather than held as a fixed inventory in the token B account
+    pub token_b_mint_and_burn: bool,
+
+    /// Admin authority that can manage the swap, set at initialization and transferred with `set_admin` and
+    /// `accept_admin`, None once it's been renounced with `renounce_admin`
+    pub admin: COption<Pubkey>,
+    /// Admin proposed with `set_admin` that hasn't called `accept_admin` yet
+    pub pending_admin: COption<Pubkey>,
+
+    /// Swaps, deposits and withdrawals are paused by the admin (with `pause`, until `resume`)
+    pub paused: bool,
+
+    /// Emergency mode has been enabled by the admin (with `enable_emergency_mode`), which permanently disables
+    /// swaps, deposits and withdrawals and lets token B holders redeem their share of the bonded token A with
+    /// `emergency_redeem`
+    pub emergency_mode: bool,
+    /// Token B outside the swap that can still be redeemed in emergency mode, starts at the token B sold by the
+    /// curve plus the token B fees built up in the swap when emergency mode is enabled (see
+    /// `SwapV2::sold_token_b_amount`) and goes down with every redemption
+    pub emergency_circulating_token_b_amount: u64,
+
+    /// Trading token fees build up in the swap's token accounts for the owner of the pool fee account to claim
+    /// with `claim_fees`, instead of being sent to a fee account on every swap (set by the admin with
+    /// `set_accrue_fees`, only bonding curves collect fees in trading tokens)
+    pub accrue_fees: bool,
+    /// Token A fees built up in the token A account, which aren't bonded to the curve or swept as surplus
+    pub accrued_token_a_fees: u64,
+    /// Token B fees built up in the token B account, which the curve doesn't count as inventory
+    pub accrued_token_b_fees: u64,
+
+    /// Token B the curve has sold, net of the token B sold back to it, updated on every swap. Emergency mode
+    /// redeems this rather than token B's supply, which can include token B minted outside the curve that none of
+    /// the bonded token A backs. Only tracked for curves that collect fees in trading tokens, like
+    /// `bonded_token_a_amount`
+    pub sold_token_b_amount: u64,
+}
+
+impl SwapV2 {
+    /// Bytes reserved after the fixed fields for ones added later, always packed as zeroes
+    pub const RESERVED_LEN: usize = 94;
+}
+
+/// Lets instructions take the swap as a typed `Account<SwapV2>` and check the accounts passed with it through
+/// Anchor constraints (e.g. `has_one = pool_mint`). There's no Anchor discriminator, swaps keep the `SwapVersion`
+/// layout, so `SwapV1` swaps are still recognized (and rejected until they're migrated)
+impl AccountDeserialize for SwapV2 {
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self, ProgramError> {
+        SwapVersion::unpack_latest(buf)
+    }
+}
+
+/// The processor packs the swap itself with `SwapVersion::pack`, so nothing is written back when an instruction
+/// exits
+impl AccountSerialize for SwapV2 {}
+
+impl Owner for SwapV2 {
+    fn owner() -> Pubkey {
+        crate::ID
+    }
+}
+
+impl SwapState for SwapV2 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+
+    fn bump_seed(&self) -> u8 {
+        self.bump_seed
+    }
+
+    fn token_program_id(&self) -> &Pubkey {
+        &self.token_program_id
+    }
+
+    fn token_a_account(&self) -> &Pubkey {
+        &self.token_a
+    }
+
+    fn token_b_account(&self) -> &Pubkey {
+        &self.token_b
+    }
+
+    fn pool_mint(&self) -> &Pubkey {
+        &self.pool_mint
+    }
+
+    fn token_a_mint(&self) -> &Pubkey {
+        &self.token_a_mint
+    }
+
+    fn token_b_mint(&self) -> &Pubkey {
+        &self.token_b_mint
+    }
+
+    fn pool_fee_account(&self) -> &Pubkey {
+        &self.pool_fee_account
+    }
+
+    fn fees(&self) -> &Fees {
+        &self.fees
+    }
+
+    fn swap_curve(&self) -> &SwapCurve {
+        &self.swap_curve
+    }
+}
+
+impl Sealed for SwapV2 {}
+impl IsInitialized for SwapV2 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SwapV2 {
+    const LEN: usize = 541;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 541];
+        let (
+            is_initialized,
+            bump_seed,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            bonded_token_a_amount,
+            token_b_mint_and_burn,
+            admin,
+            pending_admin,
+            paused,
+            emergency_mode,
+            emergency_circulating_token_b_amount,
+            accrue_fees,
+            accrued_token_a_fees,
+            accrued_token_b_fees,
+            sold_token_b_amount,
+            reserved,
+        ) = mut_array_refs![
+            output, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 41, 8, 1, 36, 36, 1, 1, 8, 1, 8, 8, 8, 94
+        ];
+        is_initialized[0] = self.is_initialized as u8;
+        bump_seed[0] = self.bump_seed;
+        token_program_id.copy_from_slice(self.token_program_id.as_ref());
+        token_a.copy_from_slice(self.token_a.as_ref());
+        token_b.copy_from_slice(self.token_b.as_ref());
+        pool_mint.copy_from_slice(self.pool_mint.as_ref());
+        token_a_mint.copy_from_slice(self.token_a_mint.as_ref());
+        token_b_mint.copy_from_slice(self.token_b_mint.as_ref());
+        pool_fee_account.copy_from_slice(self.pool_fee_account.as_ref());
+        self.fees.pack_into_slice(&mut fees[..]);
+        self.swap_curve.pack_into_slice(&mut swap_curve[..]);
+        *bonded_token_a_amount = self.bonded_token_a_amount.to_le_bytes();
+        token_b_mint_and_burn[0] = self.token_b_mint_and_burn as u8;
+        pack_coption_key(&self.admin, admin);
+        pack_coption_key(&self.pending_admin, pending_admin);
+        paused[0] = self.paused as u8;
+        emergency_mode[0] = self.emergency_mode as u8;
+        *emergency_circulating_token_b_amount =
+            self.emergency_circulating_token_b_amount.to_le_bytes();
+        accrue_fees[0] = self.accrue_fees as u8;
+        *accrued_token_a_fees = self.accrued_token_a_fees.to_le_bytes();
+        *accrued_token_b_fees = self.accrued_token_b_fees.to_le_bytes();
+        *sold_token_b_amount = self.sold_token_b_amount.to_le_bytes();
+        *reserved = [0u8; Self::RESERVED_LEN];
+    }
+
+    /// Unpacks a byte buffer into a [SwapV2](struct.SwapV2.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 541];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            bump_seed,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            bonded_token_a_amount,
+            token_b_mint_and_burn,
+            admin,
+            pending_admin,
+            paused,
+            emergency_mode,
+            emergency_circulating_token_b_amount,
+            accrue_fees,
+            accrued_token_a_fees,
+            accrued_token_b_fees,
+            sold_token_b_amount,
+            _reserved,
+        ) = array_refs![
+            input, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 41, 8, 1, 36, 36, 1, 1, 8, 1, 8, 8, 8, 94
+        ];
         Ok(Self {
             is_initialized: match is_initialized {
                 [0] => false,
@@ -243,14 +682,279 @@ impl Pack for SwapV1 {
             pool_fee_account: Pubkey::new_from_array(*pool_fee_account),
             fees: Fees::unpack_from_slice(fees)?,
             swap_curve: SwapCurve::unpack_from_slice(swap_curve)?,
+            bonded_token_a_amount: u64::from_le_bytes(*bonded_token_a_amount),
+            token_b_mint_and_burn: match token_b_mint_and_burn {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            admin: unpack_coption_key(admin)?,
+            pending_admin: unpack_coption_key(pending_admin)?,
+            paused: match paused {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            emergency_mode: match emergency_mode {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            emergency_circulating_token_b_amount: u64::from_le_bytes(
+                *emergency_circulating_token_b_amount,
+            ),
+            accrue_fees: match accrue_fees {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            accrued_token_a_fees: u64::from_le_bytes(*accrued_token_a_fees),
+            accrued_token_b_fees: u64::from_le_bytes(*accrued_token_b_fees),
+            sold_token_b_amount: u64::from_le_bytes(*sold_token_b_amount),
+        })
+    }
+}
+
+/// Registry of every live swap with a collateral (token A) mint, so clients can list them without scanning the
+/// program's accounts. It's a PDA of the mint (see `Processor::registry_address`), and the swaps are recorded in a
+/// list of `RegistryPage`s linked from the last page back to the first. Swaps are recorded when they're initialized
+/// (or migrated to SwapV2) and removed when they're closed
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Registry {
+    /// Initialized state.
+    pub is_initialized: bool,
+    /// Collateral (token A) mint of the swaps in the registry
+    pub collateral_mint: Pubkey,
+    /// Number of pages, the pages are PDAs of the mint and their index (see `Processor::registry_page_address`)
+    pub page_count: u32,
+    /// Number of swaps recorded across all pages
+    pub swap_count: u64,
+    /// Last page, swaps are appended to it until it's full. None until the first swap is recorded
+    pub last_page: COption<Pubkey>,
+    /// Number of swaps recorded in the last page. Closed swaps leave gaps in the earlier pages, so this is what
+    /// tells whether the next swap goes in a new page
+    pub last_page_entry_count: u8,
+}
+
+impl Sealed for Registry {}
+impl IsInitialized for Registry {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Registry {
+    const LEN: usize = 82;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 82];
+        let (
+            is_initialized,
+            collateral_mint,
+            page_count,
+            swap_count,
+            last_page,
+            last_page_entry_count,
+        ) = mut_array_refs![output, 1, 32, 4, 8, 36, 1];
+        is_initialized[0] = self.is_initialized as u8;
+        collateral_mint.copy_from_slice(self.collateral_mint.as_ref());
+        *page_count = self.page_count.to_le_bytes();
+        *swap_count = self.swap_count.to_le_bytes();
+        pack_coption_key(&self.last_page, last_page);
+        last_page_entry_count[0] = self.last_page_entry_count;
+    }
+
+    /// Unpacks a byte buffer into a [Registry](struct.Registry.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 82];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            collateral_mint,
+            page_count,
+            swap_count,
+            last_page,
+            last_page_entry_count,
+        ) = array_refs![input, 1, 32, 4, 8, 36, 1];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            collateral_mint: Pubkey::new_from_array(*collateral_mint),
+            page_count: u32::from_le_bytes(*page_count),
+            swap_count: u64::from_le_bytes(*swap_count),
+            last_page: unpack_coption_key(last_page)?,
+            last_page_entry_count: last_page_entry_count[0],
+        })
+    }
+}
+
+/// Swap recorded in a `RegistryPage`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RegistryEntry {
+    /// Token-swap account
+    pub swap: Pubkey,
+    /// Bonded (token B) mint of the swap
+    pub bonded_mint: Pubkey,
+    /// Curve type, see `CurveType`
+    pub curve_type: u8,
+    /// Slot the swap was initialized in, or migrated to SwapV2 in for swaps created before the registry
+    pub creation_slot: u64,
+    /// Admin that initialized the swap. Anyone can initialize a swap, so clients should only trust the swaps of
+    /// creators they know
+    pub creator: Pubkey,
+    /// Whether the swap is paused, kept up to date by `pause` and `resume`
+    pub paused: bool,
+}
+
+impl Sealed for RegistryEntry {}
+
+impl Pack for RegistryEntry {
+    const LEN: usize = 106;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 106];
+        let (swap, bonded_mint, curve_type, creation_slot, creator, paused) =
+            mut_array_refs![output, 32, 32, 1, 8, 32, 1];
+        swap.copy_from_slice(self.swap.as_ref());
+        bonded_mint.copy_from_slice(self.bonded_mint.as_ref());
+        curve_type[0] = self.curve_type;
+        *creation_slot = self.creation_slot.to_le_bytes();
+        creator.copy_from_slice(self.creator.as_ref());
+        paused[0] = self.paused as u8;
+    }
+
+    /// Unpacks a byte buffer into a [RegistryEntry](struct.RegistryEntry.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 106];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (swap, bonded_mint, curve_type, creation_slot, creator, paused) =
+            array_refs![input, 32, 32, 1, 8, 32, 1];
+        Ok(Self {
+            swap: Pubkey::new_from_array(*swap),
+            bonded_mint: Pubkey::new_from_array(*bonded_mint),
+            curve_type: curve_type[0],
+            creation_slot: u64::from_le_bytes(*creation_slot),
+            creator: Pubkey::new_from_array(*creator),
+            paused: match paused {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
         })
     }
 }
 
+/// Page of a `Registry`, holding up to `RegistryPage::CAPACITY` swaps in the order they were initialized. Closing a
+/// swap removes it from its page, the later pages aren't moved up to fill the gap so only the last page fills up again
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RegistryPage {
+    /// Initialized state.
+    pub is_initialized: bool,
+    /// Registry the page belongs to
+    pub registry: Pubkey,
+    /// Index of the page in the registry, starting at 0
+    pub index: u32,
+    /// Previous page, None for the first page
+    pub prev_page: COption<Pubkey>,
+    /// Swaps recorded in the page
+    pub entries: Vec<RegistryEntry>,
+}
+
+impl RegistryPage {
+    /// Number of swaps a page can hold
+    pub const CAPACITY: usize = 32;
+}
+
+impl Sealed for RegistryPage {}
+impl IsInitialized for RegistryPage {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for RegistryPage {
+    const LEN: usize = 74 + RegistryEntry::LEN * RegistryPage::CAPACITY;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let (header, entries) = output.split_at_mut(74);
+        let header = array_mut_ref![header, 0, 74];
+        let (is_initialized, registry, index, prev_page, entry_count) =
+            mut_array_refs![header, 1, 32, 4, 36, 1];
+        is_initialized[0] = self.is_initialized as u8;
+        registry.copy_from_slice(self.registry.as_ref());
+        *index = self.index.to_le_bytes();
+        pack_coption_key(&self.prev_page, prev_page);
+        entry_count[0] = self.entries.len() as u8;
+        for (entry, output) in self
+            .entries
+            .iter()
+            .zip(entries.chunks_exact_mut(RegistryEntry::LEN))
+        {
+            entry.pack_into_slice(output);
+        }
+    }
+
+    /// Unpacks a byte buffer into a [RegistryPage](struct.RegistryPage.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let (header, entries) = input.split_at(74);
+        let header = array_ref![header, 0, 74];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, registry, index, prev_page, entry_count) =
+            array_refs![header, 1, 32, 4, 36, 1];
+        let entry_count = entry_count[0] as usize;
+        if entry_count > Self::CAPACITY {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            registry: Pubkey::new_from_array(*registry),
+            index: u32::from_le_bytes(*index),
+            prev_page: unpack_coption_key(prev_page)?,
+            entries: entries
+                .chunks_exact(RegistryEntry::LEN)
+                .take(entry_count)
+                .map(RegistryEntry::unpack_from_slice)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+// Helpers, packed the same way as spl_token's COption<Pubkey> fields
+fn pack_coption_key(src: &COption<Pubkey>, dst: &mut [u8; 36]) {
+    let (tag, body) = mut_array_refs![dst, 4, 32];
+    match src {
+        COption::Some(key) => {
+            *tag = [1, 0, 0, 0];
+            body.copy_from_slice(key.as_ref());
+        }
+        COption::None => {
+            *tag = [0; 4];
+        }
+    }
+}
+fn unpack_coption_key(src: &[u8; 36]) -> Result<COption<Pubkey>, ProgramError> {
+    let (tag, body) = array_refs![src, 4, 32];
+    match *tag {
+        [0, 0, 0, 0] => Ok(COption::None),
+        [1, 0, 0, 0] => Ok(COption::Some(Pubkey::new_from_array(*body))),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::curve::stable::StableCurve;
+    use crate::curve::{base::CurveType, linear_price::LinearPriceCurve, stable::StableCurve};
 
     use std::convert::TryInto;
 
@@ -278,15 +982,22 @@ mod tests {
     const TEST_AMP: u64 = 1;
     const TEST_CURVE: StableCurve = StableCurve { amp: TEST_AMP };
 
-    #[test]
-    fn swap_version_pack() {
-        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
-        let calculator = Box::new(TEST_CURVE);
-        let swap_curve = SwapCurve {
-            curve_type,
-            calculator,
-        };
-        let swap_info = SwapVersion::SwapV1(SwapV1 {
+    const TEST_BONDED_TOKEN_A_AMOUNT: u64 = 1_000;
+    const TEST_ADMIN: Pubkey = Pubkey::new_from_array([8u8; 32]);
+    const TEST_EMERGENCY_CIRCULATING_TOKEN_B_AMOUNT: u64 = 500;
+    const TEST_ACCRUED_TOKEN_A_FEES: u64 = 20;
+    const TEST_ACCRUED_TOKEN_B_FEES: u64 = 30;
+    const TEST_SOLD_TOKEN_B_AMOUNT: u64 = 700;
+
+    fn test_swap_curve() -> SwapCurve {
+        SwapCurve {
+            curve_type: TEST_CURVE_TYPE.try_into().unwrap(),
+            calculator: Box::new(TEST_CURVE),
+        }
+    }
+
+    fn test_swap_v1() -> SwapV1 {
+        SwapV1 {
             is_initialized: true,
             bump_seed: TEST_BUMP_SEED,
             token_program_id: TEST_TOKEN_PROGRAM_ID,
@@ -297,35 +1008,12 @@ mod tests {
             token_b_mint: TEST_TOKEN_B_MINT,
             pool_fee_account: TEST_POOL_FEE_ACCOUNT,
             fees: TEST_FEES,
-            swap_curve: swap_curve.clone(),
-        });
-
-        let mut packed = [0u8; SwapVersion::LATEST_LEN];
-        SwapVersion::pack(swap_info, &mut packed).unwrap();
-        let unpacked = SwapVersion::unpack(&packed).unwrap();
-
-        assert!(unpacked.is_initialized());
-        assert_eq!(unpacked.bump_seed(), TEST_BUMP_SEED);
-        assert_eq!(*unpacked.token_program_id(), TEST_TOKEN_PROGRAM_ID);
-        assert_eq!(*unpacked.token_a_account(), TEST_TOKEN_A);
-        assert_eq!(*unpacked.token_b_account(), TEST_TOKEN_B);
-        assert_eq!(*unpacked.pool_mint(), TEST_POOL_MINT);
-        assert_eq!(*unpacked.token_a_mint(), TEST_TOKEN_A_MINT);
-        assert_eq!(*unpacked.token_b_mint(), TEST_TOKEN_B_MINT);
-        assert_eq!(*unpacked.pool_fee_account(), TEST_POOL_FEE_ACCOUNT);
-        assert_eq!(*unpacked.fees(), TEST_FEES);
-        assert_eq!(*unpacked.swap_curve(), swap_curve);
+            swap_curve: test_swap_curve(),
+        }
     }
 
-    #[test]
-    fn swap_v1_pack() {
-        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
-        let calculator = Box::new(TEST_CURVE);
-        let swap_curve = SwapCurve {
-            curve_type,
-            calculator,
-        };
-        let swap_info = SwapV1 {
+    fn test_swap_v2() -> SwapV2 {
+        SwapV2 {
             is_initialized: true,
             bump_seed: TEST_BUMP_SEED,
             token_program_id: TEST_TOKEN_PROGRAM_ID,
@@ -336,14 +1024,22 @@ mod tests {
             token_b_mint: TEST_TOKEN_B_MINT,
             pool_fee_account: TEST_POOL_FEE_ACCOUNT,
             fees: TEST_FEES,
-            swap_curve,
-        };
-
-        let mut packed = [0u8; SwapV1::LEN];
-        SwapV1::pack_into_slice(&swap_info, &mut packed);
-        let unpacked = SwapV1::unpack(&packed).unwrap();
-        assert_eq!(swap_info, unpacked);
+            swap_curve: test_swap_curve(),
+            bonded_token_a_amount: TEST_BONDED_TOKEN_A_AMOUNT,
+            token_b_mint_and_burn: true,
+            admin: COption::Some(TEST_ADMIN),
+            pending_admin: COption::None,
+            paused: true,
+            emergency_mode: true,
+            emergency_circulating_token_b_amount: TEST_EMERGENCY_CIRCULATING_TOKEN_B_AMOUNT,
+            accrue_fees: true,
+            accrued_token_a_fees: TEST_ACCRUED_TOKEN_A_FEES,
+            accrued_token_b_fees: TEST_ACCRUED_TOKEN_B_FEES,
+            sold_token_b_amount: TEST_SOLD_TOKEN_B_AMOUNT,
+        }
+    }
 
+    fn packed_common_fields() -> Vec<u8> {
         let mut packed = vec![1u8, TEST_BUMP_SEED];
         packed.extend_from_slice(&TEST_TOKEN_PROGRAM_ID.to_bytes());
         packed.extend_from_slice(&TEST_TOKEN_A.to_bytes());
@@ -362,6 +1058,87 @@ mod tests {
         packed.extend_from_slice(&TEST_FEES.host_fee_denominator.to_le_bytes());
         packed.push(TEST_CURVE_TYPE);
         packed.extend_from_slice(&TEST_AMP.to_le_bytes());
+        packed
+    }
+
+    #[test]
+    fn swap_version_pack() {
+        let swap_curve = test_swap_curve();
+        let swap_info = SwapVersion::SwapV2(test_swap_v2());
+
+        let mut packed = [0u8; SwapVersion::LATEST_LEN];
+        SwapVersion::pack(swap_info, &mut packed).unwrap();
+        assert_eq!(packed[0], 2);
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+
+        assert!(unpacked.is_initialized());
+        assert_eq!(unpacked.bump_seed(), TEST_BUMP_SEED);
+        assert_eq!(*unpacked.token_program_id(), TEST_TOKEN_PROGRAM_ID);
+        assert_eq!(*unpacked.token_a_account(), TEST_TOKEN_A);
+        assert_eq!(*unpacked.token_b_account(), TEST_TOKEN_B);
+        assert_eq!(*unpacked.pool_mint(), TEST_POOL_MINT);
+        assert_eq!(*unpacked.token_a_mint(), TEST_TOKEN_A_MINT);
+        assert_eq!(*unpacked.token_b_mint(), TEST_TOKEN_B_MINT);
+        assert_eq!(*unpacked.pool_fee_account(), TEST_POOL_FEE_ACCOUNT);
+        assert_eq!(*unpacked.fees(), TEST_FEES);
+        assert_eq!(*unpacked.swap_curve(), swap_curve);
+
+        let mut latest = SwapVersion::unpack_latest(&packed).unwrap();
+        assert_eq!(latest, test_swap_v2());
+        latest.bonded_token_a_amount += 1;
+        SwapVersion::pack(SwapVersion::SwapV2(latest), &mut packed).unwrap();
+        let latest = SwapVersion::unpack_latest(&packed).unwrap();
+        assert_eq!(latest.bonded_token_a_amount, TEST_BONDED_TOKEN_A_AMOUNT + 1);
+
+        // accounts can be larger than the latest version to hold extensions
+        let mut packed = [0u8; SwapVersion::LATEST_LEN + 10];
+        SwapVersion::pack(SwapVersion::SwapV2(test_swap_v2()), &mut packed).unwrap();
+        assert_eq!(SwapVersion::unpack_latest(&packed).unwrap(), test_swap_v2());
+    }
+
+    #[test]
+    fn swap_version_unpack_v1() {
+        let mut packed = [0u8; 1 + SwapV1::LEN];
+        SwapVersion::pack(SwapVersion::SwapV1(test_swap_v1()), &mut packed).unwrap();
+        assert_eq!(packed[0], 1);
+
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert!(unpacked.is_initialized());
+        assert_eq!(*unpacked.pool_fee_account(), TEST_POOL_FEE_ACCOUNT);
+        assert_eq!(*unpacked.swap_curve(), test_swap_curve());
+        assert!(SwapVersion::is_initialized(&packed));
+        match SwapVersion::unpack_versioned(&packed).unwrap() {
+            SwapVersion::SwapV1(swap_info) => assert_eq!(swap_info, test_swap_v1()),
+            SwapVersion::SwapV2(_) => panic!("expected a SwapV1"),
+        }
+
+        let err = SwapVersion::unpack_latest(&packed).unwrap_err();
+        assert_eq!(err, SwapError::IncorrectSwapVersion.into());
+    }
+
+    #[test]
+    fn swap_v2_account_deserialize() {
+        let mut packed = [0u8; SwapVersion::LATEST_LEN];
+        SwapVersion::pack(SwapVersion::SwapV2(test_swap_v2()), &mut packed).unwrap();
+        let swap_info = SwapV2::try_deserialize(&mut &packed[..]).unwrap();
+        assert_eq!(swap_info, test_swap_v2());
+
+        let mut packed = [0u8; 1 + SwapV1::LEN];
+        SwapVersion::pack(SwapVersion::SwapV1(test_swap_v1()), &mut packed).unwrap();
+        let err = SwapV2::try_deserialize(&mut &packed[..]).unwrap_err();
+        assert_eq!(err, SwapError::IncorrectSwapVersion.into());
+    }
+
+    #[test]
+    fn swap_v1_pack() {
+        let swap_info = test_swap_v1();
+
+        let mut packed = [0u8; SwapV1::LEN];
+        SwapV1::pack_into_slice(&swap_info, &mut packed);
+        let unpacked = SwapV1::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        let mut packed = packed_common_fields();
         packed.extend_from_slice(&[0u8; 24]);
         let unpacked = SwapV1::unpack(&packed).unwrap();
         assert_eq!(swap_info, unpacked);
@@ -373,4 +1150,205 @@ mod tests {
         let err = SwapV1::unpack(&packed).unwrap_err();
         assert_eq!(err, ProgramError::UninitializedAccount);
     }
+
+    #[test]
+    fn swap_v1_linear_price_pack() {
+        // V1 accounts were written before initial_token_a_amount was added to the linear curve, so the curve
+        // keeps its 33 byte slot and the amount unpacks as 0
+        let swap_info = SwapV1 {
+            swap_curve: SwapCurve {
+                curve_type: CurveType::LinearPrice,
+                calculator: Box::new(LinearPriceCurve {
+                    slope_numerator: 1,
+                    slope_denominator: 2,
+                    initial_token_a_price_numerator: 3,
+                    initial_token_a_price_denominator: 4,
+                    initial_token_a_amount: 0,
+                }),
+            },
+            ..test_swap_v1()
+        };
+
+        let mut packed = [0u8; SwapV1::LEN];
+        SwapV1::pack_into_slice(&swap_info, &mut packed);
+        assert_eq!(packed.len(), 323);
+        assert_eq!(packed[SwapV1::LEN - 33], CurveType::LinearPrice as u8);
+        let unpacked = SwapV1::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+    }
+
+    #[test]
+    fn swap_v2_pack() {
+        let swap_info = test_swap_v2();
+
+        let mut packed = [0u8; SwapV2::LEN];
+        SwapV2::pack_into_slice(&swap_info, &mut packed);
+        let unpacked = SwapV2::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        let mut packed = packed_common_fields();
+        packed.extend_from_slice(&[0u8; 32]);
+        packed.extend_from_slice(&TEST_BONDED_TOKEN_A_AMOUNT.to_le_bytes());
+        packed.push(1);
+        packed.extend_from_slice(&[1, 0, 0, 0]);
+        packed.extend_from_slice(&TEST_ADMIN.to_bytes());
+        packed.extend_from_slice(&[0u8; 36]);
+        packed.push(1);
+        packed.push(1);
+        packed.extend_from_slice(&TEST_EMERGENCY_CIRCULATING_TOKEN_B_AMOUNT.to_le_bytes());
+        packed.push(1);
+        packed.extend_from_slice(&TEST_ACCRUED_TOKEN_A_FEES.to_le_bytes());
+        packed.extend_from_slice(&TEST_ACCRUED_TOKEN_B_FEES.to_le_bytes());
+        packed.extend_from_slice(&TEST_SOLD_TOKEN_B_AMOUNT.to_le_bytes());
+        packed.extend_from_slice(&[0u8; SwapV2::RESERVED_LEN]);
+        let unpacked = SwapV2::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        let packed = [0u8; SwapV2::LEN];
+        let swap_info: SwapV2 = Default::default();
+        let unpack_unchecked = SwapV2::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpack_unchecked, swap_info);
+        let err = SwapV2::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn registry_pack() {
+        let registry = Registry {
+            is_initialized: true,
+            collateral_mint: TEST_TOKEN_A_MINT,
+            page_count: 2,
+            swap_count: RegistryPage::CAPACITY as u64 + 1,
+            last_page: COption::Some(TEST_POOL_MINT),
+            last_page_entry_count: 1,
+        };
+        let mut packed = [0u8; Registry::LEN];
+        Registry::pack(registry.clone(), &mut packed).unwrap();
+        assert_eq!(Registry::unpack(&packed).unwrap(), registry);
+
+        let entry = RegistryEntry {
+            swap: TEST_TOKEN_A,
+            bonded_mint: TEST_TOKEN_B_MINT,
+            curve_type: TEST_CURVE_TYPE,
+            creation_slot: 1234,
+            creator: TEST_POOL_FEE_ACCOUNT,
+            paused: true,
+        };
+        let mut registry_page = RegistryPage {
+            is_initialized: true,
+            registry: TEST_TOKEN_B,
+            index: 1,
+            prev_page: COption::Some(TEST_POOL_FEE_ACCOUNT),
+            entries: vec![entry],
+        };
+        let mut packed = vec![0u8; RegistryPage::LEN];
+        RegistryPage::pack(registry_page.clone(), &mut packed).unwrap();
+        assert_eq!(packed[73], 1);
+        assert_eq!(&packed[74..74 + 32], TEST_TOKEN_A.as_ref());
+        assert_eq!(packed[74 + RegistryEntry::LEN - 1], 1);
+        assert_eq!(RegistryPage::unpack(&packed).unwrap(), registry_page);
+
+        // entries past the count are ignored, and a full page packs into the same size
+        registry_page.entries = vec![entry; RegistryPage::CAPACITY];
+        RegistryPage::pack(registry_page.clone(), &mut packed).unwrap();
+        assert_eq!(RegistryPage::unpack(&packed).unwrap(), registry_page);
+        packed[73] = 1;
+        assert_eq!(RegistryPage::unpack(&packed).unwrap().entries, vec![entry]);
+        packed[73] = RegistryPage::CAPACITY as u8 + 1;
+        assert_eq!(
+            RegistryPage::unpack(&packed).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+    }
+
+    #[test]
+    fn swap_extensions() {
+        // raw types, the TLV walk doesn't care which extensions the program defines
+        const TEST_EXTENSION_TYPE: u16 = 7;
+        const OTHER_EXTENSION_TYPE: u16 = 8;
+
+        let mut extensions = [0u8; 2 * EXTENSION_HEADER_LEN + 8 + 2];
+        assert_eq!(
+            get_extension_value(&extensions, TEST_EXTENSION_TYPE).unwrap(),
+            None
+        );
+
+        set_extension_value(&mut extensions, OTHER_EXTENSION_TYPE, &[1, 2]).unwrap();
+        set_extension_value(&mut extensions, TEST_EXTENSION_TYPE, &[3u8; 8]).unwrap();
+        assert_eq!(
+            get_extension_value(&extensions, OTHER_EXTENSION_TYPE).unwrap(),
+            Some(&[1u8, 2][..])
+        );
+        assert_eq!(
+            get_extension_value(&extensions, TEST_EXTENSION_TYPE).unwrap(),
+            Some(&[3u8; 8][..])
+        );
+        let mut expected = vec![8, 0, 2, 0, 1, 2, 7, 0, 8, 0];
+        expected.extend_from_slice(&[3u8; 8]);
+        assert_eq!(extensions.to_vec(), expected);
+
+        // overwritten in place, only with a value of the same size
+        set_extension_value(&mut extensions, TEST_EXTENSION_TYPE, &[4u8; 8]).unwrap();
+        assert_eq!(
+            get_extension_value(&extensions, TEST_EXTENSION_TYPE).unwrap(),
+            Some(&[4u8; 8][..])
+        );
+        assert_eq!(
+            set_extension_value(&mut extensions, TEST_EXTENSION_TYPE, &[4u8; 7]).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+
+        // no room left to append
+        assert_eq!(
+            set_extension_value(&mut extensions, 9, &[]).unwrap_err(),
+            ProgramError::AccountDataTooSmall
+        );
+
+        // values running past the end of the area are rejected
+        extensions[8] = 9;
+        assert_eq!(
+            get_extension_value(&extensions, TEST_EXTENSION_TYPE).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+
+        // the extension area starts after the latest version's fixed fields, and SwapV1 swaps don't have one
+        let curve_params_account = Pubkey::new_from_array([9u8; 32]);
+        let mut packed = vec![0u8; SwapVersion::LATEST_LEN + SwapVersion::extension_len(32)];
+        SwapVersion::pack(SwapVersion::SwapV2(test_swap_v2()), &mut packed).unwrap();
+        assert_eq!(
+            SwapVersion::get_extension(&packed, SwapExtensionType::CurveParams).unwrap(),
+            None
+        );
+        SwapVersion::set_extension(
+            &mut packed,
+            SwapExtensionType::CurveParams,
+            curve_params_account.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(
+            SwapVersion::get_extension(&packed, SwapExtensionType::CurveParams).unwrap(),
+            Some(curve_params_account.as_ref())
+        );
+        assert_eq!(
+            &packed[SwapVersion::LATEST_LEN..SwapVersion::LATEST_LEN + EXTENSION_HEADER_LEN],
+            &[1, 0, 32, 0]
+        );
+        assert_eq!(SwapVersion::unpack_latest(&packed).unwrap(), test_swap_v2());
+        assert_eq!(
+            SwapVersion::set_extension(&mut packed, SwapExtensionType::Uninitialized, &[])
+                .unwrap_err(),
+            ProgramError::InvalidArgument
+        );
+        let mut packed = [0u8; 1 + SwapV1::LEN + 8];
+        SwapVersion::pack(SwapVersion::SwapV1(test_swap_v1()), &mut packed).unwrap();
+        assert_eq!(
+            SwapVersion::get_extension(&packed, SwapExtensionType::Uninitialized).unwrap(),
+            None
+        );
+        assert_eq!(
+            SwapVersion::set_extension(&mut packed, SwapExtensionType::Uninitialized, &[])
+                .unwrap_err(),
+            SwapError::IncorrectSwapVersion.into()
+        );
+    }
 }
@@ -0,0 +1,75 @@
+//! spl-token accounts wrapped so instructions can take them as Anchor's typed `Account` and validate them with
+//! `#[account(...)]` constraints (the same wrappers anchor-spl has, without pulling in the whole crate)
+
+use crate::error::SwapError;
+use anchor_lang::prelude::*;
+use solana_program::program_pack::Pack;
+use std::ops::Deref;
+
+/// spl-token program, for `Program<'info, Token>`
+#[derive(Clone)]
+pub struct Token;
+
+impl Id for Token {
+    fn id() -> Pubkey {
+        spl_token::ID
+    }
+}
+
+/// spl-token account, owned by the spl-token program
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TokenAccount(spl_token::state::Account);
+
+impl AccountDeserialize for TokenAccount {
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self, ProgramError> {
+        spl_token::state::Account::unpack(buf)
+            .map(TokenAccount)
+            .map_err(|_| SwapError::ExpectedAccount.into())
+    }
+}
+
+/// Token accounts are only ever written by the spl-token program
+impl AccountSerialize for TokenAccount {}
+
+impl Owner for TokenAccount {
+    fn owner() -> Pubkey {
+        spl_token::ID
+    }
+}
+
+impl Deref for TokenAccount {
+    type Target = spl_token::state::Account;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// spl-token mint, owned by the spl-token program
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mint(spl_token::state::Mint);
+
+impl AccountDeserialize for Mint {
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self, ProgramError> {
+        spl_token::state::Mint::unpack(buf)
+            .map(Mint)
+            .map_err(|_| SwapError::ExpectedMint.into())
+    }
+}
+
+/// Mints are only ever written by the spl-token program
+impl AccountSerialize for Mint {}
+
+impl Owner for Mint {
+    fn owner() -> Pubkey {
+        spl_token::ID
+    }
+}
+
+impl Deref for Mint {
+    type Target = spl_token::state::Mint;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
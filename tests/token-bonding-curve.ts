@@ -6,7 +6,18 @@ import { AccountLayout, MintLayout, Token, TOKEN_PROGRAM_ID } from '@solana/spl-
 
 
 const TOKEN_PROGRAM_PUBKEY = new anchor.web3.PublicKey(TOKEN_PROGRAM_ID);
-const SWAP_ACCOUNT_SPACE = 324;
+const SWAP_ACCOUNT_SPACE = 542;
+// `Fees` for `initialize` with every fee set to 0
+const ZERO_FEES = {
+  tradeFeeNumerator: new anchor.BN(0),
+  tradeFeeDenominator: new anchor.BN(1),
+  ownerTradeFeeNumerator: new anchor.BN(0),
+  ownerTradeFeeDenominator: new anchor.BN(1),
+  ownerWithdrawFeeNumerator: new anchor.BN(0),
+  ownerWithdrawFeeDenominator: new anchor.BN(1),
+  hostFeeNumerator: new anchor.BN(0),
+  hostFeeDenominator: new anchor.BN(1),
+};
 
 const generateNewSignerAccount = async (provider: anchor.Provider) => {
   return generateNewGenericAccount(provider, provider.wallet.publicKey, 8 + 8, anchor.web3.SystemProgram.programId, 10);
@@ -120,6 +131,56 @@ const mintToAccount = async (
   await provider.send(tx, [authority]);
 }
 
+// registry of the swaps whose token A account is `tokenA`, and the address of one of its pages (see `Registry` and
+// `RegistryPage` for the layouts)
+const REGISTRY_PAGE_CAPACITY = 32;
+const REGISTRY_ENTRY_LEN = 106;
+const findRegistry = async (provider: anchor.Provider, programId: PublicKey, tokenA: PublicKey) => {
+  const collateralMint = new PublicKey(
+    AccountLayout.decode((await provider.connection.getAccountInfo(tokenA)).data).mint);
+  const registry = (await anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("registry"), collateralMint.toBuffer()], programId))[0];
+  const findPage = async (index: number) => {
+    const pageIndex = Buffer.alloc(4);
+    pageIndex.writeUInt32LE(index);
+    return (await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("registry_page"), collateralMint.toBuffer(), pageIndex], programId))[0];
+  };
+  return { registry, registryInfo: await provider.connection.getAccountInfo(registry), findPage };
+}
+
+// registry and registry page (plus payer and system program) accounts for initializing a swap whose token A account is
+// `tokenA`, see `Processor::register_swap`
+const findRegistryAccounts = async (provider: anchor.Provider, programId: PublicKey, tokenA: PublicKey) => {
+  const { registry, registryInfo, findPage } = await findRegistry(provider, programId, tokenA);
+  // new swaps go on the last page, or on a new one once it's full
+  const pageCount = registryInfo ? registryInfo.data.readUInt32LE(33) : 0;
+  const lastPageFull = !registryInfo || pageCount == 0 || registryInfo.data[81] == REGISTRY_PAGE_CAPACITY;
+  return {
+    registry,
+    registryPage: await findPage(lastPageFull ? pageCount : pageCount - 1),
+    payer: provider.wallet.publicKey,
+    systemProgram: anchor.web3.SystemProgram.programId,
+  };
+}
+
+// registry and the page of it `swap` is recorded in, for `pause`, `resume` and `closeSwap`
+const findRegistryPageOfSwap = async (
+  provider: anchor.Provider, programId: PublicKey, tokenA: PublicKey, swap: PublicKey) => {
+  const { registry, registryInfo, findPage } = await findRegistry(provider, programId, tokenA);
+  for (let index = 0; index < registryInfo.data.readUInt32LE(33); index++) {
+    const registryPage = await findPage(index);
+    const data = (await provider.connection.getAccountInfo(registryPage)).data;
+    for (let entry = 0; entry < data[73]; entry++) {
+      const offset = 74 + entry * REGISTRY_ENTRY_LEN;
+      if (new PublicKey(data.slice(offset, offset + 32)).equals(swap)) {
+        return { registry, registryPage };
+      }
+    }
+  }
+  throw new Error("swap isn't recorded in the registry");
+}
+
 describe('token-bonding-curve', () => {
 
   // Configure the client to use the local cluster.
@@ -193,7 +254,8 @@ describe('token-bonding-curve', () => {
     let token_b_price = new anchor.BN(5);
 
     const tx = await program.rpc.initializeConstantPrice(
-      // TODO: not sure why passing fees in here doesn't work, need to look into it
+      // the loose fee args are kept for existing clients, `initialize` takes a Fees struct instead (note its fields
+      // are camelCase on the client, unlike `fees` above)
       trade_fee_numerator,
       trade_fee_denominator,
       owner_trade_fee_numerator,
@@ -213,6 +275,8 @@ describe('token-bonding-curve', () => {
           fee: feeTokenAccount.publicKey,
           destination: destinationTokenAccount.publicKey,
           tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, aTokenSwapAccount.publicKey)),
         },
         signers: [tokenSwap],
       });
@@ -277,6 +341,52 @@ describe('token-bonding-curve', () => {
     assert.strictEqual(
       (await bToken.getAccountInfo(bTokenSwapAccount.publicKey)).amount.toString(),
       "1996.20000000".replace(".", ""));
+
+    // a frontend hosting the swap can pass a pool token account after the regular accounts to earn the host fee
+    // (11% of the owner fee's pool tokens)
+    const hostAuthority = await generateNewSignerAccount(provider);
+    const hostFeeTokenAccount = await generateTokenAccount(provider, poolTokenMint, hostAuthority.publicKey);
+    const feeTokenAmountBefore = (await poolToken.getAccountInfo(feeTokenAccount.publicKey)).amount.toNumber();
+    const hostSwapAccounts = {
+      tokenSwap: tokenSwap.publicKey,
+      swapAuthority: swapAuthority,
+      userTransferAuthority: swapUser.publicKey,
+      source: aTokenUserAccount.publicKey,
+      swapSource: aTokenSwapAccount.publicKey,
+      swapDestination: bTokenSwapAccount.publicKey,
+      destination: bTokenUserAccount.publicKey,
+      poolMint: poolTokenMint.publicKey,
+      poolFee: feeTokenAccount.publicKey,
+      tokenProgram: TOKEN_PROGRAM_PUBKEY,
+    };
+
+    // the host fee account has to be a pool token account
+    await assert.rejects(program.rpc.swap(
+      new anchor.BN(5 * 10 ** 8),
+      minimum_amount_out,
+      {
+        accounts: hostSwapAccounts,
+        remainingAccounts: [{ pubkey: aTokenUserAccount.publicKey, isWritable: true, isSigner: false }],
+        signers: [swapUser]
+      },
+    ));
+
+    await program.rpc.swap(
+      new anchor.BN(5 * 10 ** 8),
+      minimum_amount_out,
+      {
+        accounts: hostSwapAccounts,
+        remainingAccounts: [{ pubkey: hostFeeTokenAccount.publicKey, isWritable: true, isSigner: false }],
+        signers: [swapUser]
+      },
+    );
+
+    // the host and the pool fee account both get pool tokens out of the owner fee
+    const hostFeeTokenAmount = (await poolToken.getAccountInfo(hostFeeTokenAccount.publicKey)).amount.toNumber();
+    const feeTokenAmountAfter = (await poolToken.getAccountInfo(feeTokenAccount.publicKey)).amount.toNumber();
+    assert.ok(hostFeeTokenAmount > 0);
+    assert.ok(feeTokenAmountAfter > feeTokenAmountBefore);
+    assert.ok(hostFeeTokenAmount < feeTokenAmountAfter - feeTokenAmountBefore);
   });
 
   const generateTestLinearSwapAccounts = async (programId: PublicKey, cTokenInitialSupply: number) => {
@@ -384,6 +494,8 @@ describe('token-bonding-curve', () => {
           fee: feeTokenAccount.publicKey,
           destination: destinationTokenAccount.publicKey,
           tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
         },
         signers: [tokenSwap],
       });
@@ -393,7 +505,7 @@ describe('token-bonding-curve', () => {
     // fee token account starts at 0
     assert.strictEqual(
       (await poolToken.getAccountInfo(feeTokenAccount.publicKey)).amount.toString(),
-      "0".replace(".", ""));
+      "0");
     // destination token starts at 10 (see CurveCalculator::INITIAL_SWAP_POOL_AMOUNT)
     assert.strictEqual(
       (await poolToken.getAccountInfo(destinationTokenAccount.publicKey)).amount.toString(),
@@ -447,6 +559,19 @@ describe('token-bonding-curve', () => {
       (await cToken.getAccountInfo(cTokenSwapAccount.publicKey)).amount.toString(),
       "460.00000000".replace(".", ""));
 
+    // the swap emits a SwapEvent with the amounts and the RLY now bonded to the curve
+    await provider.connection.confirmTransaction(swapTx, "confirmed");
+    const swapTxInfo = await provider.connection.getTransaction(swapTx, { commitment: "confirmed" });
+    const swapEvent = swapTxInfo.meta.logMessages
+      .filter((log) => log.startsWith("Program log: "))
+      .map((log) => program.coder.events.decode(log.slice("Program log: ".length)))
+      .find((event) => event && event.name === "SwapEvent");
+    assert.ok(swapEvent.data.swap.equals(tokenSwap.publicKey));
+    assert.strictEqual(swapEvent.data.aToB, true);
+    assert.strictEqual(swapEvent.data.amountIn.toString(), "2400.00000000".replace(".", ""));
+    assert.strictEqual(swapEvent.data.amountOut.toString(), "40.00000000".replace(".", ""));
+    assert.strictEqual(swapEvent.data.bondedTokenAAmount.toString(), "2400.00000000".replace(".", ""));
+
     // have another user swap 1500 RLY, get 20 CC out now
     const swapUser2 = await generateNewSignerAccount(provider);
 
@@ -564,7 +689,7 @@ describe('token-bonding-curve', () => {
     // swap's RLY balance goes from 1725 -> 0
     assert.strictEqual(
       (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
-      "0".replace(".", ""));
+      "0");
     // user CC goes from 120 -> 90 (should take 30 and not the whole 50)
     assert.strictEqual(
       (await cToken.getAccountInfo(cTokenUserAccount2.publicKey)).amount.toString(),
@@ -614,7 +739,7 @@ describe('token-bonding-curve', () => {
     // swap's CC balance goes from 500 -> 0
     assert.strictEqual(
       (await cToken.getAccountInfo(cTokenSwapAccount.publicKey)).amount.toString(),
-      "0".replace(".", ""));
+      "0");
   });
 
   it('should fail invalid linear price swaps!', async () => {
@@ -662,6 +787,8 @@ describe('token-bonding-curve', () => {
           fee: feeTokenAccount.publicKey,
           destination: destinationTokenAccount.publicKey,
           tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
         },
         signers: [tokenSwap],
       }));
@@ -685,9 +812,43 @@ describe('token-bonding-curve', () => {
           fee: feeTokenAccount.publicKey,
           destination: destinationTokenAccount.publicKey,
           tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
         },
         signers: [tokenSwap],
       }));
+
+    // non zero collateral token is allowed if the curve is seeded with exactly that much collateral (only through
+    // `initialize`, `initializeLinearPrice` always starts the curve at b = 0)
+    const initializeSeeded = async (initialTokenAAmount: anchor.BN) => program.rpc.initialize(
+      ZERO_FEES,
+      {
+        linearPrice: {
+          slopeNumerator: slope_numerator,
+          slopeDenominator: slope_denominator,
+          initialTokenAPriceNumerator: r0_numerator,
+          initialTokenAPriceDenominator: r0_denominator,
+          initialTokenAAmount: initialTokenAAmount,
+        },
+      },
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        signers: [tokenSwap],
+      });
+    await assert.rejects(initializeSeeded(new anchor.BN(2)));
+
+    await initializeSeeded(new anchor.BN(1));
   });
 
   it('should disallow linear price swaps deposits/withdrawals!', async () => {
@@ -733,6 +894,8 @@ describe('token-bonding-curve', () => {
           fee: feeTokenAccount.publicKey,
           destination: destinationTokenAccount.publicKey,
           tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
         },
         signers: [tokenSwap],
       });
@@ -896,6 +1059,8 @@ describe('token-bonding-curve', () => {
           fee: feeTokenAccount.publicKey,
           destination: destinationTokenAccount.publicKey,
           tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
         },
         signers: [tokenSwap],
       });
@@ -905,7 +1070,7 @@ describe('token-bonding-curve', () => {
     // fee token account starts at 0
     assert.strictEqual(
       (await poolToken.getAccountInfo(feeTokenAccount.publicKey)).amount.toString(),
-      "0".replace(".", ""));
+      "0");
     // destination token starts at 10 (see CurveCalculator::INITIAL_SWAP_POOL_AMOUNT)
     assert.strictEqual(
       (await poolToken.getAccountInfo(destinationTokenAccount.publicKey)).amount.toString(),
@@ -938,7 +1103,11 @@ describe('token-bonding-curve', () => {
         },
         signers: [swapUser]
       },
-    ));
+    ), (err: any) => {
+      // 1 RLY lamport is worth less than a TAKI lamport at the starting price
+      assert.strictEqual(err.msg, "The amount is below the minimum tradable size");
+      return true;
+    });
 
     await assert.rejects(program.rpc.swap(
       new anchor.BN("1000"),
@@ -1428,7 +1597,7 @@ describe('token-bonding-curve', () => {
     // swap's RLY balance goes from 20.602703163 -> 3.159459472
     assert.strictEqual(
       (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
-      "0".replace(".", ""));
+      "0");
     // user TAKI goes from 1.686 -> 0.778378376
     assert.strictEqual(
       (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
@@ -1440,4 +1609,2202 @@ describe('token-bonding-curve', () => {
 
     // note user is left with a bit less than the 10 RLY and 1 TAKI they started with due to sqrt rounding
   });
+
+  it('should charge fees on linear price swaps!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const {
+      rTokenMintAuthority,
+      cTokenMintAuthority,
+      rTokenMint,
+      cTokenMint,
+      tokenSwap,
+      swapAuthority,
+      rTokenSwapAccount,
+      cTokenSwapAccount,
+      rToken,
+      cToken,
+      poolTokenMint,
+      poolToken,
+      feeAuthority,
+      feeTokenAccount,
+      destinationAuthority,
+      destinationTokenAccount,
+    } = await generateTestLinearSwapAccounts(program.programId, 500 * 10 ** 8);
+
+    // same curve as above, with a 1% owner trade fee
+    let slope_numerator = new anchor.BN(1);
+    let slope_denominator = new anchor.BN(200000000);
+    let r0_numerator = new anchor.BN(150);
+    let r0_denominator = new anchor.BN(3);
+
+    // initialize_linear_price doesn't take fees, they're set through the generic initialize
+    const tx = await program.rpc.initialize(
+      {
+        tradeFeeNumerator: new anchor.BN(0),
+        tradeFeeDenominator: new anchor.BN(1),
+        ownerTradeFeeNumerator: new anchor.BN(1),
+        ownerTradeFeeDenominator: new anchor.BN(100),
+        ownerWithdrawFeeNumerator: new anchor.BN(0),
+        ownerWithdrawFeeDenominator: new anchor.BN(1),
+        hostFeeNumerator: new anchor.BN(0),
+        hostFeeDenominator: new anchor.BN(1),
+      },
+      {
+        linearPrice: {
+          slopeNumerator: slope_numerator,
+          slopeDenominator: slope_denominator,
+          initialTokenAPriceNumerator: r0_numerator,
+          initialTokenAPriceDenominator: r0_denominator,
+          initialTokenAAmount: new anchor.BN(0),
+        },
+      },
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        signers: [tokenSwap],
+      });
+
+    console.log("Your transaction signature", tx);
+
+    // fees are paid in the source token to accounts owned by the pool fee account's owner
+    const rFeeAccount = await generateTokenAccount(provider, rTokenMint, feeAuthority.publicKey);
+    const cFeeAccount = await generateTokenAccount(provider, cTokenMint, feeAuthority.publicKey);
+
+    const swapUser = await generateNewSignerAccount(provider);
+
+    const rTokenUserAccount = await generateTokenAccount(provider, rTokenMint, swapUser.publicKey);
+    await mintToAccount(provider, rTokenMintAuthority, rTokenMint, rTokenUserAccount.publicKey, 10000 * 10 ** 8);
+    const cTokenUserAccount = await generateTokenAccount(provider, cTokenMint, swapUser.publicKey);
+
+    const swapAccounts = (source: PublicKey, swapSource: PublicKey, swapDestination: PublicKey, destination: PublicKey) => {
+      return {
+        tokenSwap: tokenSwap.publicKey,
+        swapAuthority: swapAuthority,
+        userTransferAuthority: swapUser.publicKey,
+        source,
+        swapSource,
+        swapDestination,
+        destination,
+        poolMint: poolTokenMint.publicKey,
+        poolFee: feeTokenAccount.publicKey,
+        tokenProgram: TOKEN_PROGRAM_PUBKEY,
+      };
+    };
+
+    // swapping without the fee account should fail
+    await assert.rejects(program.rpc.swap(
+      new anchor.BN("242424242424"),
+      new anchor.BN(0),
+      {
+        accounts: swapAccounts(
+          rTokenUserAccount.publicKey, rTokenSwapAccount.publicKey, cTokenSwapAccount.publicKey, cTokenUserAccount.publicKey
+        ),
+        signers: [swapUser]
+      },
+    ));
+
+    // put in 2424.24242424 RLY, 24.24242424 RLY fee and 2400 RLY bonded, should get out 40 CC
+    let swapTx = await program.rpc.swap(
+      new anchor.BN("242424242424"),
+      new anchor.BN(0),
+      {
+        accounts: swapAccounts(
+          rTokenUserAccount.publicKey, rTokenSwapAccount.publicKey, cTokenSwapAccount.publicKey, cTokenUserAccount.publicKey
+        ),
+        remainingAccounts: [{ pubkey: rFeeAccount.publicKey, isWritable: true, isSigner: false }],
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    // user RLY goes from 10K -> 7575.75757576
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount.toString(),
+      "7575.75757576".replace(".", ""));
+    // fee doesn't go into the swap, swap's RLY balance goes from 0 -> 2400 (same as the no fee case)
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
+      "2400.00000000".replace(".", ""));
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rFeeAccount.publicKey)).amount.toString(),
+      "24.24242424".replace(".", ""));
+    // user CC goes from 0 -> 40
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "40.00000000".replace(".", ""));
+
+    // sell 30 CC back, 0.3 CC fee and 29.7 CC go back into the swap
+    swapTx = await program.rpc.swap(
+      new anchor.BN("3000000000"),
+      new anchor.BN(0),
+      {
+        accounts: swapAccounts(
+          cTokenUserAccount.publicKey, cTokenSwapAccount.publicKey, rTokenSwapAccount.publicKey, rTokenUserAccount.publicKey
+        ),
+        remainingAccounts: [{ pubkey: cFeeAccount.publicKey, isWritable: true, isSigner: false }],
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cFeeAccount.publicKey)).amount.toString(),
+      ".30000000".replace(".", ""));
+    // swap's CC balance goes from 460 -> 489.7
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenSwapAccount.publicKey)).amount.toString(),
+      "489.70000000".replace(".", ""));
+    // user CC goes from 40 -> 10
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "10.00000000".replace(".", ""));
+    // no pool tokens are minted for fees
+    assert.strictEqual(
+      (await poolToken.getAccountInfo(feeTokenAccount.publicKey)).amount.toString(),
+      "0");
+
+    // fees can build up in the swap instead, only the admin can switch that on
+    const adminAccounts = { tokenSwap: tokenSwap.publicKey, admin: provider.wallet.publicKey };
+    await assert.rejects(program.rpc.setAccrueFees(
+      true,
+      { accounts: { ...adminAccounts, admin: swapUser.publicKey }, signers: [swapUser] },
+    ));
+    await program.rpc.setAccrueFees(true, { accounts: adminAccounts });
+
+    // buy with 101 RLY and no fee account, the 1.01 RLY fee stays in the swap
+    const swapRBalance = new anchor.BN((await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString());
+    const swapCBalance = new anchor.BN((await cToken.getAccountInfo(cTokenSwapAccount.publicKey)).amount.toString());
+    swapTx = await program.rpc.swap(
+      new anchor.BN("10100000000"),
+      new anchor.BN(0),
+      {
+        accounts: swapAccounts(
+          rTokenUserAccount.publicKey, rTokenSwapAccount.publicKey, cTokenSwapAccount.publicKey, cTokenUserAccount.publicKey
+        ),
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
+      swapRBalance.add(new anchor.BN("10100000000")).toString());
+
+    // the owner of the pool fee account claims it, and the curve's token B inventory doesn't include it
+    const claimAccounts = {
+      tokenSwap: tokenSwap.publicKey,
+      swapAuthority: swapAuthority,
+      owner: feeAuthority.publicKey,
+      poolFee: feeTokenAccount.publicKey,
+      swapTokenA: rTokenSwapAccount.publicKey,
+      swapTokenB: cTokenSwapAccount.publicKey,
+      destinationA: rFeeAccount.publicKey,
+      destinationB: cFeeAccount.publicKey,
+      tokenProgram: TOKEN_PROGRAM_PUBKEY,
+    };
+    await assert.rejects(program.rpc.claimFees(
+      { accounts: { ...claimAccounts, owner: swapUser.publicKey }, signers: [swapUser] },
+    ));
+    const claimTx = await program.rpc.claimFees({ accounts: claimAccounts, signers: [feeAuthority] });
+
+    console.log("Your transaction signature", claimTx);
+
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rFeeAccount.publicKey)).amount.toString(),
+      "25.25242424".replace(".", ""));
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
+      swapRBalance.add(new anchor.BN("10000000000")).toString());
+    assert.ok(
+      new anchor.BN((await cToken.getAccountInfo(cTokenSwapAccount.publicKey)).amount.toString()).lt(swapCBalance));
+  });
+
+  it('should swap exact amount out on linear price swap!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const {
+      rTokenMintAuthority,
+      rTokenMint,
+      cTokenMint,
+      tokenSwap,
+      swapAuthority,
+      rTokenSwapAccount,
+      cTokenSwapAccount,
+      rToken,
+      cToken,
+      poolTokenMint,
+      feeTokenAccount,
+      destinationTokenAccount,
+    } = await generateTestLinearSwapAccounts(program.programId, 500 * 10 ** 8);
+
+    let slope_numerator = new anchor.BN(1);
+    let slope_denominator = new anchor.BN(200000000);
+    let r0_numerator = new anchor.BN(150);
+    let r0_denominator = new anchor.BN(3);
+
+    const tx = await program.rpc.initializeLinearPrice(
+      slope_numerator,
+      slope_denominator,
+      r0_numerator,
+      r0_denominator,
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        signers: [tokenSwap],
+      });
+
+    console.log("Your transaction signature", tx);
+
+    const swapUser = await generateNewSignerAccount(provider);
+
+    const rTokenUserAccount = await generateTokenAccount(provider, rTokenMint, swapUser.publicKey);
+    await mintToAccount(provider, rTokenMintAuthority, rTokenMint, rTokenUserAccount.publicKey, 10000 * 10 ** 8);
+    const cTokenUserAccount = await generateTokenAccount(provider, cTokenMint, swapUser.publicKey);
+
+    const buyAccounts = {
+      tokenSwap: tokenSwap.publicKey,
+      swapAuthority: swapAuthority,
+      userTransferAuthority: swapUser.publicKey,
+      source: rTokenUserAccount.publicKey,
+      swapSource: rTokenSwapAccount.publicKey,
+      swapDestination: cTokenSwapAccount.publicKey,
+      destination: cTokenUserAccount.publicKey,
+      poolMint: poolTokenMint.publicKey,
+      poolFee: feeTokenAccount.publicKey,
+      tokenProgram: TOKEN_PROGRAM_PUBKEY,
+    };
+
+    // getting out 40 CC costs 2400 RLY, so a maximum of 2399 RLY should fail
+    await assert.rejects(program.rpc.swapExactAmountOut(
+      new anchor.BN("4000000000"),
+      new anchor.BN("239900000000"),
+      {
+        accounts: buyAccounts,
+        signers: [swapUser]
+      },
+    ));
+
+    let swapTx = await program.rpc.swapExactAmountOut(
+      new anchor.BN("4000000000"),
+      new anchor.BN("240000000000"),
+      {
+        accounts: buyAccounts,
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    // user RLY goes from 10K -> 7600
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount.toString(),
+      "7600.00000000".replace(".", ""));
+    // user CC goes from 0 -> 40
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "40.00000000".replace(".", ""));
+
+    // get exactly 1000 RLY back out, costs ~15.1 CC (rounded up)
+    swapTx = await program.rpc.swapExactAmountOut(
+      new anchor.BN("100000000000"),
+      new anchor.BN("1600000000"),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: cTokenUserAccount.publicKey,
+          swapSource: cTokenSwapAccount.publicKey,
+          swapDestination: rTokenSwapAccount.publicKey,
+          destination: rTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    // user RLY goes from 7600 -> 8600
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount.toString(),
+      "8600.00000000".replace(".", ""));
+    // swap RLY goes from 2400 -> 1400
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
+      "1400.00000000".replace(".", ""));
+    // user CC goes from 40 -> 24.89995993
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "24.89995993".replace(".", ""));
+  });
+
+  it('should quote linear price swap!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const {
+      tokenSwap,
+      swapAuthority,
+      rTokenSwapAccount,
+      cTokenSwapAccount,
+      rToken,
+      poolTokenMint,
+      feeTokenAccount,
+      destinationTokenAccount,
+    } = await generateTestLinearSwapAccounts(program.programId, 500 * 10 ** 8);
+
+    let slope_numerator = new anchor.BN(1);
+    let slope_denominator = new anchor.BN(200000000);
+    let r0_numerator = new anchor.BN(150);
+    let r0_denominator = new anchor.BN(3);
+
+    const tx = await program.rpc.initializeLinearPrice(
+      slope_numerator,
+      slope_denominator,
+      r0_numerator,
+      r0_denominator,
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        signers: [tokenSwap],
+      });
+
+    console.log("Your transaction signature", tx);
+
+    // quote putting in 2400 RLY
+    const simulation = await program.simulate.quoteSwap(
+      new anchor.BN("240000000000"),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapSource: rTokenSwapAccount.publicKey,
+          swapDestination: cTokenSwapAccount.publicKey,
+        },
+      },
+    );
+
+    // the SwapQuote is returned as return data, which is logged as `Program return: <program id> <base64 data>`
+    const returnPrefix = `Program return: ${program.programId.toString()} `;
+    const returnLog = simulation.raw.find((log: string) => log.startsWith(returnPrefix));
+    const quoteData = Buffer.from(returnLog.slice(returnPrefix.length), "base64");
+    // SwapQuote is 10 little endian u128s
+    const quoteValue = (index: number) => new anchor.BN(quoteData.slice(index * 16, (index + 1) * 16), "le").toString();
+
+    // new swap source amount: 2400 RLY
+    assert.strictEqual(quoteValue(0), "2400.00000000".replace(".", ""));
+    // new swap destination amount: 460 CC
+    assert.strictEqual(quoteValue(1), "460.00000000".replace(".", ""));
+    // source amount swapped: 2400 RLY
+    assert.strictEqual(quoteValue(2), "2400.00000000".replace(".", ""));
+    // destination amount swapped: 40 CC
+    assert.strictEqual(quoteValue(3), "40.00000000".replace(".", ""));
+    // no fees
+    assert.strictEqual(quoteValue(4), "0");
+    assert.strictEqual(quoteValue(5), "0");
+    // spot price before: 50 RLY per CC (denominated in 1e18)
+    assert.strictEqual(quoteValue(6), "50000000000000000000");
+    assert.strictEqual(quoteValue(7), "1000000000000000000");
+
+    // nothing actually moved
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
+      "0");
+  });
+
+  it('should sweep surplus on linear price swap!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const {
+      rTokenMintAuthority,
+      rTokenMint,
+      cTokenMint,
+      tokenSwap,
+      swapAuthority,
+      rTokenSwapAccount,
+      cTokenSwapAccount,
+      rToken,
+      cToken,
+      poolTokenMint,
+      feeAuthority,
+      feeTokenAccount,
+      destinationTokenAccount,
+    } = await generateTestLinearSwapAccounts(program.programId, 500 * 10 ** 8);
+
+    let slope_numerator = new anchor.BN(1);
+    let slope_denominator = new anchor.BN(200000000);
+    let r0_numerator = new anchor.BN(150);
+    let r0_denominator = new anchor.BN(3);
+
+    const tx = await program.rpc.initializeLinearPrice(
+      slope_numerator,
+      slope_denominator,
+      r0_numerator,
+      r0_denominator,
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        signers: [tokenSwap],
+      });
+
+    console.log("Your transaction signature", tx);
+
+    // send 1000 RLY straight to the swap's token A account, it shouldn't move the price
+    await mintToAccount(provider, rTokenMintAuthority, rTokenMint, rTokenSwapAccount.publicKey, 1000 * 10 ** 8);
+
+    const swapUser = await generateNewSignerAccount(provider);
+
+    const rTokenUserAccount = await generateTokenAccount(provider, rTokenMint, swapUser.publicKey);
+    await mintToAccount(provider, rTokenMintAuthority, rTokenMint, rTokenUserAccount.publicKey, 10000 * 10 ** 8);
+    const cTokenUserAccount = await generateTokenAccount(provider, cTokenMint, swapUser.publicKey);
+
+    let swapTx = await program.rpc.swap(
+      new anchor.BN("240000000000"),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: rTokenUserAccount.publicKey,
+          swapSource: rTokenSwapAccount.publicKey,
+          swapDestination: cTokenSwapAccount.publicKey,
+          destination: cTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    // 2400 RLY still buys 40 CC, same as with an empty pool
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "40.00000000".replace(".", ""));
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
+      "3400.00000000".replace(".", ""));
+
+    const ownerRTokenAccount = await generateTokenAccount(provider, rTokenMint, feeAuthority.publicKey);
+    const sweepAccounts = {
+      tokenSwap: tokenSwap.publicKey,
+      swapAuthority: swapAuthority,
+      owner: feeAuthority.publicKey,
+      poolFee: feeTokenAccount.publicKey,
+      swapTokenA: rTokenSwapAccount.publicKey,
+      destination: ownerRTokenAccount.publicKey,
+      tokenProgram: TOKEN_PROGRAM_PUBKEY,
+    };
+
+    // only the owner of the pool fee account can sweep
+    await assert.rejects(program.rpc.sweepSurplus(
+      {
+        accounts: {
+          ...sweepAccounts,
+          owner: swapUser.publicKey,
+        },
+        signers: [swapUser]
+      },
+    ));
+
+    const sweepTx = await program.rpc.sweepSurplus(
+      {
+        accounts: sweepAccounts,
+        signers: [feeAuthority]
+      },
+    );
+
+    console.log("Your transaction signature", sweepTx);
+
+    // only the 1000 RLY surplus is swept, the 2400 RLY bonded to the curve stays
+    assert.strictEqual(
+      (await rToken.getAccountInfo(ownerRTokenAccount.publicKey)).amount.toString(),
+      "1000.00000000".replace(".", ""));
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
+      "2400.00000000".replace(".", ""));
+  });
+
+  it('should mint and burn token B on mint-and-burn linear price swap!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const {
+      rTokenMintAuthority,
+      cTokenMintAuthority,
+      rTokenMint,
+      cTokenMint,
+      tokenSwap,
+      swapAuthority,
+      rTokenSwapAccount,
+      cTokenSwapAccount,
+      rToken,
+      cToken,
+      poolTokenMint,
+      feeTokenAccount,
+      destinationTokenAccount,
+    } = await generateTestLinearSwapAccounts(program.programId, 0);
+
+    // hand token B's mint authority over to the swap authority, there's no token B inventory
+    const setAuthorityTx = new anchor.web3.Transaction();
+    setAuthorityTx.add(Token.createSetAuthorityInstruction(
+      TOKEN_PROGRAM_ID, cTokenMint.publicKey, swapAuthority, 'MintTokens', cTokenMintAuthority.publicKey, []
+    ));
+    await provider.send(setAuthorityTx, [cTokenMintAuthority]);
+
+    let slope_numerator = new anchor.BN(1);
+    let slope_denominator = new anchor.BN(200000000);
+    let r0_numerator = new anchor.BN(150);
+    let r0_denominator = new anchor.BN(3);
+
+    // passing token B's mint puts the swap in mint-and-burn mode
+    const cTokenMintAccount = { pubkey: cTokenMint.publicKey, isWritable: true, isSigner: false };
+
+    const tx = await program.rpc.initializeLinearPrice(
+      slope_numerator,
+      slope_denominator,
+      r0_numerator,
+      r0_denominator,
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        remainingAccounts: [cTokenMintAccount],
+        signers: [tokenSwap],
+      });
+
+    console.log("Your transaction signature", tx);
+
+    const swapUser = await generateNewSignerAccount(provider);
+
+    const rTokenUserAccount = await generateTokenAccount(provider, rTokenMint, swapUser.publicKey);
+    await mintToAccount(provider, rTokenMintAuthority, rTokenMint, rTokenUserAccount.publicKey, 10000 * 10 ** 8);
+    const cTokenUserAccount = await generateTokenAccount(provider, cTokenMint, swapUser.publicKey);
+
+    const buyAccounts = {
+      tokenSwap: tokenSwap.publicKey,
+      swapAuthority: swapAuthority,
+      userTransferAuthority: swapUser.publicKey,
+      source: rTokenUserAccount.publicKey,
+      swapSource: rTokenSwapAccount.publicKey,
+      swapDestination: cTokenSwapAccount.publicKey,
+      destination: cTokenUserAccount.publicKey,
+      poolMint: poolTokenMint.publicKey,
+      poolFee: feeTokenAccount.publicKey,
+      tokenProgram: TOKEN_PROGRAM_PUBKEY,
+    };
+
+    // the token B mint has to be passed in to swap
+    await assert.rejects(program.rpc.swap(
+      new anchor.BN("240000000000"),
+      new anchor.BN(0),
+      {
+        accounts: buyAccounts,
+        signers: [swapUser]
+      },
+    ));
+
+    let swapTx = await program.rpc.swap(
+      new anchor.BN("240000000000"),
+      new anchor.BN(0),
+      {
+        accounts: buyAccounts,
+        remainingAccounts: [cTokenMintAccount],
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    // user RLY goes from 10K -> 7600
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount.toString(),
+      "7600.00000000".replace(".", ""));
+    // user CC goes from 0 -> 40, all of it newly minted
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "40.00000000".replace(".", ""));
+    assert.strictEqual((await cToken.getMintInfo()).supply.toString(), "40.00000000".replace(".", ""));
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenSwapAccount.publicKey)).amount.toString(),
+      "0");
+
+    swapTx = await program.rpc.swap(
+      new anchor.BN("150000000000"),
+      new anchor.BN(0),
+      {
+        accounts: buyAccounts,
+        remainingAccounts: [cTokenMintAccount],
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    // user CC goes from 40 -> 60
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "60.00000000".replace(".", ""));
+    assert.strictEqual((await cToken.getMintInfo()).supply.toString(), "60.00000000".replace(".", ""));
+
+    // swap back 30 CC -> 2175 RLY, the CC is burned
+    swapTx = await program.rpc.swap(
+      new anchor.BN("3000000000"),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: cTokenUserAccount.publicKey,
+          swapSource: cTokenSwapAccount.publicKey,
+          swapDestination: rTokenSwapAccount.publicKey,
+          destination: rTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        remainingAccounts: [cTokenMintAccount],
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    // user RLY goes from 6100 -> 8275
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount.toString(),
+      "8275.00000000".replace(".", ""));
+    // swap's RLY balance goes from 3900 -> 1725
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
+      "1725.00000000".replace(".", ""));
+    // user CC goes from 60 -> 30
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "30.00000000".replace(".", ""));
+    assert.strictEqual((await cToken.getMintInfo()).supply.toString(), "30.00000000".replace(".", ""));
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenSwapAccount.publicKey)).amount.toString(),
+      "0");
+  });
+
+  it('should transfer and renounce swap admin!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const {
+      tokenSwap,
+      swapAuthority,
+      rTokenSwapAccount,
+      cTokenSwapAccount,
+      poolTokenMint,
+      feeTokenAccount,
+      destinationTokenAccount,
+    } = await generateTestLinearSwapAccounts(program.programId, 500 * 10 ** 8);
+
+    const admin = await generateNewSignerAccount(provider);
+
+    const tx = await program.rpc.initializeLinearPrice(
+      new anchor.BN(1),
+      new anchor.BN(200000000),
+      new anchor.BN(150),
+      new anchor.BN(3),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: admin.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        signers: [tokenSwap, admin],
+      });
+
+    console.log("Your transaction signature", tx);
+
+    const newAdmin = await generateNewSignerAccount(provider);
+    const someoneElse = await generateNewSignerAccount(provider);
+
+    // only the admin can propose a new admin
+    await assert.rejects(program.rpc.setAdmin(
+      newAdmin.publicKey,
+      {
+        accounts: { tokenSwap: tokenSwap.publicKey, admin: someoneElse.publicKey },
+        signers: [someoneElse]
+      },
+    ));
+
+    await program.rpc.setAdmin(
+      newAdmin.publicKey,
+      {
+        accounts: { tokenSwap: tokenSwap.publicKey, admin: admin.publicKey },
+        signers: [admin]
+      },
+    );
+
+    // only the pending admin can accept
+    await assert.rejects(program.rpc.acceptAdmin(
+      {
+        accounts: { tokenSwap: tokenSwap.publicKey, admin: someoneElse.publicKey },
+        signers: [someoneElse]
+      },
+    ));
+
+    await program.rpc.acceptAdmin(
+      {
+        accounts: { tokenSwap: tokenSwap.publicKey, admin: newAdmin.publicKey },
+        signers: [newAdmin]
+      },
+    );
+
+    // the old admin can't manage the swap anymore
+    await assert.rejects(program.rpc.renounceAdmin(
+      {
+        accounts: { tokenSwap: tokenSwap.publicKey, admin: admin.publicKey },
+        signers: [admin]
+      },
+    ));
+
+    await program.rpc.renounceAdmin(
+      {
+        accounts: { tokenSwap: tokenSwap.publicKey, admin: newAdmin.publicKey },
+        signers: [newAdmin]
+      },
+    );
+
+    // nobody can manage the swap once the admin is renounced
+    await assert.rejects(program.rpc.setAdmin(
+      newAdmin.publicKey,
+      {
+        accounts: { tokenSwap: tokenSwap.publicKey, admin: newAdmin.publicKey },
+        signers: [newAdmin]
+      },
+    ));
+  });
+
+  it('should pause and resume linear price swap!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const {
+      rTokenMintAuthority,
+      rTokenMint,
+      cTokenMint,
+      tokenSwap,
+      swapAuthority,
+      rTokenSwapAccount,
+      cTokenSwapAccount,
+      cToken,
+      poolTokenMint,
+      feeTokenAccount,
+      destinationTokenAccount,
+    } = await generateTestLinearSwapAccounts(program.programId, 500 * 10 ** 8);
+
+    const admin = await generateNewSignerAccount(provider);
+
+    const tx = await program.rpc.initializeLinearPrice(
+      new anchor.BN(1),
+      new anchor.BN(200000000),
+      new anchor.BN(150),
+      new anchor.BN(3),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: admin.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        signers: [tokenSwap, admin],
+      });
+
+    console.log("Your transaction signature", tx);
+
+    const swapUser = await generateNewSignerAccount(provider);
+
+    const rTokenUserAccount = await generateTokenAccount(provider, rTokenMint, swapUser.publicKey);
+    await mintToAccount(provider, rTokenMintAuthority, rTokenMint, rTokenUserAccount.publicKey, 10000 * 10 ** 8);
+    const cTokenUserAccount = await generateTokenAccount(provider, cTokenMint, swapUser.publicKey);
+
+    const buy = () => program.rpc.swap(
+      new anchor.BN("240000000000"),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: rTokenUserAccount.publicKey,
+          swapSource: rTokenSwapAccount.publicKey,
+          swapDestination: cTokenSwapAccount.publicKey,
+          destination: cTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    );
+
+    const { registryPage } = await findRegistryPageOfSwap(
+      provider, program.programId, rTokenSwapAccount.publicKey, tokenSwap.publicKey);
+    // the swap's registry entry is marked paused along with the swap, the flag is the entry's last byte
+    const isPausedInRegistry = async () => {
+      const data = (await provider.connection.getAccountInfo(registryPage)).data;
+      for (let offset = 74; offset < 74 + data[73] * REGISTRY_ENTRY_LEN; offset += REGISTRY_ENTRY_LEN) {
+        if (new PublicKey(data.slice(offset, offset + 32)).equals(tokenSwap.publicKey)) {
+          return data[offset + REGISTRY_ENTRY_LEN - 1] == 1;
+        }
+      }
+    };
+
+    // only the admin can pause
+    await assert.rejects(program.rpc.pause(
+      {
+        accounts: { tokenSwap: tokenSwap.publicKey, admin: swapUser.publicKey },
+        remainingAccounts: [{ pubkey: registryPage, isWritable: true, isSigner: false }],
+        signers: [swapUser]
+      },
+    ));
+
+    await program.rpc.pause(
+      {
+        accounts: { tokenSwap: tokenSwap.publicKey, admin: admin.publicKey },
+        remainingAccounts: [{ pubkey: registryPage, isWritable: true, isSigner: false }],
+        signers: [admin]
+      },
+    );
+    assert.ok(await isPausedInRegistry());
+
+    await assert.rejects(buy());
+
+    // quoting still works while paused
+    await program.simulate.quoteSwap(
+      new anchor.BN("240000000000"),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapSource: rTokenSwapAccount.publicKey,
+          swapDestination: cTokenSwapAccount.publicKey,
+        },
+      },
+    );
+
+    await program.rpc.resume(
+      {
+        accounts: { tokenSwap: tokenSwap.publicKey, admin: admin.publicKey },
+        remainingAccounts: [{ pubkey: registryPage, isWritable: true, isSigner: false }],
+        signers: [admin]
+      },
+    );
+    assert.ok(!(await isPausedInRegistry()));
+
+    const swapTx = await buy();
+
+    console.log("Your transaction signature", swapTx);
+
+    // user CC goes from 0 -> 40
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "40.00000000".replace(".", ""));
+  });
+
+  it('should redeem bonded tokens in emergency mode on linear price swap!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const {
+      rTokenMintAuthority,
+      rTokenMint,
+      cTokenMint,
+      tokenSwap,
+      swapAuthority,
+      rTokenSwapAccount,
+      cTokenSwapAccount,
+      rToken,
+      cToken,
+      poolTokenMint,
+      feeTokenAccount,
+      destinationTokenAccount,
+    } = await generateTestLinearSwapAccounts(program.programId, 500 * 10 ** 8);
+
+    const admin = await generateNewSignerAccount(provider);
+
+    const tx = await program.rpc.initializeLinearPrice(
+      new anchor.BN(1),
+      new anchor.BN(200000000),
+      new anchor.BN(150),
+      new anchor.BN(3),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: admin.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        signers: [tokenSwap, admin],
+      });
+
+    console.log("Your transaction signature", tx);
+
+    const swapUser = await generateNewSignerAccount(provider);
+
+    const rTokenUserAccount = await generateTokenAccount(provider, rTokenMint, swapUser.publicKey);
+    await mintToAccount(provider, rTokenMintAuthority, rTokenMint, rTokenUserAccount.publicKey, 10000 * 10 ** 8);
+    const cTokenUserAccount = await generateTokenAccount(provider, cTokenMint, swapUser.publicKey);
+    const cTokenAdminAccount = await generateTokenAccount(provider, cTokenMint, admin.publicKey);
+
+    const buy = () => program.rpc.swap(
+      new anchor.BN("240000000000"),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: rTokenUserAccount.publicKey,
+          swapSource: rTokenSwapAccount.publicKey,
+          swapDestination: cTokenSwapAccount.publicKey,
+          destination: cTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    );
+
+    // 2400 RLY buys 40 CC
+    await buy();
+
+    const redeem = (amount: anchor.BN) => program.rpc.emergencyRedeem(
+      amount,
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: cTokenUserAccount.publicKey,
+          tokenBMint: cTokenMint.publicKey,
+          swapTokenA: rTokenSwapAccount.publicKey,
+          destination: rTokenUserAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    );
+
+    // can't redeem before emergency mode, and only the admin can enable it
+    await assert.rejects(redeem(new anchor.BN(20 * 10 ** 8)));
+    await assert.rejects(program.rpc.enableEmergencyMode(
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          admin: swapUser.publicKey,
+          swapTokenB: cTokenSwapAccount.publicKey,
+          tokenBMint: cTokenMint.publicKey,
+        },
+        signers: [swapUser]
+      },
+    ));
+
+    const emergencyTx = await program.rpc.enableEmergencyMode(
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          admin: admin.publicKey,
+          swapTokenB: cTokenSwapAccount.publicKey,
+          tokenBMint: cTokenMint.publicKey,
+        },
+        signers: [admin]
+      },
+    );
+
+    console.log("Your transaction signature", emergencyTx);
+
+    // swaps are disabled for good, resuming doesn't bring them back
+    await assert.rejects(buy());
+    // (the registry page is optional, pausing and resuming only depend on the swap)
+    await assert.rejects(program.rpc.resume(
+      {
+        accounts: { tokenSwap: tokenSwap.publicKey, admin: admin.publicKey },
+        signers: [admin]
+      },
+    ).then(() => buy()));
+
+    // the admin recovers the 460 CC that were never sold
+    await program.rpc.recoverTokenB(
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          admin: admin.publicKey,
+          swapTokenB: cTokenSwapAccount.publicKey,
+          destination: cTokenAdminAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [admin]
+      },
+    );
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenAdminAccount.publicKey)).amount.toString(),
+      "460.00000000".replace(".", ""));
+
+    // redeeming half of the circulating CC returns half of the bonded RLY
+    const redeemTx = await redeem(new anchor.BN(20 * 10 ** 8));
+
+    console.log("Your transaction signature", redeemTx);
+
+    // user CC goes from 40 -> 20 (burned)
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "20.00000000".replace(".", ""));
+    assert.strictEqual((await cToken.getMintInfo()).supply.toString(), "480.00000000".replace(".", ""));
+    // user RLY goes from 7600 -> 8800
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount.toString(),
+      "8800.00000000".replace(".", ""));
+    // swap's RLY balance goes from 2400 -> 1200
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
+      "1200.00000000".replace(".", ""));
+  });
+
+  it('should close a drained linear price swap!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const {
+      rTokenMintAuthority,
+      rTokenMint,
+      rToken,
+      cTokenMint,
+      cToken,
+      tokenSwap,
+      swapAuthority,
+      rTokenSwapAccount,
+      cTokenSwapAccount,
+      poolTokenMint,
+      feeTokenAccount,
+      destinationTokenAccount,
+    } = await generateTestLinearSwapAccounts(program.programId, 500 * 10 ** 8);
+
+    const admin = await generateNewSignerAccount(provider);
+
+    const tx = await program.rpc.initializeLinearPrice(
+      new anchor.BN(1),
+      new anchor.BN(200000000),
+      new anchor.BN(150),
+      new anchor.BN(3),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: admin.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        signers: [tokenSwap, admin],
+      });
+
+    console.log("Your transaction signature", tx);
+
+    const swapUser = await generateNewSignerAccount(provider);
+
+    const rTokenUserAccount = await generateTokenAccount(provider, rTokenMint, swapUser.publicKey);
+    await mintToAccount(provider, rTokenMintAuthority, rTokenMint, rTokenUserAccount.publicKey, 10000 * 10 ** 8);
+    const cTokenUserAccount = await generateTokenAccount(provider, cTokenMint, swapUser.publicKey);
+    const rTokenAdminAccount = await generateTokenAccount(provider, rTokenMint, admin.publicKey);
+    const cTokenAdminAccount = await generateTokenAccount(provider, cTokenMint, admin.publicKey);
+    const recipient = anchor.web3.Keypair.generate();
+
+    await program.rpc.swap(
+      new anchor.BN("240000000000"),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: rTokenUserAccount.publicKey,
+          swapSource: rTokenSwapAccount.publicKey,
+          swapDestination: cTokenSwapAccount.publicKey,
+          destination: cTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    );
+
+    const { registry, registryPage } = await findRegistryPageOfSwap(
+      provider, program.programId, rTokenSwapAccount.publicKey, tokenSwap.publicKey);
+    const close = () => program.rpc.closeSwap(
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          admin: admin.publicKey,
+          swapTokenA: rTokenSwapAccount.publicKey,
+          swapTokenB: cTokenSwapAccount.publicKey,
+          destinationA: rTokenAdminAccount.publicKey,
+          destinationB: cTokenAdminAccount.publicKey,
+          recipient: recipient.publicKey,
+          registry,
+          registryPage,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [admin]
+      },
+    );
+
+    // the swap still holds RLY and CC
+    await assert.rejects(close());
+
+    // drain the swap in emergency mode, the admin takes back the unsold CC and the user redeems theirs
+    await program.rpc.enableEmergencyMode(
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          admin: admin.publicKey,
+          swapTokenB: cTokenSwapAccount.publicKey,
+          tokenBMint: cTokenMint.publicKey,
+        },
+        signers: [admin]
+      },
+    );
+    await program.rpc.recoverTokenB(
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          admin: admin.publicKey,
+          swapTokenB: cTokenSwapAccount.publicKey,
+          destination: cTokenAdminAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [admin]
+      },
+    );
+    const emergencyRedeem = (tokenBAmount: anchor.BN) => program.rpc.emergencyRedeem(
+      tokenBAmount,
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: cTokenUserAccount.publicKey,
+          tokenBMint: cTokenMint.publicKey,
+          swapTokenA: rTokenSwapAccount.publicKey,
+          destination: rTokenUserAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    );
+    await emergencyRedeem(new anchor.BN(30 * 10 ** 8));
+
+    // the RLY backing the CC that hasn't been redeemed yet can't be drained to the admin
+    await assert.rejects(close());
+
+    await emergencyRedeem((await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount);
+
+    // once every CC has been redeemed whatever RLY is left (rounding dust) is drained to the admin
+    const rTokenSwapAmount = (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString();
+
+    const rentLamports = (await provider.connection.getAccountInfo(tokenSwap.publicKey)).lamports
+      + (await provider.connection.getAccountInfo(rTokenSwapAccount.publicKey)).lamports
+      + (await provider.connection.getAccountInfo(cTokenSwapAccount.publicKey)).lamports;
+    const registrySwapCount = async () =>
+      new anchor.BN((await provider.connection.getAccountInfo(registry)).data.slice(37, 45), "le").toNumber();
+    const swapCount = await registrySwapCount();
+
+    const closeTx = await close();
+
+    console.log("Your transaction signature", closeTx);
+
+    // every account is gone and the recipient got all of their lamports
+    assert.strictEqual(await provider.connection.getAccountInfo(tokenSwap.publicKey), null);
+    assert.strictEqual(await provider.connection.getAccountInfo(rTokenSwapAccount.publicKey), null);
+    assert.strictEqual(await provider.connection.getAccountInfo(cTokenSwapAccount.publicKey), null);
+    assert.strictEqual(await provider.connection.getBalance(recipient.publicKey), rentLamports);
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenAdminAccount.publicKey)).amount.toString(),
+      rTokenSwapAmount);
+
+    // and the swap is no longer listed in the registry
+    assert.strictEqual(await registrySwapCount(), swapCount - 1);
+    const registryPageData = (await provider.connection.getAccountInfo(registryPage)).data;
+    for (let offset = 74; offset < 74 + registryPageData[73] * REGISTRY_ENTRY_LEN; offset += REGISTRY_ENTRY_LEN) {
+      assert.ok(!new PublicKey(registryPageData.slice(offset, offset + 32)).equals(tokenSwap.publicKey));
+    }
+  });
+
+  it('should swap on exponential price swap!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const {
+      rTokenMintAuthority,
+      rTokenMint,
+      cTokenMint,
+      tokenSwap,
+      swapAuthority,
+      rTokenSwapAccount,
+      cTokenSwapAccount,
+      rToken,
+      cToken,
+      poolTokenMint,
+      feeTokenAccount,
+      destinationTokenAccount,
+    } = await generateTestLinearSwapAccounts(program.programId, 500 * 10 ** 8);
+
+    // example curve - starts at 50 base RLY per base CC and grows by 1% for every 1 display CC (1e8 CC) swapped out
+    let r0_numerator = new anchor.BN(150);
+    let r0_denominator = new anchor.BN(3);  // not reducing to test out division
+    let growth_rate_numerator = 1;
+    let growth_rate_denominator = 100;
+    let growth_interval = new anchor.BN(100000000);
+
+    const tx = await program.rpc.initializeExponentialPrice(
+      ZERO_FEES,
+      r0_numerator,
+      r0_denominator,
+      growth_rate_numerator,
+      growth_rate_denominator,
+      growth_interval,
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        signers: [tokenSwap],
+      });
+
+    console.log("Your transaction signature", tx);
+
+    const swapUser = await generateNewSignerAccount(provider);
+
+    const rTokenUserAccount = await generateTokenAccount(provider, rTokenMint, swapUser.publicKey);
+    await mintToAccount(provider, rTokenMintAuthority, rTokenMint, rTokenUserAccount.publicKey, 10000 * 10 ** 8);
+    const cTokenUserAccount = await generateTokenAccount(provider, cTokenMint, swapUser.publicKey);
+
+    // put in 5000 RLY, should get out 69.41082105 CC (price goes from 50 to ~100 RLY over that range)
+    let swapTx = await program.rpc.swap(
+      new anchor.BN("500000000000"),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: rTokenUserAccount.publicKey,
+          swapSource: rTokenSwapAccount.publicKey,
+          swapDestination: cTokenSwapAccount.publicKey,
+          destination: cTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    // user RLY goes from 10K -> 5000
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount.toString(),
+      "5000.00000000".replace(".", ""));
+    // swap's RLY balance goes from 0 -> 5000
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
+      "5000.00000000".replace(".", ""));
+    // user CC goes from 0 -> 69.41082105
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "69.41082105".replace(".", ""));
+    // swap's CC balance goes from 500 -> 430.58917895
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenSwapAccount.publicKey)).amount.toString(),
+      "430.58917895".replace(".", ""));
+
+    // sell it all back, shouldn't get more than the 5000 RLY that was put in
+    swapTx = await program.rpc.swap(
+      new anchor.BN("6941082105"),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: cTokenUserAccount.publicKey,
+          swapSource: cTokenSwapAccount.publicKey,
+          swapDestination: rTokenSwapAccount.publicKey,
+          destination: rTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    const userRTokenAmount = (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount;
+    assert.ok(userRTokenAmount.lte(new anchor.BN("1000000000000")));
+    // only rounding dust should be left in the swap
+    assert.ok(userRTokenAmount.gte(new anchor.BN("999999999990")));
+  });
+
+  it('should swap on power price swap!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const {
+      rTokenMintAuthority,
+      rTokenMint,
+      cTokenMint,
+      tokenSwap,
+      swapAuthority,
+      rTokenSwapAccount,
+      cTokenSwapAccount,
+      rToken,
+      cToken,
+      poolTokenMint,
+      feeTokenAccount,
+      destinationTokenAccount,
+    } = await generateTestLinearSwapAccounts(program.programId, 500 * 10 ** 8);
+
+    // example curve - quadratic price of 3 * (display CC swapped out)^2 + 50 base RLY per base CC
+    let slope_numerator = new anchor.BN(3);
+    let slope_denominator = new anchor.BN(1);
+    let r0_numerator = new anchor.BN(50);
+    let r0_denominator = 1;
+    let exponent = 2;
+    let token_b_decimals = 8;
+
+    const tx = await program.rpc.initializePowerPrice(
+      ZERO_FEES,
+      slope_numerator,
+      slope_denominator,
+      r0_numerator,
+      r0_denominator,
+      exponent,
+      token_b_decimals,
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        signers: [tokenSwap],
+      });
+
+    console.log("Your transaction signature", tx);
+
+    const swapUser = await generateNewSignerAccount(provider);
+
+    const rTokenUserAccount = await generateTokenAccount(provider, rTokenMint, swapUser.publicKey);
+    await mintToAccount(provider, rTokenMintAuthority, rTokenMint, rTokenUserAccount.publicKey, 10000 * 10 ** 8);
+    const cTokenUserAccount = await generateTokenAccount(provider, cTokenMint, swapUser.publicKey);
+
+    // put in 5000 RLY, should get out 16.12620231 CC (price goes from 50 to ~830 RLY over that range)
+    let swapTx = await program.rpc.swap(
+      new anchor.BN("500000000000"),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: rTokenUserAccount.publicKey,
+          swapSource: rTokenSwapAccount.publicKey,
+          swapDestination: cTokenSwapAccount.publicKey,
+          destination: cTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    // user RLY goes from 10K -> 5000
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount.toString(),
+      "5000.00000000".replace(".", ""));
+    // swap's RLY balance goes from 0 -> 5000
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
+      "5000.00000000".replace(".", ""));
+    // user CC goes from 0 -> 16.12620231
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "16.12620231".replace(".", ""));
+    // swap's CC balance goes from 500 -> 483.87379769
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenSwapAccount.publicKey)).amount.toString(),
+      "483.87379769".replace(".", ""));
+
+    // sell it all back, shouldn't get more than the 5000 RLY that was put in
+    swapTx = await program.rpc.swap(
+      new anchor.BN("1612620231"),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: cTokenUserAccount.publicKey,
+          swapSource: cTokenSwapAccount.publicKey,
+          swapDestination: rTokenSwapAccount.publicKey,
+          destination: rTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    const userRTokenAmount = (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount;
+    assert.ok(userRTokenAmount.lte(new anchor.BN("1000000000000")));
+    // only rounding dust should be left in the swap
+    assert.ok(userRTokenAmount.gte(new anchor.BN("999999999900")));
+  });
+
+  it('should swap across segments on piecewise linear swap!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const {
+      rTokenMintAuthority,
+      rTokenMint,
+      cTokenMint,
+      tokenSwap,
+      swapAuthority,
+      rTokenSwapAccount,
+      cTokenSwapAccount,
+      rToken,
+      cToken,
+      poolTokenMint,
+      feeTokenAccount,
+      destinationTokenAccount,
+    } = await generateTestLinearSwapAccounts(program.programId, 500 * 10 ** 8);
+
+    // the segments are kept in the swap's curve params account, a PDA the program creates on initialization
+    const segmentsAccount = (await anchor.web3.PublicKey.findProgramAddress(
+      [tokenSwap.publicKey.toBuffer(), Buffer.from("curve_params")], program.programId))[0];
+
+    // example curve - starts at 50 RLY per CC and goes up by 1 RLY for every CC swapped out until 20 CC, then by 5
+    // RLY for every CC swapped out after that
+    let r0_numerator = new anchor.BN(150);
+    let r0_denominator = new anchor.BN(3);  // not reducing to test out division
+    let segments = [
+      { breakpoint: new anchor.BN(0), slopeNumerator: new anchor.BN(1), slopeDenominator: new anchor.BN(100000000) },
+      { breakpoint: new anchor.BN(20 * 10 ** 8), slopeNumerator: new anchor.BN(5), slopeDenominator: new anchor.BN(100000000) },
+    ];
+
+    const tx = await program.rpc.initializePiecewiseLinear(
+      ZERO_FEES,
+      r0_numerator,
+      r0_denominator,
+      segments,
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        remainingAccounts: [
+          { pubkey: segmentsAccount, isWritable: true, isSigner: false },
+        ],
+        signers: [tokenSwap],
+      });
+
+    console.log("Your transaction signature", tx);
+
+    const swapUser = await generateNewSignerAccount(provider);
+
+    const rTokenUserAccount = await generateTokenAccount(provider, rTokenMint, swapUser.publicKey);
+    await mintToAccount(provider, rTokenMintAuthority, rTokenMint, rTokenUserAccount.publicKey, 10000 * 10 ** 8);
+    const cTokenUserAccount = await generateTokenAccount(provider, cTokenMint, swapUser.publicKey);
+
+    // put in 5000 RLY, should get out 47.42463034 CC (1200 RLY for the first 20 CC, the rest in the second segment)
+    let swapTx = await program.rpc.swap(
+      new anchor.BN("500000000000"),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: rTokenUserAccount.publicKey,
+          swapSource: rTokenSwapAccount.publicKey,
+          swapDestination: cTokenSwapAccount.publicKey,
+          destination: cTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        remainingAccounts: [
+          { pubkey: segmentsAccount, isWritable: false, isSigner: false },
+        ],
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    // user RLY goes from 10K -> 5000
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount.toString(),
+      "5000.00000000".replace(".", ""));
+    // swap's RLY balance goes from 0 -> 5000
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
+      "5000.00000000".replace(".", ""));
+    // user CC goes from 0 -> 47.42463034
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "47.42463034".replace(".", ""));
+    // swap's CC balance goes from 500 -> 452.57536966
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenSwapAccount.publicKey)).amount.toString(),
+      "452.57536966".replace(".", ""));
+
+    // swapping without the segments account should fail
+    await assert.rejects(
+      program.rpc.swap(
+        new anchor.BN("100000000"),
+        new anchor.BN(0),
+        {
+          accounts: {
+            tokenSwap: tokenSwap.publicKey,
+            swapAuthority: swapAuthority,
+            userTransferAuthority: swapUser.publicKey,
+            source: cTokenUserAccount.publicKey,
+            swapSource: cTokenSwapAccount.publicKey,
+            swapDestination: rTokenSwapAccount.publicKey,
+            destination: rTokenUserAccount.publicKey,
+            poolMint: poolTokenMint.publicKey,
+            poolFee: feeTokenAccount.publicKey,
+            tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          },
+          signers: [swapUser]
+        },
+      )
+    );
+
+    // sell it all back across both segments, shouldn't get more than the 5000 RLY that was put in
+    swapTx = await program.rpc.swap(
+      new anchor.BN("4742463034"),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: cTokenUserAccount.publicKey,
+          swapSource: cTokenSwapAccount.publicKey,
+          swapDestination: rTokenSwapAccount.publicKey,
+          destination: rTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        remainingAccounts: [
+          { pubkey: segmentsAccount, isWritable: false, isSigner: false },
+        ],
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    const userRTokenAmount = (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount;
+    assert.ok(userRTokenAmount.lte(new anchor.BN("1000000000000")));
+    // only rounding dust should be left in the swap
+    assert.ok(userRTokenAmount.gte(new anchor.BN("999999999800")));
+  });
+
+  it('should level off at the max price on sigmoid price swap!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const {
+      rTokenMintAuthority,
+      rTokenMint,
+      cTokenMint,
+      tokenSwap,
+      swapAuthority,
+      rTokenSwapAccount,
+      cTokenSwapAccount,
+      rToken,
+      cToken,
+      poolTokenMint,
+      feeTokenAccount,
+      destinationTokenAccount,
+    } = await generateTestLinearSwapAccounts(program.programId, 500 * 10 ** 8);
+
+    // example curve - price levels off at 100 RLY per CC, reaches 50 RLY per CC after 50 CC are swapped out and
+    // k = 1/10 per whole CC (RLY and CC both have 8 decimals, so the max price is 100 base RLY per base CC)
+    let max_price_numerator = new anchor.BN(100);
+    let max_price_denominator = 1;
+    let steepness_numerator = 1;
+    let steepness_denominator = new anchor.BN(10 * 10 ** 8);
+    let midpoint = new anchor.BN(50 * 10 ** 8);
+
+    const tx = await program.rpc.initializeSigmoidPrice(
+      ZERO_FEES,
+      max_price_numerator,
+      max_price_denominator,
+      steepness_numerator,
+      steepness_denominator,
+      midpoint,
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        signers: [tokenSwap],
+      });
+
+    console.log("Your transaction signature", tx);
+
+    const swapUser = await generateNewSignerAccount(provider);
+
+    const rTokenUserAccount = await generateTokenAccount(provider, rTokenMint, swapUser.publicKey);
+    await mintToAccount(provider, rTokenMintAuthority, rTokenMint, rTokenUserAccount.publicKey, 10000 * 10 ** 8);
+    const cTokenUserAccount = await generateTokenAccount(provider, cTokenMint, swapUser.publicKey);
+
+    // put in 2500 RLY, should get out 74.21663197 CC (price goes from 0.67 to ~92 RLY over that range)
+    let swapTx = await program.rpc.swap(
+      new anchor.BN("250000000000"),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: rTokenUserAccount.publicKey,
+          swapSource: rTokenSwapAccount.publicKey,
+          swapDestination: cTokenSwapAccount.publicKey,
+          destination: cTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    // user RLY goes from 10K -> 7500
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount.toString(),
+      "7500.00000000".replace(".", ""));
+    // swap's RLY balance goes from 0 -> 2500
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
+      "2500.00000000".replace(".", ""));
+    // user CC goes from 0 -> 74.21663197
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "74.21663197".replace(".", ""));
+    // swap's CC balance goes from 500 -> 425.78336803
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenSwapAccount.publicKey)).amount.toString(),
+      "425.78336803".replace(".", ""));
+
+    // sell it all back, shouldn't get more than the 2500 RLY that was put in
+    swapTx = await program.rpc.swap(
+      new anchor.BN("7421663197"),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: cTokenUserAccount.publicKey,
+          swapSource: cTokenSwapAccount.publicKey,
+          swapDestination: rTokenSwapAccount.publicKey,
+          destination: rTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    const userRTokenAmount = (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount;
+    assert.ok(userRTokenAmount.lte(new anchor.BN("1000000000000")));
+    // only rounding dust should be left in the swap
+    assert.ok(userRTokenAmount.gte(new anchor.BN("999999999900")));
+  });
+
+  it('should follow the Bancor formula on reserve ratio swap!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const {
+      rTokenMintAuthority,
+      rTokenMint,
+      cTokenMint,
+      tokenSwap,
+      swapAuthority,
+      rTokenSwapAccount,
+      cTokenSwapAccount,
+      rToken,
+      cToken,
+      poolTokenMint,
+      feeTokenAccount,
+      destinationTokenAccount,
+    } = await generateTestLinearSwapAccounts(program.programId, 500 * 10 ** 8);
+
+    // example curve - 50% reserve ratio, migrated from a token with 1000 RLY in its reserve and 100 CC of supply,
+    // so the price starts at 1000 / (100 * 0.5) = 20 RLY per CC
+    let reserve_ratio_numerator = 1;
+    let reserve_ratio_denominator = 2;
+    let initial_reserve = new anchor.BN(1000 * 10 ** 8);
+    let initial_supply = new anchor.BN(100 * 10 ** 8);
+
+    const tx = await program.rpc.initializeReserveRatio(
+      ZERO_FEES,
+      reserve_ratio_numerator,
+      reserve_ratio_denominator,
+      initial_reserve,
+      initial_supply,
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        signers: [tokenSwap],
+      });
+
+    console.log("Your transaction signature", tx);
+
+    const swapUser = await generateNewSignerAccount(provider);
+
+    const rTokenUserAccount = await generateTokenAccount(provider, rTokenMint, swapUser.publicKey);
+    await mintToAccount(provider, rTokenMintAuthority, rTokenMint, rTokenUserAccount.publicKey, 10000 * 10 ** 8);
+    const cTokenUserAccount = await generateTokenAccount(provider, cTokenMint, swapUser.publicKey);
+
+    // put in 5000 RLY, the reserve goes from 1000 to 6000 RLY so the supply goes from 100 to 100 * 6^0.5 CC,
+    // should get out 144.94897427 CC
+    let swapTx = await program.rpc.swap(
+      new anchor.BN("500000000000"),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: rTokenUserAccount.publicKey,
+          swapSource: rTokenSwapAccount.publicKey,
+          swapDestination: cTokenSwapAccount.publicKey,
+          destination: cTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    // user RLY goes from 10K -> 5000
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount.toString(),
+      "5000.00000000".replace(".", ""));
+    // swap's RLY balance goes from 0 -> 5000
+    assert.strictEqual(
+      (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount.toString(),
+      "5000.00000000".replace(".", ""));
+    // user CC goes from 0 -> 144.94897427
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "144.94897427".replace(".", ""));
+    // swap's CC balance goes from 500 -> 355.05102573
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenSwapAccount.publicKey)).amount.toString(),
+      "355.05102573".replace(".", ""));
+
+    // sell it all back, shouldn't get more than the 5000 RLY that was put in
+    swapTx = await program.rpc.swap(
+      new anchor.BN("14494897427"),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: cTokenUserAccount.publicKey,
+          swapSource: cTokenSwapAccount.publicKey,
+          swapDestination: rTokenSwapAccount.publicKey,
+          destination: rTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    const userRTokenAmount = (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount;
+    assert.ok(userRTokenAmount.lte(new anchor.BN("1000000000000")));
+    // only rounding dust should be left in the swap
+    assert.ok(userRTokenAmount.gte(new anchor.BN("999999999900")));
+  });
+
+  it('should initialize constant product swap with fees and curve params!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const {
+      rTokenMintAuthority,
+      rTokenMint,
+      cTokenMint,
+      tokenSwap,
+      swapAuthority,
+      rTokenSwapAccount,
+      cTokenSwapAccount,
+      rToken,
+      cToken,
+      poolTokenMint,
+      feeTokenAccount,
+      destinationTokenAccount,
+    } = await generateTestLinearSwapAccounts(program.programId, 500 * 10 ** 8);
+
+    // constant product needs liquidity on both sides
+    await mintToAccount(provider, rTokenMintAuthority, rTokenMint, rTokenSwapAccount.publicKey, 1000 * 10 ** 8);
+
+    const fees = {
+      tradeFeeNumerator: new anchor.BN(0),
+      tradeFeeDenominator: new anchor.BN(1),
+      ownerTradeFeeNumerator: new anchor.BN(0),
+      ownerTradeFeeDenominator: new anchor.BN(1),
+      ownerWithdrawFeeNumerator: new anchor.BN(0),
+      ownerWithdrawFeeDenominator: new anchor.BN(1),
+      hostFeeNumerator: new anchor.BN(0),
+      hostFeeDenominator: new anchor.BN(1),
+    };
+
+    const tx = await program.rpc.initialize(
+      fees,
+      { constantProduct: {} },
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          tokenA: rTokenSwapAccount.publicKey,
+          tokenB: cTokenSwapAccount.publicKey,
+          pool: poolTokenMint.publicKey,
+          fee: feeTokenAccount.publicKey,
+          destination: destinationTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+          admin: provider.wallet.publicKey,
+          ...(await findRegistryAccounts(provider, program.programId, rTokenSwapAccount.publicKey)),
+        },
+        signers: [tokenSwap],
+      });
+
+    console.log("Your transaction signature", tx);
+
+    const swapUser = await generateNewSignerAccount(provider);
+
+    const rTokenUserAccount = await generateTokenAccount(provider, rTokenMint, swapUser.publicKey);
+    await mintToAccount(provider, rTokenMintAuthority, rTokenMint, rTokenUserAccount.publicKey, 100 * 10 ** 8);
+    const cTokenUserAccount = await generateTokenAccount(provider, cTokenMint, swapUser.publicKey);
+
+    // put in 100 RLY against 1000 RLY * 500 CC, should get out 500 - 500000 / 1100 = 45.45454545 CC
+    const swapTx = await program.rpc.swap(
+      new anchor.BN(100 * 10 ** 8),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: tokenSwap.publicKey,
+          swapAuthority: swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: rTokenUserAccount.publicKey,
+          swapSource: rTokenSwapAccount.publicKey,
+          swapDestination: cTokenSwapAccount.publicKey,
+          destination: cTokenUserAccount.publicKey,
+          poolMint: poolTokenMint.publicKey,
+          poolFee: feeTokenAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    // user CC goes from 0 -> 45.45454545
+    assert.strictEqual(
+      (await cToken.getAccountInfo(cTokenUserAccount.publicKey)).amount.toString(),
+      "45.45454545".replace(".", ""));
+    // and the RLY paid for it (rounded in the swap's favour) ends up in the swap
+    const swapRTokenAmount = (await rToken.getAccountInfo(rTokenSwapAccount.publicKey)).amount;
+    const userRTokenAmount = (await rToken.getAccountInfo(rTokenUserAccount.publicKey)).amount;
+    assert.strictEqual(swapRTokenAmount.add(userRTokenAmount).toString(), "1100.00000000".replace(".", ""));
+    assert.ok(swapRTokenAmount.gte(new anchor.BN("109999999999")));
+  });
+
+  it('should create and initialize a swap at a PDA of its mints in one instruction!', async () => {
+    const program = anchor.workspace.TokenBondingCurve;
+
+    const aTokenMintAuthority = await generateNewSignerAccount(provider);
+    const bTokenMintAuthority = await generateNewSignerAccount(provider);
+    const aTokenMint = await generateTokenMint(provider, aTokenMintAuthority.publicKey);
+    const bTokenMint = await generateTokenMint(provider, bTokenMintAuthority.publicKey);
+    let aToken = new Token(provider.connection, aTokenMint.publicKey, TOKEN_PROGRAM_ID, aTokenMintAuthority);
+    let bToken = new Token(provider.connection, bTokenMint.publicKey, TOKEN_PROGRAM_ID, bTokenMintAuthority);
+
+    // the payer funds the swap's token accounts from its own
+    const aTokenSourceAccount = await generateTokenAccount(provider, aTokenMint, provider.wallet.publicKey);
+    await mintToAccount(provider, aTokenMintAuthority, aTokenMint, aTokenSourceAccount.publicKey, 1000 * 10 ** 8);
+    const bTokenSourceAccount = await generateTokenAccount(provider, bTokenMint, provider.wallet.publicKey);
+    await mintToAccount(provider, bTokenMintAuthority, bTokenMint, bTokenSourceAccount.publicKey, 2000 * 10 ** 8);
+
+    // corresponds to Processor::swap_address and Processor::swap_account_address
+    const findSwapAccounts = async (index: anchor.BN) => {
+      const tokenSwap = (await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("swap"),
+          aTokenMint.publicKey.toBuffer(),
+          bTokenMint.publicKey.toBuffer(),
+          index.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId))[0];
+      const findSwapAccount = async (seed: string) => (await anchor.web3.PublicKey.findProgramAddress(
+        [tokenSwap.toBuffer(), Buffer.from(seed)], program.programId))[0];
+      return {
+        tokenSwap,
+        swapAuthority: (await anchor.web3.PublicKey.findProgramAddress([tokenSwap.toBuffer()], program.programId))[0],
+        tokenAMint: aTokenMint.publicKey,
+        tokenBMint: bTokenMint.publicKey,
+        tokenA: await findSwapAccount("token_a"),
+        tokenB: await findSwapAccount("token_b"),
+        pool: await findSwapAccount("pool_mint"),
+        fee: await findSwapAccount("pool_fee"),
+        sourceTokenA: aTokenSourceAccount.publicKey,
+        sourceTokenB: bTokenSourceAccount.publicKey,
+        admin: provider.wallet.publicKey,
+        ...(await findRegistryAccounts(provider, program.programId, aTokenSourceAccount.publicKey)),
+        tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      };
+    }
+
+    const fees = {
+      tradeFeeNumerator: new anchor.BN(0),
+      tradeFeeDenominator: new anchor.BN(1),
+      ownerTradeFeeNumerator: new anchor.BN(0),
+      ownerTradeFeeDenominator: new anchor.BN(1),
+      ownerWithdrawFeeNumerator: new anchor.BN(0),
+      ownerWithdrawFeeDenominator: new anchor.BN(1),
+      hostFeeNumerator: new anchor.BN(0),
+      hostFeeDenominator: new anchor.BN(1),
+    };
+    const initializePda = (index: anchor.BN, accounts: any) => program.rpc.initializePda(
+      index,
+      fees,
+      { constantPrice: { tokenBPrice: new anchor.BN(5) } },
+      new anchor.BN(500 * 10 ** 8),
+      new anchor.BN(1000 * 10 ** 8),
+      false,
+      { accounts });
+
+    const swapAccounts = await findSwapAccounts(new anchor.BN(0));
+    const tx = await initializePda(new anchor.BN(0), swapAccounts);
+
+    console.log("Your transaction signature", tx);
+
+    // the swap's token accounts are funded from the payer's
+    assert.strictEqual(
+      (await aToken.getAccountInfo(swapAccounts.tokenA)).amount.toString(),
+      "500.00000000".replace(".", ""));
+    assert.strictEqual(
+      (await bToken.getAccountInfo(swapAccounts.tokenB)).amount.toString(),
+      "1000.00000000".replace(".", ""));
+    assert.ok((await aToken.getAccountInfo(swapAccounts.tokenA)).owner.equals(swapAccounts.swapAuthority));
+    // the admin's pool fee account gets the initial pool token supply (see CurveCalculator::INITIAL_SWAP_POOL_AMOUNT)
+    let poolToken = new Token(provider.connection, swapAccounts.pool, TOKEN_PROGRAM_ID, aTokenMintAuthority);
+    const feeTokenAccountInfo = await poolToken.getAccountInfo(swapAccounts.fee);
+    assert.ok(feeTokenAccountInfo.owner.equals(provider.wallet.publicKey));
+    assert.strictEqual(feeTokenAccountInfo.amount.toString(), "10.00000000".replace(".", ""));
+
+    // there's only one swap per mint pair and index, but any number of indexes
+    await assert.rejects(initializePda(new anchor.BN(0), swapAccounts));
+    const secondSwapAccounts = await findSwapAccounts(new anchor.BN(1));
+    await initializePda(new anchor.BN(1), secondSwapAccounts);
+
+    // both swaps are recorded in token A's registry (see `RegistryPage` for the layout)
+    const registryPage = (await provider.connection.getAccountInfo(secondSwapAccounts.registryPage)).data;
+    assert.strictEqual(registryPage[73], 2);
+    assert.ok(new PublicKey(registryPage.slice(74, 106)).equals(swapAccounts.tokenSwap));
+    assert.ok(new PublicKey(registryPage.slice(74 + REGISTRY_ENTRY_LEN, 106 + REGISTRY_ENTRY_LEN))
+      .equals(secondSwapAccounts.tokenSwap));
+    assert.ok(new PublicKey(registryPage.slice(106, 138)).equals(bTokenMint.publicKey));
+    // with the admin that created it, not paused
+    assert.ok(new PublicKey(registryPage.slice(147, 179)).equals(provider.wallet.publicKey));
+    assert.strictEqual(registryPage[179], 0);
+    assert.strictEqual(
+      (await aToken.getAccountInfo(aTokenSourceAccount.publicKey)).amount.toString(),
+      "0");
+
+    const swapUser = await generateNewSignerAccount(provider);
+    const aTokenUserAccount = await generateTokenAccount(provider, aTokenMint, swapUser.publicKey);
+    await mintToAccount(provider, aTokenMintAuthority, aTokenMint, aTokenUserAccount.publicKey, 20 * 10 ** 8);
+    const bTokenUserAccount = await generateTokenAccount(provider, bTokenMint, swapUser.publicKey);
+
+    const swapTx = await program.rpc.swap(
+      new anchor.BN(20 * 10 ** 8),
+      new anchor.BN(0),
+      {
+        accounts: {
+          tokenSwap: swapAccounts.tokenSwap,
+          swapAuthority: swapAccounts.swapAuthority,
+          userTransferAuthority: swapUser.publicKey,
+          source: aTokenUserAccount.publicKey,
+          swapSource: swapAccounts.tokenA,
+          swapDestination: swapAccounts.tokenB,
+          destination: bTokenUserAccount.publicKey,
+          poolMint: swapAccounts.pool,
+          poolFee: swapAccounts.fee,
+          tokenProgram: TOKEN_PROGRAM_PUBKEY,
+        },
+        signers: [swapUser]
+      },
+    )
+
+    console.log("Your transaction signature", swapTx);
+
+    // user gets 20/5 B tokens without fees
+    assert.strictEqual(
+      (await bToken.getAccountInfo(bTokenUserAccount.publicKey)).amount.toString(),
+      "4.00000000".replace(".", ""));
+  });
 });